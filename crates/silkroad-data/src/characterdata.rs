@@ -1,4 +1,5 @@
 use crate::common::RefCommon;
+use crate::itemdata::RefBiologicalType;
 use crate::{DataEntry, DataMap, FileError, ParseError};
 use pk2_sync::sync::Pk2;
 use silkroad_definitions::rarity::EntityRarity;
@@ -16,6 +17,7 @@ pub struct RefCharacterData {
     pub common: RefCommon,
     pub rarity: EntityRarity,             // column 16
     pub level: u8,                        // column 57
+    pub gender: RefBiologicalType,        // column 58
     pub exp: u32,                         // column 79
     pub hp: u32,                          // column 59
     pub walk_speed: u32,                  // column 46
@@ -46,6 +48,7 @@ impl FromStr for RefCharacterData {
         let rarity_kind: u8 = elements.get(15).ok_or(ParseError::MissingColumn(16))?.parse()?;
         let aggressive: u8 = elements.get(93).ok_or(ParseError::MissingColumn(94))?.parse()?;
         let pickup_range: u16 = elements.get(61).ok_or(ParseError::MissingColumn(61))?.parse()?;
+        let gender: u8 = elements.get(58).ok_or(ParseError::MissingColumn(58))?.parse()?;
         let mut skills: Vec<u32> = Vec::new();
         for i in 83..=92 {
             let skill_id: u32 = elements.get(i).ok_or(ParseError::MissingColumn(i as u8))?.parse()?;
@@ -57,6 +60,8 @@ impl FromStr for RefCharacterData {
             common,
             rarity: EntityRarity::try_from(rarity_kind)?,
             level: elements.get(57).ok_or(ParseError::MissingColumn(57))?.parse()?,
+            // Only player characters have a meaningful gender, anything else we treat as genderless.
+            gender: RefBiologicalType::try_from(gender).unwrap_or(RefBiologicalType::Both),
             exp: elements.get(79).ok_or(ParseError::MissingColumn(79))?.parse()?,
             hp: elements.get(59).ok_or(ParseError::MissingColumn(59))?.parse()?,
             walk_speed: elements.get(46).ok_or(ParseError::MissingColumn(46))?.parse()?,
@@ -20,7 +20,7 @@ pub enum RefItemRarity {
     Legend = 8,
 }
 
-#[derive(TryFromPrimitive, Copy, Clone)]
+#[derive(TryFromPrimitive, Copy, Clone, Eq, PartialEq)]
 #[repr(u8)]
 pub enum RefBiologicalType {
     Female = 0,
@@ -40,6 +40,80 @@ impl FromStr for RefBiologicalType {
     }
 }
 
+/// A stat of an item which is rolled between a lower and upper bound when the item is created.
+#[derive(Copy, Clone, Default, PartialEq, Debug)]
+pub struct RefStatRange {
+    pub lower: f32,
+    pub upper: f32,
+}
+
+impl RefStatRange {
+    fn from_columns(elements: &[&str], lower: u8) -> Result<Self, ParseError> {
+        let upper = lower + 1;
+        Ok(Self {
+            lower: elements
+                .get(lower as usize)
+                .ok_or(ParseError::MissingColumn(lower))?
+                .parse()?,
+            upper: elements
+                .get(upper as usize)
+                .ok_or(ParseError::MissingColumn(upper))?
+                .parse()?,
+        })
+    }
+
+    /// Gets the value at the given ratio between the lower and the upper bound, where `0.0` is the lower
+    /// and `1.0` the upper bound.
+    pub fn value_at(&self, ratio: f32) -> f32 {
+        self.lower + (self.upper - self.lower) * ratio
+    }
+}
+
+/// The base (white) stats of an equipment item. Stats with an `_increase` are increased by that amount
+/// for each upgrade level of the item.
+#[derive(Copy, Clone, Default)]
+pub struct RefItemStats {
+    pub durability: RefStatRange,      // column 63-64
+    pub phys_defense: RefStatRange,    // column 65-66
+    pub phys_defense_increase: f32,    // column 67
+    pub parry_rate: RefStatRange,      // column 68-69
+    pub parry_rate_increase: f32,      // column 70
+    pub block_rate: RefStatRange,      // column 74-75
+    pub mag_defense: RefStatRange,     // column 76-77
+    pub mag_defense_increase: f32,     // column 78
+    pub phys_attack_min: RefStatRange, // column 95-96
+    pub phys_attack_max: RefStatRange, // column 97-98
+    pub phys_attack_increase: f32,     // column 99
+    pub mag_attack_min: RefStatRange,  // column 100-101
+    pub mag_attack_max: RefStatRange,  // column 102-103
+    pub mag_attack_increase: f32,      // column 104
+    pub hit_rate: RefStatRange,        // column 113-114
+    pub hit_rate_increase: f32,        // column 115
+}
+
+impl RefItemStats {
+    pub fn from_columns(elements: &[&str]) -> Result<Self, ParseError> {
+        Ok(Self {
+            durability: RefStatRange::from_columns(elements, 63)?,
+            phys_defense: RefStatRange::from_columns(elements, 65)?,
+            phys_defense_increase: elements.get(67).ok_or(ParseError::MissingColumn(67))?.parse()?,
+            parry_rate: RefStatRange::from_columns(elements, 68)?,
+            parry_rate_increase: elements.get(70).ok_or(ParseError::MissingColumn(70))?.parse()?,
+            block_rate: RefStatRange::from_columns(elements, 74)?,
+            mag_defense: RefStatRange::from_columns(elements, 76)?,
+            mag_defense_increase: elements.get(78).ok_or(ParseError::MissingColumn(78))?.parse()?,
+            phys_attack_min: RefStatRange::from_columns(elements, 95)?,
+            phys_attack_max: RefStatRange::from_columns(elements, 97)?,
+            phys_attack_increase: elements.get(99).ok_or(ParseError::MissingColumn(99))?.parse()?,
+            mag_attack_min: RefStatRange::from_columns(elements, 100)?,
+            mag_attack_max: RefStatRange::from_columns(elements, 102)?,
+            mag_attack_increase: elements.get(104).ok_or(ParseError::MissingColumn(104))?.parse()?,
+            hit_rate: RefStatRange::from_columns(elements, 113)?,
+            hit_rate_increase: elements.get(115).ok_or(ParseError::MissingColumn(115))?.parse()?,
+        })
+    }
+}
+
 #[derive(Clone)]
 pub struct RefItemData {
     pub common: RefCommon,
@@ -47,8 +121,15 @@ pub struct RefItemData {
     pub max_stack_size: u16,
     pub range: Option<NonZeroU16>,
     pub required_level: Option<NonZeroU8>,
+    /// Additional level requirements besides the character level, as pairs of the required kind and
+    /// level. For masteries, the kind is the ref id of the mastery.
+    pub required_levels: Vec<(u32, NonZeroU8)>,
+    pub required_strength: u16,
+    pub required_intelligence: u16,
     pub biological_type: RefBiologicalType,
+    pub two_handed: bool,
     pub params: [isize; 4],
+    pub stats: RefItemStats,
 }
 
 impl PartialEq for RefItemData {
@@ -75,6 +156,22 @@ impl FromStr for RefItemData {
         let common = RefCommon::from_columns(&elements)?;
         let range: u16 = elements.get(94).ok_or(ParseError::MissingColumn(94))?.parse()?;
         let required_level: u8 = elements.get(33).ok_or(ParseError::MissingColumn(33))?.parse()?;
+        let mut required_levels = Vec::new();
+        for kind_column in [34u8, 36, 38] {
+            let level_column = kind_column + 1;
+            let kind: i32 = elements
+                .get(kind_column as usize)
+                .ok_or(ParseError::MissingColumn(kind_column))?
+                .parse()?;
+            let level: u8 = elements
+                .get(level_column as usize)
+                .ok_or(ParseError::MissingColumn(level_column))?
+                .parse()?;
+            if let (Ok(kind), Some(level)) = (u32::try_from(kind), NonZeroU8::new(level)) {
+                required_levels.push((kind, level));
+            }
+        }
+        let two_handed: u8 = elements.get(93).ok_or(ParseError::MissingColumn(93))?.parse()?;
         Ok(Self {
             common,
             price: elements.get(26).ok_or(ParseError::MissingColumn(26))?.parse()?,
@@ -86,8 +183,13 @@ impl FromStr for RefItemData {
             ],
             range: NonZeroU16::new(range),
             required_level: NonZeroU8::new(required_level),
+            required_levels,
+            required_strength: elements.get(59).ok_or(ParseError::MissingColumn(59))?.parse()?,
+            required_intelligence: elements.get(60).ok_or(ParseError::MissingColumn(60))?.parse()?,
             biological_type: elements.get(58).ok_or(ParseError::MissingColumn(58))?.parse()?,
+            two_handed: two_handed == 1,
             max_stack_size: elements.get(57).ok_or(ParseError::MissingColumn(57))?.parse()?,
+            stats: RefItemStats::from_columns(&elements)?,
         })
     }
 }
@@ -8,8 +8,10 @@ pub mod datamap;
 pub mod gold;
 pub mod itemdata;
 pub mod level;
+pub mod magicoption;
 pub mod masterydata;
 pub mod npc_pos;
+pub mod shop;
 pub mod skilldata;
 pub mod teleport;
 
@@ -0,0 +1,82 @@
+use crate::{parse_file, DataEntry, DataMap, FileError, ParseError};
+use pk2_sync::sync::Pk2;
+use std::str::FromStr;
+
+pub fn load_magic_option_map(
+    pk2: &Pk2<impl std::io::Read + std::io::Seek>,
+) -> Result<DataMap<RefMagicOption>, FileError> {
+    let mut file = pk2.open_file("/server_dep/silkroad/textdata/magicoption.txt")?;
+    let options: Vec<RefMagicOption> = parse_file(&mut file)?;
+    Ok(DataMap::new(options))
+}
+
+/// The attribute a magic option modifies, derived from the option name.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum MagicOptionKind {
+    /// Flat increase of strength.
+    Strength,
+    /// Flat increase of intelligence.
+    Intelligence,
+    /// Flat increase of the maximum health.
+    Health,
+    /// Flat increase of the maximum mana.
+    Mana,
+    /// Percentual increase of the maximum durability.
+    Durability,
+    /// Percentual increase of the hit (attack) rate.
+    HitRate,
+    /// Percentual increase of the parry (evasion) rate.
+    ParryRate,
+    /// Percentual increase of the block rate.
+    BlockRate,
+    /// Any option we don't apply ourselves, but still need to keep around.
+    Other,
+}
+
+impl MagicOptionKind {
+    fn from_name(name: &str) -> Self {
+        match name {
+            "MATTR_STR" => MagicOptionKind::Strength,
+            "MATTR_INT" => MagicOptionKind::Intelligence,
+            "MATTR_HP" => MagicOptionKind::Health,
+            "MATTR_MP" => MagicOptionKind::Mana,
+            "MATTR_DUR" => MagicOptionKind::Durability,
+            "MATTR_HR" => MagicOptionKind::HitRate,
+            "MATTR_ER" | "MATTR_EVADE_PARRY" => MagicOptionKind::ParryRate,
+            "MATTR_EVADE_BLOCK" => MagicOptionKind::BlockRate,
+            _ => MagicOptionKind::Other,
+        }
+    }
+}
+
+pub struct RefMagicOption {
+    pub ref_id: u32,
+    pub name: String,
+    pub kind: MagicOptionKind,
+    pub level: u8,
+}
+
+impl DataEntry for RefMagicOption {
+    fn ref_id(&self) -> u32 {
+        self.ref_id
+    }
+
+    fn code(&self) -> &str {
+        &self.name
+    }
+}
+
+impl FromStr for RefMagicOption {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let elements = s.split('\t').collect::<Vec<&str>>();
+        let name = elements.get(2).ok_or(ParseError::MissingColumn(2))?.to_string();
+        Ok(Self {
+            ref_id: elements.get(1).ok_or(ParseError::MissingColumn(1))?.parse()?,
+            kind: MagicOptionKind::from_name(&name),
+            name,
+            level: elements.get(4).ok_or(ParseError::MissingColumn(4))?.parse()?,
+        })
+    }
+}
@@ -0,0 +1,180 @@
+use crate::{parse_file, FileError, ParseError};
+use pk2_sync::sync::Pk2;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Loads the goods of all NPC shops, as the code names of the items sold by each NPC. An NPC is linked
+/// to its goods through several tables: its shop group contains shops, each shop has tab groups which
+/// consist of tabs, and each tab lists packages that wrap a single item.
+pub fn load_shop_goods(pk2: &Pk2<impl std::io::Read + std::io::Seek>) -> Result<ShopGoods, FileError> {
+    let groups: Vec<RefShopGroup> = parse_file(&mut pk2.open_file("/server_dep/silkroad/textdata/RefShopGroup.txt")?)?;
+    let group_shops: Vec<RefMappingShopGroup> =
+        parse_file(&mut pk2.open_file("/server_dep/silkroad/textdata/RefMappingShopGroup.txt")?)?;
+    let shop_tab_groups: Vec<RefMappingShopWithTab> =
+        parse_file(&mut pk2.open_file("/server_dep/silkroad/textdata/RefMappingShopWithTab.txt")?)?;
+    let tabs: Vec<RefShopTab> = parse_file(&mut pk2.open_file("/server_dep/silkroad/textdata/RefShopTab.txt")?)?;
+    let goods: Vec<RefShopGoods> = parse_file(&mut pk2.open_file("/server_dep/silkroad/textdata/RefShopGoods.txt")?)?;
+    let packages: Vec<RefScrapOfPackageItem> =
+        parse_file(&mut pk2.open_file("/server_dep/silkroad/textdata/RefScrapOfPackageItem.txt")?)?;
+
+    let package_items: HashMap<&str, &str> = packages
+        .iter()
+        .filter(|package| package.active)
+        .map(|package| (package.package.as_str(), package.item.as_str()))
+        .collect();
+
+    let mut goods_by_npc: HashMap<String, Vec<String>> = HashMap::new();
+    for group in groups.iter().filter(|group| group.active) {
+        let items = goods_by_npc.entry(group.npc.clone()).or_default();
+        let shops = group_shops
+            .iter()
+            .filter(|mapping| mapping.active && mapping.group == group.code)
+            .map(|mapping| mapping.shop.as_str());
+        for shop in shops {
+            let tab_groups = shop_tab_groups
+                .iter()
+                .filter(|mapping| mapping.active && mapping.shop == shop)
+                .map(|mapping| mapping.tab_group.as_str());
+            for tab_group in tab_groups {
+                let tab_goods = tabs
+                    .iter()
+                    .filter(|tab| tab.active && tab.tab_group == tab_group)
+                    .flat_map(|tab| goods.iter().filter(|good| good.active && good.tab == tab.code))
+                    .filter_map(|good| package_items.get(good.package.as_str()));
+                items.extend(tab_goods.map(|item| item.to_string()));
+            }
+        }
+    }
+
+    Ok(ShopGoods(goods_by_npc))
+}
+
+/// The code names of the items sold by each NPC, keyed by the code name of the NPC.
+pub struct ShopGoods(HashMap<String, Vec<String>>);
+
+impl ShopGoods {
+    /// Gets the code names of all items the given NPC sells, which is empty for NPCs without a shop.
+    pub fn sold_by(&self, npc_code: &str) -> &[String] {
+        self.0.get(npc_code).map(Vec::as_slice).unwrap_or_default()
+    }
+}
+
+pub struct RefShopGroup {
+    pub active: bool,
+    pub code: String,
+    pub npc: String,
+}
+
+impl FromStr for RefShopGroup {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let elements = s.split('\t').collect::<Vec<&str>>();
+        let active: u8 = elements.get(0).ok_or(ParseError::MissingColumn(0))?.parse()?;
+        Ok(Self {
+            active: active == 1,
+            code: elements.get(3).ok_or(ParseError::MissingColumn(3))?.to_string(),
+            npc: elements.get(4).ok_or(ParseError::MissingColumn(4))?.to_string(),
+        })
+    }
+}
+
+pub struct RefMappingShopGroup {
+    pub active: bool,
+    pub group: String,
+    pub shop: String,
+}
+
+impl FromStr for RefMappingShopGroup {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let elements = s.split('\t').collect::<Vec<&str>>();
+        let active: u8 = elements.get(0).ok_or(ParseError::MissingColumn(0))?.parse()?;
+        Ok(Self {
+            active: active == 1,
+            group: elements.get(2).ok_or(ParseError::MissingColumn(2))?.to_string(),
+            shop: elements.get(3).ok_or(ParseError::MissingColumn(3))?.to_string(),
+        })
+    }
+}
+
+pub struct RefMappingShopWithTab {
+    pub active: bool,
+    pub shop: String,
+    pub tab_group: String,
+}
+
+impl FromStr for RefMappingShopWithTab {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let elements = s.split('\t').collect::<Vec<&str>>();
+        let active: u8 = elements.get(0).ok_or(ParseError::MissingColumn(0))?.parse()?;
+        Ok(Self {
+            active: active == 1,
+            shop: elements.get(2).ok_or(ParseError::MissingColumn(2))?.to_string(),
+            tab_group: elements.get(3).ok_or(ParseError::MissingColumn(3))?.to_string(),
+        })
+    }
+}
+
+pub struct RefShopTab {
+    pub active: bool,
+    pub code: String,
+    pub tab_group: String,
+}
+
+impl FromStr for RefShopTab {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let elements = s.split('\t').collect::<Vec<&str>>();
+        let active: u8 = elements.get(0).ok_or(ParseError::MissingColumn(0))?.parse()?;
+        Ok(Self {
+            active: active == 1,
+            code: elements.get(3).ok_or(ParseError::MissingColumn(3))?.to_string(),
+            tab_group: elements.get(4).ok_or(ParseError::MissingColumn(4))?.to_string(),
+        })
+    }
+}
+
+pub struct RefShopGoods {
+    pub active: bool,
+    pub tab: String,
+    pub package: String,
+}
+
+impl FromStr for RefShopGoods {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let elements = s.split('\t').collect::<Vec<&str>>();
+        let active: u8 = elements.get(0).ok_or(ParseError::MissingColumn(0))?.parse()?;
+        Ok(Self {
+            active: active == 1,
+            tab: elements.get(2).ok_or(ParseError::MissingColumn(2))?.to_string(),
+            package: elements.get(3).ok_or(ParseError::MissingColumn(3))?.to_string(),
+        })
+    }
+}
+
+pub struct RefScrapOfPackageItem {
+    pub active: bool,
+    pub package: String,
+    pub item: String,
+}
+
+impl FromStr for RefScrapOfPackageItem {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let elements = s.split('\t').collect::<Vec<&str>>();
+        let active: u8 = elements.get(0).ok_or(ParseError::MissingColumn(0))?.parse()?;
+        Ok(Self {
+            active: active == 1,
+            package: elements.get(2).ok_or(ParseError::MissingColumn(2))?.to_string(),
+            item: elements.get(3).ok_or(ParseError::MissingColumn(3))?.to_string(),
+        })
+    }
+}
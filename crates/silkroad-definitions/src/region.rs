@@ -1,5 +1,7 @@
 use std::fmt::{Display, Formatter};
 
+const DUNGEON_FLAG: u16 = 0x8000;
+
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd, Debug)]
 pub struct Region(u16);
 
@@ -29,7 +31,16 @@ impl Region {
     }
 
     pub fn is_dungeon(&self) -> bool {
-        (self.0 & 0x8000) != 0
+        (self.0 & DUNGEON_FLAG) != 0
+    }
+
+    /// The id of the dungeon this region represents, if it is a dungeon.
+    pub fn dungeon_id(&self) -> Option<u16> {
+        self.is_dungeon().then_some(self.0 & !DUNGEON_FLAG)
+    }
+
+    pub const fn dungeon(id: u16) -> Region {
+        Region(id | DUNGEON_FLAG)
     }
 
     pub const fn new(id: u16) -> Region {
@@ -4,6 +4,14 @@ use std::fmt::{Display, Formatter};
 #[derive(Eq, PartialEq, Copy, Clone, Hash, Ord, PartialOrd)]
 pub struct TypeId(pub u8, pub u8, pub u8, pub u8);
 
+impl TypeId {
+    /// Packs the type id the way the client refers to item types, but without the cash and bionic
+    /// flags, which would occupy the two lowest bits.
+    pub fn packed(&self) -> u16 {
+        u16::from(self.0) | (u16::from(self.1) << 3) | (u16::from(self.2) << 5) | (u16::from(self.3) << 9)
+    }
+}
+
 impl Display for TypeId {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}|{}|{}|{}", self.0, self.1, self.2, self.3)
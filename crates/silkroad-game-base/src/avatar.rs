@@ -0,0 +1,181 @@
+use crate::{Change, ChangeTracked, Item, MergeResult, MoveError};
+use silkroad_definitions::type_id::{ObjectAvatar, ObjectEquippable, ObjectItem, ObjectType};
+use std::collections::hash_map::Iter;
+use std::collections::HashMap;
+
+/// The size of the avatar inventory as known by the client. The last slot is reserved for the devil
+/// spirit, which we do not support yet.
+pub const AVATAR_INVENTORY_SIZE: usize = 5;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum AvatarSlot {
+    Hat = 0,
+    Dress = 1,
+    Accessory = 2,
+    Flag = 3,
+}
+
+impl AvatarSlot {
+    pub fn of(avatar: ObjectAvatar) -> AvatarSlot {
+        match avatar {
+            ObjectAvatar::Hat => AvatarSlot::Hat,
+            ObjectAvatar::Dress => AvatarSlot::Dress,
+            ObjectAvatar::Attachment => AvatarSlot::Accessory,
+            ObjectAvatar::ETC => AvatarSlot::Flag,
+        }
+    }
+
+    /// Determines the slot the given item would be worn in, if it is an avatar item at all.
+    pub fn of_item(item: &Item) -> Option<AvatarSlot> {
+        match ObjectType::from_type_id(&item.reference.common.type_id)? {
+            ObjectType::Item(ObjectItem::Equippable(ObjectEquippable::Avatar(avatar))) => Some(Self::of(avatar)),
+            _ => None,
+        }
+    }
+}
+
+impl From<AvatarSlot> for u8 {
+    fn from(value: AvatarSlot) -> Self {
+        value as u8
+    }
+}
+
+pub enum AvatarChange {
+    Equip { slot: u8, item: Item },
+    Unequip { slot: u8 },
+}
+
+impl Change for AvatarChange {
+    fn merge(self, other: Self) -> MergeResult<Self> {
+        match (&self, &other) {
+            (AvatarChange::Equip { slot, .. }, AvatarChange::Unequip { slot: other_slot }) if slot == other_slot => {
+                MergeResult::Cancelled
+            },
+            (AvatarChange::Equip { slot, .. }, AvatarChange::Equip { slot: other_slot, .. }) if slot == other_slot => {
+                MergeResult::Merged(other)
+            },
+            (AvatarChange::Unequip { slot }, AvatarChange::Equip { slot: other_slot, .. }) if slot == other_slot => {
+                MergeResult::Incompatible(self, other)
+            },
+            _ => MergeResult::Unchanged(self, other),
+        }
+    }
+}
+
+/// The avatar items a character is wearing. Avatars are kept separate from the regular inventory
+/// and only change the appearance of the character.
+#[derive(Default)]
+pub struct AvatarInventory {
+    items: HashMap<u8, Item>,
+    changes: Vec<AvatarChange>,
+}
+
+impl AvatarInventory {
+    pub fn size(&self) -> usize {
+        AVATAR_INVENTORY_SIZE
+    }
+
+    pub fn get_item_at(&self, slot: u8) -> Option<&Item> {
+        self.items.get(&slot)
+    }
+
+    pub fn items(&self) -> Iter<u8, Item> {
+        self.items.iter()
+    }
+
+    pub fn set_item(&mut self, slot: u8, item: Item) {
+        self.items.insert(slot, item);
+    }
+
+    /// Puts on the given avatar item in the given slot, returning the item that was worn in that slot
+    /// before, if any. Fails if the item does not belong in that slot.
+    pub fn equip(&mut self, slot: u8, item: Item) -> Result<Option<Item>, MoveError> {
+        if AvatarSlot::of_item(&item).map(u8::from) != Some(slot) {
+            return Err(MoveError::Impossible);
+        }
+
+        let previous = self.items.insert(slot, item);
+        if previous.is_some() {
+            self.changes.push(AvatarChange::Unequip { slot });
+        }
+        self.changes.push(AvatarChange::Equip { slot, item });
+        Ok(previous)
+    }
+
+    /// Takes off the avatar item in the given slot.
+    pub fn unequip(&mut self, slot: u8) -> Option<Item> {
+        let item = self.items.remove(&slot)?;
+        self.changes.push(AvatarChange::Unequip { slot });
+        Some(item)
+    }
+}
+
+impl ChangeTracked for AvatarInventory {
+    type ChangeItem = AvatarChange;
+
+    fn changes(&mut self) -> Vec<Self::ChangeItem> {
+        std::mem::take(&mut self.changes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Durability, ItemTypeData, ToOptimizedChange};
+    use once_cell::sync::Lazy;
+    use silkroad_data::common::{RefCommon, RefOrigin};
+    use silkroad_data::itemdata::{RefBiologicalType, RefItemData};
+    use std::ops::Deref;
+
+    static HAT_DATA: Lazy<RefItemData> = Lazy::new(|| RefItemData {
+        common: RefCommon {
+            ref_id: 1,
+            id: "TestHat".to_string(),
+            type_id: ObjectType::Item(ObjectItem::Equippable(ObjectEquippable::Avatar(ObjectAvatar::Hat))).type_id(),
+            country: RefOrigin::Chinese,
+            despawn_time: Default::default(),
+        },
+        price: 100,
+        max_stack_size: 1,
+        range: None,
+        required_level: None,
+        required_levels: Vec::new(),
+        required_strength: 0,
+        required_intelligence: 0,
+        biological_type: RefBiologicalType::Both,
+        two_handed: false,
+        params: [0, 0, 0, 0],
+        stats: Default::default(),
+    });
+
+    fn hat() -> Item {
+        Item {
+            reference: HAT_DATA.deref(),
+            variance: None,
+            type_data: ItemTypeData::Equipment {
+                upgrade_level: 0,
+                magic: Default::default(),
+                durability: Durability::new(1, 1),
+            },
+            rental: None,
+        }
+    }
+
+    #[test]
+    pub fn test_equip_into_matching_slot() {
+        let mut avatars = AvatarInventory::default();
+        assert!(avatars.equip(AvatarSlot::Dress.into(), hat()).is_err());
+        assert!(matches!(avatars.equip(AvatarSlot::Hat.into(), hat()), Ok(None)));
+        assert!(matches!(avatars.equip(AvatarSlot::Hat.into(), hat()), Ok(Some(_))));
+        assert!(avatars.get_item_at(AvatarSlot::Hat.into()).is_some());
+    }
+
+    #[test]
+    pub fn test_equip_and_unequip_cancel_out() {
+        let mut avatars = AvatarInventory::default();
+        avatars.equip(AvatarSlot::Hat.into(), hat()).unwrap();
+        assert!(avatars.unequip(AvatarSlot::Hat.into()).is_some());
+        assert!(avatars.unequip(AvatarSlot::Hat.into()).is_none());
+        assert!(avatars.changes().optimize().is_empty());
+    }
+}
@@ -18,6 +18,7 @@ pub struct Character {
     pub gold: u64,
     pub beginner_mark: bool,
     pub gm: bool,
+    pub recall_point: Option<u16>,
     pub state: SpawningState,
     pub masteries: Vec<(u32, u8)>,
     pub skills: Vec<(u32, u8)>,
@@ -0,0 +1,247 @@
+use crate::Item;
+use silkroad_data::itemdata::RefItemData;
+use silkroad_definitions::type_id::{ObjectEquippable, ObjectItem, ObjectType};
+use std::ops::AddAssign;
+
+/// The kind of equipment, which determines how the variance of an item is to be interpreted.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum EquipmentKind {
+    Weapon,
+    Shield,
+    Armor,
+    Accessory,
+    Other,
+}
+
+impl EquipmentKind {
+    pub fn of(item: &Item) -> EquipmentKind {
+        Self::of_reference(item.reference)
+    }
+
+    pub fn of_reference(reference: &RefItemData) -> EquipmentKind {
+        match ObjectType::from_type_id(&reference.common.type_id) {
+            Some(ObjectType::Item(ObjectItem::Equippable(equippable))) => match equippable {
+                ObjectEquippable::Weapon(_) => EquipmentKind::Weapon,
+                ObjectEquippable::Shield(_) => EquipmentKind::Shield,
+                ObjectEquippable::Clothing(_, _) => EquipmentKind::Armor,
+                ObjectEquippable::Jewelry(_, _) => EquipmentKind::Accessory,
+                _ => EquipmentKind::Other,
+            },
+            _ => EquipmentKind::Other,
+        }
+    }
+}
+
+/// The individual stats which are encoded in the variance of an item. Each stat occupies five bits,
+/// where the position depends on the [EquipmentKind].
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum VarianceStat {
+    Durability,
+    PhysicalReinforcement,
+    MagicalReinforcement,
+    HitRate,
+    PhysicalAttack,
+    MagicalAttack,
+    CriticalRate,
+    PhysicalDefense,
+    MagicalDefense,
+    ParryRate,
+    BlockRate,
+    PhysicalAbsorption,
+    MagicalAbsorption,
+}
+
+const VARIANCE_BITS: u64 = 5;
+const VARIANCE_MAX: u64 = (1 << VARIANCE_BITS) - 1;
+
+impl VarianceStat {
+    fn position(self, kind: EquipmentKind) -> Option<u64> {
+        let position = match (kind, self) {
+            (EquipmentKind::Accessory, VarianceStat::PhysicalAbsorption) => 0,
+            (EquipmentKind::Accessory, VarianceStat::MagicalAbsorption) => 1,
+            (EquipmentKind::Accessory, _) | (EquipmentKind::Other, _) => return None,
+            (_, VarianceStat::Durability) => 0,
+            (_, VarianceStat::PhysicalReinforcement) => 1,
+            (_, VarianceStat::MagicalReinforcement) => 2,
+            (EquipmentKind::Weapon, VarianceStat::HitRate) => 3,
+            (EquipmentKind::Weapon, VarianceStat::PhysicalAttack) => 4,
+            (EquipmentKind::Weapon, VarianceStat::MagicalAttack) => 5,
+            (EquipmentKind::Weapon, VarianceStat::CriticalRate) => 6,
+            (EquipmentKind::Armor, VarianceStat::PhysicalDefense) => 3,
+            (EquipmentKind::Armor, VarianceStat::MagicalDefense) => 4,
+            (EquipmentKind::Armor, VarianceStat::ParryRate) => 5,
+            (EquipmentKind::Shield, VarianceStat::BlockRate) => 3,
+            (EquipmentKind::Shield, VarianceStat::PhysicalDefense) => 4,
+            (EquipmentKind::Shield, VarianceStat::MagicalDefense) => 5,
+            _ => return None,
+        };
+        Some(position)
+    }
+
+    /// Gets the ratio, between `0.0` and `1.0`, this stat has been rolled with in the given variance.
+    /// Stats that are not part of the variance for this kind of equipment are always at `0.0`.
+    pub fn ratio(self, kind: EquipmentKind, variance: u64) -> f32 {
+        match self.position(kind) {
+            Some(position) => {
+                let value = (variance >> (position * VARIANCE_BITS)) & VARIANCE_MAX;
+                value as f32 / VARIANCE_MAX as f32
+            },
+            None => 0.0,
+        }
+    }
+}
+
+/// The white stats provided by a piece of equipment, i.e. the stats of the item itself scaled by its
+/// variance and upgrade level.
+#[derive(Copy, Clone, Default, PartialEq, Debug)]
+pub struct EquipmentStats {
+    pub phys_attack_min: f32,
+    pub phys_attack_max: f32,
+    pub mag_attack_min: f32,
+    pub mag_attack_max: f32,
+    pub phys_defense: f32,
+    pub mag_defense: f32,
+    pub hit_rate: f32,
+    pub parry_rate: f32,
+    pub block_rate: f32,
+}
+
+impl EquipmentStats {
+    pub fn of(item: &Item) -> EquipmentStats {
+        let kind = EquipmentKind::of(item);
+        if kind == EquipmentKind::Other {
+            return EquipmentStats::default();
+        }
+
+        let stats = &item.reference.stats;
+        let variance = item.variance.unwrap_or_default();
+        let upgrade = f32::from(item.upgrade_level());
+        let ratio = |stat: VarianceStat| stat.ratio(kind, variance);
+
+        EquipmentStats {
+            phys_attack_min: stats.phys_attack_min.value_at(ratio(VarianceStat::PhysicalAttack))
+                + stats.phys_attack_increase * upgrade,
+            phys_attack_max: stats.phys_attack_max.value_at(ratio(VarianceStat::PhysicalAttack))
+                + stats.phys_attack_increase * upgrade,
+            mag_attack_min: stats.mag_attack_min.value_at(ratio(VarianceStat::MagicalAttack))
+                + stats.mag_attack_increase * upgrade,
+            mag_attack_max: stats.mag_attack_max.value_at(ratio(VarianceStat::MagicalAttack))
+                + stats.mag_attack_increase * upgrade,
+            phys_defense: stats.phys_defense.value_at(ratio(VarianceStat::PhysicalDefense))
+                + stats.phys_defense_increase * upgrade,
+            mag_defense: stats.mag_defense.value_at(ratio(VarianceStat::MagicalDefense))
+                + stats.mag_defense_increase * upgrade,
+            hit_rate: stats.hit_rate.value_at(ratio(VarianceStat::HitRate)) + stats.hit_rate_increase * upgrade,
+            parry_rate: stats.parry_rate.value_at(ratio(VarianceStat::ParryRate)) + stats.parry_rate_increase * upgrade,
+            block_rate: stats.block_rate.value_at(ratio(VarianceStat::BlockRate)),
+        }
+    }
+}
+
+impl AddAssign for EquipmentStats {
+    fn add_assign(&mut self, rhs: Self) {
+        self.phys_attack_min += rhs.phys_attack_min;
+        self.phys_attack_max += rhs.phys_attack_max;
+        self.mag_attack_min += rhs.mag_attack_min;
+        self.mag_attack_max += rhs.mag_attack_max;
+        self.phys_defense += rhs.phys_defense;
+        self.mag_defense += rhs.mag_defense;
+        self.hit_rate += rhs.hit_rate;
+        self.parry_rate += rhs.parry_rate;
+        self.block_rate += rhs.block_rate;
+    }
+}
+
+/// The durability of a piece of equipment. Once the current durability reaches zero, the item is
+/// considered broken and no longer provides any stats until it gets repaired.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct Durability {
+    current: u32,
+    max: u32,
+}
+
+impl Durability {
+    pub fn new(current: u32, max: u32) -> Self {
+        Durability {
+            current: current.min(max),
+            max,
+        }
+    }
+
+    pub fn full(max: u32) -> Self {
+        Durability { current: max, max }
+    }
+
+    /// Gets the durability a newly created item has, depending on the durability it was rolled with
+    /// in its variance. The maximum is raised by `bonus_percent`, which is the sum of all durability
+    /// magic options of the item.
+    pub fn initial(reference: &RefItemData, variance: Option<u64>, bonus_percent: u32) -> Self {
+        let kind = EquipmentKind::of_reference(reference);
+        let ratio = VarianceStat::Durability.ratio(kind, variance.unwrap_or_default());
+        let base = reference.stats.durability.value_at(ratio);
+        Durability::full((base * (100.0 + bonus_percent as f32) / 100.0).round() as u32)
+    }
+
+    pub fn current(&self) -> u32 {
+        self.current
+    }
+
+    pub fn max(&self) -> u32 {
+        self.max
+    }
+
+    pub fn missing(&self) -> u32 {
+        self.max - self.current
+    }
+
+    /// Items without any maximum durability, like accessories, cannot break.
+    pub fn is_broken(&self) -> bool {
+        self.max > 0 && self.current == 0
+    }
+
+    pub fn reduce(&mut self, amount: u32) {
+        self.current = self.current.saturating_sub(amount);
+    }
+
+    pub fn repair(&mut self) {
+        self.current = self.max;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_variance_ratio() {
+        // Durability maxed, physical attack at 0, magical attack at about half
+        let variance = VARIANCE_MAX | (15 << (5 * VARIANCE_BITS));
+        assert_eq!(1.0, VarianceStat::Durability.ratio(EquipmentKind::Weapon, variance));
+        assert_eq!(0.0, VarianceStat::PhysicalAttack.ratio(EquipmentKind::Weapon, variance));
+        assert_eq!(
+            15.0 / 31.0,
+            VarianceStat::MagicalAttack.ratio(EquipmentKind::Weapon, variance)
+        );
+        // Armor has its parry rate in the place of the weapon's magical attack
+        assert_eq!(
+            15.0 / 31.0,
+            VarianceStat::ParryRate.ratio(EquipmentKind::Armor, variance)
+        );
+        assert_eq!(0.0, VarianceStat::ParryRate.ratio(EquipmentKind::Weapon, variance));
+    }
+
+    #[test]
+    fn test_durability() {
+        let mut durability = Durability::new(50, 20);
+        assert_eq!(20, durability.current());
+        durability.reduce(5);
+        assert_eq!(5, durability.missing());
+        assert!(!durability.is_broken());
+        durability.reduce(30);
+        assert_eq!(0, durability.current());
+        assert!(durability.is_broken());
+        durability.repair();
+        assert_eq!(20, durability.current());
+        assert!(!Durability::full(0).is_broken());
+    }
+}
@@ -1,18 +1,22 @@
-use crate::{Change, ChangeTracked, MergeResult};
+use crate::{Change, ChangeTracked, Durability, MagicOptions, MergeResult};
 use silkroad_data::itemdata::RefItemData;
 use silkroad_data::DataEntry;
 use silkroad_definitions::inventory::EquipmentSlot;
+use std::cmp::Reverse;
 use std::collections::hash_map::Iter;
 use std::collections::HashMap;
 
 pub const WEAPON_SLOT: u8 = 6;
 pub const GOLD_SLOT: u8 = 0xFE;
+pub const DEFAULT_INVENTORY_SIZE: usize = 45;
+pub const MAX_INVENTORY_SIZE: usize = 109;
 
 #[derive(Copy, Clone)]
 pub struct Item {
     pub reference: &'static RefItemData,
     pub variance: Option<u64>,
     pub type_data: ItemTypeData,
+    pub rental: Option<Rental>,
 }
 
 impl Item {
@@ -27,13 +31,40 @@ impl Item {
         self.stack_size() >= self.reference.max_stack_size
     }
 
+    /// Checks if the other item may be put onto the same stack as this item.
+    pub fn can_stack_with(&self, other: &Item) -> bool {
+        self.reference.max_stack_size > 1
+            && self.reference.ref_id() == other.reference.ref_id()
+            && self.rental == other.rental
+    }
+
     pub fn upgrade_level(&self) -> u8 {
         match &self.type_data {
-            ItemTypeData::Equipment { upgrade_level } => *upgrade_level,
+            ItemTypeData::Equipment { upgrade_level, .. } => *upgrade_level,
             _ => 0,
         }
     }
 
+    pub fn magic_options(&self) -> Option<&MagicOptions> {
+        self.type_data.magic_options()
+    }
+
+    pub fn durability(&self) -> Option<Durability> {
+        self.type_data.durability()
+    }
+
+    pub fn is_broken(&self) -> bool {
+        self.durability()
+            .map(|durability| durability.is_broken())
+            .unwrap_or(false)
+    }
+
+    /// Checks if the item has been rented and its rental period has ended, given the current time in
+    /// seconds since the unix epoch.
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.rental.is_some_and(|rental| rental.is_expired(now))
+    }
+
     pub fn change_stack_size(&mut self, amount: i16) -> Result<(), MoveError> {
         self.type_data = match self.type_data {
             ItemTypeData::Consumable { amount: old_amount } => {
@@ -59,18 +90,67 @@ impl Item {
     }
 }
 
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum RentalKind {
+    /// The item can be used for the given period and is removed afterward.
+    Period,
+    /// Like [RentalKind::Period], but the period may be extended before it ends.
+    Rechargeable,
+}
+
+/// Limits the usage of an item to the given period, with both start and end given in seconds since
+/// the unix epoch.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Rental {
+    pub kind: RentalKind,
+    pub start: u64,
+    pub end: u64,
+}
+
+impl Rental {
+    pub fn new(kind: RentalKind, start: u64, end: u64) -> Self {
+        Rental { kind, start, end }
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.end <= now
+    }
+}
+
 #[derive(Copy, Clone, Eq, PartialEq)]
 pub enum ItemTypeData {
-    Equipment { upgrade_level: u8 },
+    Equipment {
+        upgrade_level: u8,
+        magic: MagicOptions,
+        durability: Durability,
+    },
     COS,
-    Consumable { amount: u16 },
-    Gold { amount: u32 },
+    Consumable {
+        amount: u16,
+    },
+    Gold {
+        amount: u32,
+    },
 }
 
 impl ItemTypeData {
     pub fn upgrade_level(&self) -> Option<u8> {
         match self {
-            ItemTypeData::Equipment { upgrade_level } => Some(*upgrade_level),
+            ItemTypeData::Equipment { upgrade_level, .. } => Some(*upgrade_level),
+            _ => None,
+        }
+    }
+
+    pub fn magic_options(&self) -> Option<&MagicOptions> {
+        match self {
+            ItemTypeData::Equipment { magic, .. } => Some(magic),
+            _ => None,
+        }
+    }
+
+    pub fn durability(&self) -> Option<Durability> {
+        match self {
+            ItemTypeData::Equipment { durability, .. } => Some(*durability),
             _ => None,
         }
     }
@@ -84,6 +164,7 @@ impl ItemTypeData {
     }
 }
 
+#[derive(Clone)]
 pub enum InventoryChange {
     AddItem {
         slot: u8,
@@ -105,11 +186,18 @@ pub enum InventoryChange {
         first_slot: u8,
         second_slot: u8,
     },
+    Resize {
+        size: usize,
+    },
 }
 
 impl Change for InventoryChange {
     fn merge(self, other: Self) -> MergeResult<InventoryChange> {
         match &self {
+            InventoryChange::Resize { .. } => match &other {
+                InventoryChange::Resize { .. } => MergeResult::Merged(other),
+                _ => MergeResult::Unchanged(self, other),
+            },
             InventoryChange::AddItem { slot, item } => match &other {
                 InventoryChange::AddItem {
                     slot: new_slot,
@@ -134,9 +222,8 @@ impl Change for InventoryChange {
                     MergeResult::Merged(InventoryChange::AddItem {
                         slot: *slot,
                         item: Item {
-                            reference: item.reference,
-                            variance: item.variance,
                             type_data: *new_item,
+                            ..*item
                         },
                     })
                 },
@@ -291,6 +378,7 @@ impl Change for InventoryChange {
     }
 }
 
+#[derive(Clone)]
 pub struct Inventory {
     size: usize,
     // TODO: wouldn't this make more sense as an array of N size?
@@ -312,6 +400,18 @@ impl Inventory {
         self.size
     }
 
+    /// Grows the inventory by the given amount of slots, up to [MAX_INVENTORY_SIZE]. Returns the
+    /// amount of slots that were actually added.
+    pub fn expand(&mut self, amount: usize) -> usize {
+        let new_size = (self.size + amount).min(MAX_INVENTORY_SIZE);
+        let added = new_size - self.size;
+        if added > 0 {
+            self.size = new_size;
+            self.changes.push(InventoryChange::Resize { size: new_size });
+        }
+        added
+    }
+
     pub fn get_item_at(&self, slot: u8) -> Option<&Item> {
         self.items.get(&slot)
     }
@@ -320,6 +420,11 @@ impl Inventory {
         self.items.iter().filter(|(index, _)| Self::is_equipment_slot(**index))
     }
 
+    /// Gets the equipped items which aren't broken and thus still provide their stats.
+    pub fn intact_equipment_items(&self) -> impl Iterator<Item = (&u8, &Item)> {
+        self.equipment_items().filter(|(_, item)| !item.is_broken())
+    }
+
     pub fn items(&self) -> Iter<u8, Item> {
         self.items.iter()
     }
@@ -336,12 +441,29 @@ impl Inventory {
         self.non_equipment_slots().find(|slot| !self.items.contains_key(slot))
     }
 
+    /// Counts the non-equipment slots that are not occupied by any item.
+    pub fn free_slots(&self) -> usize {
+        self.non_equipment_slots()
+            .filter(|slot| !self.items.contains_key(slot))
+            .count()
+    }
+
     pub fn move_item(&mut self, source: u8, target: u8, amount: u16) -> Result<u16, MoveError> {
+        let source_item = self.items.get(&source).ok_or(MoveError::ItemDoesNotExist)?;
+        // Stack sizes are changed by a signed amount below, so larger amounts would wrap around.
+        if amount == 0 || amount > i16::MAX as u16 {
+            return Err(MoveError::Impossible);
+        }
+        // Only stacks can be split or merged, the amount doesn't matter for anything else.
+        let is_stack = matches!(source_item.type_data, ItemTypeData::Consumable { .. });
+        if is_stack && amount > source_item.stack_size() {
+            return Err(MoveError::Impossible);
+        }
+        let amount = if is_stack { amount } else { source_item.stack_size() };
+
         if let Some(mut source_item) = self.items.remove(&source) {
             if let Some(mut target_item) = self.items.remove(&target) {
-                if source_item.reference.ref_id() == target_item.reference.ref_id()
-                    && source_item.reference.max_stack_size > 1
-                {
+                if source_item.can_stack_with(&target_item) {
                     let available_on_target_stack = target_item.reference.max_stack_size - target_item.stack_size();
                     if available_on_target_stack == 0 {
                         self.items.insert(source, source_item);
@@ -390,6 +512,26 @@ impl Inventory {
                     self.items.insert(target, source_item);
                     self.items.insert(source, target_item);
                 }
+            } else if amount < source_item.stack_size() {
+                // Only part of the stack is moved, which splits it into a new stack on the empty slot.
+                let old_data = source_item.type_data;
+                source_item.change_stack_size(-(amount as i16))?;
+                let new_data = source_item.type_data;
+                self.changes.push(InventoryChange::ChangeTypeData {
+                    slot: source,
+                    old_item: old_data,
+                    new_item: new_data,
+                });
+                let split = Item {
+                    type_data: ItemTypeData::Consumable { amount },
+                    ..source_item
+                };
+                self.changes.push(InventoryChange::AddItem {
+                    slot: target,
+                    item: split,
+                });
+                self.items.insert(source, source_item);
+                self.items.insert(target, split);
             } else {
                 self.changes.push(InventoryChange::MoveItem {
                     source_slot: source,
@@ -404,6 +546,87 @@ impl Inventory {
         Ok(amount)
     }
 
+    /// Combines all partial stacks of the same item in the bag, filling up the stacks in the lower
+    /// slots first. Returns the performed moves as source slot, target slot and amount.
+    pub fn merge_stacks(&mut self) -> Vec<(u8, u8, u16)> {
+        let occupied = self
+            .non_equipment_slots()
+            .filter(|slot| self.items.contains_key(slot))
+            .collect::<Vec<_>>();
+        let mut moves = Vec::new();
+        for (index, target) in occupied.iter().enumerate() {
+            for source in occupied[index + 1..].iter() {
+                let (Some(target_item), Some(source_item)) = (self.items.get(target), self.items.get(source)) else {
+                    continue;
+                };
+                if target_item.is_max_stacked() || !target_item.can_stack_with(source_item) {
+                    continue;
+                }
+
+                let amount = source_item.stack_size();
+                if let Ok(moved) = self.move_item(*source, *target, amount) {
+                    moves.push((*source, *target, moved));
+                }
+            }
+        }
+        moves
+    }
+
+    /// Reorders the items in the bag such that they are grouped by their type, with larger stacks
+    /// first, and without any gaps in between. The resulting order only depends on the items and not
+    /// on where they were before. Partial stacks get merged first, as the client would merge them
+    /// instead of swapping them. Returns the performed moves as source slot, target slot and the
+    /// amount of the item moved from the source, which the client can replay as regular moves.
+    pub fn sort(&mut self) -> Vec<(u8, u8, u16)> {
+        let mut moves = self.merge_stacks();
+        let slots = self.non_equipment_slots().collect::<Vec<_>>();
+        let mut order = slots
+            .iter()
+            .filter_map(|slot| self.items.get(slot).map(|item| (*slot, *item)))
+            .collect::<Vec<_>>();
+        order.sort_by_key(|(slot, item)| {
+            (
+                item.reference.common.type_id.packed(),
+                item.reference.ref_id(),
+                item.upgrade_level(),
+                Reverse(item.stack_size()),
+                item.rental.map(|rental| rental.end),
+                *slot,
+            )
+        });
+
+        // Tracks where the items, identified by their original slot, currently are and vice versa.
+        let mut location = order.iter().map(|(slot, _)| (*slot, *slot)).collect::<HashMap<_, _>>();
+        let mut occupant = location.clone();
+        for ((original, item), target) in order.iter().zip(slots) {
+            let current = location[original];
+            if current == target {
+                continue;
+            }
+
+            // After merging, the only stacks of the same item left are a full one and at most one
+            // partial one, with the full one sorted first. Moving the full stack onto the partial
+            // one fills it up, which leaves the partial amount behind, just like a swap would.
+            // Two full stacks are the same, so there is nothing to move at all.
+            match self.move_item(current, target, item.stack_size()) {
+                Ok(0) | Err(_) => {},
+                Ok(amount) => moves.push((current, target, amount)),
+            }
+            match occupant.remove(&target) {
+                Some(displaced) => {
+                    location.insert(displaced, current);
+                    occupant.insert(current, displaced);
+                },
+                None => {
+                    occupant.remove(&current);
+                },
+            }
+            location.insert(*original, target);
+            occupant.insert(target, *original);
+        }
+        moves
+    }
+
     pub fn is_equipment_slot(slot: u8) -> bool {
         slot <= 0xCu8
     }
@@ -416,6 +639,65 @@ impl Inventory {
         self.items.insert(slot, item);
     }
 
+    /// Places the item into the given, empty, non-equipment slot.
+    pub fn put_item_at(&mut self, slot: u8, item: Item) -> Result<(), MoveError> {
+        if Self::is_equipment_slot(slot) || usize::from(slot) >= self.size || self.items.contains_key(&slot) {
+            return Err(MoveError::Impossible);
+        }
+
+        self.items.insert(slot, item);
+        self.changes.push(InventoryChange::AddItem { slot, item });
+        Ok(())
+    }
+
+    /// Updates the type data of the item in the given slot, recording the change if there was any.
+    /// Returns the updated type data or `None` if there was no item in the slot.
+    pub fn update_type_data(&mut self, slot: u8, update: impl FnOnce(&mut ItemTypeData)) -> Option<ItemTypeData> {
+        let item = self.items.get_mut(&slot)?;
+        let old_data = item.type_data;
+        update(&mut item.type_data);
+        let new_data = item.type_data;
+        if old_data != new_data {
+            self.changes.push(InventoryChange::ChangeTypeData {
+                slot,
+                old_item: old_data,
+                new_item: new_data,
+            });
+        }
+        Some(new_data)
+    }
+
+    /// Removes the item in the given slot entirely, regardless of its stack size.
+    pub fn remove_item_at(&mut self, slot: u8) -> Option<Item> {
+        let item = self.items.remove(&slot)?;
+        self.changes.push(InventoryChange::RemoveItem { slot });
+        Some(item)
+    }
+
+    /// Consumes the given amount of the item in the given slot, removing the item entirely once
+    /// nothing remains of it. Returns the amount that is left in the slot.
+    pub fn consume_item(&mut self, slot: u8, amount: u16) -> Result<u16, MoveError> {
+        let item = self.items.get_mut(&slot).ok_or(MoveError::ItemDoesNotExist)?;
+        let old_data = item.type_data;
+        if item.stack_size() < amount {
+            return Err(MoveError::Impossible);
+        }
+
+        let remaining = item.stack_size() - amount;
+        if remaining == 0 {
+            self.items.remove(&slot);
+            self.changes.push(InventoryChange::RemoveItem { slot });
+        } else {
+            item.change_stack_size(-(amount as i16))?;
+            self.changes.push(InventoryChange::ChangeTypeData {
+                slot,
+                old_item: old_data,
+                new_item: item.type_data,
+            });
+        }
+        Ok(remaining)
+    }
+
     fn find_slots_matching(&self, item: Item) -> impl Iterator<Item = u8> + '_ {
         self.items
             .iter()
@@ -428,7 +710,10 @@ impl Inventory {
         if item.reference.max_stack_size > 1 {
             for i in self.find_slots_matching(item).collect::<Vec<_>>() {
                 let existing = self.items.get_mut(&i).expect("The matching slot should have an item");
-                if !existing.is_max_stacked() && existing.reference.ref_id() == item.reference.ref_id() {
+                if !existing.is_max_stacked()
+                    && existing.reference.ref_id() == item.reference.ref_id()
+                    && existing.rental == item.rental
+                {
                     return match (existing.type_data, item.type_data) {
                         (
                             ItemTypeData::Consumable {
@@ -529,7 +814,7 @@ impl ChangeTracked for Inventory {
 
 impl Default for Inventory {
     fn default() -> Self {
-        Inventory::new(45)
+        Inventory::new(DEFAULT_INVENTORY_SIZE)
     }
 }
 
@@ -565,8 +850,13 @@ mod test {
         max_stack_size: 50,
         range: None,
         required_level: None,
+        required_levels: Vec::new(),
+        required_strength: 0,
+        required_intelligence: 0,
         biological_type: RefBiologicalType::Both,
+        two_handed: false,
         params: [0, 0, 0, 0],
+        stats: Default::default(),
     });
 
     static SECOND_ITEM_DATA: Lazy<RefItemData> = Lazy::new(|| RefItemData {
@@ -584,8 +874,13 @@ mod test {
         max_stack_size: 50,
         range: None,
         required_level: None,
+        required_levels: Vec::new(),
+        required_strength: 0,
+        required_intelligence: 0,
         biological_type: RefBiologicalType::Both,
+        two_handed: false,
         params: [0, 0, 0, 0],
+        stats: Default::default(),
     });
 
     #[test]
@@ -596,6 +891,7 @@ mod test {
         let slot = inv
             .add_item(Item {
                 variance: None,
+                rental: None,
                 reference,
                 type_data: ItemTypeData::Consumable { amount: 5 },
             })
@@ -604,6 +900,7 @@ mod test {
         let other_slot = inv
             .add_item(Item {
                 variance: None,
+                rental: None,
                 reference,
                 type_data: ItemTypeData::Consumable { amount: 5 },
             })
@@ -631,6 +928,7 @@ mod test {
             .add_item(Item {
                 reference: first_item,
                 variance: None,
+                rental: None,
                 type_data: ItemTypeData::Consumable { amount: 5 },
             })
             .unwrap();
@@ -638,6 +936,7 @@ mod test {
             .add_item(Item {
                 reference: second_item,
                 variance: None,
+                rental: None,
                 type_data: ItemTypeData::Consumable { amount: 5 },
             })
             .unwrap();
@@ -656,6 +955,7 @@ mod test {
         let item_ref = FIRST_ITEM_DATA.deref();
         let item = Item {
             variance: None,
+            rental: None,
             reference: item_ref,
             type_data: ItemTypeData::Consumable { amount: 5 },
         };
@@ -667,4 +967,285 @@ mod test {
         assert_eq!(1, changes.len());
         assert!(matches!(changes.pop().unwrap(), InventoryChange::RemoveItem { slot }));
     }
+
+    #[test]
+    pub fn test_update_type_data() {
+        let mut inv = Inventory::default();
+
+        let item_ref = FIRST_ITEM_DATA.deref();
+        let slot = inv
+            .add_item(Item {
+                variance: None,
+                rental: None,
+                reference: item_ref,
+                type_data: ItemTypeData::Consumable { amount: 5 },
+            })
+            .unwrap();
+        let _ = inv.changes();
+
+        inv.update_type_data(slot, |_| {});
+        assert_eq!(0, inv.changes().len());
+
+        let updated = inv.update_type_data(slot, |data| *data = ItemTypeData::Consumable { amount: 3 });
+        assert!(matches!(updated, Some(ItemTypeData::Consumable { amount: 3 })));
+        let mut changes = inv.changes();
+        assert_eq!(1, changes.len());
+        assert!(matches!(changes.pop().unwrap(), InventoryChange::ChangeTypeData { .. }));
+        assert!(inv.update_type_data(slot + 1, |_| {}).is_none());
+    }
+
+    #[test]
+    pub fn test_repeated_type_data_updates_merge() {
+        let mut inv = Inventory::default();
+
+        let mut add = |reference| {
+            inv.add_item(Item {
+                variance: None,
+                rental: None,
+                reference,
+                type_data: ItemTypeData::Consumable { amount: 10 },
+            })
+            .unwrap()
+        };
+        let first = add(FIRST_ITEM_DATA.deref());
+        let second = add(SECOND_ITEM_DATA.deref());
+        let _ = inv.changes();
+
+        for _ in 0..3 {
+            for slot in [first, second] {
+                inv.update_type_data(slot, |data| {
+                    if let ItemTypeData::Consumable { amount } = data {
+                        *amount -= 1;
+                    }
+                });
+            }
+        }
+
+        let optimized = inv.changes().optimize();
+        assert_eq!(2, optimized.len());
+        assert!(optimized.iter().all(|change| matches!(
+            change,
+            InventoryChange::ChangeTypeData {
+                old_item: ItemTypeData::Consumable { amount: 10 },
+                new_item: ItemTypeData::Consumable { amount: 7 },
+                ..
+            }
+        )));
+    }
+
+    #[test]
+    pub fn test_consume_item() {
+        let mut inv = Inventory::default();
+
+        let item_ref = FIRST_ITEM_DATA.deref();
+        let slot = inv
+            .add_item(Item {
+                variance: None,
+                rental: None,
+                reference: item_ref,
+                type_data: ItemTypeData::Consumable { amount: 2 },
+            })
+            .unwrap();
+        let _ = inv.changes();
+
+        assert!(inv.consume_item(slot, 3).is_err());
+        assert_eq!(1, inv.consume_item(slot, 1).unwrap());
+        assert_eq!(0, inv.consume_item(slot, 1).unwrap());
+        assert!(inv.get_item_at(slot).is_none());
+        let changes = inv.changes();
+        assert_eq!(2, changes.len());
+        assert!(matches!(changes[1], InventoryChange::RemoveItem { .. }));
+        assert!(matches!(inv.consume_item(slot, 1), Err(MoveError::ItemDoesNotExist)));
+    }
+
+    #[test]
+    pub fn test_remove_item_at() {
+        let mut inv = Inventory::default();
+        let free_slots = inv.free_slots();
+
+        let item_ref = FIRST_ITEM_DATA.deref();
+        let slot = inv
+            .add_item(Item {
+                variance: None,
+                rental: None,
+                reference: item_ref,
+                type_data: ItemTypeData::Consumable { amount: 5 },
+            })
+            .unwrap();
+        assert_eq!(free_slots - 1, inv.free_slots());
+        let _ = inv.changes();
+
+        let removed = inv.remove_item_at(slot).unwrap();
+        assert_eq!(5, removed.stack_size());
+        assert_eq!(free_slots, inv.free_slots());
+        assert!(inv.remove_item_at(slot).is_none());
+        let mut changes = inv.changes();
+        assert_eq!(1, changes.len());
+        assert!(matches!(changes.pop().unwrap(), InventoryChange::RemoveItem { .. }));
+    }
+
+    #[test]
+    pub fn test_rented_items_do_not_stack() {
+        let mut inv = Inventory::default();
+
+        let item_ref = FIRST_ITEM_DATA.deref();
+        let owned_slot = inv
+            .add_item(Item {
+                variance: None,
+                rental: None,
+                reference: item_ref,
+                type_data: ItemTypeData::Consumable { amount: 5 },
+            })
+            .unwrap();
+        let rented_slot = inv
+            .add_item(Item {
+                variance: None,
+                rental: Some(Rental::new(RentalKind::Period, 100, 200)),
+                reference: item_ref,
+                type_data: ItemTypeData::Consumable { amount: 5 },
+            })
+            .unwrap();
+        assert_ne!(owned_slot, rented_slot);
+
+        let rented = inv.get_item_at(rented_slot).unwrap();
+        assert!(!rented.is_expired(199));
+        assert!(rented.is_expired(200));
+        assert!(!inv.get_item_at(owned_slot).unwrap().is_expired(200));
+    }
+
+    fn consumable(reference: &'static RefItemData, amount: u16) -> Item {
+        Item {
+            variance: None,
+            rental: None,
+            reference,
+            type_data: ItemTypeData::Consumable { amount },
+        }
+    }
+
+    #[test]
+    pub fn test_split_stack() {
+        let mut inv = Inventory::default();
+        inv.set_item(13, consumable(FIRST_ITEM_DATA.deref(), 10));
+
+        assert_eq!(4, inv.move_item(13, 14, 4).unwrap());
+        assert_eq!(6, inv.get_item_at(13).unwrap().stack_size());
+        assert_eq!(4, inv.get_item_at(14).unwrap().stack_size());
+
+        let changes = inv.changes();
+        assert_eq!(2, changes.len());
+        assert!(matches!(changes[0], InventoryChange::ChangeTypeData { slot: 13, .. }));
+        assert!(matches!(changes[1], InventoryChange::AddItem { slot: 14, .. }));
+    }
+
+    #[test]
+    pub fn test_merge_stacks() {
+        let mut inv = Inventory::default();
+        let item_ref = FIRST_ITEM_DATA.deref();
+        inv.set_item(13, consumable(item_ref, 20));
+        inv.set_item(15, consumable(item_ref, 20));
+        inv.set_item(17, consumable(item_ref, 20));
+        inv.set_item(18, consumable(SECOND_ITEM_DATA.deref(), 20));
+
+        let moves = inv.merge_stacks();
+        assert_eq!(vec![(15, 13, 20), (17, 13, 10)], moves);
+        assert_eq!(50, inv.get_item_at(13).unwrap().stack_size());
+        assert!(inv.get_item_at(15).is_none());
+        assert_eq!(10, inv.get_item_at(17).unwrap().stack_size());
+        assert_eq!(20, inv.get_item_at(18).unwrap().stack_size());
+    }
+
+    #[test]
+    pub fn test_sort() {
+        let mut inv = Inventory::default();
+        let first_ref = FIRST_ITEM_DATA.deref();
+        let second_ref = SECOND_ITEM_DATA.deref();
+        inv.set_item(20, consumable(second_ref, 5));
+        inv.set_item(14, consumable(first_ref, 5));
+        inv.set_item(16, consumable(first_ref, 10));
+
+        let moves = inv.sort();
+        assert_eq!(vec![(16, 14, 10), (14, 13, 15), (20, 14, 5)], moves);
+        assert_eq!(15, inv.get_item_at(13).unwrap().stack_size());
+        assert_eq!(2, inv.get_item_at(14).unwrap().reference.ref_id());
+        assert!(inv.get_item_at(15).is_none());
+        assert!(inv.get_item_at(16).is_none());
+        assert!(inv.get_item_at(20).is_none());
+        assert!(inv.sort().is_empty());
+    }
+
+    #[test]
+    pub fn test_sort_full_and_partial_stack() {
+        let mut inv = Inventory::default();
+        inv.set_item(20, consumable(FIRST_ITEM_DATA.deref(), 5));
+        inv.set_item(13, consumable(SECOND_ITEM_DATA.deref(), 50));
+        inv.set_item(14, consumable(SECOND_ITEM_DATA.deref(), 10));
+
+        // Moving the full stack onto the partial one is a merge for the client, which has to end up
+        // the same way as on the server.
+        let moves = inv.sort();
+        assert_eq!(vec![(20, 13, 5), (20, 14, 40), (20, 15, 10)], moves);
+        assert_eq!(1, inv.get_item_at(13).unwrap().reference.ref_id());
+        assert_eq!(50, inv.get_item_at(14).unwrap().stack_size());
+        assert_eq!(10, inv.get_item_at(15).unwrap().stack_size());
+        assert!(inv.get_item_at(20).is_none());
+    }
+
+    #[test]
+    pub fn test_move_invalid_amount() {
+        let mut inv = Inventory::default();
+        inv.set_item(13, consumable(FIRST_ITEM_DATA.deref(), 10));
+
+        assert!(matches!(inv.move_item(13, 14, 0), Err(MoveError::Impossible)));
+        assert!(matches!(inv.move_item(13, 14, 11), Err(MoveError::Impossible)));
+        assert!(matches!(inv.move_item(13, 14, 40000), Err(MoveError::Impossible)));
+        assert_eq!(10, inv.get_item_at(13).unwrap().stack_size());
+        assert!(inv.get_item_at(14).is_none());
+        assert!(inv.changes().is_empty());
+    }
+
+    #[test]
+    pub fn test_move_equipment_ignores_amount() {
+        let mut inv = Inventory::default();
+        let equipment = Item {
+            variance: None,
+            rental: None,
+            reference: FIRST_ITEM_DATA.deref(),
+            type_data: ItemTypeData::Equipment {
+                upgrade_level: 0,
+                magic: Default::default(),
+                durability: Durability::full(10),
+            },
+        };
+        inv.set_item(13, equipment);
+
+        assert!(matches!(inv.move_item(13, 14, 0), Err(MoveError::Impossible)));
+        assert_eq!(1, inv.move_item(13, 14, 5).unwrap());
+        assert!(inv.get_item_at(13).is_none());
+        assert!(matches!(
+            inv.get_item_at(14).unwrap().type_data,
+            ItemTypeData::Equipment { .. }
+        ));
+    }
+
+    #[test]
+    pub fn test_expand() {
+        let mut inv = Inventory::default();
+        let free_slots = inv.free_slots();
+
+        assert_eq!(32, inv.expand(32));
+        assert_eq!(DEFAULT_INVENTORY_SIZE + 32, inv.size());
+        assert_eq!(free_slots + 32, inv.free_slots());
+        assert_eq!(MAX_INVENTORY_SIZE - DEFAULT_INVENTORY_SIZE - 32, inv.expand(100));
+        assert_eq!(0, inv.expand(1));
+        assert_eq!(MAX_INVENTORY_SIZE, inv.size());
+
+        let mut optimized = inv.changes().optimize();
+        assert_eq!(1, optimized.len());
+        assert!(matches!(
+            optimized.pop().unwrap(),
+            InventoryChange::Resize {
+                size: MAX_INVENTORY_SIZE
+            }
+        ));
+    }
 }
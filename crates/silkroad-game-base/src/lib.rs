@@ -1,15 +1,21 @@
+mod avatar;
 mod changes;
 mod character;
+mod equipment;
 mod inventory;
+mod magic;
 mod movement;
 mod pos;
 mod skill;
 mod stats;
 mod vec;
 
+pub use avatar::*;
 pub use changes::*;
 pub use character::*;
+pub use equipment::*;
 pub use inventory::*;
+pub use magic::*;
 pub use movement::*;
 pub use pos::*;
 pub use skill::*;
@@ -0,0 +1,100 @@
+/// The maximum amount of magic options a single item can carry.
+pub const MAX_MAGIC_OPTIONS: usize = 12;
+
+/// A single magic option (blue stat) of an item, consisting of the reference id of the magic option
+/// and the value it has been rolled with.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct MagicParam {
+    pub id: u32,
+    pub value: u32,
+}
+
+impl MagicParam {
+    pub fn new(id: u32, value: u32) -> Self {
+        MagicParam { id, value }
+    }
+
+    /// Unpacks a param from its stored representation, where the lower 32 bits contain the option
+    /// id and the upper 32 bits contain the value.
+    pub fn from_packed(packed: u64) -> Self {
+        MagicParam {
+            id: (packed & 0xFFFFFFFF) as u32,
+            value: (packed >> 32) as u32,
+        }
+    }
+
+    pub fn packed(&self) -> u64 {
+        (u64::from(self.value) << 32) | u64::from(self.id)
+    }
+}
+
+/// The collection of magic options of an item. This is a fixed size collection such that [crate::Item]
+/// can stay `Copy`.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct MagicOptions {
+    params: [MagicParam; MAX_MAGIC_OPTIONS],
+    len: u8,
+}
+
+impl MagicOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the given param, returning `false` if there is no more space for another option.
+    pub fn push(&mut self, param: MagicParam) -> bool {
+        if self.len() >= MAX_MAGIC_OPTIONS {
+            return false;
+        }
+
+        self.params[self.len()] = param;
+        self.len += 1;
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &MagicParam> {
+        self.params[..self.len()].iter()
+    }
+
+    pub fn get(&self, id: u32) -> Option<u32> {
+        self.iter().find(|param| param.id == id).map(|param| param.value)
+    }
+}
+
+impl FromIterator<MagicParam> for MagicOptions {
+    fn from_iter<T: IntoIterator<Item = MagicParam>>(iter: T) -> Self {
+        let mut options = MagicOptions::new();
+        for param in iter.into_iter().take(MAX_MAGIC_OPTIONS) {
+            options.push(param);
+        }
+        options
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_packing() {
+        let param = MagicParam::new(0x2A, 7);
+        assert_eq!(0x7_0000_002A, param.packed());
+        assert_eq!(param, MagicParam::from_packed(param.packed()));
+    }
+
+    #[test]
+    fn test_capacity() {
+        let options: MagicOptions = (0..20).map(|i| MagicParam::new(i, i)).collect();
+        assert_eq!(MAX_MAGIC_OPTIONS, options.len());
+        assert_eq!(Some(3), options.get(3));
+        assert_eq!(None, options.get(15));
+    }
+}
@@ -2,6 +2,7 @@ use cgmath::num_traits::Pow;
 use cgmath::{Deg, InnerSpace, MetricSpace, Vector2, Vector3};
 use silkroad_data::npc_pos::NpcPosition;
 use silkroad_definitions::Region;
+use silkroad_navmesh::region::{region_of, region_origin};
 use std::fmt::{Display, Formatter};
 use std::ops::{Add, Deref};
 
@@ -41,9 +42,7 @@ impl From<LocalLocation> for GlobalLocation {
 
 impl LocalLocation {
     pub fn to_global(&self) -> GlobalLocation {
-        let global_x = self.1.x + (self.0.x() as f32 * 1920.);
-        let global_z = self.1.y + (self.0.y() as f32 * 1920.);
-        GlobalLocation(Vector2::new(global_x, global_z))
+        GlobalLocation(region_origin(self.0) + self.1)
     }
 
     pub fn with_y(&self, y: f32) -> LocalPosition {
@@ -54,9 +53,7 @@ impl LocalLocation {
 impl GlobalLocation {
     pub fn to_local(&self) -> LocalLocation {
         let region = self.region();
-        let local_x = self.0.x % 1920.;
-        let local_z = self.0.y % 1920.;
-        LocalLocation(region, Vector2::new(local_x, local_z))
+        LocalLocation(region, self.0 - region_origin(region))
     }
 
     pub fn from_ingame_location(x: f32, z: f32) -> GlobalLocation {
@@ -84,9 +81,7 @@ impl GlobalLocation {
     }
 
     pub fn region(&self) -> Region {
-        let region_x = (self.0.x / 1920.) as u8;
-        let region_y = (self.0.y / 1920.) as u8;
-        Region::from_xy(region_x, region_y)
+        region_of(self.0)
     }
 }
 
@@ -107,9 +102,8 @@ impl Display for LocalPosition {
 
 impl LocalPosition {
     pub fn to_global(&self) -> GlobalPosition {
-        let global_x = self.1.x + (self.0.x() as f32 * 1920.);
-        let global_z = self.1.z + (self.0.y() as f32 * 1920.);
-        GlobalPosition(Vector3::new(global_x, self.1.y, global_z))
+        let origin = region_origin(self.0);
+        GlobalPosition(Vector3::new(self.1.x + origin.x, self.1.y, self.1.z + origin.y))
     }
 }
 
@@ -140,18 +134,12 @@ impl Display for GlobalPosition {
 
 impl GlobalPosition {
     pub fn to_local(&self) -> LocalPosition {
-        let region_x = (self.0.x / 1920.) as u8;
-        let region_y = (self.0.z / 1920.) as u8;
-        let region = Region::from_xy(region_x, region_y);
-        let local_x = self.0.x % 1920.;
-        let local_z = self.0.z % 1920.;
-        LocalPosition(region, Vector3::new(local_x, self.0.y, local_z))
+        let local = self.to_location().to_local();
+        LocalPosition(local.0, Vector3::new(local.1.x, self.0.y, local.1.y))
     }
 
     pub fn region(&self) -> Region {
-        let region_x = (self.0.x / 1920.) as u8;
-        let region_y = (self.0.z / 1920.) as u8;
-        Region::from_xy(region_x, region_y)
+        self.to_location().region()
     }
 
     pub fn to_location(&self) -> GlobalLocation {
@@ -247,6 +235,17 @@ mod test {
         assert_eq!(local.1.y, 1840.0);
     }
 
+    #[test]
+    pub fn test_convert_dungeon() {
+        let local = LocalPosition(Region::dungeon(1), Vector3::new(-2500.0, 40.0, 3100.0));
+        let global = local.to_global();
+        assert_eq!(global.region(), Region::dungeon(1));
+
+        let converted = global.to_local();
+        assert_eq!(converted.0, Region::dungeon(1));
+        assert_eq!(converted.1, Vector3::new(-2500.0, 40.0, 3100.0));
+    }
+
     #[test]
     pub fn test_point_with_range() {
         let origin = GlobalLocation(Vector2::zero());
@@ -1,3 +1,5 @@
+use silkroad_data::magicoption::MagicOptionKind;
+
 const SCALING: f32 = 1.02;
 
 pub enum StatType {
@@ -38,6 +40,27 @@ impl Stats {
         result as u32
     }
 
+    /// The physical attack a character has from their strength alone, before any equipment. The game
+    /// data doesn't say how much a point is worth, so the caller has to provide it.
+    pub fn phys_attack(&self, per_point: f32) -> f32 {
+        f32::from(self.str) * per_point
+    }
+
+    /// The magical attack a character has from their intelligence alone, before any equipment.
+    pub fn mag_attack(&self, per_point: f32) -> f32 {
+        f32::from(self.int) * per_point
+    }
+
+    /// The physical defense a character has from their strength alone, before any equipment.
+    pub fn phys_defense(&self, per_point: f32) -> f32 {
+        f32::from(self.str) * per_point
+    }
+
+    /// The magical defense a character has from their intelligence alone, before any equipment.
+    pub fn mag_defense(&self, per_point: f32) -> f32 {
+        f32::from(self.int) * per_point
+    }
+
     pub fn increase_strength(&mut self, amount: u16) {
         self.str += amount
     }
@@ -45,6 +68,51 @@ impl Stats {
     pub fn increase_intelligence(&mut self, amount: u16) {
         self.int += amount
     }
+
+    pub fn with_bonus(&self, bonus: &StatBonus) -> Stats {
+        Stats::new(
+            self.str.saturating_add(bonus.strength),
+            self.int.saturating_add(bonus.intelligence),
+        )
+    }
+
+    pub fn max_health_with(&self, level: u8, bonus: &StatBonus) -> u32 {
+        self.with_bonus(bonus).max_health(level).saturating_add(bonus.health)
+    }
+
+    pub fn max_mana_with(&self, level: u8, bonus: &StatBonus) -> u32 {
+        self.with_bonus(bonus).max_mana(level).saturating_add(bonus.mana)
+    }
+}
+
+/// Additional stats a character receives on top of their own [Stats], for example through the magic
+/// options of their equipment.
+#[derive(Copy, Clone, Eq, PartialEq, Default, Debug)]
+pub struct StatBonus {
+    pub strength: u16,
+    pub intelligence: u16,
+    pub health: u32,
+    pub mana: u32,
+    pub hit_rate_percent: u16,
+    pub parry_rate_percent: u16,
+    pub block_rate_percent: u16,
+}
+
+impl StatBonus {
+    pub fn add_magic_option(&mut self, kind: MagicOptionKind, value: u32) {
+        let small_value = u16::try_from(value).unwrap_or(u16::MAX);
+        match kind {
+            MagicOptionKind::Strength => self.strength = self.strength.saturating_add(small_value),
+            MagicOptionKind::Intelligence => self.intelligence = self.intelligence.saturating_add(small_value),
+            MagicOptionKind::Health => self.health = self.health.saturating_add(value),
+            MagicOptionKind::Mana => self.mana = self.mana.saturating_add(value),
+            MagicOptionKind::HitRate => self.hit_rate_percent = self.hit_rate_percent.saturating_add(small_value),
+            MagicOptionKind::ParryRate => self.parry_rate_percent = self.parry_rate_percent.saturating_add(small_value),
+            MagicOptionKind::BlockRate => self.block_rate_percent = self.block_rate_percent.saturating_add(small_value),
+            // Durability only affects the item itself and not the character.
+            MagicOptionKind::Durability | MagicOptionKind::Other => {},
+        }
+    }
 }
 
 impl Default for Stats {
@@ -63,4 +131,26 @@ mod test {
         assert_eq!(200, default.max_health(1));
         assert_eq!(200, default.max_mana(1));
     }
+
+    #[test]
+    fn test_base_attack_defense() {
+        let stats = Stats::new(40, 20);
+        assert_eq!(20.0, stats.phys_attack(0.5));
+        assert_eq!(10.0, stats.mag_attack(0.5));
+        assert_eq!(16.0, stats.phys_defense(0.4));
+        assert_eq!(8.0, stats.mag_defense(0.4));
+    }
+
+    #[test]
+    fn test_bonus() {
+        let mut bonus = StatBonus::default();
+        bonus.add_magic_option(MagicOptionKind::Strength, 5);
+        bonus.add_magic_option(MagicOptionKind::Health, 100);
+        bonus.add_magic_option(MagicOptionKind::Durability, 100);
+
+        let default = Stats::default();
+        assert_eq!(25, default.with_bonus(&bonus).strength());
+        assert_eq!(350, default.max_health_with(1, &bonus));
+        assert_eq!(200, default.max_mana_with(1, &bonus));
+    }
 }
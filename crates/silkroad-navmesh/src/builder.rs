@@ -1,7 +1,9 @@
+use crate::dungeon::DungeonLoader;
 use crate::map_info_ext::MapInfoExt;
 use crate::navmesh::NavmeshContainer;
 use crate::object::ObjectLoader;
 use crate::{get_path_for_region, FileLoader, GlobalNavmesh, MAP_INFO_FILE};
+use log::debug;
 use silkroad_definitions::Region;
 use sr_formats::jmxvmfo::JmxMapInfo;
 use sr_formats::jmxvnvm::JmxNvm;
@@ -15,9 +17,10 @@ pub struct NavmeshBuilder;
 impl NavmeshBuilder {
     pub fn build_from(loader: &dyn FileLoader) -> io::Result<GlobalNavmesh> {
         let objects = ObjectLoader::load_objects(loader)?;
+        let object_meshes = ObjectLoader::load_collision_meshes(&objects, loader);
         let (_, region_info) = JmxMapInfo::parse(&loader.load_file(MAP_INFO_FILE)?)
             .map_err(|_| io::Error::new(ErrorKind::InvalidData, "Could not parse map info file."))?;
-        let regions = region_info
+        let mut containers = region_info
             .enabled_regions()
             .filter_map(|region| {
                 let new_mesh = match Self::load_mesh_for_region(loader, region) {
@@ -25,15 +28,33 @@ impl NavmeshBuilder {
                     Err(_) => return None,
                 };
 
-                let container = NavmeshContainer::new(region, new_mesh);
-                let new_mesh = Arc::new(container);
-                Some((region, new_mesh))
+                Some((region, NavmeshContainer::new(region, new_mesh, &object_meshes)))
             })
             .collect::<HashMap<_, _>>();
-        Ok(GlobalNavmesh {
-            loaded_meshes: regions,
-            loaded_objects: objects,
-        })
+
+        // The nvm of a region only lists the objects placed in it, even if they reach into a
+        // neighbouring region, where they need to be walked on and collided with as well.
+        let reaching_out = containers
+            .values()
+            .flat_map(NavmeshContainer::objects_reaching_out)
+            .collect::<Vec<_>>();
+        for (region, object) in reaching_out {
+            if let Some(container) = containers.get_mut(&region) {
+                container.add_object(object);
+            }
+        }
+
+        let mut regions = containers
+            .into_iter()
+            .map(|(region, container)| (region, Arc::new(container)))
+            .collect::<HashMap<_, _>>();
+
+        match DungeonLoader::load_dungeons(loader) {
+            Ok(dungeons) => regions.extend(dungeons.into_iter().map(|(region, mesh)| (region, Arc::new(mesh)))),
+            Err(e) => debug!("Could not load dungeons: {}", e),
+        }
+
+        Ok(GlobalNavmesh { loaded_meshes: regions })
     }
 
     fn load_mesh_for_region(loader: &dyn FileLoader, region: Region) -> io::Result<JmxNvm> {
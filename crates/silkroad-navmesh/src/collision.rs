@@ -0,0 +1,168 @@
+use cgmath::Vector2;
+
+const EDGE_BLOCKED_DST_TO_SRC: u8 = 0x01;
+const EDGE_BLOCKED_SRC_TO_DST: u8 = 0x02;
+
+/// An axis aligned rectangle of the navmesh that can be walked on.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Cell {
+    min: Vector2<f32>,
+    max: Vector2<f32>,
+}
+
+impl Cell {
+    pub fn new(min: Vector2<f32>, max: Vector2<f32>) -> Self {
+        Self { min, max }
+    }
+
+    pub fn contains(&self, point: Vector2<f32>) -> bool {
+        point.x >= self.min.x && point.x <= self.max.x && point.y >= self.min.y && point.y <= self.max.y
+    }
+
+    /// Grows the rectangle such that it also contains the given point.
+    pub fn extend(self, point: Vector2<f32>) -> Self {
+        Self {
+            min: Vector2::new(self.min.x.min(point.x), self.min.y.min(point.y)),
+            max: Vector2::new(self.max.x.max(point.x), self.max.y.max(point.y)),
+        }
+    }
+
+    pub fn min(&self) -> Vector2<f32> {
+        self.min
+    }
+
+    pub fn max(&self) -> Vector2<f32> {
+        self.max
+    }
+
+    /// Moves the rectangle by the given offset.
+    pub fn moved(self, offset: Vector2<f32>) -> Self {
+        Self {
+            min: self.min + offset,
+            max: self.max + offset,
+        }
+    }
+}
+
+/// The border between two cells, or between a cell and a neighbouring region. An edge may block
+/// movement across it, for example along walls or cliffs.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Edge {
+    start: Vector2<f32>,
+    end: Vector2<f32>,
+    flag: u8,
+}
+
+impl Edge {
+    pub fn new(start: Vector2<f32>, end: Vector2<f32>, flag: u8) -> Self {
+        Self { start, end, flag }
+    }
+
+    /// Checks if the edge blocks movement. Edges that only block one direction are treated as
+    /// blocking in both directions.
+    pub fn is_blocking(&self) -> bool {
+        self.flag & (EDGE_BLOCKED_DST_TO_SRC | EDGE_BLOCKED_SRC_TO_DST) != 0
+    }
+
+    /// Moves the edge by the given offset.
+    pub fn moved(self, offset: Vector2<f32>) -> Self {
+        Self {
+            start: self.start + offset,
+            end: self.end + offset,
+            flag: self.flag,
+        }
+    }
+
+    pub fn midpoint(&self) -> Vector2<f32> {
+        (self.start + self.end) / 2.0
+    }
+
+    /// Calculates where the segment from `from` to `to` crosses this edge, as a fraction of the
+    /// way along the segment. If the segment doesn't cross the edge, [None] is returned.
+    pub fn intersection(&self, from: Vector2<f32>, to: Vector2<f32>) -> Option<f32> {
+        segment_intersection(from, to, self.start, self.end)
+    }
+}
+
+/// Finds the fraction along the segment from `a_start` to `a_end` at which it intersects with the
+/// segment from `b_start` to `b_end`. Parallel segments are never considered to intersect.
+fn segment_intersection(
+    a_start: Vector2<f32>,
+    a_end: Vector2<f32>,
+    b_start: Vector2<f32>,
+    b_end: Vector2<f32>,
+) -> Option<f32> {
+    let a = a_end - a_start;
+    let b = b_end - b_start;
+    let denominator = cross(a, b);
+    if denominator.abs() < f32::EPSILON {
+        return None;
+    }
+
+    let offset = b_start - a_start;
+    let along_a = cross(offset, b) / denominator;
+    let along_b = cross(offset, a) / denominator;
+    if (0.0..=1.0).contains(&along_a) && (0.0..=1.0).contains(&along_b) {
+        Some(along_a)
+    } else {
+        None
+    }
+}
+
+fn cross(a: Vector2<f32>, b: Vector2<f32>) -> f32 {
+    a.x * b.y - a.y * b.x
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_crossing_segments() {
+        let edge = Edge::new(Vector2::new(5.0, 0.0), Vector2::new(5.0, 10.0), EDGE_BLOCKED_SRC_TO_DST);
+        assert_eq!(
+            Some(0.5),
+            edge.intersection(Vector2::new(0.0, 5.0), Vector2::new(10.0, 5.0))
+        );
+        assert_eq!(
+            Some(0.25),
+            edge.intersection(Vector2::new(0.0, 5.0), Vector2::new(20.0, 5.0))
+        );
+    }
+
+    #[test]
+    pub fn test_segment_ending_before_edge() {
+        let edge = Edge::new(Vector2::new(5.0, 0.0), Vector2::new(5.0, 10.0), EDGE_BLOCKED_SRC_TO_DST);
+        assert_eq!(None, edge.intersection(Vector2::new(0.0, 5.0), Vector2::new(4.0, 5.0)));
+        // Passes by the end of the edge
+        assert_eq!(
+            None,
+            edge.intersection(Vector2::new(0.0, 15.0), Vector2::new(10.0, 15.0))
+        );
+    }
+
+    #[test]
+    pub fn test_parallel_segments() {
+        let edge = Edge::new(Vector2::new(5.0, 0.0), Vector2::new(5.0, 10.0), EDGE_BLOCKED_SRC_TO_DST);
+        assert_eq!(None, edge.intersection(Vector2::new(4.0, 0.0), Vector2::new(4.0, 10.0)));
+    }
+
+    #[test]
+    pub fn test_blocking_flags() {
+        let start = Vector2::new(0.0, 0.0);
+        let end = Vector2::new(1.0, 0.0);
+        assert!(!Edge::new(start, end, 0x00).is_blocking());
+        assert!(!Edge::new(start, end, 0x04).is_blocking());
+        assert!(Edge::new(start, end, EDGE_BLOCKED_DST_TO_SRC).is_blocking());
+        assert!(Edge::new(start, end, EDGE_BLOCKED_SRC_TO_DST | 0x04).is_blocking());
+    }
+
+    #[test]
+    pub fn test_cell_contains() {
+        let cell = Cell::new(Vector2::new(0.0, 0.0), Vector2::new(20.0, 10.0));
+        assert!(cell.contains(Vector2::new(0.0, 0.0)));
+        assert!(cell.contains(Vector2::new(15.0, 5.0)));
+        assert!(!cell.contains(Vector2::new(15.0, 11.0)));
+        assert!(!cell.contains(Vector2::new(-1.0, 5.0)));
+    }
+}
@@ -0,0 +1,110 @@
+use crate::navmesh::NavmeshContainer;
+use crate::object::{Object, ObjectFile};
+use crate::object_mesh::PlacedObject;
+use crate::FileLoader;
+use cgmath::{Rad, Vector3};
+use encoding_rs::WINDOWS_1252;
+use log::debug;
+use silkroad_definitions::Region;
+use sr_formats::jmxvdof::JmxDungeon;
+use std::collections::HashMap;
+use std::io;
+use std::io::ErrorKind;
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+const DUNGEON_INFO_FILE: &str = "dungeon/dungeoninfo.txt";
+
+/// A dungeon listed in the dungeon info file, which maps the region of the dungeon to the file
+/// describing the blocks it's made of.
+#[derive(Clone, Debug)]
+pub struct DungeonInfoEntry {
+    region: Region,
+    file: String,
+}
+
+impl DungeonInfoEntry {
+    pub fn region(&self) -> Region {
+        self.region
+    }
+
+    pub fn file_name(&self) -> &str {
+        &self.file
+    }
+}
+
+impl FromStr for DungeonInfoEntry {
+    type Err = ParseIntError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let line = line.trim();
+        let (id, file) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        // Dungeon regions are listed as signed numbers, as their highest bit is always set.
+        let region = Region::from(i16::from_str(id)? as u16);
+        let file = file.trim().trim_matches('"').replace('\\', "/");
+        Ok(DungeonInfoEntry { region, file })
+    }
+}
+
+pub struct DungeonLoader;
+
+impl DungeonLoader {
+    pub fn load_dungeons(loader: &dyn FileLoader) -> io::Result<HashMap<Region, NavmeshContainer>> {
+        let data = loader.load_file(DUNGEON_INFO_FILE)?;
+        let (content, _enc, _bool) = WINDOWS_1252.decode(&data);
+        let entries = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with("//"))
+            .map(DungeonInfoEntry::from_str)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+
+        let dungeons = entries
+            .iter()
+            .filter_map(|entry| match Self::load_dungeon(loader, entry) {
+                Ok(mesh) => Some((entry.region, mesh)),
+                Err(e) => {
+                    debug!("Could not load dungeon {}: {}", entry.file, e);
+                    None
+                },
+            })
+            .collect();
+        Ok(dungeons)
+    }
+
+    fn load_dungeon(loader: &dyn FileLoader, entry: &DungeonInfoEntry) -> io::Result<NavmeshContainer> {
+        let data = loader.load_file(&entry.file)?;
+        let (_, dungeon) = JmxDungeon::parse(&data)
+            .map_err(|_| io::Error::new(ErrorKind::InvalidData, "Could not parse dungeon file."))?;
+        let objects = dungeon
+            .blocks
+            .iter()
+            .filter_map(|block| {
+                let path = block.path.to_str().filter(|path| !path.is_empty())?.replace('\\', "/");
+                let object = Object::from(&ObjectFile::from(&path), loader).ok()?;
+                let mesh = object.collision_mesh(loader)?;
+                let position = Vector3::new(block.position.x, block.position.y, block.position.z);
+                Some(PlacedObject::new(&mesh, position, Rad(block.yaw)))
+            })
+            .collect();
+        Ok(NavmeshContainer::dungeon(entry.region, objects))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_parse_entry() {
+        let entry = DungeonInfoEntry::from_str("-32767 \"dungeon\\donwhang_stone_cave.dof\"").unwrap();
+        assert_eq!(Region::dungeon(1), entry.region());
+        assert_eq!("dungeon/donwhang_stone_cave.dof", entry.file_name());
+    }
+
+    #[test]
+    pub fn test_invalid_entry() {
+        assert!(DungeonInfoEntry::from_str("cave \"dungeon\\cave.dof\"").is_err());
+    }
+}
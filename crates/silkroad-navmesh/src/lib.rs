@@ -1,17 +1,23 @@
 use crate::navmesh::NavmeshContainer;
-use crate::object::Object;
+use crate::region::{region_of, region_origin};
+use cgmath::Vector2;
 use silkroad_definitions::Region;
+use std::cmp::{max, min};
 use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
 use std::{fs, io};
 
 pub mod builder;
+pub mod collision;
+pub mod dungeon;
 pub mod heightmap;
 pub mod map_info_ext;
 pub mod navmesh;
 pub mod object;
 pub mod object_info;
+pub mod object_mesh;
+pub mod pathfinding;
 pub mod region;
 
 pub trait FileLoader {
@@ -39,8 +45,6 @@ const MAP_INFO_FILE: &str = "navmesh/mapinfo.mfo";
 
 pub struct GlobalNavmesh {
     loaded_meshes: HashMap<Region, Arc<NavmeshContainer>>,
-    #[allow(unused)] // We will eventually use objects for navigation
-    loaded_objects: HashMap<u32, Arc<Object>>,
 }
 
 impl GlobalNavmesh {
@@ -51,4 +55,47 @@ impl GlobalNavmesh {
     pub fn mesh_ref_for(&self, region: Region) -> Option<&NavmeshContainer> {
         self.loaded_meshes.get(&region).map(|arc| arc.as_ref())
     }
+
+    /// Finds the first blocking edge that is crossed when moving from `from` to `to`, both given
+    /// in world coordinates, and returns how far along the way the edge is crossed as a fraction
+    /// between `0.0` and `1.0`. If nothing blocks the way, [None] is returned.
+    pub fn first_blocker(&self, from: Vector2<f32>, to: Vector2<f32>) -> Option<f32> {
+        regions_between(from, to)
+            .into_iter()
+            .filter_map(|region| {
+                let mesh = self.mesh_ref_for(region)?;
+                let origin = region_origin(region);
+                mesh.first_blocker(from - origin, to - origin)
+            })
+            .min_by(f32::total_cmp)
+    }
+
+    /// Like [Self::first_blocker], but for an entity moving at the given height, which may be
+    /// standing on an object instead of the terrain.
+    pub fn first_blocker_at(&self, from: Vector2<f32>, to: Vector2<f32>, height: f32) -> Option<f32> {
+        regions_between(from, to)
+            .into_iter()
+            .filter_map(|region| {
+                let mesh = self.mesh_ref_for(region)?;
+                let origin = region_origin(region);
+                mesh.first_blocker_at(from - origin, to - origin, height)
+            })
+            .min_by(f32::total_cmp)
+    }
+}
+
+/// All regions inside the rectangle spanned by the regions of the two locations. Dungeons aren't part
+/// of the grid, so only the regions of the locations themselves are considered for them.
+fn regions_between(from: Vector2<f32>, to: Vector2<f32>) -> Vec<Region> {
+    let start = region_of(from);
+    let end = region_of(to);
+    if start.is_dungeon() || end.is_dungeon() {
+        return if start == end { vec![start] } else { vec![start, end] };
+    }
+
+    let (min_x, max_x) = (min(start.x(), end.x()), max(start.x(), end.x()));
+    let (min_y, max_y) = (min(start.y(), end.y()), max(start.y(), end.y()));
+    (min_x..=max_x)
+        .flat_map(|x| (min_y..=max_y).map(move |y| Region::from_xy(x, y)))
+        .collect()
 }
@@ -1,14 +1,33 @@
+use crate::collision::{Cell, Edge};
 use crate::heightmap::Heightmap;
+use crate::object_mesh::{select_surface, ObjectMesh, PlacedObject, Triangle, MAX_STEP_HEIGHT};
+use crate::pathfinding::CellLink;
+use crate::region::region_origin;
 use crate::Region;
+use cgmath::{Rad, Vector2, Vector3};
 use sr_formats::jmxvnvm::JmxNvm;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
 
 const MESH_SIZE: usize = 96;
 const MESH_TILE_SIZE: usize = 20;
+/// Marks an edge side that doesn't belong to any cell.
+const NO_CELL: u16 = 0xFFFF;
+/// Corners of object triangles closer than `1 / VERTEX_PRECISION` are considered the same, such
+/// that triangles of neighbouring objects can be linked.
+const VERTEX_PRECISION: f32 = 10.0;
 
 pub struct NavmeshContainer {
     region: Region,
-    mesh: JmxNvm,
+    /// The terrain of the region, which dungeons don't have.
+    mesh: Option<JmxNvm>,
+    cells: Vec<Cell>,
+    /// The triangles of the objects a dungeon is made of, which take the place of the cells there.
+    floors: Vec<Triangle>,
+    blocking_edges: Vec<Edge>,
+    links: Vec<Vec<CellLink>>,
+    objects: Vec<PlacedObject>,
 }
 
 impl Debug for NavmeshContainer {
@@ -18,11 +37,294 @@ impl Debug for NavmeshContainer {
 }
 
 impl NavmeshContainer {
-    pub fn new(region: Region, jmx: JmxNvm) -> Self {
-        Self { region, mesh: jmx }
+    pub fn new(region: Region, jmx: JmxNvm, object_meshes: &HashMap<u32, Arc<ObjectMesh>>) -> Self {
+        let cells: Vec<Cell> = jmx
+            .cells
+            .iter()
+            .map(|cell| {
+                Cell::new(
+                    Vector2::new(cell.rect.min.x, cell.rect.min.y),
+                    Vector2::new(cell.rect.max.x, cell.rect.max.y),
+                )
+            })
+            .collect();
+
+        let inter_edges = jmx
+            .inter_edges
+            .iter()
+            .map(|edge| (edge.line, edge.flag, edge.source_cell, edge.destination_cell, region));
+        let outer_edges = jmx.outer_edges.iter().map(|edge| {
+            (
+                edge.line,
+                edge.flag,
+                edge.source_cell,
+                edge.destination_cell,
+                Region::from(edge.destination_region),
+            )
+        });
+        let borders = inter_edges
+            .chain(outer_edges)
+            .map(|(line, flag, source, destination, destination_region)| CellBorder {
+                edge: Edge::new(Vector2::new(line.a.x, line.a.y), Vector2::new(line.b.x, line.b.y), flag),
+                source,
+                destination,
+                destination_region,
+            });
+        let (blocking_edges, links) = link_cells(region, cells.len(), borders);
+
+        let objects = jmx
+            .entries
+            .iter()
+            .filter_map(|entry| {
+                let mesh = object_meshes.get(&entry.id)?;
+                let position = Vector3::new(entry.position.x, entry.position.y, entry.position.z);
+                Some(PlacedObject::new(mesh, position, Rad(entry.yaw)))
+            })
+            .collect();
+
+        Self {
+            region,
+            mesh: Some(jmx),
+            cells,
+            floors: Vec::new(),
+            blocking_edges,
+            links,
+            objects,
+        }
+    }
+
+    /// Creates a navmesh without terrain from the given cells and the borders between them.
+    #[cfg(test)]
+    pub(crate) fn from_cells(region: Region, cells: Vec<Cell>, borders: Vec<CellBorder>) -> Self {
+        let (blocking_edges, links) = link_cells(region, cells.len(), borders);
+        Self {
+            region,
+            mesh: None,
+            cells,
+            floors: Vec::new(),
+            blocking_edges,
+            links,
+            objects: Vec::new(),
+        }
+    }
+
+    /// Creates the navmesh of a dungeon, which only consists of the objects it's made of. The
+    /// triangles of the objects become the cells of the dungeon, linked wherever two triangles
+    /// share an edge that doesn't block movement.
+    pub fn dungeon(region: Region, objects: Vec<PlacedObject>) -> Self {
+        let floors: Vec<Triangle> = objects
+            .iter()
+            .flat_map(|object| object.triangles().iter().copied())
+            .collect();
+        let blocked = objects
+            .iter()
+            .flat_map(PlacedObject::blocking_edge_ends)
+            .map(|[start, end]| edge_key(start, end))
+            .collect::<HashSet<_>>();
+
+        let mut sides: HashMap<_, (Vector2<f32>, Vec<u16>)> = HashMap::new();
+        for (index, floor) in floors.iter().enumerate() {
+            let Ok(index) = u16::try_from(index) else {
+                break;
+            };
+            let [a, b, c] = floor.corners();
+            for (start, end) in [(a, b), (b, c), (c, a)] {
+                let key = edge_key(start, end);
+                if !blocked.contains(&key) {
+                    let portal = Vector2::new(start.x + end.x, start.z + end.z) / 2.0;
+                    sides.entry(key).or_insert((portal, Vec::new())).1.push(index);
+                }
+            }
+        }
+
+        let mut links = vec![Vec::new(); floors.len()];
+        for (portal, floors) in sides.into_values() {
+            for source in floors.iter() {
+                for destination in floors.iter().filter(|destination| *destination != source) {
+                    links[usize::from(*source)].push(CellLink::new(region, *destination, portal));
+                }
+            }
+        }
+
+        Self {
+            region,
+            mesh: None,
+            cells: Vec::new(),
+            floors,
+            blocking_edges: Vec::new(),
+            links,
+            objects,
+        }
+    }
+
+    pub fn region(&self) -> Region {
+        self.region
     }
 
-    pub fn heightmap(&self) -> Heightmap {
-        Heightmap::new(&self.mesh.height_map, MESH_SIZE, MESH_TILE_SIZE)
+    /// The objects of this region that reach into neighbouring regions, each moved into the
+    /// coordinates of every neighbouring region it reaches into.
+    pub(crate) fn objects_reaching_out(&self) -> Vec<(Region, PlacedObject)> {
+        let origin = region_origin(self.region);
+        self.objects
+            .iter()
+            .flat_map(|object| {
+                object
+                    .regions(self.region)
+                    .into_iter()
+                    .filter(|region| *region != self.region)
+                    .map(move |region| (region, object.moved(origin - region_origin(region))))
+            })
+            .collect()
+    }
+
+    /// Adds an object of a neighbouring region that reaches into this region, which must already
+    /// be moved into the coordinates of this region.
+    pub(crate) fn add_object(&mut self, object: PlacedObject) {
+        self.objects.push(object);
+    }
+
+    pub fn heightmap(&self) -> Option<Heightmap> {
+        self.mesh
+            .as_ref()
+            .map(|mesh| Heightmap::new(&mesh.height_map, MESH_SIZE, MESH_TILE_SIZE))
+    }
+
+    fn terrain_height(&self, point: Vector2<f32>) -> Option<f32> {
+        self.heightmap()?.height_at_position(point.x, point.y)
+    }
+
+    /// The height of the ground at the given position, relative to the region. This is the terrain
+    /// or, in dungeons, the lowest floor at that position.
+    pub fn ground_height(&self, point: Vector2<f32>) -> Option<f32> {
+        self.terrain_height(point).or_else(|| {
+            self.objects
+                .iter()
+                .flat_map(|object| object.heights_at(point))
+                .min_by(f32::total_cmp)
+        })
+    }
+
+    /// Calculates the height of the surface an entity at the height `reference` would stand on at
+    /// the given position, relative to the region. This is either the terrain or the floor of an
+    /// object, like a bridge or a building, depending on which one can be reached from the
+    /// reference height. If there is neither terrain nor an object, [None] is returned.
+    pub fn height_at(&self, point: Vector2<f32>, reference: f32) -> Option<f32> {
+        let terrain = self.terrain_height(point);
+        let objects = self.objects.iter().flat_map(|object| object.heights_at(point));
+        select_surface(terrain.into_iter().chain(objects), reference)
+    }
+
+    /// Checks if the given position, relative to the region, is inside a cell or on the surface of
+    /// an object that can be walked on.
+    pub fn is_walkable(&self, x: f32, y: f32) -> bool {
+        let point = Vector2::new(x, y);
+        self.cell_at(point).is_some()
+            || self
+                .objects
+                .iter()
+                .any(|object| object.heights_at(point).next().is_some())
+    }
+
+    /// Finds the index of the cell containing the given position, relative to the region. In
+    /// dungeons, this is the index of the floor triangle instead.
+    pub fn cell_at(&self, point: Vector2<f32>) -> Option<u16> {
+        self.cells
+            .iter()
+            .position(|cell| cell.contains(point))
+            .or_else(|| self.floors.iter().position(|floor| floor.contains(point)))
+            .and_then(|index| u16::try_from(index).ok())
+    }
+
+    /// The links from the given cell to its neighbouring cells that can be walked to.
+    pub(crate) fn links_of(&self, cell: u16) -> &[CellLink] {
+        self.links.get(usize::from(cell)).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Finds the first blocking edge that is crossed when moving from `from` to `to`, both relative
+    /// to the region, and returns how far along the way the edge is crossed as a fraction between
+    /// `0.0` and `1.0`. If nothing blocks the way, [None] is returned.
+    pub fn first_blocker(&self, from: Vector2<f32>, to: Vector2<f32>) -> Option<f32> {
+        self.blocking_edges
+            .iter()
+            .filter_map(|edge| edge.intersection(from, to))
+            .min_by(f32::total_cmp)
+    }
+
+    /// Like [Self::first_blocker], but for an entity moving at the given height, which may be
+    /// standing on an object. Only the edges of objects at about that height block the entity, and
+    /// the edges of the terrain only if the entity isn't standing on an object above the terrain.
+    pub fn first_blocker_at(&self, from: Vector2<f32>, to: Vector2<f32>, height: f32) -> Option<f32> {
+        let on_terrain = self
+            .terrain_height(from)
+            .is_none_or(|terrain| height - terrain <= MAX_STEP_HEIGHT);
+        let terrain = if on_terrain { self.first_blocker(from, to) } else { None };
+        self.objects
+            .iter()
+            .filter_map(|object| object.first_blocker(from, to, height))
+            .chain(terrain)
+            .min_by(f32::total_cmp)
+    }
+}
+
+/// An edge between two cells, where the destination cell may be part of another region.
+pub(crate) struct CellBorder {
+    pub(crate) edge: Edge,
+    pub(crate) source: u16,
+    pub(crate) destination: u16,
+    pub(crate) destination_region: Region,
+}
+
+/// Collects the blocking edges among the given borders and links the cells on both sides of all
+/// other borders with each other.
+fn link_cells(
+    region: Region,
+    cell_count: usize,
+    borders: impl IntoIterator<Item = CellBorder>,
+) -> (Vec<Edge>, Vec<Vec<CellLink>>) {
+    let mut blocking_edges = Vec::new();
+    let mut links = vec![Vec::new(); cell_count];
+    for border in borders {
+        if border.edge.is_blocking() {
+            blocking_edges.push(border.edge);
+            continue;
+        }
+
+        if border.source == NO_CELL || border.destination == NO_CELL {
+            continue;
+        }
+
+        let portal = border.edge.midpoint();
+        if let Some(source_links) = links.get_mut(usize::from(border.source)) {
+            source_links.push(CellLink::new(border.destination_region, border.destination, portal));
+        }
+
+        // Outer edges only describe the way out of this region, the way back in is part of the
+        // neighbouring region.
+        if border.destination_region == region {
+            if let Some(destination_links) = links.get_mut(usize::from(border.destination)) {
+                destination_links.push(CellLink::new(region, border.source, portal));
+            }
+        }
+    }
+    (blocking_edges, links)
+}
+
+type VertexKey = (i32, i32, i32);
+
+/// Identifies an edge between two corners, regardless of its direction.
+fn edge_key(start: Vector3<f32>, end: Vector3<f32>) -> (VertexKey, VertexKey) {
+    let vertex_key = |vertex: Vector3<f32>| {
+        let scaled = vertex * VERTEX_PRECISION;
+        (
+            scaled.x.round() as i32,
+            scaled.y.round() as i32,
+            scaled.z.round() as i32,
+        )
+    };
+    let (start, end) = (vertex_key(start), vertex_key(end));
+    if start <= end {
+        (start, end)
+    } else {
+        (end, start)
     }
 }
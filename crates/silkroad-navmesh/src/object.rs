@@ -1,5 +1,7 @@
 use crate::object_info::ObjectInfo;
+use crate::object_mesh::ObjectMesh;
 use crate::FileLoader;
+use cgmath::Vector3;
 use log::debug;
 use sr_formats::jmxvbms::JmxBMesh;
 use sr_formats::jmxvbsr::JmxRes;
@@ -100,6 +102,48 @@ impl Object {
             Object::Mesh(mesh) => mesh.header.name.borrow(),
         }
     }
+
+    /// Loads the mesh describing where the object can be walked on. Compounds use the mesh of
+    /// their collision resource, resources the collision mesh they refer to. Objects without a
+    /// walkable surface result in [None].
+    pub fn collision_mesh(&self, loader: &dyn FileLoader) -> Option<ObjectMesh> {
+        let mesh = match self {
+            Object::Compound { collision_resource, .. } => {
+                Self::load_collision_mesh(collision_resource.as_ref()?, loader)?
+            },
+            Object::Resource(resource) => Self::load_collision_mesh(resource, loader)?,
+            Object::Mesh(mesh) => Self::mesh_to_collision(mesh)?,
+        };
+        Some(mesh).filter(|mesh| !mesh.is_empty())
+    }
+
+    fn load_collision_mesh(resource: &JmxRes, loader: &dyn FileLoader) -> Option<ObjectMesh> {
+        let path = resource.collision_mesh_path.to_str().filter(|path| !path.is_empty())?;
+        let data = loader.load_file(path).ok()?;
+        let (_, mesh) = JmxBMesh::parse(&data).ok()?;
+        Self::mesh_to_collision(&mesh)
+    }
+
+    fn mesh_to_collision(mesh: &JmxBMesh) -> Option<ObjectMesh> {
+        let nav_mesh = mesh.nav_mesh.as_ref()?;
+        let vertices = nav_mesh
+            .vertices
+            .iter()
+            .map(|vertex| Vector3::new(vertex.position.x, vertex.position.y, vertex.position.z))
+            .collect();
+        let triangles = nav_mesh
+            .cells
+            .iter()
+            .map(|cell| cell.vertices.map(usize::from))
+            .collect();
+        let edges = nav_mesh
+            .outer_edges
+            .iter()
+            .chain(nav_mesh.inner_edges.iter())
+            .map(|edge| (edge.vertices.map(usize::from), edge.flag))
+            .collect();
+        Some(ObjectMesh::new(vertices, triangles, edges))
+    }
 }
 
 pub struct ObjectLoader;
@@ -125,4 +169,18 @@ impl ObjectLoader {
 
         Ok(objects)
     }
+
+    /// Loads the collision meshes of all given objects, skipping the ones that can't be walked on.
+    pub fn load_collision_meshes(
+        objects: &HashMap<u32, Arc<Object>>,
+        loader: &dyn FileLoader,
+    ) -> HashMap<u32, Arc<ObjectMesh>> {
+        objects
+            .iter()
+            .filter_map(|(id, object)| {
+                let mesh = object.collision_mesh(loader)?;
+                Some((*id, Arc::new(mesh)))
+            })
+            .collect()
+    }
 }
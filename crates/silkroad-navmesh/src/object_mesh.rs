@@ -0,0 +1,297 @@
+use crate::collision::{Cell, Edge};
+use crate::region::region_origin;
+use crate::regions_between;
+use cgmath::{Quaternion, Rad, Rotation3, Vector2, Vector3};
+use silkroad_definitions::Region;
+
+/// How far an entity can step up onto a surface, or down from it, without it being considered a
+/// different floor. Object edges also only block entities standing within this height of them.
+pub const MAX_STEP_HEIGHT: f32 = 20.0;
+
+/// The walkable surface of an object, like the floors of a building or the deck of a bridge, in
+/// coordinates relative to the object. Heights are stored in the `y` component of the vertices.
+#[derive(Clone, Debug, Default)]
+pub struct ObjectMesh {
+    vertices: Vec<Vector3<f32>>,
+    triangles: Vec<[usize; 3]>,
+    edges: Vec<([usize; 2], u8)>,
+}
+
+impl ObjectMesh {
+    /// Creates a new mesh from its vertices, the triangles that can be walked on and the edges
+    /// along the triangles with their flags. Triangles and edges referring to vertices that don't
+    /// exist are dropped.
+    pub fn new(vertices: Vec<Vector3<f32>>, triangles: Vec<[usize; 3]>, edges: Vec<([usize; 2], u8)>) -> Self {
+        let vertex_count = vertices.len();
+        let triangles = triangles
+            .into_iter()
+            .filter(|triangle| triangle.iter().all(|index| *index < vertex_count))
+            .collect();
+        let edges = edges
+            .into_iter()
+            .filter(|(edge, _)| edge.iter().all(|index| *index < vertex_count))
+            .collect();
+        Self {
+            vertices,
+            triangles,
+            edges,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.triangles.is_empty()
+    }
+}
+
+/// A triangle of an object surface, relative to the region the object has been placed in.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Triangle([Vector3<f32>; 3]);
+
+impl Triangle {
+    pub(crate) fn corners(&self) -> [Vector3<f32>; 3] {
+        self.0
+    }
+
+    pub(crate) fn contains(&self, point: Vector2<f32>) -> bool {
+        self.height_at(point).is_some()
+    }
+
+    /// Calculates the height of the surface at the given point by interpolating the heights of
+    /// the corners. If the point is outside the triangle, [None] is returned.
+    fn height_at(&self, point: Vector2<f32>) -> Option<f32> {
+        let [a, b, c] = self.0;
+        let denominator = (b.z - c.z) * (a.x - c.x) + (c.x - b.x) * (a.z - c.z);
+        if denominator.abs() < f32::EPSILON {
+            return None;
+        }
+
+        let weight_a = ((b.z - c.z) * (point.x - c.x) + (c.x - b.x) * (point.y - c.z)) / denominator;
+        let weight_b = ((c.z - a.z) * (point.x - c.x) + (a.x - c.x) * (point.y - c.z)) / denominator;
+        let weight_c = 1.0 - weight_a - weight_b;
+        let inside = |weight: f32| (-f32::EPSILON..=1.0 + f32::EPSILON).contains(&weight);
+        if inside(weight_a) && inside(weight_b) && inside(weight_c) {
+            Some(weight_a * a.y + weight_b * b.y + weight_c * c.y)
+        } else {
+            None
+        }
+    }
+}
+
+/// An edge of an object surface together with the height it is at.
+#[derive(Copy, Clone, Debug)]
+struct ObjectEdge {
+    edge: Edge,
+    ends: [Vector3<f32>; 2],
+    height: f32,
+}
+
+/// An object that has been placed into a region, with its surface moved and rotated into the
+/// coordinates of the region.
+#[derive(Clone, Debug)]
+pub struct PlacedObject {
+    bounds: Cell,
+    triangles: Vec<Triangle>,
+    blocking_edges: Vec<ObjectEdge>,
+}
+
+impl PlacedObject {
+    /// Places the mesh at the given position, relative to the region, turned around the vertical
+    /// axis by `yaw`.
+    pub fn new(mesh: &ObjectMesh, position: Vector3<f32>, yaw: Rad<f32>) -> Self {
+        let rotation = Quaternion::from_angle_y(yaw);
+        let vertices: Vec<Vector3<f32>> = mesh
+            .vertices
+            .iter()
+            .map(|vertex| position + rotation * vertex)
+            .collect();
+
+        let triangles = mesh
+            .triangles
+            .iter()
+            .map(|[a, b, c]| Triangle([vertices[*a], vertices[*b], vertices[*c]]))
+            .collect();
+        let blocking_edges = mesh
+            .edges
+            .iter()
+            .map(|([start, end], flag)| {
+                let (start, end) = (vertices[*start], vertices[*end]);
+                ObjectEdge {
+                    edge: Edge::new(Vector2::new(start.x, start.z), Vector2::new(end.x, end.z), *flag),
+                    ends: [start, end],
+                    height: (start.y + end.y) / 2.0,
+                }
+            })
+            .filter(|edge| edge.edge.is_blocking())
+            .collect();
+
+        let bounds = vertices.iter().fold(
+            Cell::new(Vector2::new(f32::MAX, f32::MAX), Vector2::new(f32::MIN, f32::MIN)),
+            |bounds, vertex| bounds.extend(Vector2::new(vertex.x, vertex.z)),
+        );
+
+        Self {
+            bounds,
+            triangles,
+            blocking_edges,
+        }
+    }
+
+    /// All regions the bounds of this object overlap, given the region it has been placed in.
+    /// Objects inside dungeons never reach into other regions.
+    pub(crate) fn regions(&self, region: Region) -> Vec<Region> {
+        if region.is_dungeon() {
+            return vec![region];
+        }
+
+        let origin = region_origin(region);
+        regions_between(origin + self.bounds.min(), origin + self.bounds.max())
+    }
+
+    /// Moves the object by the given offset, for example into the coordinates of a neighbouring
+    /// region.
+    pub(crate) fn moved(&self, offset: Vector2<f32>) -> Self {
+        let offset_3d = Vector3::new(offset.x, 0.0, offset.y);
+        Self {
+            bounds: self.bounds.moved(offset),
+            triangles: self
+                .triangles
+                .iter()
+                .map(|triangle| Triangle(triangle.0.map(|corner| corner + offset_3d)))
+                .collect(),
+            blocking_edges: self
+                .blocking_edges
+                .iter()
+                .map(|edge| ObjectEdge {
+                    edge: edge.edge.moved(offset),
+                    ends: edge.ends.map(|end| end + offset_3d),
+                    height: edge.height,
+                })
+                .collect(),
+        }
+    }
+
+    pub(crate) fn triangles(&self) -> &[Triangle] {
+        &self.triangles
+    }
+
+    /// The corners of all edges of this object that block movement.
+    pub(crate) fn blocking_edge_ends(&self) -> impl Iterator<Item = [Vector3<f32>; 2]> + '_ {
+        self.blocking_edges.iter().map(|edge| edge.ends)
+    }
+
+    /// The heights of all surfaces of this object at the given point, relative to the region.
+    pub fn heights_at(&self, point: Vector2<f32>) -> impl Iterator<Item = f32> + '_ {
+        let triangles = if self.bounds.contains(point) {
+            self.triangles.as_slice()
+        } else {
+            &[]
+        };
+        triangles.iter().filter_map(move |triangle| triangle.height_at(point))
+    }
+
+    /// Finds the first blocking edge of this object that is crossed when moving from `from` to
+    /// `to` at the given height. Edges of floors above or below the mover are ignored, such that
+    /// the railing of a bridge doesn't block anyone walking underneath it.
+    pub fn first_blocker(&self, from: Vector2<f32>, to: Vector2<f32>, height: f32) -> Option<f32> {
+        self.blocking_edges
+            .iter()
+            .filter(|edge| (edge.height - height).abs() <= MAX_STEP_HEIGHT)
+            .filter_map(|edge| edge.edge.intersection(from, to))
+            .min_by(f32::total_cmp)
+    }
+}
+
+/// Picks the surface an entity at the height `reference` would end up standing on: the highest
+/// surface it can still step onto. If all surfaces are too high up, the lowest one is used.
+pub fn select_surface(heights: impl IntoIterator<Item = f32>, reference: f32) -> Option<f32> {
+    let mut reachable: Option<f32> = None;
+    let mut lowest: Option<f32> = None;
+    for height in heights {
+        if height <= reference + MAX_STEP_HEIGHT {
+            reachable = Some(reachable.map_or(height, |current| current.max(height)));
+        }
+        lowest = Some(lowest.map_or(height, |current| current.min(height)));
+    }
+    reachable.or(lowest)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn floor(height: f32) -> ObjectMesh {
+        ObjectMesh::new(
+            vec![
+                Vector3::new(0.0, height, 0.0),
+                Vector3::new(10.0, height, 0.0),
+                Vector3::new(10.0, height, 10.0),
+                Vector3::new(0.0, height, 10.0),
+            ],
+            vec![[0, 1, 2], [0, 2, 3]],
+            vec![([1, 2], 0x02), ([2, 3], 0x00)],
+        )
+    }
+
+    #[test]
+    pub fn test_surface_height() {
+        let slope = Triangle([
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(10.0, 10.0, 0.0),
+            Vector3::new(0.0, 0.0, 10.0),
+        ]);
+        assert_eq!(Some(5.0), slope.height_at(Vector2::new(5.0, 2.0)));
+        assert_eq!(None, slope.height_at(Vector2::new(8.0, 8.0)));
+    }
+
+    #[test]
+    pub fn test_placed_object() {
+        let object = PlacedObject::new(&floor(5.0), Vector3::new(100.0, 10.0, 100.0), Rad(0.0));
+        assert_eq!(
+            vec![15.0],
+            object.heights_at(Vector2::new(107.0, 103.0)).collect::<Vec<_>>()
+        );
+        assert_eq!(0, object.heights_at(Vector2::new(95.0, 105.0)).count());
+    }
+
+    #[test]
+    pub fn test_rotated_object() {
+        let object = PlacedObject::new(&floor(0.0), Vector3::new(0.0, 0.0, 0.0), Rad(std::f32::consts::PI));
+        assert_eq!(1, object.heights_at(Vector2::new(-7.0, -3.0)).count());
+        assert_eq!(0, object.heights_at(Vector2::new(7.0, 3.0)).count());
+    }
+
+    #[test]
+    pub fn test_object_edges_block_at_their_height() {
+        let object = PlacedObject::new(&floor(50.0), Vector3::new(0.0, 0.0, 0.0), Rad(0.0));
+        let from = Vector2::new(5.0, 5.0);
+        let to = Vector2::new(15.0, 5.0);
+        assert_eq!(Some(0.5), object.first_blocker(from, to, 50.0));
+        assert_eq!(None, object.first_blocker(from, to, 0.0));
+        // The edge towards the top isn't blocking
+        assert_eq!(None, object.first_blocker(from, Vector2::new(5.0, 15.0), 50.0));
+    }
+
+    #[test]
+    pub fn test_object_reaching_into_neighbour() {
+        let region = Region::from_xy(100, 100);
+        let object = PlacedObject::new(&floor(0.0), Vector3::new(1915.0, 0.0, 100.0), Rad(0.0));
+        assert_eq!(vec![region, Region::from_xy(101, 100)], object.regions(region));
+
+        let moved = object.moved(Vector2::new(-1920.0, 0.0));
+        assert_eq!(1, moved.heights_at(Vector2::new(2.0, 105.0)).count());
+        assert_eq!(
+            Some(0.5),
+            moved.first_blocker(Vector2::new(0.0, 105.0), Vector2::new(10.0, 105.0), 0.0)
+        );
+    }
+
+    #[test]
+    pub fn test_select_surface() {
+        assert_eq!(None, select_surface([], 0.0));
+        // Standing on the bridge
+        assert_eq!(Some(50.0), select_surface([0.0, 50.0], 48.0));
+        // Walking underneath the bridge
+        assert_eq!(Some(0.0), select_surface([0.0, 50.0], 2.0));
+        assert_eq!(Some(30.0), select_surface([30.0, 50.0], 0.0));
+    }
+}
@@ -0,0 +1,449 @@
+use crate::region::{region_of, region_origin};
+use crate::GlobalNavmesh;
+use cgmath::{MetricSpace, Vector2};
+use silkroad_definitions::Region;
+use std::cmp::Ordering;
+use std::collections::hash_map::Entry;
+use std::collections::{BinaryHeap, HashMap};
+use thiserror::Error;
+
+/// A connection from one cell to a neighbouring cell, which may be part of another region.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct CellLink {
+    region: Region,
+    cell: u16,
+    /// The point on the shared edge, relative to the region of the cell the link belongs to.
+    portal: Vector2<f32>,
+}
+
+impl CellLink {
+    pub(crate) fn new(region: Region, cell: u16, portal: Vector2<f32>) -> Self {
+        Self { region, cell, portal }
+    }
+}
+
+#[derive(Error, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PathError {
+    #[error("The start or the destination is not on a walkable cell")]
+    NotWalkable,
+    #[error("There is no walkable way to the destination")]
+    NoPath,
+    #[error("The search gave up after visiting too many cells")]
+    LimitReached,
+}
+
+/// The outcome of a path search, including how many cells had to be visited to get there.
+#[derive(Debug)]
+pub struct PathSearch {
+    /// The waypoints to walk along in world coordinates, ending with the destination.
+    pub path: Result<Vec<Vector2<f32>>, PathError>,
+    pub visited_cells: usize,
+}
+
+impl PathSearch {
+    fn failed(error: PathError, visited_cells: usize) -> Self {
+        Self {
+            path: Err(error),
+            visited_cells,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+struct Node {
+    region: Region,
+    cell: u16,
+}
+
+struct Candidate {
+    estimate: f32,
+    node: Node,
+}
+
+impl Eq for Candidate {}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed, such that the binary heap pops the candidate with the lowest estimate first.
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+struct Visit {
+    cost: f32,
+    /// Where the cell was entered, in world coordinates.
+    entry: Vector2<f32>,
+    previous: Option<Node>,
+}
+
+impl GlobalNavmesh {
+    /// Finds the region and the index of the cell containing the given location in world
+    /// coordinates.
+    pub fn cell_at(&self, location: Vector2<f32>) -> Option<(Region, u16)> {
+        let region = region_of(location);
+        let cell = self.mesh_ref_for(region)?.cell_at(location - region_origin(region))?;
+        Some((region, cell))
+    }
+
+    fn node_at(&self, location: Vector2<f32>) -> Option<Node> {
+        self.cell_at(location).map(|(region, cell)| Node { region, cell })
+    }
+
+    /// Searches for a walkable way from `from` to `to`, both given in world coordinates, using A*
+    /// over the cells of the navmesh. The search may cross region borders and gives up after
+    /// having visited `max_cells` cells. The resulting waypoints skip every corner that can be cut
+    /// without crossing a blocking edge.
+    pub fn find_path(&self, from: Vector2<f32>, to: Vector2<f32>, max_cells: usize) -> PathSearch {
+        let (Some(start), Some(goal)) = (self.node_at(from), self.node_at(to)) else {
+            return PathSearch::failed(PathError::NotWalkable, 0);
+        };
+
+        let mut visits = HashMap::from([(
+            start,
+            Visit {
+                cost: 0.0,
+                entry: from,
+                previous: None,
+            },
+        )]);
+        let mut open = BinaryHeap::from([Candidate {
+            estimate: from.distance(to),
+            node: start,
+        }]);
+        let mut visited_cells = 0;
+
+        while let Some(Candidate { node, .. }) = open.pop() {
+            if node == goal {
+                let path = self.smooth(from, collect_waypoints(&visits, goal, to));
+                return PathSearch {
+                    path: Ok(path),
+                    visited_cells,
+                };
+            }
+
+            if visited_cells >= max_cells {
+                return PathSearch::failed(PathError::LimitReached, visited_cells);
+            }
+            visited_cells += 1;
+
+            let Some(mesh) = self.mesh_ref_for(node.region) else {
+                continue;
+            };
+            let (cost, entry) = {
+                let visit = &visits[&node];
+                (visit.cost, visit.entry)
+            };
+            let origin = region_origin(node.region);
+            for link in mesh.links_of(node.cell) {
+                let neighbour = Node {
+                    region: link.region,
+                    cell: link.cell,
+                };
+                let portal = origin + link.portal;
+                let neighbour_cost = cost + entry.distance(portal);
+                let visit = Visit {
+                    cost: neighbour_cost,
+                    entry: portal,
+                    previous: Some(node),
+                };
+                match visits.entry(neighbour) {
+                    Entry::Occupied(mut existing) => {
+                        if existing.get().cost <= neighbour_cost {
+                            continue;
+                        }
+                        existing.insert(visit);
+                    },
+                    Entry::Vacant(vacant) => {
+                        vacant.insert(visit);
+                    },
+                }
+                open.push(Candidate {
+                    estimate: neighbour_cost + portal.distance(to),
+                    node: neighbour,
+                });
+            }
+        }
+
+        PathSearch::failed(PathError::NoPath, visited_cells)
+    }
+
+    /// Removes all waypoints that can be skipped by walking straight to a later waypoint. The
+    /// height along the way is followed from the ground at `from`, such that the edges of objects
+    /// the walker is standing on block shortcuts as well.
+    fn smooth(&self, from: Vector2<f32>, waypoints: Vec<Vector2<f32>>) -> Vec<Vector2<f32>> {
+        let mut smoothed = Vec::with_capacity(waypoints.len());
+        let mut current = from;
+        let mut height = self.surface_height(from, None);
+        let mut index = 0;
+        while index < waypoints.len() {
+            let furthest_visible = (index..waypoints.len())
+                .rev()
+                .find(|candidate| {
+                    let to = waypoints[*candidate];
+                    let blocker = match height {
+                        Some(height) => self.first_blocker_at(current, to, height),
+                        None => self.first_blocker(current, to),
+                    };
+                    blocker.is_none()
+                })
+                .unwrap_or(index);
+            current = waypoints[furthest_visible];
+            height = self.surface_height(current, height);
+            smoothed.push(current);
+            index = furthest_visible + 1;
+        }
+        smoothed
+    }
+
+    /// The height of the surface at the given location that a walker at the height `reference`
+    /// would stand on, or of the ground if there is no reference yet.
+    fn surface_height(&self, location: Vector2<f32>, reference: Option<f32>) -> Option<f32> {
+        let region = region_of(location);
+        let mesh = self.mesh_ref_for(region)?;
+        let local = location - region_origin(region);
+        match reference {
+            Some(reference) => mesh.height_at(local, reference).or(Some(reference)),
+            None => mesh.ground_height(local),
+        }
+    }
+}
+
+fn collect_waypoints(visits: &HashMap<Node, Visit>, goal: Node, destination: Vector2<f32>) -> Vec<Vector2<f32>> {
+    let mut waypoints = vec![destination];
+    let mut current = &visits[&goal];
+    while let Some(previous) = current.previous {
+        waypoints.push(current.entry);
+        current = &visits[&previous];
+    }
+    waypoints.reverse();
+    waypoints
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::collision::{Cell, Edge};
+    use crate::navmesh::{CellBorder, NavmeshContainer};
+    use crate::object_mesh::{ObjectMesh, PlacedObject};
+    use cgmath::{Rad, Vector3};
+    use std::sync::Arc;
+
+    const BLOCKING: u8 = 0x03;
+
+    fn cell(min_x: f32, min_y: f32, max_x: f32, max_y: f32) -> Cell {
+        Cell::new(Vector2::new(min_x, min_y), Vector2::new(max_x, max_y))
+    }
+
+    fn border(
+        region: Region,
+        start: (f32, f32),
+        end: (f32, f32),
+        flag: u8,
+        source: u16,
+        destination: u16,
+    ) -> CellBorder {
+        CellBorder {
+            edge: Edge::new(Vector2::new(start.0, start.1), Vector2::new(end.0, end.1), flag),
+            source,
+            destination,
+            destination_region: region,
+        }
+    }
+
+    fn navmesh(meshes: Vec<NavmeshContainer>) -> GlobalNavmesh {
+        GlobalNavmesh {
+            loaded_meshes: meshes.into_iter().map(|mesh| (mesh.region(), Arc::new(mesh))).collect(),
+        }
+    }
+
+    /// Two cells next to each other, separated by a wall, with a third cell above both of them.
+    fn walled_region(region: Region) -> NavmeshContainer {
+        NavmeshContainer::from_cells(
+            region,
+            vec![
+                cell(0.0, 0.0, 100.0, 100.0),
+                cell(100.0, 0.0, 200.0, 100.0),
+                cell(0.0, 100.0, 200.0, 200.0),
+            ],
+            vec![
+                border(region, (100.0, 0.0), (100.0, 100.0), BLOCKING, 0, 1),
+                border(region, (0.0, 100.0), (100.0, 100.0), 0, 0, 2),
+                border(region, (100.0, 100.0), (200.0, 100.0), 0, 1, 2),
+            ],
+        )
+    }
+
+    /// A dungeon made of a single room with two halves, which may be separated by a wall.
+    fn dungeon_room(region: Region, divider_flag: u8) -> NavmeshContainer {
+        let room = ObjectMesh::new(
+            vec![
+                Vector3::new(0.0, 0.0, 0.0),
+                Vector3::new(10.0, 0.0, 0.0),
+                Vector3::new(20.0, 0.0, 0.0),
+                Vector3::new(0.0, 0.0, 10.0),
+                Vector3::new(10.0, 0.0, 10.0),
+                Vector3::new(20.0, 0.0, 10.0),
+            ],
+            vec![[0, 1, 4], [0, 4, 3], [1, 2, 5], [1, 5, 4]],
+            vec![([1, 4], divider_flag)],
+        );
+        NavmeshContainer::dungeon(
+            region,
+            vec![PlacedObject::new(&room, Vector3::new(0.0, 0.0, 0.0), Rad(0.0))],
+        )
+    }
+
+    #[test]
+    pub fn test_candidate_order() {
+        let node = Node {
+            region: Region::new(0),
+            cell: 0,
+        };
+        let mut heap = BinaryHeap::from([
+            Candidate { estimate: 5.0, node },
+            Candidate { estimate: 1.0, node },
+            Candidate { estimate: 3.0, node },
+        ]);
+        assert_eq!(Some(1.0), heap.pop().map(|candidate| candidate.estimate));
+        assert_eq!(Some(3.0), heap.pop().map(|candidate| candidate.estimate));
+        assert_eq!(Some(5.0), heap.pop().map(|candidate| candidate.estimate));
+    }
+
+    #[test]
+    pub fn test_path_around_wall() {
+        let region = Region::from_xy(100, 100);
+        let navmesh = navmesh(vec![walled_region(region)]);
+        let origin = region_origin(region);
+        let search = navmesh.find_path(
+            origin + Vector2::new(50.0, 50.0),
+            origin + Vector2::new(150.0, 50.0),
+            10,
+        );
+        assert_eq!(
+            Ok(vec![
+                origin + Vector2::new(50.0, 100.0),
+                origin + Vector2::new(150.0, 100.0),
+                origin + Vector2::new(150.0, 50.0),
+            ]),
+            search.path
+        );
+    }
+
+    #[test]
+    pub fn test_path_across_regions() {
+        let left = Region::from_xy(100, 100);
+        let right = Region::from_xy(101, 100);
+        let navmesh = navmesh(vec![
+            NavmeshContainer::from_cells(
+                left,
+                vec![cell(1820.0, 0.0, 1920.0, 100.0)],
+                vec![border(right, (1920.0, 0.0), (1920.0, 100.0), 0, 0, 0)],
+            ),
+            NavmeshContainer::from_cells(right, vec![cell(0.0, 0.0, 100.0, 100.0)], Vec::new()),
+        ]);
+        let from = region_origin(left) + Vector2::new(1850.0, 50.0);
+        let to = region_origin(right) + Vector2::new(50.0, 50.0);
+        let search = navmesh.find_path(from, to, 10);
+        assert_eq!(Ok(vec![to]), search.path);
+        assert_eq!(1, search.visited_cells);
+    }
+
+    #[test]
+    pub fn test_search_limit() {
+        let region = Region::from_xy(100, 100);
+        let navmesh = navmesh(vec![walled_region(region)]);
+        let origin = region_origin(region);
+        let search = navmesh.find_path(origin + Vector2::new(50.0, 50.0), origin + Vector2::new(150.0, 50.0), 1);
+        assert_eq!(Err(PathError::LimitReached), search.path);
+        assert_eq!(1, search.visited_cells);
+    }
+
+    #[test]
+    pub fn test_no_path() {
+        let region = Region::from_xy(100, 100);
+        let navmesh = navmesh(vec![NavmeshContainer::from_cells(
+            region,
+            vec![cell(0.0, 0.0, 100.0, 100.0), cell(100.0, 0.0, 200.0, 100.0)],
+            vec![border(region, (100.0, 0.0), (100.0, 100.0), BLOCKING, 0, 1)],
+        )]);
+        let origin = region_origin(region);
+        let search = navmesh.find_path(
+            origin + Vector2::new(50.0, 50.0),
+            origin + Vector2::new(150.0, 50.0),
+            10,
+        );
+        assert_eq!(Err(PathError::NoPath), search.path);
+    }
+
+    /// Three cells in the shape of an L, all covered by an object floor with a wall at the given
+    /// height between the lower and the right cell.
+    fn corner_with_wall(region: Region, wall_height: f32) -> NavmeshContainer {
+        let mut container = NavmeshContainer::from_cells(
+            region,
+            vec![
+                cell(0.0, 0.0, 100.0, 100.0),
+                cell(0.0, 100.0, 100.0, 200.0),
+                cell(100.0, 100.0, 200.0, 200.0),
+            ],
+            vec![
+                border(region, (0.0, 100.0), (100.0, 100.0), 0, 0, 1),
+                border(region, (100.0, 100.0), (100.0, 200.0), 0, 1, 2),
+            ],
+        );
+        let floor = ObjectMesh::new(
+            vec![
+                Vector3::new(0.0, 0.0, 0.0),
+                Vector3::new(200.0, 0.0, 0.0),
+                Vector3::new(200.0, 0.0, 200.0),
+                Vector3::new(0.0, 0.0, 200.0),
+                Vector3::new(100.0, wall_height, 50.0),
+                Vector3::new(100.0, wall_height, 120.0),
+            ],
+            vec![[0, 1, 2], [0, 2, 3]],
+            vec![([4, 5], BLOCKING)],
+        );
+        container.add_object(PlacedObject::new(&floor, Vector3::new(0.0, 0.0, 0.0), Rad(0.0)));
+        container
+    }
+
+    #[test]
+    pub fn test_smoothing_around_object_wall() {
+        let region = Region::from_xy(100, 100);
+        let origin = region_origin(region);
+        let from = origin + Vector2::new(50.0, 50.0);
+        let to = origin + Vector2::new(150.0, 150.0);
+
+        let search = navmesh(vec![corner_with_wall(region, 0.0)]).find_path(from, to, 10);
+        assert_eq!(Ok(vec![origin + Vector2::new(100.0, 150.0), to]), search.path);
+
+        // A wall on a floor high above doesn't block the shortcut
+        let search = navmesh(vec![corner_with_wall(region, 50.0)]).find_path(from, to, 10);
+        assert_eq!(Ok(vec![to]), search.path);
+    }
+
+    #[test]
+    pub fn test_path_in_dungeon() {
+        let region = Region::dungeon(1);
+        let origin = region_origin(region);
+        let from = origin + Vector2::new(2.0, 5.0);
+        let to = origin + Vector2::new(18.0, 5.0);
+
+        let search = navmesh(vec![dungeon_room(region, 0x00)]).find_path(from, to, 10);
+        assert_eq!(Ok(vec![to]), search.path);
+
+        let search = navmesh(vec![dungeon_room(region, BLOCKING)]).find_path(from, to, 10);
+        assert_eq!(Err(PathError::NoPath), search.path);
+    }
+}
@@ -1,12 +1,55 @@
+use cgmath::Vector2;
 use silkroad_definitions::Region;
 
+pub const REGION_SIZE: f32 = 1920.0;
+
+/// Dungeons don't fit into the grid of the world, as their local coordinates can exceed the size of
+/// a region in any direction. Instead, every dungeon gets its own area of this size, with the local
+/// coordinates being relative to the center of that area.
+const DUNGEON_SIZE: f32 = 16.0 * REGION_SIZE;
+const DUNGEONS_PER_ROW: u16 = 16;
+/// Region ids with the highest bit set are dungeons, so the world never uses the grid rows from here
+/// onwards. The areas of the dungeons are placed in rows starting at this row.
+const DUNGEON_AREA_START: f32 = 128.0 * REGION_SIZE;
+
+/// Finds the region containing the given location in world coordinates.
+pub fn region_of(location: Vector2<f32>) -> Region {
+    if location.y >= DUNGEON_AREA_START {
+        let column = (location.x / DUNGEON_SIZE) as u16;
+        let row = ((location.y - DUNGEON_AREA_START) / DUNGEON_SIZE) as u16;
+        return Region::dungeon(row * DUNGEONS_PER_ROW + column);
+    }
+
+    Region::from_xy((location.x / REGION_SIZE) as u8, (location.y / REGION_SIZE) as u8)
+}
+
+/// The world coordinates that the local coordinates of the given region are relative to.
+pub fn region_origin(region: Region) -> Vector2<f32> {
+    match region.dungeon_id() {
+        Some(dungeon) => {
+            let column = f32::from(dungeon % DUNGEONS_PER_ROW);
+            let row = f32::from(dungeon / DUNGEONS_PER_ROW);
+            Vector2::new(
+                (column + 0.5) * DUNGEON_SIZE,
+                DUNGEON_AREA_START + (row + 0.5) * DUNGEON_SIZE,
+            )
+        },
+        None => Vector2::new(f32::from(region.x()) * REGION_SIZE, f32::from(region.y()) * REGION_SIZE),
+    }
+}
+
 pub trait GridRegion {
-    fn with_grid_neighbours(&self) -> [Region; 9];
+    fn with_grid_neighbours(&self) -> Vec<Region>;
 }
 
 impl GridRegion for Region {
-    fn with_grid_neighbours(&self) -> [Region; 9] {
-        [
+    fn with_grid_neighbours(&self) -> Vec<Region> {
+        // A dungeon is a single region without any neighbours.
+        if self.is_dungeon() {
+            return vec![*self];
+        }
+
+        vec![
             Region::from_xy(self.x() - 1, self.y()),
             *self,
             Region::from_xy(self.x() + 1, self.y()),
@@ -19,3 +62,25 @@ impl GridRegion for Region {
         ]
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_world_region() {
+        let region = Region::from_xy(168, 98);
+        let origin = region_origin(region);
+        assert_eq!(region, region_of(origin));
+        assert_eq!(region, region_of(origin + Vector2::new(1919.0, 1919.0)));
+    }
+
+    #[test]
+    pub fn test_dungeon_region() {
+        let region = Region::dungeon(17);
+        let origin = region_origin(region);
+        assert_eq!(region, region_of(origin));
+        assert_eq!(region, region_of(origin + Vector2::new(-5000.0, 5000.0)));
+        assert_ne!(region, region_of(origin + Vector2::new(DUNGEON_SIZE, 0.0)));
+    }
+}
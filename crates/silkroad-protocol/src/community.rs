@@ -93,6 +93,130 @@ pub struct AddFriend {
     pub name: String,
 }
 
+#[derive(Clone, Copy, Eq, PartialEq, Serialize, ByteSize, Deserialize, Debug)]
+#[silkroad(size = 2)]
+pub enum FriendListError {
+    #[silkroad(value = 0x6401)]
+    CharacterNotFound,
+    #[silkroad(value = 0x6402)]
+    AlreadyFriends,
+    #[silkroad(value = 0x6403)]
+    ListFull,
+    #[silkroad(value = 0x6404)]
+    TargetListFull,
+    #[silkroad(value = 0x6405)]
+    Declined,
+    #[silkroad(value = 0x6406)]
+    NotFriends,
+    #[silkroad(value = 0x6407)]
+    InvalidGroup,
+    #[silkroad(value = 0x6408)]
+    TooManyGroups,
+    #[silkroad(value = 0x6409)]
+    AlreadyBlocked,
+    #[silkroad(value = 0x640A)]
+    NotBlocked,
+    #[silkroad(value = 0x640B)]
+    BlockListFull,
+    #[silkroad(value = 0x640C)]
+    Busy,
+}
+
+#[derive(Clone, Serialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0xB302)]
+pub enum AddFriendResponse {
+    #[silkroad(value = 1)]
+    Success(FriendListEntry),
+    #[silkroad(value = 2)]
+    Failure(FriendListError),
+}
+
+/// Asks the player whether they want to become friends with the requesting player.
+#[derive(Clone, Serialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0x7302)]
+pub struct FriendRequest {
+    pub requester: u32,
+    pub name: String,
+}
+
+#[derive(Clone, Deserialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0x7303)]
+pub struct FriendRequestAnswer {
+    pub requester: u32,
+    pub accepted: bool,
+}
+
+#[derive(Clone, Serialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0xB303)]
+pub enum FriendRequestAnswerResponse {
+    #[silkroad(value = 1)]
+    Success,
+    #[silkroad(value = 2)]
+    Failure(FriendListError),
+}
+
+#[derive(Clone, Serialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0xB304)]
+pub enum DeleteFriendResponse {
+    #[silkroad(value = 1)]
+    Success { friend_character_id: u32 },
+    #[silkroad(value = 2)]
+    Failure(FriendListError),
+}
+
+#[derive(Clone, Serialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0xB310)]
+pub enum CreateFriendGroupResponse {
+    #[silkroad(value = 1)]
+    Success(FriendListGroup),
+    #[silkroad(value = 2)]
+    Failure(FriendListError),
+}
+
+#[derive(Clone, Deserialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0x7311)]
+pub struct DeleteFriendGroup {
+    pub group_id: u16,
+}
+
+#[derive(Clone, Serialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0xB311)]
+pub enum DeleteFriendGroupResponse {
+    #[silkroad(value = 1)]
+    Success { group_id: u16 },
+    #[silkroad(value = 2)]
+    Failure(FriendListError),
+}
+
+#[derive(Clone, Deserialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0x7312)]
+pub struct MoveFriendToGroup {
+    pub friend_character_id: u32,
+    pub group_id: u16,
+}
+
+#[derive(Clone, Serialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0xB312)]
+pub enum MoveFriendToGroupResponse {
+    #[silkroad(value = 1)]
+    Success { friend_character_id: u32, group_id: u16 },
+    #[silkroad(value = 2)]
+    Failure(FriendListError),
+}
+
+/// Changes to the friend list that were not requested by the player themselves, such as another
+/// player accepting a friend request or a friend coming online.
+#[derive(Clone, Serialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0x3303)]
+pub enum FriendListUpdate {
+    #[silkroad(value = 1)]
+    Added(FriendListEntry),
+    #[silkroad(value = 2)]
+    Removed { char_id: u32 },
+    #[silkroad(value = 3)]
+    Status { char_id: u32, offline: bool },
+}
+
 #[derive(Clone, Deserialize, ByteSize, Packet, Debug)]
 #[packet(opcode = 0x7310)]
 pub struct CreateFriendGroup {
@@ -105,12 +229,543 @@ pub struct DeleteFriend {
     pub friend_character_id: u32,
 }
 
+#[derive(Clone, Serialize, ByteSize, Debug)]
+pub struct BlockedCharacter {
+    pub char_id: u32,
+    pub name: String,
+}
+
+/// The characters the player has blocked. Blocked characters cannot whisper the player and their
+/// requests to trade, party or become friends are dropped.
+#[derive(Clone, Serialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0x3306)]
+pub struct BlockListInfo {
+    pub blocked: Vec<BlockedCharacter>,
+}
+
+#[derive(Clone, Deserialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0x7305)]
+pub struct BlockCharacter {
+    pub name: String,
+}
+
+#[derive(Clone, Serialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0xB305)]
+pub enum BlockCharacterResponse {
+    #[silkroad(value = 1)]
+    Success(BlockedCharacter),
+    #[silkroad(value = 2)]
+    Failure(FriendListError),
+}
+
+#[derive(Clone, Deserialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0x7306)]
+pub struct UnblockCharacter {
+    pub char_id: u32,
+}
+
+#[derive(Clone, Serialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0xB306)]
+pub enum UnblockCharacterResponse {
+    #[silkroad(value = 1)]
+    Success { char_id: u32 },
+    #[silkroad(value = 2)]
+    Failure(FriendListError),
+}
+
 define_inbound_protocol! { FriendListClientProtocol =>
     AddFriend,
+    FriendRequestAnswer,
     CreateFriendGroup,
-    DeleteFriend
+    DeleteFriendGroup,
+    MoveFriendToGroup,
+    DeleteFriend,
+    BlockCharacter,
+    UnblockCharacter
 }
 
 define_outbound_protocol! { FriendListServerProtocol =>
-    FriendListInfo
+    FriendListInfo,
+    AddFriendResponse,
+    FriendRequest,
+    FriendRequestAnswerResponse,
+    DeleteFriendResponse,
+    CreateFriendGroupResponse,
+    DeleteFriendGroupResponse,
+    MoveFriendToGroupResponse,
+    FriendListUpdate,
+    BlockListInfo,
+    BlockCharacterResponse,
+    UnblockCharacterResponse
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Serialize, ByteSize, Deserialize, Debug)]
+#[silkroad(size = 2)]
+pub enum GuildError {
+    #[silkroad(value = 0x4C01)]
+    InvalidNpc,
+    #[silkroad(value = 0x4C02)]
+    InvalidName,
+    #[silkroad(value = 0x4C03)]
+    NameTaken,
+    #[silkroad(value = 0x4C04)]
+    LevelTooLow,
+    #[silkroad(value = 0x4C05)]
+    NotEnoughGold,
+    #[silkroad(value = 0x4C06)]
+    AlreadyInGuild,
+    #[silkroad(value = 0x4C07)]
+    TargetInGuild,
+    #[silkroad(value = 0x4C08)]
+    NotInGuild,
+    #[silkroad(value = 0x4C09)]
+    NoPermission,
+    #[silkroad(value = 0x4C0A)]
+    GuildFull,
+    #[silkroad(value = 0x4C0B)]
+    InvalidTarget,
+    #[silkroad(value = 0x4C0C)]
+    Declined,
+    #[silkroad(value = 0x4C0D)]
+    NotEnoughGp,
+    #[silkroad(value = 0x4C0E)]
+    NotEnoughSp,
+    #[silkroad(value = 0x4C0F)]
+    MaxLevel,
+    #[silkroad(value = 0x4C10)]
+    MasterCannotLeave,
+    #[silkroad(value = 0x4C11)]
+    AlreadyInUnion,
+    #[silkroad(value = 0x4C12)]
+    TargetInUnion,
+    #[silkroad(value = 0x4C13)]
+    NotInUnion,
+    #[silkroad(value = 0x4C14)]
+    UnionFull,
+    #[silkroad(value = 0x4C15)]
+    Busy,
+}
+
+#[derive(Clone, Serialize, ByteSize, Debug)]
+pub struct GuildMemberData {
+    pub character_id: u32,
+    pub name: String,
+    pub ref_id: u32,
+    pub level: u8,
+    pub master: bool,
+    pub permissions: u32,
+    pub online: bool,
+}
+
+/// The complete state of the guild, sent to a player when they join the game or the guild.
+#[derive(Clone, Serialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0x3101)]
+pub struct GuildInfo {
+    pub id: u32,
+    pub name: String,
+    pub level: u8,
+    pub gp: u32,
+    pub notice_title: String,
+    pub notice: String,
+    pub members: Vec<GuildMemberData>,
+}
+
+/// Changes to the guild that other members caused, such as members joining or the notice changing.
+#[derive(Clone, Serialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0x38F5)]
+pub enum GuildUpdate {
+    #[silkroad(value = 1)]
+    MemberJoined(GuildMemberData),
+    #[silkroad(value = 2)]
+    MemberLeft { character_id: u32, kicked: bool },
+    #[silkroad(value = 3)]
+    MemberPermissions { character_id: u32, permissions: u32 },
+    #[silkroad(value = 4)]
+    MemberStatus { character_id: u32, online: bool },
+    #[silkroad(value = 5)]
+    Notice { title: String, notice: String },
+    #[silkroad(value = 6)]
+    Progress { level: u8, gp: u32 },
+    #[silkroad(value = 7)]
+    MasterTransferred { old_master: u32, new_master: u32 },
+    #[silkroad(value = 8)]
+    Disbanded,
+}
+
+/// Informs players close by about the guild of the given player changing.
+#[derive(Clone, Serialize, ByteSize, Packet)]
+#[packet(opcode = 0x30FF)]
+pub struct EntityGuildUpdate {
+    pub unique_id: u32,
+    pub guild: GuildInformation,
+}
+
+#[derive(Clone, Deserialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0x70F0)]
+pub struct GuildCreate {
+    pub npc_unique_id: u32,
+    pub name: String,
+}
+
+#[derive(Clone, Serialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0xB0F0)]
+pub enum GuildCreateResponse {
+    #[silkroad(value = 1)]
+    Success,
+    #[silkroad(value = 2)]
+    Failure(GuildError),
+}
+
+/// Disbands the guild, which only the master may do.
+#[derive(Clone, Deserialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0x70F1)]
+pub struct GuildDisband;
+
+#[derive(Clone, Serialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0xB0F1)]
+pub enum GuildDisbandResponse {
+    #[silkroad(value = 1)]
+    Success,
+    #[silkroad(value = 2)]
+    Failure(GuildError),
+}
+
+#[derive(Clone, Deserialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0x70F2)]
+pub struct GuildLeave;
+
+#[derive(Clone, Serialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0xB0F2)]
+pub enum GuildLeaveResponse {
+    #[silkroad(value = 1)]
+    Success,
+    #[silkroad(value = 2)]
+    Failure(GuildError),
+}
+
+#[derive(Clone, Deserialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0x70F3)]
+pub struct GuildInvite {
+    pub target: u32,
+}
+
+#[derive(Clone, Serialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0xB0F3)]
+pub enum GuildInviteResponse {
+    #[silkroad(value = 1)]
+    Success,
+    #[silkroad(value = 2)]
+    Failure(GuildError),
+}
+
+#[derive(Clone, Deserialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0x70F4)]
+pub struct GuildKick {
+    pub character_id: u32,
+}
+
+#[derive(Clone, Serialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0xB0F4)]
+pub enum GuildKickResponse {
+    #[silkroad(value = 1)]
+    Success,
+    #[silkroad(value = 2)]
+    Failure(GuildError),
+}
+
+/// Hands the leadership of the guild over to another member, which only the master may do.
+#[derive(Clone, Deserialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0x70F5)]
+pub struct GuildTransferMaster {
+    pub character_id: u32,
+}
+
+#[derive(Clone, Serialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0xB0F5)]
+pub enum GuildTransferMasterResponse {
+    #[silkroad(value = 1)]
+    Success,
+    #[silkroad(value = 2)]
+    Failure(GuildError),
+}
+
+/// Raises the level of the guild at the guild manager, paying with the gold of the master and the
+/// GP of the guild.
+#[derive(Clone, Deserialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0x70F6)]
+pub struct GuildLevelUp {
+    pub npc_unique_id: u32,
+}
+
+#[derive(Clone, Serialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0xB0F6)]
+pub enum GuildLevelUpResponse {
+    #[silkroad(value = 1)]
+    Success { level: u8 },
+    #[silkroad(value = 2)]
+    Failure(GuildError),
+}
+
+#[derive(Clone, Deserialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0x70F7)]
+pub struct GuildUpdateNotice {
+    pub title: String,
+    pub notice: String,
+}
+
+#[derive(Clone, Serialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0xB0F7)]
+pub enum GuildUpdateNoticeResponse {
+    #[silkroad(value = 1)]
+    Success,
+    #[silkroad(value = 2)]
+    Failure(GuildError),
+}
+
+/// Changes what the member is allowed to do within the guild, which only the master may do.
+#[derive(Clone, Deserialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0x70FF)]
+pub struct GuildPromote {
+    pub character_id: u32,
+    pub permissions: u32,
+}
+
+#[derive(Clone, Serialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0xB0FF)]
+pub enum GuildPromoteResponse {
+    #[silkroad(value = 1)]
+    Success,
+    #[silkroad(value = 2)]
+    Failure(GuildError),
+}
+
+/// Converts skill points of the member into GP of the guild.
+#[derive(Clone, Deserialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0x7258)]
+pub struct GuildDonateGp {
+    pub amount: u32,
+}
+
+#[derive(Clone, Serialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0xB258)]
+pub enum GuildDonateGpResponse {
+    #[silkroad(value = 1)]
+    Success { gp: u32 },
+    #[silkroad(value = 2)]
+    Failure(GuildError),
+}
+
+define_inbound_protocol! { GuildClientProtocol =>
+    GuildCreate,
+    GuildDisband,
+    GuildLeave,
+    GuildInvite,
+    GuildKick,
+    GuildTransferMaster,
+    GuildLevelUp,
+    GuildUpdateNotice,
+    GuildPromote,
+    GuildDonateGp
+}
+
+define_outbound_protocol! { GuildServerProtocol =>
+    GuildInfo,
+    GuildUpdate,
+    EntityGuildUpdate,
+    GuildCreateResponse,
+    GuildDisbandResponse,
+    GuildLeaveResponse,
+    GuildInviteResponse,
+    GuildKickResponse,
+    GuildTransferMasterResponse,
+    GuildLevelUpResponse,
+    GuildUpdateNoticeResponse,
+    GuildPromoteResponse,
+    GuildDonateGpResponse
+}
+
+#[derive(Clone, Serialize, ByteSize, Debug)]
+pub struct UnionGuildData {
+    pub id: u32,
+    pub name: String,
+}
+
+/// The guilds that are allied in the union of the player's guild. A union is led by the guild that
+/// formed it, whose id is also the id of the union.
+#[derive(Clone, Serialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0x3102)]
+pub struct UnionInfo {
+    pub id: u32,
+    pub guilds: Vec<UnionGuildData>,
+}
+
+#[derive(Clone, Serialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0x38F6)]
+pub enum UnionUpdate {
+    #[silkroad(value = 1)]
+    GuildJoined(UnionGuildData),
+    #[silkroad(value = 2)]
+    GuildLeft { guild_id: u32 },
+    #[silkroad(value = 3)]
+    Disbanded,
+}
+
+/// Invites the guild of the targeted guild master into the union, forming a new union if the guild
+/// of the inviting master isn't in one yet.
+#[derive(Clone, Deserialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0x70FB)]
+pub struct UnionInvite {
+    pub target: u32,
+}
+
+#[derive(Clone, Serialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0xB0FB)]
+pub enum UnionInviteResponse {
+    #[silkroad(value = 1)]
+    Success,
+    #[silkroad(value = 2)]
+    Failure(GuildError),
+}
+
+#[derive(Clone, Deserialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0x70FC)]
+pub struct UnionLeave;
+
+#[derive(Clone, Serialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0xB0FC)]
+pub enum UnionLeaveResponse {
+    #[silkroad(value = 1)]
+    Success,
+    #[silkroad(value = 2)]
+    Failure(GuildError),
+}
+
+define_inbound_protocol! { UnionClientProtocol =>
+    UnionInvite,
+    UnionLeave
+}
+
+define_outbound_protocol! { UnionServerProtocol =>
+    UnionInfo,
+    UnionUpdate,
+    UnionInviteResponse,
+    UnionLeaveResponse
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Serialize, ByteSize, Deserialize, Debug)]
+#[silkroad(size = 2)]
+pub enum MemoError {
+    #[silkroad(value = 0x6501)]
+    CharacterNotFound,
+    #[silkroad(value = 0x6502)]
+    InboxFull,
+    #[silkroad(value = 0x6503)]
+    NotEnoughGold,
+    #[silkroad(value = 0x6504)]
+    MemoNotFound,
+    #[silkroad(value = 0x6505)]
+    InvalidMessage,
+    #[silkroad(value = 0x6506)]
+    GoldNotClaimed,
+    #[silkroad(value = 0x6507)]
+    Busy,
+}
+
+#[derive(Clone, Serialize, ByteSize)]
+pub struct MemoSummary {
+    pub id: u32,
+    pub sender: String,
+    pub gold: u64,
+    pub read: bool,
+    pub sent_at: SilkroadTime,
+}
+
+/// The memos in the inbox of the player, sent when joining the game or when requested.
+#[derive(Clone, Serialize, ByteSize, Packet)]
+#[packet(opcode = 0x3308)]
+pub struct MemoListInfo {
+    pub memos: Vec<MemoSummary>,
+}
+
+/// Lets the player know about memos they have not read yet, either when joining the game or when
+/// a new memo arrived while they were online.
+#[derive(Clone, Serialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0x3309)]
+pub struct MemoNotification {
+    pub unread: u16,
+}
+
+#[derive(Clone, Deserialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0x7308)]
+pub struct ListMemos;
+
+/// Sends a memo to the character with the given name, who doesn't need to be online. Attached gold
+/// is taken from the sender together with a fee.
+#[derive(Clone, Deserialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0x7309)]
+pub struct SendMemo {
+    pub recipient: String,
+    pub message: String,
+    pub gold: u64,
+}
+
+#[derive(Clone, Serialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0xB309)]
+pub enum SendMemoResponse {
+    #[silkroad(value = 1)]
+    Success { fee: u64 },
+    #[silkroad(value = 2)]
+    Failure(MemoError),
+}
+
+/// Reads the memo, claiming the gold attached to it.
+#[derive(Clone, Deserialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0x730A)]
+pub struct ReadMemo {
+    pub id: u32,
+}
+
+#[derive(Clone, Serialize, ByteSize, Packet)]
+#[packet(opcode = 0xB30A)]
+pub enum ReadMemoResponse {
+    #[silkroad(value = 1)]
+    Success {
+        id: u32,
+        sender: String,
+        message: String,
+        gold: u64,
+        sent_at: SilkroadTime,
+    },
+    #[silkroad(value = 2)]
+    Failure(MemoError),
+}
+
+#[derive(Clone, Deserialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0x730B)]
+pub struct DeleteMemo {
+    pub id: u32,
+}
+
+#[derive(Clone, Serialize, ByteSize, Packet, Debug)]
+#[packet(opcode = 0xB30B)]
+pub enum DeleteMemoResponse {
+    #[silkroad(value = 1)]
+    Success { id: u32 },
+    #[silkroad(value = 2)]
+    Failure(MemoError),
+}
+
+define_inbound_protocol! { MemoClientProtocol =>
+    ListMemos,
+    SendMemo,
+    ReadMemo,
+    DeleteMemo
+}
+
+define_outbound_protocol! { MemoServerProtocol =>
+    MemoListInfo,
+    MemoNotification,
+    SendMemoResponse,
+    ReadMemoResponse,
+    DeleteMemoResponse
 }
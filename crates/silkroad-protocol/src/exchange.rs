@@ -0,0 +1,119 @@
+use crate::inventory::{InventoryItemData, InventoryOperationError};
+use skrillax_packet::Packet;
+use skrillax_protocol::{define_inbound_protocol, define_outbound_protocol};
+use skrillax_serde::*;
+
+#[derive(Clone, Copy, Deserialize, ByteSize, Serialize, Packet, Debug)]
+#[packet(opcode = 0x7081)]
+pub struct ExchangeRequest {
+    pub target: u32,
+}
+
+#[derive(Clone, Copy, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0xB081)]
+pub enum ExchangeRequestResponse {
+    #[silkroad(value = 1)]
+    Success,
+    #[silkroad(value = 2)]
+    Failure(InventoryOperationError),
+}
+
+#[derive(Clone, Copy, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0x3085)]
+pub struct ExchangeStarted {
+    pub partner: u32,
+}
+
+#[derive(Clone, Copy, Deserialize, ByteSize, Serialize, Packet, Debug)]
+#[packet(opcode = 0x7082)]
+pub struct ExchangeConfirm;
+
+#[derive(Clone, Copy, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0xB082)]
+pub enum ExchangeConfirmResponse {
+    #[silkroad(value = 1)]
+    Success,
+    #[silkroad(value = 2)]
+    Failure(InventoryOperationError),
+}
+
+/// Informs the player that their partner has confirmed their side of the exchange.
+#[derive(Clone, Copy, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0x3086)]
+pub struct ExchangeConfirmed;
+
+#[derive(Clone, Copy, Deserialize, ByteSize, Serialize, Packet, Debug)]
+#[packet(opcode = 0x7083)]
+pub struct ExchangeApprove;
+
+#[derive(Clone, Copy, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0xB083)]
+pub enum ExchangeApproveResponse {
+    #[silkroad(value = 1)]
+    Success,
+    #[silkroad(value = 2)]
+    Failure(InventoryOperationError),
+}
+
+#[derive(Clone, Copy, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0x3087)]
+pub struct ExchangeCompleted;
+
+#[derive(Clone, Copy, Deserialize, ByteSize, Serialize, Packet, Debug)]
+#[packet(opcode = 0x7084)]
+pub struct ExchangeCancel;
+
+#[derive(Clone, Copy, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0xB084)]
+pub enum ExchangeCancelResponse {
+    #[silkroad(value = 1)]
+    Success,
+    #[silkroad(value = 2)]
+    Failure(InventoryOperationError),
+}
+
+#[derive(Clone, Copy, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0x3088)]
+pub struct ExchangeCancelled;
+
+/// The items one side of the exchange currently offers, where the slot of each item is its position
+/// in the exchange window.
+#[derive(Clone, Serialize, ByteSize, Packet)]
+#[packet(opcode = 0x308C)]
+pub struct ExchangeItemsUpdate {
+    pub owner: u32,
+    pub items: Vec<InventoryItemData>,
+}
+
+impl ExchangeItemsUpdate {
+    pub fn new(owner: u32, items: Vec<InventoryItemData>) -> Self {
+        ExchangeItemsUpdate { owner, items }
+    }
+}
+
+/// The amount of gold the partner currently offers.
+#[derive(Clone, Copy, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0x3089)]
+pub struct ExchangeGoldUpdate {
+    pub amount: u64,
+}
+
+define_inbound_protocol! { ExchangeClientProtocol =>
+    ExchangeRequest,
+    ExchangeConfirm,
+    ExchangeApprove,
+    ExchangeCancel
+}
+
+define_outbound_protocol! { ExchangeServerProtocol =>
+    ExchangeRequestResponse,
+    ExchangeStarted,
+    ExchangeConfirmResponse,
+    ExchangeConfirmed,
+    ExchangeApproveResponse,
+    ExchangeCompleted,
+    ExchangeCancelResponse,
+    ExchangeCancelled,
+    ExchangeItemsUpdate,
+    ExchangeGoldUpdate
+}
@@ -12,6 +12,24 @@ pub enum InventoryOperationRequest {
     PickupItem { unique_id: u32 },
     #[silkroad(value = 0x07)]
     DropItem { slot: u8 },
+    #[silkroad(value = 0x04)]
+    AddExchangeItem { slot: u8 },
+    #[silkroad(value = 0x05)]
+    RemoveExchangeItem { slot: u8 },
+    #[silkroad(value = 0x0D)]
+    ExchangeGold { amount: u64 },
+    /// Combines all partial stacks of the same item in the bag.
+    #[silkroad(value = 0x1C)]
+    MergeStacks,
+    /// Reorders the items in the bag.
+    #[silkroad(value = 0x1D)]
+    Sort,
+    /// Takes off the avatar item in the `source` avatar slot and puts it into the `target` bag slot.
+    #[silkroad(value = 0x22)]
+    UnequipAvatar { source: u8, target: u8 },
+    /// Puts on the avatar item in the `source` bag slot into the `target` avatar slot.
+    #[silkroad(value = 0x23)]
+    EquipAvatar { source: u8, target: u8 },
 }
 
 impl InventoryOperationRequest {
@@ -110,6 +128,27 @@ pub enum InventoryOperationResponseData {
         unknown: u8,
         data: ItemPickupData,
     },
+    #[silkroad(value = 0x04)]
+    AddExchangeItem { slot: u8 },
+    #[silkroad(value = 0x05)]
+    RemoveExchangeItem { slot: u8 },
+    #[silkroad(value = 0x0D)]
+    ExchangeGold { amount: u64 },
+    #[silkroad(value = 0x0F)]
+    RemovedByServer { slot: u8, reason: ItemRemovalReason },
+    #[silkroad(value = 0x22)]
+    UnequipAvatar { source: u8, target: u8 },
+    #[silkroad(value = 0x23)]
+    EquipAvatar { source: u8, target: u8 },
+}
+
+#[derive(Clone, Copy, Serialize, ByteSize, Debug)]
+pub enum ItemRemovalReason {
+    /// The item has been taken by the server, e.g. when it has been handed to the consignment house.
+    #[silkroad(value = 1)]
+    Removed,
+    #[silkroad(value = 2)]
+    Expired,
 }
 
 impl InventoryOperationResponseData {
@@ -134,6 +173,16 @@ impl InventoryOperationResponseData {
 #[derive(Clone, Eq, PartialEq, Copy, Serialize, ByteSize, Deserialize, Debug)]
 #[silkroad(size = 2)]
 pub enum ConsignmentErrorCode {
+    #[silkroad(value = 0x7001)]
+    InvalidItem,
+    #[silkroad(value = 0x7003)]
+    ListingUnavailable,
+    #[silkroad(value = 0x7005)]
+    InventoryFull,
+    #[silkroad(value = 0x7008)]
+    TooManyListings,
+    #[silkroad(value = 0x700A)]
+    ServiceUnavailable,
     #[silkroad(value = 0x700D)]
     NotEnoughGold,
 }
@@ -270,16 +319,16 @@ impl InventoryOperationResult {
         })
     }
 
-    pub fn success_gain_item(slot: u8, ref_id: u32, content: InventoryItemContentData) -> Self {
+    pub fn success_gain_item(slot: u8, rent: RentInfo, ref_id: u32, content: InventoryItemContentData) -> Self {
         InventoryOperationResult::Success(InventoryOperationResponseData::PickupItem {
             slot,
-            item: ItemPickupData::Item {
-                rent: RentInfo::Empty,
-                ref_id,
-                content,
-            },
+            item: ItemPickupData::Item { rent, ref_id, content },
         })
     }
+
+    pub fn removed_by_server(slot: u8, reason: ItemRemovalReason) -> Self {
+        InventoryOperationResult::Success(InventoryOperationResponseData::RemovedByServer { slot, reason })
+    }
 }
 
 #[derive(Clone, Serialize, ByteSize)]
@@ -327,7 +376,16 @@ impl InventoryItemData {
 }
 
 #[derive(Clone, Serialize, ByteSize, Deserialize, Debug)]
-pub struct InventoryItemMagicData;
+pub struct InventoryItemMagicData {
+    pub id: u32,
+    pub value: u32,
+}
+
+impl InventoryItemMagicData {
+    pub fn new(id: u32, value: u32) -> Self {
+        InventoryItemMagicData { id, value }
+    }
+}
 
 #[derive(Clone, Serialize, ByteSize, Deserialize, Debug)]
 pub struct InventoryItemBindingData {
@@ -345,11 +403,16 @@ impl InventoryItemBindingData {
 pub struct CharacterSpawnItemData {
     pub item_id: u32,
     pub upgrade_level: u8,
+    pub magic: Vec<InventoryItemMagicData>,
 }
 
 impl CharacterSpawnItemData {
-    pub fn new(item_id: u32, upgrade_level: u8) -> Self {
-        CharacterSpawnItemData { item_id, upgrade_level }
+    pub fn new(item_id: u32, upgrade_level: u8, magic: Vec<InventoryItemMagicData>) -> Self {
+        CharacterSpawnItemData {
+            item_id,
+            upgrade_level,
+            magic,
+        }
     }
 }
 
@@ -411,12 +474,280 @@ impl ConsignmentResponse {
     }
 }
 
+/// Lists the item in the given inventory slot in the consignment house for the given amount of days.
+#[derive(Clone, Copy, Deserialize, ByteSize, Serialize, Packet, Debug)]
+#[packet(opcode = 0x7508)]
+pub struct ConsignmentRegister {
+    pub slot: u8,
+    pub price: u64,
+    pub days: u8,
+}
+
+#[derive(Clone, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0xB508)]
+pub struct ConsignmentRegisterResponse {
+    pub result: ConsignmentResult,
+}
+
+impl ConsignmentRegisterResponse {
+    pub fn new(result: ConsignmentResult) -> Self {
+        ConsignmentRegisterResponse { result }
+    }
+}
+
+/// Collects the gold of all sold listings and takes back the items of all expired listings.
+#[derive(Clone, Copy, Deserialize, ByteSize, Serialize, Packet, Debug)]
+#[packet(opcode = 0x750A)]
+pub struct ConsignmentSettle;
+
+#[derive(Clone, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0xB50A)]
+pub struct ConsignmentSettleResponse {
+    pub result: ConsignmentResult,
+}
+
+impl ConsignmentSettleResponse {
+    pub fn new(result: ConsignmentResult) -> Self {
+        ConsignmentSettleResponse { result }
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, ByteSize, Serialize, Packet, Debug)]
+#[packet(opcode = 0x750B)]
+pub struct ConsignmentBuy {
+    pub listing_id: u32,
+}
+
+#[derive(Clone, Copy, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0xB50B)]
+pub enum ConsignmentBuyResponse {
+    #[silkroad(value = 1)]
+    Success { listing_id: u32 },
+    #[silkroad(value = 2)]
+    Failure { code: ConsignmentErrorCode },
+}
+
+/// Searches the listings of the consignment house. Each type id component, as well as the maximum
+/// level and price, matches anything if it is zero.
+#[derive(Clone, Copy, Deserialize, ByteSize, Serialize, Packet, Debug)]
+#[packet(opcode = 0x750C)]
+pub struct ConsignmentSearch {
+    pub type_id_2: u8,
+    pub type_id_3: u8,
+    pub type_id_4: u8,
+    pub min_level: u8,
+    pub max_level: u8,
+    pub max_price: u64,
+    pub page: u8,
+}
+
+#[derive(Clone, Serialize, ByteSize)]
+pub struct ConsignmentSearchEntry {
+    pub listing_id: u32,
+    pub seller: String,
+    pub item: InventoryItemData,
+    pub price: u64,
+    pub end_date: u32,
+}
+
+impl ConsignmentSearchEntry {
+    pub fn new(listing_id: u32, seller: String, item: InventoryItemData, price: u64, end_date: u32) -> Self {
+        ConsignmentSearchEntry {
+            listing_id,
+            seller,
+            item,
+            price,
+            end_date,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, ByteSize)]
+pub enum ConsignmentSearchResult {
+    #[silkroad(value = 1)]
+    Success {
+        page: u8,
+        total_pages: u8,
+        entries: Vec<ConsignmentSearchEntry>,
+    },
+    #[silkroad(value = 2)]
+    Failure { code: ConsignmentErrorCode },
+}
+
+#[derive(Clone, Serialize, ByteSize, Packet)]
+#[packet(opcode = 0xB50C)]
+pub struct ConsignmentSearchResponse {
+    pub result: ConsignmentSearchResult,
+}
+
+impl ConsignmentSearchResponse {
+    pub fn success(page: u8, total_pages: u8, entries: Vec<ConsignmentSearchEntry>) -> Self {
+        ConsignmentSearchResponse {
+            result: ConsignmentSearchResult::Success {
+                page,
+                total_pages,
+                entries,
+            },
+        }
+    }
+
+    pub fn error(code: ConsignmentErrorCode) -> Self {
+        ConsignmentSearchResponse {
+            result: ConsignmentSearchResult::Failure { code },
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, ByteSize, Serialize, Packet)]
 #[packet(opcode = 0x7034)]
 pub struct InventoryOperation {
     pub data: InventoryOperationRequest,
 }
 
+#[derive(Clone, Copy, Deserialize, ByteSize, Serialize, Debug)]
+pub enum RepairKind {
+    #[silkroad(value = 1)]
+    Single { slot: u8 },
+    #[silkroad(value = 2)]
+    All,
+}
+
+#[derive(Clone, Copy, Deserialize, ByteSize, Serialize, Packet, Debug)]
+#[packet(opcode = 0x703E)]
+pub struct RepairItemRequest {
+    pub npc_unique_id: u32,
+    pub kind: RepairKind,
+}
+
+#[derive(Clone, Copy, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0xB03E)]
+pub enum RepairItemResponse {
+    #[silkroad(value = 1)]
+    Success,
+    #[silkroad(value = 2)]
+    Failure(InventoryOperationError),
+}
+
+#[derive(Clone, Copy, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0x3052)]
+pub struct ItemDurabilityUpdate {
+    pub slot: u8,
+    pub durability: u32,
+}
+
+impl ItemDurabilityUpdate {
+    pub fn new(slot: u8, durability: u32) -> Self {
+        ItemDurabilityUpdate { slot, durability }
+    }
+}
+
+#[derive(Clone, Copy, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0x3040)]
+pub struct ItemAmountUpdate {
+    pub slot: u8,
+    pub kind: u8, // 8 = amount
+    pub amount: u16,
+}
+
+impl ItemAmountUpdate {
+    pub fn new(slot: u8, amount: u16) -> Self {
+        ItemAmountUpdate { slot, kind: 8, amount }
+    }
+}
+
+/// Informs about the new size of the inventory, after it has been expanded.
+#[derive(Clone, Copy, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0x3092)]
+pub struct InventorySizeUpdate {
+    pub size: u8,
+}
+
+/// The item type, without the cash and bionic bits, of the reverse return scroll. Only when using
+/// this scroll, the client includes the chosen destination.
+pub const REVERSE_SCROLL_TYPE: u16 = 0x67B;
+
+/// The item type, without the cash and bionic bits, of the global chatting scroll. When using this
+/// scroll, the client includes the message to send to everyone.
+pub const GLOBAL_CHAT_SCROLL_TYPE: u16 = 0xA7B;
+
+#[derive(Clone, Copy, Deserialize, ByteSize, Serialize, Debug)]
+pub enum ReverseScrollTarget {
+    #[silkroad(value = 2)]
+    RecentLocation,
+    #[silkroad(value = 3)]
+    DeathLocation,
+    #[silkroad(value = 7)]
+    MapLocation(u32),
+}
+
+#[derive(Clone, Deserialize, ByteSize, Serialize, Packet, Debug)]
+#[packet(opcode = 0x704C)]
+pub struct UseItemRequest {
+    pub slot: u8,
+    pub item_type: u16,
+    #[silkroad(when = "item_type >> 2 == REVERSE_SCROLL_TYPE")]
+    pub reverse_target: Option<ReverseScrollTarget>,
+    #[silkroad(when = "item_type >> 2 == GLOBAL_CHAT_SCROLL_TYPE")]
+    pub global_message: Option<String>,
+}
+
+#[derive(Clone, Copy, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0xB04C)]
+pub enum UseItemResponse {
+    #[silkroad(value = 1)]
+    Success { slot: u8, remaining: u16, item_type: u16 },
+    #[silkroad(value = 2)]
+    Failure(InventoryOperationError),
+}
+
+impl UseItemResponse {
+    pub fn success(slot: u8, remaining: u16, item_type: u16) -> Self {
+        UseItemResponse::Success {
+            slot,
+            remaining,
+            item_type,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, ByteSize, Serialize, Packet, Debug)]
+#[packet(opcode = 0x7059)]
+pub struct DesignateRecallPoint {
+    pub npc_unique_id: u32,
+}
+
+#[derive(Clone, Copy, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0xB059)]
+pub enum DesignateRecallPointResponse {
+    #[silkroad(value = 1)]
+    Success,
+    #[silkroad(value = 2)]
+    Failure(InventoryOperationError),
+}
+
+#[derive(Clone, Copy, Deserialize, ByteSize, Serialize, Debug)]
+pub enum TeleportKind {
+    #[silkroad(value = 2)]
+    Teleport { destination: u32 },
+}
+
+/// Requests to be teleported by the given teleport NPC or building.
+#[derive(Clone, Copy, Deserialize, ByteSize, Serialize, Packet, Debug)]
+#[packet(opcode = 0x705A)]
+pub struct TeleportUse {
+    pub npc_unique_id: u32,
+    pub kind: TeleportKind,
+}
+
+#[derive(Clone, Copy, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0xB05A)]
+pub enum TeleportUseResponse {
+    #[silkroad(value = 1)]
+    Success,
+    #[silkroad(value = 2)]
+    Failure(InventoryOperationError),
+}
+
 #[derive(Copy, Clone, Deserialize, ByteSize, Serialize, Packet, Debug)]
 #[packet(opcode = 0x755D)]
 pub struct OpenItemMall;
@@ -436,11 +767,30 @@ pub enum OpenItemMallResult {
 define_inbound_protocol! { InventoryClientProtocol =>
     OpenItemMall,
     InventoryOperation,
-    ConsignmentList
+    ConsignmentList,
+    ConsignmentRegister,
+    ConsignmentSettle,
+    ConsignmentBuy,
+    ConsignmentSearch,
+    RepairItemRequest,
+    UseItemRequest,
+    DesignateRecallPoint,
+    TeleportUse
 }
 
 define_outbound_protocol! { InventoryServerProtocol =>
     OpenItemMallResponse,
     ConsignmentResponse,
-    InventoryOperationResult
+    ConsignmentRegisterResponse,
+    ConsignmentSettleResponse,
+    ConsignmentBuyResponse,
+    ConsignmentSearchResponse,
+    InventoryOperationResult,
+    RepairItemResponse,
+    ItemDurabilityUpdate,
+    ItemAmountUpdate,
+    InventorySizeUpdate,
+    UseItemResponse,
+    DesignateRecallPointResponse,
+    TeleportUseResponse
 }
@@ -3,12 +3,15 @@ pub mod character;
 pub mod chat;
 pub mod combat;
 pub mod community;
+pub mod exchange;
 pub mod general;
 pub mod gm;
 pub mod inventory;
 pub mod movement;
+pub mod party;
 pub mod skill;
 pub mod spawn;
+pub mod stall;
 pub mod world;
 
 pub use skrillax_serde::SilkroadTime;
@@ -0,0 +1,290 @@
+use skrillax_packet::Packet;
+use skrillax_protocol::{define_inbound_protocol, define_outbound_protocol};
+use skrillax_serde::*;
+
+/// How experience and items are distributed between the members of a party.
+#[derive(Clone, Copy, Eq, PartialEq, Serialize, ByteSize, Deserialize, Debug)]
+pub enum PartyType {
+    #[silkroad(value = 0)]
+    FreeForAll,
+    #[silkroad(value = 1)]
+    ExperienceShare,
+    #[silkroad(value = 2)]
+    ItemShare,
+    #[silkroad(value = 3)]
+    Shared,
+}
+
+impl PartyType {
+    pub fn shares_experience(&self) -> bool {
+        matches!(self, PartyType::ExperienceShare | PartyType::Shared)
+    }
+
+    pub fn shares_items(&self) -> bool {
+        matches!(self, PartyType::ItemShare | PartyType::Shared)
+    }
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Serialize, ByteSize, Deserialize, Debug)]
+#[silkroad(size = 2)]
+pub enum PartyError {
+    #[silkroad(value = 0x2C01)]
+    InvalidTarget,
+    #[silkroad(value = 0x2C02)]
+    AlreadyInParty,
+    #[silkroad(value = 0x2C03)]
+    TargetInParty,
+    #[silkroad(value = 0x2C04)]
+    PartyFull,
+    #[silkroad(value = 0x2C05)]
+    NotInParty,
+    #[silkroad(value = 0x2C06)]
+    NotLeader,
+    #[silkroad(value = 0x2C07)]
+    Declined,
+    #[silkroad(value = 0x2C08)]
+    Busy,
+    #[silkroad(value = 0x2C10)]
+    EntryNotFound,
+    #[silkroad(value = 0x2C11)]
+    AlreadyListed,
+    #[silkroad(value = 0x2C12)]
+    InvalidLevelRange,
+    #[silkroad(value = 0x2C13)]
+    LevelOutOfRange,
+}
+
+#[derive(Clone, Serialize, ByteSize, Deserialize, Debug)]
+pub struct PartyMember {
+    pub unique_id: u32,
+    pub name: String,
+    pub ref_id: u32,
+    pub level: u8,
+}
+
+impl PartyMember {
+    pub fn new(unique_id: u32, name: String, ref_id: u32, level: u8) -> Self {
+        PartyMember {
+            unique_id,
+            name,
+            ref_id,
+            level,
+        }
+    }
+}
+
+/// Asks the target to form a new party with the requesting player.
+#[derive(Clone, Copy, Deserialize, ByteSize, Serialize, Packet, Debug)]
+#[packet(opcode = 0x7060)]
+pub struct PartyCreateRequest {
+    pub target: u32,
+    pub party_type: PartyType,
+}
+
+#[derive(Clone, Copy, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0xB060)]
+pub enum PartyCreateResponse {
+    #[silkroad(value = 1)]
+    Success,
+    #[silkroad(value = 2)]
+    Failure(PartyError),
+}
+
+#[derive(Clone, Copy, Deserialize, ByteSize, Serialize, Packet, Debug)]
+#[packet(opcode = 0x7061)]
+pub struct PartyLeave;
+
+#[derive(Clone, Copy, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0xB061)]
+pub enum PartyLeaveResponse {
+    #[silkroad(value = 1)]
+    Success,
+    #[silkroad(value = 2)]
+    Failure(PartyError),
+}
+
+#[derive(Clone, Copy, Deserialize, ByteSize, Serialize, Packet, Debug)]
+#[packet(opcode = 0x7062)]
+pub struct PartyInvite {
+    pub target: u32,
+}
+
+#[derive(Clone, Copy, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0xB062)]
+pub enum PartyInviteResponse {
+    #[silkroad(value = 1)]
+    Success,
+    #[silkroad(value = 2)]
+    Failure(PartyError),
+}
+
+#[derive(Clone, Copy, Deserialize, ByteSize, Serialize, Packet, Debug)]
+#[packet(opcode = 0x7063)]
+pub struct PartyKick {
+    pub member: u32,
+}
+
+#[derive(Clone, Copy, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0xB063)]
+pub enum PartyKickResponse {
+    #[silkroad(value = 1)]
+    Success,
+    #[silkroad(value = 2)]
+    Failure(PartyError),
+}
+
+#[derive(Clone, Copy, Deserialize, ByteSize, Serialize, Packet, Debug)]
+#[packet(opcode = 0x7064)]
+pub struct PartyLeaderTransfer {
+    pub member: u32,
+}
+
+#[derive(Clone, Copy, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0xB064)]
+pub enum PartyLeaderTransferResponse {
+    #[silkroad(value = 1)]
+    Success,
+    #[silkroad(value = 2)]
+    Failure(PartyError),
+}
+
+/// The complete state of the party, sent to a player when they join it.
+#[derive(Clone, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0x3065)]
+pub struct PartyInfo {
+    pub party_id: u32,
+    pub leader: u32,
+    pub party_type: PartyType,
+    pub members: Vec<PartyMember>,
+}
+
+impl PartyInfo {
+    pub fn new(party_id: u32, leader: u32, party_type: PartyType, members: Vec<PartyMember>) -> Self {
+        PartyInfo {
+            party_id,
+            leader,
+            party_type,
+            members,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0x3864)]
+pub enum PartyUpdate {
+    #[silkroad(value = 1)]
+    Disbanded,
+    #[silkroad(value = 2)]
+    MemberJoined(PartyMember),
+    #[silkroad(value = 3)]
+    MemberLeft { member: u32, kicked: bool },
+    #[silkroad(value = 9)]
+    LeaderChanged { leader: u32 },
+}
+
+/// A party that is looking for more members, as shown in the party matching window.
+#[derive(Clone, Serialize, ByteSize, Deserialize, Debug)]
+pub struct PartyMatchingEntry {
+    pub id: u32,
+    pub leader: u32,
+    pub leader_name: String,
+    pub member_count: u8,
+    pub party_type: PartyType,
+    pub min_level: u8,
+    pub max_level: u8,
+    pub title: String,
+}
+
+/// Lists the party of the requesting leader in the party matching.
+#[derive(Clone, Deserialize, ByteSize, Serialize, Packet, Debug)]
+#[packet(opcode = 0x7069)]
+pub struct PartyMatchingForm {
+    pub min_level: u8,
+    pub max_level: u8,
+    pub title: String,
+}
+
+#[derive(Clone, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0xB069)]
+pub enum PartyMatchingFormResponse {
+    #[silkroad(value = 1)]
+    Success(PartyMatchingEntry),
+    #[silkroad(value = 2)]
+    Failure(PartyError),
+}
+
+#[derive(Clone, Copy, Deserialize, ByteSize, Serialize, Packet, Debug)]
+#[packet(opcode = 0x706B)]
+pub struct PartyMatchingDelete {
+    pub id: u32,
+}
+
+#[derive(Clone, Copy, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0xB06B)]
+pub enum PartyMatchingDeleteResponse {
+    #[silkroad(value = 1)]
+    Success { id: u32 },
+    #[silkroad(value = 2)]
+    Failure(PartyError),
+}
+
+#[derive(Clone, Copy, Deserialize, ByteSize, Serialize, Packet, Debug)]
+#[packet(opcode = 0x706C)]
+pub struct PartyMatchingList {
+    pub page: u8,
+}
+
+#[derive(Clone, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0xB06C)]
+pub enum PartyMatchingListResponse {
+    #[silkroad(value = 1)]
+    Success {
+        page_count: u8,
+        page: u8,
+        entries: Vec<PartyMatchingEntry>,
+    },
+    #[silkroad(value = 2)]
+    Failure(PartyError),
+}
+
+/// Asks the leader of the listed party to let the requesting player join.
+#[derive(Clone, Copy, Deserialize, ByteSize, Serialize, Packet, Debug)]
+#[packet(opcode = 0x706D)]
+pub struct PartyMatchingJoin {
+    pub id: u32,
+}
+
+#[derive(Clone, Copy, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0xB06D)]
+pub enum PartyMatchingJoinResponse {
+    #[silkroad(value = 1)]
+    Success,
+    #[silkroad(value = 2)]
+    Failure(PartyError),
+}
+
+define_inbound_protocol! { PartyClientProtocol =>
+    PartyCreateRequest,
+    PartyLeave,
+    PartyInvite,
+    PartyKick,
+    PartyLeaderTransfer,
+    PartyMatchingForm,
+    PartyMatchingDelete,
+    PartyMatchingList,
+    PartyMatchingJoin
+}
+
+define_outbound_protocol! { PartyServerProtocol =>
+    PartyCreateResponse,
+    PartyLeaveResponse,
+    PartyInviteResponse,
+    PartyKickResponse,
+    PartyLeaderTransferResponse,
+    PartyInfo,
+    PartyUpdate,
+    PartyMatchingFormResponse,
+    PartyMatchingDeleteResponse,
+    PartyMatchingListResponse,
+    PartyMatchingJoinResponse
+}
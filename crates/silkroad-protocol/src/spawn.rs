@@ -2,7 +2,7 @@ use crate::community::GuildInformation;
 use crate::inventory::{BagContent, CharacterSpawnItemData};
 use crate::movement::{EntityMovementState, Position};
 use crate::skill::{HotbarItem, MasteryData, SkillData};
-use crate::world::{ActiveScroll, EntityState, InteractOptions, JobType, PlayerKillState, PvpCape};
+use crate::world::{ActiveScroll, EntityState, InteractMode, InteractOptions, JobType, PlayerKillState, PvpCape};
 use chrono::{DateTime, Datelike, TimeZone, Timelike, Utc};
 use silkroad_definitions::rarity::EntityRarity;
 use skrillax_packet::Packet;
@@ -431,6 +431,22 @@ impl ActiveQuestObjectData {
     }
 }
 
+#[derive(Clone, Serialize, ByteSize)]
+pub struct StallSpawnInfo {
+    #[silkroad(size = 2)]
+    pub title: String,
+    pub decoration: u32,
+}
+
+/// The stall a character currently has opened, which is only present if the interact mode of the
+/// character is [InteractMode::Stall].
+#[derive(Clone, Serialize, ByteSize)]
+#[silkroad(size = 0)]
+pub enum StallSpawnData {
+    None,
+    Open(StallSpawnInfo),
+}
+
 #[derive(Clone, Serialize, ByteSize)]
 #[silkroad(size = 0)]
 pub enum EntityTypeSpawnData {
@@ -455,9 +471,10 @@ pub enum EntityTypeSpawnData {
         mounted: bool,
         in_combat: bool,
         active_scroll: ActiveScroll,
-        unknown2: u8,
+        interact_mode: InteractMode,
         guild: GuildInformation,
         unknown3: [u8; 11],
+        stall: StallSpawnData,
         equipment_cooldown: bool,
         pk_state: PlayerKillState,
         unknown4: u8,
@@ -479,6 +496,11 @@ pub enum EntityTypeSpawnData {
         rarity: EntityRarity,
         unknown: u32,
     },
+    Teleport {
+        unique_id: u32,
+        position: Position,
+        unknown: [u8; 4],
+    },
 }
 
 impl EntityTypeSpawnData {
@@ -537,9 +559,10 @@ impl EntityTypeSpawnData {
             mounted,
             in_combat,
             active_scroll,
-            unknown2: 0,
+            interact_mode: InteractMode::None,
             guild,
             unknown3,
+            stall: StallSpawnData::None,
             equipment_cooldown,
             pk_state,
             unknown4: 0xFF,
@@ -566,4 +589,12 @@ impl EntityTypeSpawnData {
             unknown,
         }
     }
+
+    pub fn teleport(unique_id: u32, position: Position) -> Self {
+        EntityTypeSpawnData::Teleport {
+            unique_id,
+            position,
+            unknown: [0, 1, 0, 0],
+        }
+    }
 }
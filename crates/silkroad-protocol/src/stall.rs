@@ -0,0 +1,208 @@
+use crate::inventory::{InventoryItemData, InventoryOperationError};
+use skrillax_packet::Packet;
+use skrillax_protocol::{define_inbound_protocol, define_outbound_protocol};
+use skrillax_serde::*;
+
+/// An item offered in a stall, where the slot of the item data is its position in the stall.
+#[derive(Clone, Serialize, ByteSize)]
+pub struct StallItemData {
+    pub item: InventoryItemData,
+    pub inventory_slot: u8,
+    pub price: u64,
+}
+
+impl StallItemData {
+    pub fn new(item: InventoryItemData, inventory_slot: u8, price: u64) -> Self {
+        StallItemData {
+            item,
+            inventory_slot,
+            price,
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, ByteSize, Serialize, Packet, Debug)]
+#[packet(opcode = 0x70B1)]
+pub struct StallCreate {
+    #[silkroad(size = 2)]
+    pub title: String,
+}
+
+#[derive(Clone, Copy, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0xB0B1)]
+pub enum StallCreateResponse {
+    #[silkroad(value = 1)]
+    Success,
+    #[silkroad(value = 2)]
+    Failure(InventoryOperationError),
+}
+
+#[derive(Clone, Copy, Deserialize, ByteSize, Serialize, Packet, Debug)]
+#[packet(opcode = 0x70B2)]
+pub struct StallClose;
+
+#[derive(Clone, Copy, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0xB0B2)]
+pub enum StallCloseResponse {
+    #[silkroad(value = 1)]
+    Success,
+    #[silkroad(value = 2)]
+    Failure(InventoryOperationError),
+}
+
+#[derive(Clone, Copy, Deserialize, ByteSize, Serialize, Packet, Debug)]
+#[packet(opcode = 0x70BA)]
+pub enum StallUpdate {
+    #[silkroad(value = 2)]
+    AddItem {
+        stall_slot: u8,
+        inventory_slot: u8,
+        price: u64,
+    },
+    #[silkroad(value = 3)]
+    RemoveItem { stall_slot: u8 },
+    #[silkroad(value = 5)]
+    State { open: bool },
+}
+
+#[derive(Clone, Serialize, ByteSize, Packet)]
+#[packet(opcode = 0xB0BA)]
+pub enum StallUpdateResponse {
+    #[silkroad(value = 1)]
+    Success { open: bool, items: Vec<StallItemData> },
+    #[silkroad(value = 2)]
+    Failure(InventoryOperationError),
+}
+
+impl StallUpdateResponse {
+    pub fn success(open: bool, items: Vec<StallItemData>) -> Self {
+        StallUpdateResponse::Success { open, items }
+    }
+}
+
+/// The current content of the stall that is being browsed, sent whenever the owner changes it.
+#[derive(Clone, Serialize, ByteSize, Packet)]
+#[packet(opcode = 0x30BA)]
+pub struct StallContentUpdate {
+    pub open: bool,
+    pub items: Vec<StallItemData>,
+}
+
+impl StallContentUpdate {
+    pub fn new(open: bool, items: Vec<StallItemData>) -> Self {
+        StallContentUpdate { open, items }
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, ByteSize, Serialize, Packet, Debug)]
+#[packet(opcode = 0x70B3)]
+pub struct StallEnter {
+    pub target: u32,
+}
+
+#[derive(Clone, Serialize, ByteSize, Packet)]
+#[packet(opcode = 0xB0B3)]
+pub enum StallEnterResponse {
+    #[silkroad(value = 1)]
+    Success {
+        owner: u32,
+        open: bool,
+        items: Vec<StallItemData>,
+    },
+    #[silkroad(value = 2)]
+    Failure(InventoryOperationError),
+}
+
+impl StallEnterResponse {
+    pub fn success(owner: u32, open: bool, items: Vec<StallItemData>) -> Self {
+        StallEnterResponse::Success { owner, open, items }
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, ByteSize, Serialize, Packet, Debug)]
+#[packet(opcode = 0x70B5)]
+pub struct StallLeave;
+
+#[derive(Clone, Copy, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0xB0B5)]
+pub enum StallLeaveResponse {
+    #[silkroad(value = 1)]
+    Success,
+    #[silkroad(value = 2)]
+    Failure(InventoryOperationError),
+}
+
+#[derive(Clone, Copy, Deserialize, ByteSize, Serialize, Packet, Debug)]
+#[packet(opcode = 0x70B4)]
+pub struct StallBuy {
+    pub stall_slot: u8,
+}
+
+#[derive(Clone, Copy, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0xB0B4)]
+pub enum StallBuyResponse {
+    #[silkroad(value = 1)]
+    Success { stall_slot: u8 },
+    #[silkroad(value = 2)]
+    Failure(InventoryOperationError),
+}
+
+/// Informs the owner and the visitors of a stall about other players entering, leaving or buying
+/// from the stall.
+#[derive(Clone, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0x30B7)]
+pub enum StallVisitorUpdate {
+    #[silkroad(value = 1)]
+    Entered { unique_id: u32 },
+    #[silkroad(value = 2)]
+    Left { unique_id: u32 },
+    #[silkroad(value = 3)]
+    Bought { stall_slot: u8, buyer: String },
+}
+
+#[derive(Clone, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0x30B8)]
+pub struct StallEntityCreated {
+    pub unique_id: u32,
+    #[silkroad(size = 2)]
+    pub title: String,
+    pub decoration: u32,
+}
+
+impl StallEntityCreated {
+    pub fn new(unique_id: u32, title: String, decoration: u32) -> Self {
+        StallEntityCreated {
+            unique_id,
+            title,
+            decoration,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0x30B9)]
+pub struct StallEntityClosed {
+    pub unique_id: u32,
+}
+
+define_inbound_protocol! { StallClientProtocol =>
+    StallCreate,
+    StallClose,
+    StallUpdate,
+    StallEnter,
+    StallLeave,
+    StallBuy
+}
+
+define_outbound_protocol! { StallServerProtocol =>
+    StallCreateResponse,
+    StallCloseResponse,
+    StallUpdateResponse,
+    StallContentUpdate,
+    StallEnterResponse,
+    StallLeaveResponse,
+    StallBuyResponse,
+    StallVisitorUpdate,
+    StallEntityCreated,
+    StallEntityClosed
+}
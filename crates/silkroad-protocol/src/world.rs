@@ -41,6 +41,14 @@ pub enum JobType {
     Hunter,
 }
 
+#[derive(Clone, Eq, PartialEq, Copy, Serialize, ByteSize, Deserialize, Debug)]
+pub enum InteractMode {
+    #[silkroad(value = 0)]
+    None,
+    #[silkroad(value = 4)]
+    Stall,
+}
+
 #[derive(Clone, Eq, PartialEq, PartialOrd, Copy, Serialize, ByteSize, Deserialize, Debug)]
 pub enum PlayerKillState {
     #[silkroad(value = 0xFF)]
@@ -323,7 +331,7 @@ pub enum UpdatedState {
     #[silkroad(value = 8)]
     Battle(bool),
     #[silkroad(value = 11)]
-    Scroll(u8),
+    Scroll(ActiveScroll),
 }
 
 #[derive(Clone, Copy, Serialize, ByteSize, Deserialize, Packet, Debug)]
@@ -354,6 +362,13 @@ impl EntityUpdateState {
             update: UpdatedState::Body(new),
         }
     }
+
+    pub fn scroll(unique_id: u32, new: ActiveScroll) -> Self {
+        EntityUpdateState {
+            unique_id,
+            update: UpdatedState::Scroll(new),
+        }
+    }
 }
 
 #[derive(Clone, Copy, Deserialize, Serialize, ByteSize, Packet, Debug)]
@@ -518,6 +533,76 @@ pub enum IncreaseIntResponse {
     Failure(u16),
 }
 
+#[derive(Clone, Copy, Eq, PartialEq, Serialize, ByteSize, Deserialize, Debug)]
+pub enum InvitationKind {
+    #[silkroad(value = 1)]
+    Exchange,
+    #[silkroad(value = 2)]
+    PartyCreation,
+    #[silkroad(value = 3)]
+    PartyInvitation,
+    #[silkroad(value = 4)]
+    PartyJoinRequest,
+    #[silkroad(value = 5)]
+    GuildInvitation,
+    #[silkroad(value = 6)]
+    UnionInvitation,
+}
+
+/// Asks the player to accept or decline the invitation of another player.
+#[derive(Clone, Copy, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0x3080)]
+pub struct PlayerInvitation {
+    pub kind: InvitationKind,
+    pub requester: u32,
+}
+
+impl PlayerInvitation {
+    pub fn exchange(requester: u32) -> Self {
+        PlayerInvitation {
+            kind: InvitationKind::Exchange,
+            requester,
+        }
+    }
+
+    pub fn party(kind: InvitationKind, requester: u32) -> Self {
+        PlayerInvitation { kind, requester }
+    }
+
+    pub fn guild(requester: u32) -> Self {
+        PlayerInvitation {
+            kind: InvitationKind::GuildInvitation,
+            requester,
+        }
+    }
+
+    pub fn union(requester: u32) -> Self {
+        PlayerInvitation {
+            kind: InvitationKind::UnionInvitation,
+            requester,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0x3080)]
+pub enum PlayerInvitationResponse {
+    #[silkroad(value = 1)]
+    Accept,
+    #[silkroad(value = 2)]
+    Decline(u16),
+}
+
+/// Tells the client to show the loading screen before the player is spawned at a new location.
+#[derive(Clone, Copy, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0x34B5)]
+pub struct TeleportStart;
+
+/// Sent by the client once it is showing the loading screen and awaits the new spawn.
+#[derive(Clone, Copy, Serialize, ByteSize, Deserialize, Packet, Debug)]
+#[packet(opcode = 0x34B6)]
+pub struct TeleportReady;
+
 define_inbound_protocol! { StatClientProtocol =>
     IncreaseStr,
     IncreaseInt
@@ -531,7 +616,9 @@ define_outbound_protocol! { StatServerProtocol =>
 define_inbound_protocol! { WorldClientProtocol =>
     TargetEntity,
     UnTargetEntity,
-    UpdateGameGuide
+    UpdateGameGuide,
+    PlayerInvitationResponse,
+    TeleportReady
 }
 
 define_outbound_protocol! { WorldServerProtocol =>
@@ -540,5 +627,7 @@ define_outbound_protocol! { WorldServerProtocol =>
     EntityBarsUpdate,
     LevelUpEffect,
     PlayerPickupAnimation,
-    GameGuideResponse
+    GameGuideResponse,
+    PlayerInvitation,
+    TeleportStart
 }
@@ -0,0 +1,2 @@
+ALTER TABLE character_items
+    ADD COLUMN magic_params BIGINT[] NOT NULL DEFAULT '{}';
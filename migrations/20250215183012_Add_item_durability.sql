@@ -0,0 +1,3 @@
+ALTER TABLE character_items
+    ADD COLUMN durability INTEGER,
+    ADD COLUMN max_durability INTEGER;
@@ -0,0 +1,2 @@
+ALTER TABLE characters
+    ADD COLUMN recall_point SMALLINT;
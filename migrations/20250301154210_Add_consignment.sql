@@ -0,0 +1,21 @@
+CREATE TABLE consignment_listings
+(
+    server_id      SMALLINT    NOT NULL,
+    id             INTEGER     NOT NULL,
+    seller_id      INTEGER     NOT NULL REFERENCES characters ON DELETE CASCADE,
+    item_obj_id    INTEGER     NOT NULL,
+    upgrade_level  SMALLINT    NOT NULL DEFAULT 0,
+    variance       BIGINT,
+    amount         SMALLINT    NOT NULL DEFAULT 1,
+    magic_params   BIGINT[]    NOT NULL DEFAULT '{}',
+    durability     INTEGER,
+    max_durability INTEGER,
+    price          BIGINT      NOT NULL,
+    deposit        BIGINT      NOT NULL,
+    fee            BIGINT      NOT NULL,
+    end_date       TIMESTAMPTZ NOT NULL,
+    sold           BOOLEAN     NOT NULL DEFAULT FALSE,
+    PRIMARY KEY (server_id, id)
+);
+
+CREATE INDEX consignment_listings_seller_id_index ON consignment_listings (seller_id);
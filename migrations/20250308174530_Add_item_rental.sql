@@ -0,0 +1,4 @@
+ALTER TABLE character_items
+    ADD COLUMN rent_type SMALLINT,
+    ADD COLUMN rent_start TIMESTAMPTZ,
+    ADD COLUMN rent_end TIMESTAMPTZ;
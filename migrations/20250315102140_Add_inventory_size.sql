@@ -0,0 +1,2 @@
+ALTER TABLE characters
+    ADD COLUMN inventory_size SMALLINT DEFAULT 45 NOT NULL;
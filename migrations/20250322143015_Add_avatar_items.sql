@@ -0,0 +1,17 @@
+CREATE TABLE character_avatar_items
+(
+    id             SERIAL PRIMARY KEY,
+    character_id   INTEGER  NOT NULL REFERENCES characters (id) ON DELETE CASCADE,
+    item_obj_id    INTEGER  NOT NULL,
+    upgrade_level  SMALLINT NOT NULL DEFAULT 0,
+    slot           SMALLINT NOT NULL,
+    variance       BIGINT,
+    amount         SMALLINT NOT NULL DEFAULT 1,
+    magic_params   BIGINT[] NOT NULL DEFAULT '{}',
+    durability     INTEGER,
+    max_durability INTEGER,
+    rent_type      SMALLINT,
+    rent_start     TIMESTAMPTZ,
+    rent_end       TIMESTAMPTZ,
+    CONSTRAINT character_avatar_items_slot_uniq UNIQUE (character_id, slot)
+);
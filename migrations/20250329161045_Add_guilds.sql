@@ -0,0 +1,24 @@
+CREATE TABLE guilds
+(
+    id           SERIAL PRIMARY KEY,
+    server_id    SMALLINT    NOT NULL,
+    name         VARCHAR     NOT NULL,
+    level        SMALLINT    NOT NULL DEFAULT 1,
+    gp           INTEGER     NOT NULL DEFAULT 0,
+    notice_title VARCHAR     NOT NULL DEFAULT '',
+    notice       VARCHAR     NOT NULL DEFAULT '',
+    created_at   TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE UNIQUE INDEX guilds_server_id_name_uindex ON guilds (server_id, name);
+
+CREATE TABLE guild_members
+(
+    character_id INTEGER     NOT NULL PRIMARY KEY REFERENCES characters ON DELETE CASCADE,
+    guild_id     INTEGER     NOT NULL REFERENCES guilds ON DELETE CASCADE,
+    master       BOOLEAN     NOT NULL DEFAULT FALSE,
+    permissions  INTEGER     NOT NULL DEFAULT 0,
+    joined_at    TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX guild_members_guild_id_index ON guild_members (guild_id);
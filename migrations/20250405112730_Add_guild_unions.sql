@@ -0,0 +1,4 @@
+ALTER TABLE guilds
+    ADD COLUMN union_id INTEGER REFERENCES guilds ON DELETE SET NULL;
+
+CREATE INDEX guilds_union_id_index ON guilds (union_id);
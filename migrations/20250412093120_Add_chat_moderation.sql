@@ -0,0 +1,21 @@
+CREATE TYPE "ChatChannel" AS ENUM ('ALL', 'PRIVATE', 'PARTY', 'GUILD');
+
+CREATE TABLE character_mutes
+(
+    character_id INTEGER     NOT NULL PRIMARY KEY REFERENCES characters ON DELETE CASCADE,
+    muted_until  TIMESTAMPTZ NOT NULL,
+    reason       VARCHAR     NOT NULL DEFAULT '',
+    muted_by     VARCHAR     NOT NULL DEFAULT ''
+);
+
+CREATE TABLE chat_logs
+(
+    id           BIGSERIAL PRIMARY KEY,
+    character_id INTEGER       NOT NULL REFERENCES characters ON DELETE CASCADE,
+    channel      "ChatChannel" NOT NULL,
+    recipient    VARCHAR,
+    message      VARCHAR       NOT NULL,
+    sent_at      TIMESTAMPTZ   NOT NULL DEFAULT now()
+);
+
+CREATE INDEX chat_logs_character_id_sent_at_index ON chat_logs (character_id, sent_at);
@@ -0,0 +1,7 @@
+CREATE TABLE character_blocks
+(
+    character_id INTEGER     NOT NULL REFERENCES characters ON DELETE CASCADE,
+    blocked_id   INTEGER     NOT NULL REFERENCES characters ON DELETE CASCADE,
+    blocked_at   TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (character_id, blocked_id)
+);
@@ -0,0 +1,13 @@
+CREATE TABLE memos
+(
+    id           SERIAL PRIMARY KEY,
+    recipient_id INTEGER     NOT NULL REFERENCES characters ON DELETE CASCADE,
+    sender_id    INTEGER REFERENCES characters ON DELETE SET NULL,
+    sender_name  VARCHAR     NOT NULL,
+    message      VARCHAR     NOT NULL,
+    gold         BIGINT      NOT NULL DEFAULT 0,
+    read         BOOLEAN     NOT NULL DEFAULT FALSE,
+    sent_at      TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX memos_recipient_id_index ON memos (recipient_id);
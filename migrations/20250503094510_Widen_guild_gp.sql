@@ -0,0 +1,2 @@
+ALTER TABLE guilds
+    ALTER COLUMN gp TYPE BIGINT;
@@ -194,7 +194,7 @@ pub(crate) fn apply_goal(
                         .location()
                         .point_in_line_with_range(target_pos.location(), range - 0.1);
                     let new_height = navmesh
-                        .height_for(new_target_position)
+                        .height_near(new_target_position, target_pos.position().y)
                         .unwrap_or(target_pos.position().y);
                     let final_position = new_target_position.with_y(new_height);
                     state.push(Transition::new(AgentState::Moving(MovementTarget::Location(
@@ -248,7 +248,9 @@ pub(crate) fn apply_goal(
                     let my_location = position.location();
                     let target_movement_pos = my_location.point_in_line_with_range(target_pos.location(), range - 0.1);
 
-                    let target_height = navmesh.height_for(target_movement_pos).unwrap_or(position.position().y);
+                    let target_height = navmesh
+                        .height_near(target_movement_pos, target_pos.position().y)
+                        .unwrap_or(position.position().y);
                     state.push(Transition::new(AgentState::Moving(MovementTarget::Location(
                         target_movement_pos.with_y(target_height),
                     ))));
@@ -273,7 +275,9 @@ pub(crate) fn apply_goal(
                     let dir_vector = position.position().to_flat_vec2() - target_pos.position().to_flat_vec2();
                     let target_vector = dir_vector.normalize() * FOLLOW_DISTANCE_SQUARED;
                     let target_location = GlobalLocation(position.position().to_flat_vec2() - target_vector);
-                    let height = navmesh.height_for(target_location).unwrap_or(position.position().y);
+                    let height = navmesh
+                        .height_near(target_location, target_pos.position().y)
+                        .unwrap_or(position.position().y);
                     state.push(Transition::new(AgentState::Moving(MovementTarget::Location(
                         target_location.with_y(height),
                     ))));
@@ -1,11 +1,13 @@
 use crate::agent::component::AgentGoalReachedEvent;
 use crate::agent::goal::{apply_goal, handle_state_reached_notification};
+use crate::agent::pathfinding::{plan_paths, PathCache};
 use crate::agent::state::{run_transitions, StateTransitionEvent};
 use crate::agent::system::{action, movement, movement_input, pickup, turning};
 use bevy::prelude::*;
 
 pub mod component;
 pub mod goal;
+pub(crate) mod pathfinding;
 pub mod state;
 mod system;
 
@@ -21,7 +23,8 @@ pub(crate) enum AgentSet {
 
 impl Plugin for AgentPlugin {
     fn build(&self, app: &mut App) {
-        app.configure_sets(PreUpdate, AgentSet::Input)
+        app.init_resource::<PathCache>()
+            .configure_sets(PreUpdate, AgentSet::Input)
             .configure_sets(Update, (AgentSet::Transition, AgentSet::Execute).chain())
             .configure_sets(PostUpdate, AgentSet::Broadcast)
             .add_systems(PreUpdate, (movement_input, turning).in_set(AgentSet::Input))
@@ -31,7 +34,10 @@ impl Plugin for AgentPlugin {
                     .chain()
                     .in_set(AgentSet::Transition),
             )
-            .add_systems(Update, (pickup, movement, action).in_set(AgentSet::Execute));
+            .add_systems(
+                Update,
+                (pickup, plan_paths.before(movement), movement, action).in_set(AgentSet::Execute),
+            );
         app.add_event::<StateTransitionEvent>()
             .add_event::<AgentGoalReachedEvent>();
     }
@@ -0,0 +1,115 @@
+use crate::agent::state::{MovementTarget, Moving};
+use crate::comp::player::Player;
+use crate::comp::pos::Position;
+use crate::ext::Navmesh;
+use bevy::prelude::*;
+use cgmath::MetricSpace;
+use silkroad_definitions::Region;
+use silkroad_game_base::{GlobalLocation, GlobalPosition};
+use std::collections::{HashMap, VecDeque};
+
+/// How many cells a single path search may visit before giving up.
+const MAX_CELLS_PER_SEARCH: usize = 2_000;
+/// How many cells all path searches within a single tick may visit together. Agents that didn't
+/// get to search for a path keep walking straight and try again in the next tick.
+const MAX_CELLS_PER_TICK: usize = 10_000;
+const PATH_CACHE_SIZE: usize = 1_024;
+
+type CellKey = (Region, u16);
+
+/// The waypoints an agent walks along to reach the location it's moving to.
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+pub(crate) struct FollowingPath {
+    destination: GlobalPosition,
+    waypoints: VecDeque<GlobalLocation>,
+}
+
+impl FollowingPath {
+    pub(crate) fn is_for(&self, destination: GlobalPosition) -> bool {
+        self.destination.distance2(destination.0) < 1.0
+    }
+
+    pub(crate) fn next_waypoint(&self) -> Option<GlobalLocation> {
+        self.waypoints.front().copied()
+    }
+
+    pub(crate) fn has_further_waypoints(&self) -> bool {
+        self.waypoints.len() > 1
+    }
+
+    pub(crate) fn reach_waypoint(&mut self) {
+        self.waypoints.pop_front();
+    }
+}
+
+/// Paths that have been found recently, keyed by the cells of their start and their destination,
+/// such that agents moving between the same places don't need to search again.
+#[derive(Resource, Default)]
+pub(crate) struct PathCache {
+    paths: HashMap<(CellKey, CellKey), Vec<GlobalLocation>>,
+}
+
+impl PathCache {
+    fn get(&self, key: (CellKey, CellKey), destination: GlobalLocation) -> Option<VecDeque<GlobalLocation>> {
+        let path = self.paths.get(&key)?;
+        let mut waypoints: VecDeque<_> = path.iter().copied().collect();
+        // The destination may be anywhere inside the destination cell.
+        waypoints.pop_back();
+        waypoints.push_back(destination);
+        Some(waypoints)
+    }
+
+    fn insert(&mut self, key: (CellKey, CellKey), path: Vec<GlobalLocation>) {
+        if self.paths.len() >= PATH_CACHE_SIZE {
+            self.paths.clear();
+        }
+        self.paths.insert(key, path);
+    }
+}
+
+pub(crate) fn plan_paths(
+    query: Query<(Entity, &Position, &Moving, Option<&FollowingPath>), Without<Player>>,
+    navmesh: Res<Navmesh>,
+    mut cache: ResMut<PathCache>,
+    mut cmd: Commands,
+) {
+    let mut budget = MAX_CELLS_PER_TICK;
+    for (entity, position, moving, path) in query.iter() {
+        let MovementTarget::Location(destination) = moving.parameter else {
+            continue;
+        };
+
+        if path.is_some_and(|path| path.is_for(destination)) {
+            continue;
+        }
+
+        let from = position.location();
+        let to = destination.to_location();
+        let key = navmesh.cell_at(from.0).zip(navmesh.cell_at(to.0));
+        let waypoints = match key.and_then(|key| cache.get(key, to)) {
+            Some(waypoints) => waypoints,
+            None => {
+                if budget < MAX_CELLS_PER_SEARCH {
+                    continue;
+                }
+
+                let search = navmesh.find_path(from.0, to.0, MAX_CELLS_PER_SEARCH);
+                budget = budget.saturating_sub(search.visited_cells);
+                match search.path {
+                    Ok(path) => {
+                        let path: Vec<_> = path.into_iter().map(GlobalLocation).collect();
+                        if let Some(key) = key {
+                            cache.insert(key, path.clone());
+                        }
+                        path.into()
+                    },
+                    // Without a known way, walk straight until something blocks the way.
+                    Err(_) => VecDeque::from([to]),
+                }
+            },
+        };
+
+        cmd.entity(entity).try_insert(FollowingPath { destination, waypoints });
+    }
+}
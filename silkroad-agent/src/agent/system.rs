@@ -1,16 +1,18 @@
 use crate::agent::component::{Agent, MovementState};
 use crate::agent::goal::{AgentGoal, GoalTracker};
+use crate::agent::pathfinding::FollowingPath;
 use crate::agent::state::{
     Idle, MovementTarget as AgentMovementTarget, Moving, PerformingSkill, PickingUp, SkillParameter,
     SkillProgressState, SkillTarget,
 };
 use crate::comp::gold::GoldPouch;
-use crate::comp::inventory::PlayerInventory;
+use crate::comp::inventory::{item_content_data, rent_info, PlayerInventory};
 use crate::comp::net::Client;
 use crate::comp::pos::Position;
 use crate::comp::{drop, EntityReference, GameEntity, Health, Mana};
 use crate::event::{ConsumeItemEvent, DamageReceiveEvent, SkillDefinition};
 use crate::ext::{ActionIdCounter, Navmesh};
+use crate::game::inventory::find_consumable_slot;
 use crate::input::PlayerInput;
 use crate::world::WorldData;
 use bevy::ecs::query::QueryEntityError;
@@ -18,10 +20,11 @@ use bevy::prelude::*;
 use cgmath::{Array, Deg, InnerSpace, Quaternion, Rotation3, Vector2, Vector3, Zero};
 use silkroad_data::skilldata::SkillParam;
 use silkroad_data::DataEntry;
-use silkroad_definitions::type_id::{ObjectItem, ObjectType};
+use silkroad_definitions::type_id::{ObjectConsumable, ObjectItem, ObjectType};
+use silkroad_definitions::Region;
 use silkroad_game_base::{GlobalLocation, Heading, ItemTypeData, LocalLocation, Vector3Ext};
 use silkroad_protocol::combat::{DoActionResponseCode, PerformActionError, PerformActionResponse};
-use silkroad_protocol::inventory::{InventoryItemContentData, InventoryOperationError, InventoryOperationResult};
+use silkroad_protocol::inventory::{InventoryOperationError, InventoryOperationResult};
 use silkroad_protocol::movement::MovementTarget;
 use std::ops::Deref;
 use std::time::Duration;
@@ -36,10 +39,22 @@ pub(crate) fn movement_input(
     for (client, input, position, mut goal) in query.iter_mut() {
         if let Some(kind) = input.movement {
             match kind {
-                MovementTarget::TargetLocation { region, x, y: _, z } => {
+                MovementTarget::TargetLocation { region, x, y, z } => {
                     let local_position = position.position().to_local();
-                    let target_loc = LocalLocation(region.into(), Vector2::new(x.into(), z.into()));
-                    let target_height = navmesh.height_for(target_loc).unwrap_or(position.position().y);
+                    let region = Region::from(region);
+                    // Coordinates inside dungeons may be negative.
+                    let (x, z) = if region.is_dungeon() {
+                        (f32::from(x as i16), f32::from(z as i16))
+                    } else {
+                        (f32::from(x), f32::from(z))
+                    };
+                    let target_loc = LocalLocation(region, Vector2::new(x, z));
+                    // The client sends the height of the clicked spot, which tells apart clicking on a
+                    // bridge from clicking on the ground below it.
+                    let clicked_height = f32::from(y as i16);
+                    let target_height = navmesh
+                        .height_near(target_loc, clicked_height)
+                        .unwrap_or(position.position().y);
                     let target_pos = target_loc.with_y(target_height);
                     debug!(identifier = ?client.id(), "Movement: {} -> {}", local_position, target_pos);
                     goal.switch_goal_notified(AgentGoal::moving_to(target_pos.to_global()));
@@ -94,10 +109,9 @@ pub(crate) fn pickup(
                     if let Some(slot) = inventory.add_item(drop.item) {
                         client.send(InventoryOperationResult::success_gain_item(
                             slot,
+                            rent_info(&drop.item),
                             drop.item.reference.ref_id(),
-                            InventoryItemContentData::Expendable {
-                                stack_size: drop.item.stack_size(),
-                            },
+                            item_content_data(&drop.item),
                         ));
                     } else {
                         client.send(InventoryOperationResult::Failure(
@@ -119,6 +133,7 @@ pub(crate) fn action(
         Option<&mut Mana>,
         &mut Health,
         Option<&PlayerInventory>,
+        Option<&Client>,
     )>,
     target_query: Query<&GameEntity>,
     time: Res<Time>,
@@ -126,7 +141,7 @@ pub(crate) fn action(
     mut cmd: Commands,
 ) {
     let delta = time.delta();
-    for (entity, game_entity, mut action, mana, mut health, inventory) in query.iter_mut() {
+    'skills: for (entity, game_entity, mut action, mana, mut health, inventory, client) in query.iter_mut() {
         if action.timer.tick(delta).just_finished() {
             let Some(next) = action.progress.next() else {
                 if let Some(next_skill) = action.parameter.skill.next_in_chain {
@@ -144,6 +159,41 @@ pub(crate) fn action(
             };
 
             if next == SkillProgressState::Casting {
+                let mut consumptions = Vec::new();
+                for param in &action.parameter.skill.params {
+                    match param {
+                        SkillParam::RequiredItem(item) => {
+                            let type_id = ObjectType::Item(ObjectItem::Equippable(*item)).type_id();
+                            let Some(inventory) = inventory else {
+                                cmd.entity(entity).remove::<PerformingSkill>();
+                                warn!("Tried to use skill with item, but doesn't have inventory.");
+                                continue;
+                            };
+                            if !inventory
+                                .equipment_items()
+                                .any(|(_, equip_item)| type_id == equip_item.reference.common.type_id)
+                            {
+                                warn!("Missing requirement equipment");
+                            }
+                        },
+                        SkillParam::ConsumeItem { kind, amount } => {
+                            let amount = u16::from(*amount);
+                            match inventory.and_then(|inventory| find_consumable_slot(inventory, *kind, amount)) {
+                                Some(slot) => consumptions.push((slot, amount)),
+                                None => {
+                                    cmd.entity(entity).remove::<PerformingSkill>();
+                                    debug!("Cancelling skill due to missing items to consume.");
+                                    if let Some(client) = client {
+                                        client.send(PerformActionResponse::Stop(missing_consumable_error(*kind)));
+                                    }
+                                    continue 'skills;
+                                },
+                            }
+                        },
+                        _ => {},
+                    };
+                }
+
                 if action.parameter.skill.consumed_mp > 0 {
                     let Some(mut mana) = mana else {
                         cmd.entity(entity).remove::<PerformingSkill>();
@@ -171,45 +221,12 @@ pub(crate) fn action(
                     health.reduce(action.parameter.skill.consumed_hp);
                 }
 
-                for param in &action.parameter.skill.params {
-                    match param {
-                        SkillParam::RequiredItem(item) => {
-                            let type_id = ObjectType::Item(ObjectItem::Equippable(*item)).type_id();
-                            let Some(inventory) = inventory else {
-                                cmd.entity(entity).remove::<PerformingSkill>();
-                                warn!("Tried to use skill with item, but doesn't have inventory.");
-                                continue;
-                            };
-                            if !inventory
-                                .equipment_items()
-                                .any(|(_, equip_item)| type_id == equip_item.reference.common.type_id)
-                            {
-                                warn!("Missing requirement equipment");
-                            }
-                        },
-                        SkillParam::ConsumeItem { kind, amount } => {
-                            // TODO: handle non-ammo type
-                            let amount = *amount as u16;
-                            let type_id = ObjectType::Item(ObjectItem::Consumable(*kind)).type_id();
-                            let Some(inventory) = inventory else {
-                                cmd.entity(entity).remove::<PerformingSkill>();
-                                warn!("Tried to use skill with item, but doesn't have inventory.");
-                                continue;
-                            };
-                            if !inventory.equipment_items().any(|(_, equip_item)| {
-                                equip_item.reference.common.type_id == type_id && equip_item.stack_size() >= amount
-                            }) {
-                                warn!("Can't consume item, because it's not equipped");
-                            } else {
-                                cmd.send_event(ConsumeItemEvent {
-                                    player: entity,
-                                    item: type_id,
-                                    amount,
-                                });
-                            }
-                        },
-                        _ => {},
-                    };
+                for (slot, amount) in consumptions {
+                    cmd.send_event(ConsumeItemEvent {
+                        player: entity,
+                        slot,
+                        amount,
+                    });
                 }
             }
 
@@ -249,17 +266,37 @@ pub(crate) fn action(
 }
 
 pub(crate) fn movement(
-    mut query: Query<(Entity, &mut Position, &Agent, &Moving, &MovementState)>,
+    mut query: Query<(
+        Entity,
+        &mut Position,
+        &Agent,
+        &Moving,
+        &MovementState,
+        &mut GoalTracker,
+        Option<&mut FollowingPath>,
+    )>,
     time: Res<Time>,
     mut cmd: Commands,
     navmesh: Res<Navmesh>,
 ) {
     let delta = time.delta_secs();
-    for (entity, mut pos, agent, movement, speed_state) in query.iter_mut() {
+    for (entity, mut pos, agent, movement, speed_state, mut goal, path) in query.iter_mut() {
         let speed = agent.get_speed_value(*speed_state.deref());
         let (next_location, heading, finished) = match movement.parameter {
             AgentMovementTarget::Location(location) => {
-                get_next_step(delta, pos.location(), speed, location.to_location())
+                let mut path = path.filter(|path| path.is_for(location));
+                let waypoint = path
+                    .as_ref()
+                    .and_then(|path| path.next_waypoint())
+                    .unwrap_or(location.to_location());
+                let (next_location, heading, reached) = get_next_step(delta, pos.location(), speed, waypoint);
+                match path.as_mut() {
+                    Some(path) if reached && path.has_further_waypoints() => {
+                        path.reach_waypoint();
+                        (next_location, heading, false)
+                    },
+                    _ => (next_location, heading, reached),
+                }
             },
             AgentMovementTarget::Direction(direction) => {
                 let current_location_2d = pos.location().0;
@@ -270,10 +307,19 @@ pub(crate) fn movement(
             },
         };
 
+        let (next_location, blocked) = navmesh.clamp_movement(pos.position(), next_location);
         move_with_step(&navmesh, &mut pos, next_location, heading);
 
-        if finished {
-            cmd.entity(entity).remove::<Moving>().try_insert(Idle);
+        if blocked {
+            // Trying to reach the goal again would only run into the same blocker.
+            goal.reset();
+        }
+
+        if finished || blocked {
+            cmd.entity(entity)
+                .remove::<Moving>()
+                .remove::<FollowingPath>()
+                .try_insert(Idle);
         }
     }
 }
@@ -301,7 +347,9 @@ fn get_next_step(
 
 fn move_with_step(navmesh: &Navmesh, pos: &mut Position, target: GlobalLocation, heading: Heading) {
     let target_location = target.to_local();
-    let height = navmesh.height_for(target_location).unwrap_or(pos.position().0.y);
+    let height = navmesh
+        .height_near(target_location, pos.position().0.y)
+        .unwrap_or(pos.position().0.y);
 
     let position = target.with_y(height);
     pos.update(position, heading);
@@ -314,3 +362,11 @@ pub(crate) fn turning(mut query: Query<(&mut Position, &PlayerInput), With<Idle>
         }
     }
 }
+
+fn missing_consumable_error(kind: ObjectConsumable) -> PerformActionError {
+    match kind {
+        ObjectConsumable::Ammo(_) => PerformActionError::InsufficientAmmunition,
+        // There doesn't seem to be a more fitting error for other kinds of consumables.
+        _ => PerformActionError::InvalidWeapon,
+    }
+}
@@ -0,0 +1,122 @@
+use chrono::{DateTime, Utc};
+use sqlx::{Error, PgPool};
+use std::fmt::{Display, Formatter};
+use tracing::error;
+
+/// The chat channels of which messages are logged for moderation.
+#[derive(sqlx::Type, Copy, Clone, Debug)]
+#[sqlx(type_name = "ChatChannel")]
+#[sqlx(rename_all = "UPPERCASE")]
+pub(crate) enum ChatChannel {
+    All,
+    Private,
+    Party,
+    Guild,
+}
+
+impl Display for ChatChannel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ChatChannel::All => "All",
+            ChatChannel::Private => "Private",
+            ChatChannel::Party => "Party",
+            ChatChannel::Guild => "Guild",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+pub(crate) struct ChatLogRow {
+    pub channel: ChatChannel,
+    pub recipient: Option<String>,
+    pub message: String,
+    pub sent_at: DateTime<Utc>,
+}
+
+/// Loads the end of the mute of the character, if they're currently muted.
+pub(crate) async fn load_mute(character_id: u32, pool: PgPool) -> Result<Option<DateTime<Utc>>, Error> {
+    let mute = sqlx::query!(
+        "SELECT muted_until FROM character_mutes WHERE character_id = $1 AND muted_until > NOW()",
+        character_id as i32
+    )
+    .fetch_optional(&pool)
+    .await?;
+    Ok(mute.map(|mute| mute.muted_until))
+}
+
+/// Mutes the character with the given name, returning `false` if no such character exists.
+pub(crate) async fn mute_character(
+    name: String,
+    server_id: u16,
+    until: DateTime<Utc>,
+    reason: String,
+    muted_by: String,
+    pool: PgPool,
+) -> Result<bool, Error> {
+    let result = sqlx::query!(
+        "INSERT INTO character_mutes(character_id, muted_until, reason, muted_by) SELECT id, $3, $4, $5 FROM characters WHERE charname = $1 AND server_id = $2 ON CONFLICT(character_id) DO UPDATE SET muted_until = EXCLUDED.muted_until, reason = EXCLUDED.reason, muted_by = EXCLUDED.muted_by",
+        name,
+        server_id as i32,
+        until,
+        reason,
+        muted_by,
+    )
+    .execute(&pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Lifts the mute of the character with the given name, returning `false` if they weren't muted.
+pub(crate) async fn unmute_character(name: String, server_id: u16, pool: PgPool) -> Result<bool, Error> {
+    let result = sqlx::query!(
+        "DELETE FROM character_mutes WHERE character_id IN (SELECT id FROM characters WHERE charname = $1 AND server_id = $2)",
+        name,
+        server_id as i32,
+    )
+    .execute(&pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub(crate) async fn log_chat_message(
+    character_id: u32,
+    channel: ChatChannel,
+    recipient: Option<String>,
+    message: String,
+    pool: PgPool,
+) {
+    let result = sqlx::query!(
+        "INSERT INTO chat_logs(character_id, channel, recipient, message) VALUES($1, $2, $3, $4)",
+        character_id as i32,
+        channel as ChatChannel,
+        recipient,
+        message,
+    )
+    .execute(&pool)
+    .await;
+
+    if let Err(e) = result {
+        error!(error = %e, character_id, "Could not log chat message.");
+    }
+}
+
+/// Finds the most recent messages of the character with the given name, optionally only those
+/// containing the given text.
+pub(crate) async fn search_chat_logs(
+    name: String,
+    server_id: u16,
+    contains: Option<String>,
+    limit: u32,
+    pool: PgPool,
+) -> Result<Vec<ChatLogRow>, Error> {
+    sqlx::query_as!(
+        ChatLogRow,
+        "SELECT l.channel AS \"channel: ChatChannel\", l.recipient, l.message, l.sent_at FROM chat_logs l JOIN characters c ON c.id = l.character_id WHERE c.charname = $1 AND c.server_id = $2 AND ($3::VARCHAR IS NULL OR l.message ILIKE '%' || $3 || '%') ORDER BY l.sent_at DESC LIMIT $4",
+        name,
+        server_id as i32,
+        contains,
+        i64::from(limit),
+    )
+    .fetch_all(&pool)
+    .await
+}
@@ -0,0 +1,87 @@
+use crate::config::ChatConfig;
+use bevy::prelude::*;
+
+/// Replaces blocked words in chat messages, ignoring the case in which they were written.
+#[derive(Resource, Default)]
+pub(crate) struct ChatFilter {
+    blocked_words: Vec<Vec<char>>,
+    replacement: String,
+}
+
+impl ChatFilter {
+    pub(crate) fn new(config: &ChatConfig) -> Self {
+        ChatFilter {
+            blocked_words: config
+                .blocked_words
+                .iter()
+                .filter(|word| !word.is_empty())
+                .map(|word| word.chars().collect())
+                .collect(),
+            replacement: config.replacement.clone(),
+        }
+    }
+
+    pub(crate) fn censor(&self, message: &str) -> String {
+        if self.blocked_words.is_empty() {
+            return message.to_string();
+        }
+
+        let chars = message.chars().collect::<Vec<_>>();
+        let mut censored = String::with_capacity(message.len());
+        let mut index = 0;
+        while index < chars.len() {
+            let remaining = &chars[index..];
+            match self
+                .blocked_words
+                .iter()
+                .find(|word| starts_with_ignore_case(remaining, word))
+            {
+                Some(word) => {
+                    censored.push_str(&self.replacement);
+                    index += word.len();
+                },
+                None => {
+                    censored.push(chars[index]);
+                    index += 1;
+                },
+            }
+        }
+        censored
+    }
+}
+
+fn starts_with_ignore_case(text: &[char], word: &[char]) -> bool {
+    text.len() >= word.len()
+        && text
+            .iter()
+            .zip(word)
+            .all(|(text, word)| text.to_lowercase().eq(word.to_lowercase()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn filter(words: &[&str]) -> ChatFilter {
+        ChatFilter::new(&ChatConfig {
+            blocked_words: words.iter().map(|word| word.to_string()).collect(),
+            replacement: "***".to_string(),
+        })
+    }
+
+    #[test]
+    fn test_censor_ignores_case() {
+        let filter = filter(&["bad", "worse"]);
+        assert_eq!(
+            filter.censor("This is BAD, but that is Worse."),
+            "This is ***, but that is ***."
+        );
+    }
+
+    #[test]
+    fn test_censor_keeps_clean_messages() {
+        let filter = filter(&["bad"]);
+        assert_eq!(filter.censor("Hello there!"), "Hello there!");
+        assert_eq!(filter(&[]).censor("bad"), "bad");
+    }
+}
@@ -1,12 +1,32 @@
+pub(crate) mod db;
+mod filter;
+pub(crate) mod moderation;
 mod system;
 
-use crate::chat::system::{handle_chat, handle_gm_commands};
+use crate::chat::filter::ChatFilter;
+use crate::chat::moderation::{expire_mutes, finish_loading_mutes, load_mutes};
+use crate::chat::system::{handle_chat, handle_global_chat, handle_gm_commands};
+use crate::config::GameConfig;
 use bevy::prelude::*;
 
 pub(crate) struct ChatPlugin;
 
 impl Plugin for ChatPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, (handle_chat, handle_gm_commands));
+        let filter = ChatFilter::new(
+            &app.world()
+                .get_resource::<GameConfig>()
+                .expect("Game config should exist.")
+                .chat,
+        );
+        app.insert_resource(filter).add_systems(
+            Update,
+            (
+                handle_chat,
+                handle_global_chat,
+                handle_gm_commands,
+                (load_mutes, finish_loading_mutes, expire_mutes).chain(),
+            ),
+        );
     }
 }
@@ -0,0 +1,71 @@
+use crate::chat::db::load_mute;
+use crate::comp::player::Player;
+use crate::event::LoadingFinishedEvent;
+use crate::ext::DbPool;
+use crate::tasks::TaskCreator;
+use bevy::prelude::*;
+use chrono::{DateTime, Utc};
+use sqlx::{Error, PgPool};
+use tokio::sync::oneshot::error::TryRecvError;
+use tokio::sync::oneshot::Receiver;
+use tracing::error;
+
+/// Prevents the player from chatting until the mute runs out.
+#[derive(Component, Copy, Clone)]
+pub(crate) struct Muted {
+    pub(crate) until: DateTime<Utc>,
+}
+
+impl Muted {
+    pub(crate) fn is_active(&self) -> bool {
+        self.until > Utc::now()
+    }
+}
+
+#[derive(Component)]
+pub(crate) struct MuteLoading(Receiver<Result<Option<DateTime<Utc>>, Error>>);
+
+pub(crate) fn load_mutes(
+    mut reader: EventReader<LoadingFinishedEvent>,
+    query: Query<&Player>,
+    task_creator: Res<TaskCreator>,
+    pool: Res<DbPool>,
+    mut cmd: Commands,
+) {
+    for LoadingFinishedEvent(entity) in reader.read() {
+        let Ok(player) = query.get(*entity) else {
+            continue;
+        };
+
+        let receiver = task_creator.create_task(load_mute(player.character.id, PgPool::clone(&pool)));
+        cmd.entity(*entity).try_insert(MuteLoading(receiver));
+    }
+}
+
+pub(crate) fn finish_loading_mutes(mut query: Query<(Entity, &Player, &mut MuteLoading)>, mut cmd: Commands) {
+    for (entity, player, mut loading) in query.iter_mut() {
+        let until = match loading.0.try_recv() {
+            Ok(Ok(until)) => until,
+            Ok(Err(e)) => {
+                error!(error = %e, id = player.character.id, "Could not load mute.");
+                None
+            },
+            Err(TryRecvError::Empty) => continue,
+            Err(TryRecvError::Closed) => None,
+        };
+
+        let mut entity = cmd.entity(entity);
+        entity.remove::<MuteLoading>();
+        if let Some(until) = until {
+            entity.try_insert(Muted { until });
+        }
+    }
+}
+
+pub(crate) fn expire_mutes(query: Query<(Entity, &Muted)>, mut cmd: Commands) {
+    for (entity, muted) in query.iter() {
+        if !muted.is_active() {
+            cmd.entity(entity).remove::<Muted>();
+        }
+    }
+}
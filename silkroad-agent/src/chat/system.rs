@@ -1,5 +1,9 @@
+use crate::chat::db::{log_chat_message, ChatChannel};
+use crate::chat::filter::ChatFilter;
+use crate::chat::moderation::Muted;
 use crate::cmd::{CommandExecutionExt, Sender};
 use crate::comp::damage::Invincible;
+use crate::comp::inventory::PlayerInventory;
 use crate::comp::monster::SpawnedBy;
 use crate::comp::net::Client;
 use crate::comp::player::Player;
@@ -7,34 +11,65 @@ use crate::comp::pos::Position;
 use crate::comp::visibility::{Invisible, Visibility};
 use crate::comp::GameEntity;
 use crate::event::SpawnMonster;
+use crate::ext::DbPool;
+use crate::friends::block::is_blocked_by;
+use crate::friends::list::BlockList;
 use crate::game::drop::SpawnDrop;
+use crate::game::inventory::is_global_chat_scroll;
+use crate::game::stall::{Stall, VisitingStall};
+use crate::guild::guilds::{Guild, Guilds, InGuild};
 use crate::input::PlayerInput;
+use crate::party::parties::{InParty, Parties};
+use crate::tasks::TaskCreator;
 use crate::world::{EntityLookup, WorldData};
 use bevy::prelude::*;
 use silkroad_definitions::type_id::{ObjectConsumable, ObjectConsumableCurrency, ObjectItem, ObjectType};
-use silkroad_game_base::{Item, ItemTypeData};
+use silkroad_game_base::{Durability, Item, ItemTypeData, MagicOptions};
 use silkroad_protocol::chat::{
     ChatClientProtocol, ChatErrorCode, ChatMessage, ChatMessageResponse, ChatMessageResult, ChatSource, ChatTarget,
     ChatUpdate,
 };
 use silkroad_protocol::gm::{GmCommand, GmResponse};
+use silkroad_protocol::inventory::{InventoryOperationError, UseItemResponse};
+use sqlx::PgPool;
 use tracing::debug;
 
 fn can_send_message(message: &ChatMessage, player: &Player) -> bool {
     match message.target {
-        ChatTarget::AllGm => player.character.gm,
-        ChatTarget::NPC | ChatTarget::Notice | ChatTarget::Global => false,
+        ChatTarget::AllGm | ChatTarget::Notice => player.character.gm,
+        // Global messages can only be sent by using a global chatting scroll.
+        ChatTarget::NPC | ChatTarget::Global => false,
         _ => true,
     }
 }
 
 pub(crate) fn handle_chat(
-    mut query: Query<(Entity, &Client, &GameEntity, &PlayerInput, &Visibility, &Player)>,
+    mut query: Query<(
+        Entity,
+        &Client,
+        &GameEntity,
+        &PlayerInput,
+        &Visibility,
+        &Player,
+        Option<&VisitingStall>,
+        Option<&InParty>,
+        Option<&InGuild>,
+        Option<&Muted>,
+    )>,
     lookup: Res<EntityLookup>,
     others: Query<(&Client, &Player)>,
+    blocks: Query<&BlockList>,
+    stalls: Query<&Stall>,
+    parties: Res<Parties>,
+    guilds: Res<Guilds>,
+    filter: Res<ChatFilter>,
+    task_creator: Res<TaskCreator>,
+    pool: Res<DbPool>,
     mut cmds: Commands,
 ) {
-    for (entity, client, game_entity, input, visibility, player) in query.iter_mut() {
+    for (entity, client, game_entity, input, visibility, player, visiting, in_party, in_guild, muted) in
+        query.iter_mut()
+    {
         for message in input.chat.iter() {
             let ChatClientProtocol::ChatMessage(message) = message;
 
@@ -48,6 +83,16 @@ pub(crate) fn handle_chat(
                 continue;
             }
 
+            if muted.is_some_and(Muted::is_active) && message.target != ChatTarget::AllGm {
+                client.send(ChatMessageResponse::new(
+                    ChatMessageResult::error(ChatErrorCode::WhisperMuted),
+                    message.target,
+                    message.index,
+                ));
+                continue;
+            }
+
+            let text = filter.censor(&message.message);
             match message.target {
                 ChatTarget::All => {
                     visibility
@@ -55,11 +100,15 @@ pub(crate) fn handle_chat(
                         .iter()
                         .filter_map(|entity| others.get(entity.0).ok())
                         .for_each(|(client, _)| {
-                            client.send(ChatUpdate::new(
-                                ChatSource::all(game_entity.unique_id),
-                                message.message.clone(),
-                            ));
+                            client.send(ChatUpdate::new(ChatSource::all(game_entity.unique_id), text.clone()));
                         });
+                    task_creator.spawn(log_chat_message(
+                        player.character.id,
+                        ChatChannel::All,
+                        None,
+                        message.message.clone(),
+                        PgPool::clone(&pool),
+                    ));
                     client.send(ChatMessageResponse::new(
                         ChatMessageResult::Success,
                         message.target,
@@ -78,10 +127,7 @@ pub(crate) fn handle_chat(
                         .filter(|(_, player)| player.character.gm)
                         .filter(|(_, other)| other.user.id != player.user.id)
                         .for_each(|(client, _)| {
-                            client.send(ChatUpdate::new(
-                                ChatSource::allgm(game_entity.unique_id),
-                                message.message.clone(),
-                            ));
+                            client.send(ChatUpdate::new(ChatSource::allgm(game_entity.unique_id), text.clone()));
                         });
                     client.send(ChatMessageResponse::new(
                         ChatMessageResult::Success,
@@ -94,12 +140,20 @@ pub(crate) fn handle_chat(
                         .recipient
                         .as_ref()
                         .and_then(|target| lookup.get_entity_for_name(target))
+                        .filter(|target| !is_blocked_by(&blocks, *target, player.character.id))
                         .and_then(|entity| others.get(entity).ok())
                     {
-                        Some((other, _)) => {
+                        Some((other, recipient)) => {
                             other.send(ChatUpdate::new(
                                 ChatSource::privatemessage(player.character.name.clone()),
+                                text.clone(),
+                            ));
+                            task_creator.spawn(log_chat_message(
+                                player.character.id,
+                                ChatChannel::Private,
+                                Some(recipient.character.name.clone()),
                                 message.message.clone(),
+                                PgPool::clone(&pool),
                             ));
                             client.send(ChatMessageResponse::new(
                                 ChatMessageResult::Success,
@@ -116,12 +170,184 @@ pub(crate) fn handle_chat(
                         },
                     }
                 },
+                ChatTarget::Stall => {
+                    let stall_owner = visiting.map(|visiting| visiting.0).unwrap_or(entity);
+                    let Ok(stall) = stalls.get(stall_owner) else {
+                        client.send(ChatMessageResponse::new(
+                            ChatMessageResult::error(ChatErrorCode::InvalidTarget),
+                            message.target,
+                            message.index,
+                        ));
+                        continue;
+                    };
+
+                    others
+                        .iter_many(stall.visitors.iter().chain([&stall_owner]))
+                        .filter(|(_, other)| other.character.id != player.character.id)
+                        .for_each(|(client, _)| {
+                            client.send(ChatUpdate::new(
+                                ChatSource::stall(player.character.name.clone()),
+                                text.clone(),
+                            ));
+                        });
+                    client.send(ChatMessageResponse::new(
+                        ChatMessageResult::Success,
+                        message.target,
+                        message.index,
+                    ));
+                },
+                ChatTarget::Party => {
+                    let Some(party) = in_party.and_then(|in_party| parties.get(in_party.0)) else {
+                        client.send(ChatMessageResponse::new(
+                            ChatMessageResult::error(ChatErrorCode::InvalidTarget),
+                            message.target,
+                            message.index,
+                        ));
+                        continue;
+                    };
+
+                    others
+                        .iter_many(party.members.iter().map(|member| member.0))
+                        .filter(|(_, other)| other.character.id != player.character.id)
+                        .for_each(|(client, _)| {
+                            client.send(ChatUpdate::new(
+                                ChatSource::party(player.character.name.clone()),
+                                text.clone(),
+                            ));
+                        });
+                    task_creator.spawn(log_chat_message(
+                        player.character.id,
+                        ChatChannel::Party,
+                        None,
+                        message.message.clone(),
+                        PgPool::clone(&pool),
+                    ));
+                    client.send(ChatMessageResponse::new(
+                        ChatMessageResult::Success,
+                        message.target,
+                        message.index,
+                    ));
+                },
+                ChatTarget::Guild => {
+                    let Some(guild) = in_guild.and_then(|in_guild| guilds.get(in_guild.0)) else {
+                        client.send(ChatMessageResponse::new(
+                            ChatMessageResult::error(ChatErrorCode::InvalidTarget),
+                            message.target,
+                            message.index,
+                        ));
+                        continue;
+                    };
+
+                    others
+                        .iter_many(guild.online_members())
+                        .filter(|(_, other)| other.character.id != player.character.id)
+                        .for_each(|(client, _)| {
+                            client.send(ChatUpdate::new(
+                                ChatSource::guild(player.character.name.clone()),
+                                text.clone(),
+                            ));
+                        });
+                    task_creator.spawn(log_chat_message(
+                        player.character.id,
+                        ChatChannel::Guild,
+                        None,
+                        message.message.clone(),
+                        PgPool::clone(&pool),
+                    ));
+                    client.send(ChatMessageResponse::new(
+                        ChatMessageResult::Success,
+                        message.target,
+                        message.index,
+                    ));
+                },
+                ChatTarget::Notice => {
+                    others.iter().for_each(|(client, _)| {
+                        client.send(ChatUpdate::new(ChatSource::Notice, text.clone()));
+                    });
+                    client.send(ChatMessageResponse::new(
+                        ChatMessageResult::Success,
+                        message.target,
+                        message.index,
+                    ));
+                },
+                ChatTarget::Union => {
+                    let Some(union_id) = in_guild
+                        .and_then(|in_guild| guilds.get(in_guild.0))
+                        .and_then(Guild::union_id)
+                    else {
+                        client.send(ChatMessageResponse::new(
+                            ChatMessageResult::error(ChatErrorCode::InvalidTarget),
+                            message.target,
+                            message.index,
+                        ));
+                        continue;
+                    };
+
+                    others
+                        .iter_many(guilds.union_guilds(union_id).flat_map(Guild::online_members))
+                        .filter(|(_, other)| other.character.id != player.character.id)
+                        .for_each(|(client, _)| {
+                            client.send(ChatUpdate::new(
+                                ChatSource::union(player.character.name.clone()),
+                                text.clone(),
+                            ));
+                        });
+                    client.send(ChatMessageResponse::new(
+                        ChatMessageResult::Success,
+                        message.target,
+                        message.index,
+                    ));
+                },
                 _ => {},
             }
         }
     }
 }
 
+/// Sends the message attached to a used global chatting scroll to every player in the game.
+pub(crate) fn handle_global_chat(
+    mut query: Query<(&Client, &Player, &PlayerInput, &mut PlayerInventory, Option<&Muted>)>,
+    others: Query<&Client, With<Player>>,
+    filter: Res<ChatFilter>,
+) {
+    for (client, player, input, mut inventory, muted) in query.iter_mut() {
+        let Some(ref request) = input.use_item else {
+            continue;
+        };
+
+        let is_global_chat = inventory.get_item_at(request.slot).is_some_and(|item| {
+            item.reference.common.type_id.packed() == request.item_type >> 2 && is_global_chat_scroll(item)
+        });
+        if !is_global_chat {
+            continue;
+        }
+
+        let Some(message) = request.global_message.as_ref().filter(|message| !message.is_empty()) else {
+            client.send(UseItemResponse::Failure(InventoryOperationError::Unusable));
+            continue;
+        };
+
+        if muted.is_some_and(Muted::is_active) {
+            client.send(UseItemResponse::Failure(InventoryOperationError::Unusable));
+            continue;
+        }
+
+        let Ok(remaining) = inventory.consume_item(request.slot, 1) else {
+            client.send(UseItemResponse::Failure(InventoryOperationError::InvalidTarget));
+            continue;
+        };
+
+        client.send(UseItemResponse::success(request.slot, remaining, request.item_type));
+        let message = filter.censor(message);
+        for other in others.iter() {
+            other.send(ChatUpdate::new(
+                ChatSource::global(player.character.name.clone()),
+                message.clone(),
+            ));
+        }
+    }
+}
+
 pub(crate) fn handle_gm_commands(
     mut query: Query<(Entity, &Client, &Position, &PlayerInput)>,
     mut commands: Commands,
@@ -152,6 +378,8 @@ pub(crate) fn handle_gm_commands(
                     let item_type = if matches!(object_type, ObjectType::Item(ObjectItem::Equippable(_))) {
                         ItemTypeData::Equipment {
                             upgrade_level: *upgrade,
+                            magic: MagicOptions::default(),
+                            durability: Durability::initial(item, None, 0),
                         }
                     } else if matches!(
                         object_type,
@@ -168,6 +396,7 @@ pub(crate) fn handle_gm_commands(
                             reference: item,
                             variance: None,
                             type_data: item_type,
+                            rental: None,
                         },
                         position.location(),
                         None,
@@ -1,20 +1,28 @@
 use crate::agent::component::Agent;
+use crate::chat::db::{mute_character, search_chat_logs, unmute_character};
+use crate::chat::moderation::Muted;
 use crate::comp::net::Client;
 use crate::comp::player::{Player, StatPoints};
 use crate::comp::pos::Position;
 use crate::comp::{EntityReference, GameEntity};
-use crate::ext::Navmesh;
+use crate::ext::{DbPool, Navmesh};
 use crate::game::exp::ReceiveExperienceEvent;
 use crate::game::target::Target;
-use crate::world::WorldData;
+use crate::server_plugin::ServerId;
+use crate::tasks::TaskCreator;
+use crate::world::{EntityLookup, WorldData};
 use bevy::app::MainScheduleOrder;
 use bevy::ecs::schedule::ScheduleLabel;
 use bevy::prelude::*;
+use chrono::Utc;
 use gumdrop::Options;
 use silkroad_game_base::{GlobalLocation, GlobalPosition, MovementSpeed};
 use silkroad_protocol::chat::{ChatSource, ChatUpdate};
 use silkroad_protocol::movement::ChangeSpeed;
+use sqlx::PgPool;
 use std::fmt::Display;
+use tokio::sync::oneshot::error::TryRecvError;
+use tokio::sync::oneshot::Receiver;
 use tracing::{info, warn};
 
 #[derive(Copy, Clone, Eq, PartialEq)]
@@ -60,6 +68,23 @@ struct CommandResult {
     outcome: CommandOutcome,
 }
 
+/// The outcomes of commands that are still waiting for the database, together with who invoked them.
+type PendingOutcomes = Vec<(Sender, Receiver<CommandOutcome>)>;
+
+fn report_finished(pending: &mut PendingOutcomes, results: &mut EventWriter<CommandResult>) {
+    pending.retain_mut(|(sender, receiver)| match receiver.try_recv() {
+        Ok(outcome) => {
+            results.send(CommandResult {
+                receiver: *sender,
+                outcome,
+            });
+            false
+        },
+        Err(TryRecvError::Empty) => true,
+        Err(TryRecvError::Closed) => false,
+    });
+}
+
 #[derive(Event)]
 struct IncomingCommand {
     sender: Sender,
@@ -83,6 +108,10 @@ impl Plugin for CommandPlugin {
             .add_event::<CommandInvocation<PrintPos>>()
             .add_event::<CommandInvocation<PrintTarget>>()
             .add_event::<CommandInvocation<TeleportArgs>>()
+            .add_event::<CommandInvocation<SendNotice>>()
+            .add_event::<CommandInvocation<MuteArgs>>()
+            .add_event::<CommandInvocation<UnmuteArgs>>()
+            .add_event::<CommandInvocation<ChatLogArgs>>()
             .add_systems(
                 CommandSchedule,
                 (
@@ -95,6 +124,10 @@ impl Plugin for CommandPlugin {
                         handle_print_pos,
                         handle_print_target,
                         handle_teleport,
+                        handle_notice,
+                        handle_mute,
+                        handle_unmute,
+                        handle_chat_log,
                     ),
                     output_results,
                 )
@@ -138,6 +171,14 @@ enum SilkroadCommands {
     Target(PrintTarget),
     #[options(help = "Teleports to the given position")]
     Tp(TeleportArgs),
+    #[options(help = "Sends a notice to all players")]
+    Notice(SendNotice),
+    #[options(help = "Prevents a character from chatting for the given amount of minutes")]
+    Mute(MuteArgs),
+    #[options(help = "Allows a muted character to chat again")]
+    Unmute(UnmuteArgs),
+    #[options(help = "Searches the recent chat messages of a character")]
+    ChatLog(ChatLogArgs),
     #[options(help = "Show the help output")]
     Help(Help),
 }
@@ -219,6 +260,30 @@ fn parse_commands(mut incoming: EventReader<IncomingCommand>, mut cmds: Commands
                     args,
                 });
             },
+            SilkroadCommands::Notice(args) => {
+                cmds.send_event(CommandInvocation {
+                    sender: incoming.sender,
+                    args,
+                });
+            },
+            SilkroadCommands::Mute(args) => {
+                cmds.send_event(CommandInvocation {
+                    sender: incoming.sender,
+                    args,
+                });
+            },
+            SilkroadCommands::Unmute(args) => {
+                cmds.send_event(CommandInvocation {
+                    sender: incoming.sender,
+                    args,
+                });
+            },
+            SilkroadCommands::ChatLog(args) => {
+                cmds.send_event(CommandInvocation {
+                    sender: incoming.sender,
+                    args,
+                });
+            },
             SilkroadCommands::Help(_) => {
                 unreachable!("Help should have already been handled above.")
             },
@@ -525,3 +590,194 @@ fn handle_teleport(
         position.move_to(target);
     }
 }
+
+#[derive(Options, Debug, PartialEq)]
+struct SendNotice {
+    #[options(free)]
+    message: Vec<String>,
+}
+
+fn handle_notice(
+    mut invocations: EventReader<CommandInvocation<SendNotice>>,
+    mut results: EventWriter<CommandResult>,
+    query: Query<&Client, With<Player>>,
+) {
+    for notice in invocations.read() {
+        let message = notice.args.message.join(" ");
+        if message.is_empty() {
+            results.send(CommandResult {
+                receiver: notice.sender,
+                outcome: CommandOutcome::InvalidArguments("The notice cannot be empty.".to_string()),
+            });
+            continue;
+        }
+
+        for client in query.iter() {
+            client.send(ChatUpdate::new(ChatSource::Notice, message.clone()));
+        }
+    }
+}
+
+const DEFAULT_CHAT_LOG_LIMIT: u32 = 10;
+
+#[derive(Options, Debug, PartialEq)]
+struct MuteArgs {
+    #[options(free)]
+    name: String,
+    #[options(free)]
+    minutes: u32,
+    #[options(free)]
+    reason: Vec<String>,
+}
+
+fn handle_mute(
+    mut invocations: EventReader<CommandInvocation<MuteArgs>>,
+    mut results: EventWriter<CommandResult>,
+    mut pending: Local<PendingOutcomes>,
+    players: Query<&Player>,
+    lookup: Res<EntityLookup>,
+    server_id: Res<ServerId>,
+    task_creator: Res<TaskCreator>,
+    pool: Res<DbPool>,
+    mut cmds: Commands,
+) {
+    report_finished(&mut pending, &mut results);
+    for mute in invocations.read() {
+        let args = &mute.args;
+        if args.minutes == 0 {
+            results.send(CommandResult {
+                receiver: mute.sender,
+                outcome: CommandOutcome::InvalidArguments("A mute needs to last at least a minute.".to_string()),
+            });
+            continue;
+        }
+
+        let until = Utc::now() + chrono::Duration::minutes(i64::from(args.minutes));
+        if let Some(muted) = lookup.get_entity_for_name(&args.name) {
+            cmds.entity(muted).try_insert(Muted { until });
+        }
+
+        let muted_by = match mute.sender {
+            Sender::Player(player) => players
+                .get(player)
+                .map(|player| player.character.name.clone())
+                .unwrap_or_default(),
+            Sender::System => "System".to_string(),
+        };
+        let name = args.name.clone();
+        let minutes = args.minutes;
+        let task = mute_character(
+            args.name.clone(),
+            server_id.0,
+            until,
+            args.reason.join(" "),
+            muted_by,
+            PgPool::clone(&pool),
+        );
+        let receiver = task_creator.create_task(async move {
+            match task.await {
+                Ok(true) => CommandOutcome::Success(Some(format!("Muted {} for {} minutes.", name, minutes))),
+                Ok(false) => CommandOutcome::ExecutionFailure(format!("The character {} does not exist.", name)),
+                Err(e) => CommandOutcome::ExecutionFailure(e.to_string()),
+            }
+        });
+        pending.push((mute.sender, receiver));
+    }
+}
+
+#[derive(Options, Debug, PartialEq)]
+struct UnmuteArgs {
+    #[options(free)]
+    name: String,
+}
+
+fn handle_unmute(
+    mut invocations: EventReader<CommandInvocation<UnmuteArgs>>,
+    mut results: EventWriter<CommandResult>,
+    mut pending: Local<PendingOutcomes>,
+    lookup: Res<EntityLookup>,
+    server_id: Res<ServerId>,
+    task_creator: Res<TaskCreator>,
+    pool: Res<DbPool>,
+    mut cmds: Commands,
+) {
+    report_finished(&mut pending, &mut results);
+    for unmute in invocations.read() {
+        if let Some(muted) = lookup.get_entity_for_name(&unmute.args.name) {
+            cmds.entity(muted).remove::<Muted>();
+        }
+
+        let name = unmute.args.name.clone();
+        let task = unmute_character(name.clone(), server_id.0, PgPool::clone(&pool));
+        let receiver = task_creator.create_task(async move {
+            match task.await {
+                Ok(true) => CommandOutcome::Success(Some(format!("Unmuted {}.", name))),
+                Ok(false) => CommandOutcome::ExecutionFailure(format!("The character {} is not muted.", name)),
+                Err(e) => CommandOutcome::ExecutionFailure(e.to_string()),
+            }
+        });
+        pending.push((unmute.sender, receiver));
+    }
+}
+
+#[derive(Options, Debug, PartialEq)]
+struct ChatLogArgs {
+    #[options(free)]
+    name: String,
+    #[options(short = "c", help = "Only show messages containing this text")]
+    contains: Option<String>,
+    #[options(short = "l", help = "The maximum amount of messages to show")]
+    limit: Option<u32>,
+}
+
+fn handle_chat_log(
+    mut invocations: EventReader<CommandInvocation<ChatLogArgs>>,
+    mut results: EventWriter<CommandResult>,
+    mut pending: Local<PendingOutcomes>,
+    server_id: Res<ServerId>,
+    task_creator: Res<TaskCreator>,
+    pool: Res<DbPool>,
+) {
+    report_finished(&mut pending, &mut results);
+    for search in invocations.read() {
+        let args = &search.args;
+        let name = args.name.clone();
+        let task = search_chat_logs(
+            args.name.clone(),
+            server_id.0,
+            args.contains.clone(),
+            args.limit.unwrap_or(DEFAULT_CHAT_LOG_LIMIT),
+            PgPool::clone(&pool),
+        );
+        let receiver = task_creator.create_task(async move {
+            match task.await {
+                Ok(messages) if messages.is_empty() => {
+                    CommandOutcome::Success(Some(format!("No messages of {} found.", name)))
+                },
+                Ok(messages) => {
+                    let lines = messages
+                        .into_iter()
+                        .map(|log| match log.recipient {
+                            Some(recipient) => format!(
+                                "[{}] {} -> {}: {}",
+                                log.sent_at.format("%Y-%m-%d %H:%M"),
+                                log.channel,
+                                recipient,
+                                log.message
+                            ),
+                            None => format!(
+                                "[{}] {}: {}",
+                                log.sent_at.format("%Y-%m-%d %H:%M"),
+                                log.channel,
+                                log.message
+                            ),
+                        })
+                        .collect::<Vec<_>>();
+                    CommandOutcome::Success(Some(lines.join("\n")))
+                },
+                Err(e) => CommandOutcome::ExecutionFailure(e.to_string()),
+            }
+        });
+        pending.push((search.sender, receiver));
+    }
+}
@@ -1,12 +1,20 @@
 use crate::db::character::CharacterItem;
-use crate::persistence::ApplyToDatabase;
+use crate::persistence::{ApplyInTransaction, ApplyToDatabase};
 use crate::world::WorldData;
 use axum::async_trait;
 use bevy::prelude::*;
+use chrono::DateTime;
 use silkroad_data::itemdata::RefItemData;
+use silkroad_data::magicoption::MagicOptionKind;
 use silkroad_definitions::type_id::{ObjectItem, ObjectType};
-use silkroad_game_base::{ChangeTracked, Inventory, InventoryChange, Item, ItemTypeData};
-use sqlx::PgPool;
+use silkroad_game_base::{
+    AvatarChange, AvatarInventory, ChangeTracked, Durability, Inventory, InventoryChange, Item, ItemTypeData,
+    MagicOptions, MagicParam, Rental, RentalKind,
+};
+use silkroad_protocol::inventory::{
+    CharacterSpawnItemData, InventoryItemBindingData, InventoryItemContentData, InventoryItemMagicData, RentInfo,
+};
+use sqlx::{PgConnection, PgPool};
 use std::ops::{Deref, DerefMut};
 
 #[derive(Component)]
@@ -36,30 +44,120 @@ impl ChangeTracked for PlayerInventory {
     }
 }
 
+/// The avatar items worn by a player, see [AvatarInventory].
+#[derive(Component, Default)]
+pub(crate) struct PlayerAvatarInventory {
+    inventory: AvatarInventory,
+}
+
+impl Deref for PlayerAvatarInventory {
+    type Target = AvatarInventory;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inventory
+    }
+}
+
+impl DerefMut for PlayerAvatarInventory {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inventory
+    }
+}
+
+impl ChangeTracked for PlayerAvatarInventory {
+    type ChangeItem = AvatarChange;
+
+    fn changes(&mut self) -> Vec<Self::ChangeItem> {
+        self.inventory.changes()
+    }
+}
+
+impl PlayerAvatarInventory {
+    pub(crate) fn from_db(items: &[CharacterItem]) -> Self {
+        let mut inventory = AvatarInventory::default();
+        for item in items {
+            inventory.set_item(item.slot as u8, PlayerInventory::item_from_db(item));
+        }
+        PlayerAvatarInventory { inventory }
+    }
+}
+
 #[async_trait]
-impl ApplyToDatabase for InventoryChange {
+impl ApplyInTransaction for AvatarChange {
+    async fn apply_in(&self, character_id: u32, connection: &mut PgConnection) -> Result<(), sqlx::Error> {
+        match self {
+            AvatarChange::Equip { slot, item } => {
+                sqlx::query!(
+                    "INSERT INTO character_avatar_items(character_id, item_obj_id, upgrade_level, slot, variance, amount, magic_params, durability, max_durability, rent_type, rent_start, rent_end) VALUES($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12) ON CONFLICT(character_id, slot) DO UPDATE SET item_obj_id = EXCLUDED.item_obj_id, upgrade_level = EXCLUDED.upgrade_level, variance = EXCLUDED.variance, amount = EXCLUDED.amount, magic_params = EXCLUDED.magic_params, durability = EXCLUDED.durability, max_durability = EXCLUDED.max_durability, rent_type = EXCLUDED.rent_type, rent_start = EXCLUDED.rent_start, rent_end = EXCLUDED.rent_end",
+                    character_id as i32,
+                    item.reference.common.ref_id as i32,
+                    item.type_data.upgrade_level().map(|a| a as i16).unwrap_or(0),
+                    *slot as i16,
+                    item.variance.map(|a| a as i64),
+                    item.type_data.amount() as i16,
+                    magic_params_of(&item.type_data),
+                    item.durability().map(|durability| durability.current() as i32),
+                    item.durability().map(|durability| durability.max() as i32),
+                    item.rental.map(|rental| rent_type_of(rental.kind)),
+                    item.rental.and_then(|rental| DateTime::from_timestamp(rental.start as i64, 0)),
+                    item.rental.and_then(|rental| DateTime::from_timestamp(rental.end as i64, 0)),
+                ).execute(&mut *connection).await?;
+            },
+            AvatarChange::Unequip { slot } => {
+                sqlx::query!(
+                    "DELETE FROM character_avatar_items WHERE character_id = $1 AND slot = $2",
+                    character_id as i32,
+                    *slot as i16,
+                )
+                .execute(&mut *connection)
+                .await?;
+            },
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ApplyToDatabase for AvatarChange {
     async fn apply(&self, character_id: u32, pool: &PgPool) -> Result<(), sqlx::Error> {
+        let mut connection = pool.acquire().await?;
+        self.apply_in(character_id, &mut connection).await
+    }
+}
+
+#[async_trait]
+impl ApplyInTransaction for InventoryChange {
+    async fn apply_in(&self, character_id: u32, connection: &mut PgConnection) -> Result<(), sqlx::Error> {
         match self {
             InventoryChange::AddItem { slot, item } => {
                 sqlx::query!(
-                    "INSERT INTO character_items(character_id, item_obj_id, upgrade_level, slot, variance, amount) VALUES($1, $2, $3, $4, $5, $6) ON CONFLICT(character_id, slot) DO UPDATE SET item_obj_id = EXCLUDED.item_obj_id, upgrade_level = EXCLUDED.upgrade_level, variance = EXCLUDED.variance, amount = EXCLUDED.amount",
+                    "INSERT INTO character_items(character_id, item_obj_id, upgrade_level, slot, variance, amount, magic_params, durability, max_durability, rent_type, rent_start, rent_end) VALUES($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12) ON CONFLICT(character_id, slot) DO UPDATE SET item_obj_id = EXCLUDED.item_obj_id, upgrade_level = EXCLUDED.upgrade_level, variance = EXCLUDED.variance, amount = EXCLUDED.amount, magic_params = EXCLUDED.magic_params, durability = EXCLUDED.durability, max_durability = EXCLUDED.max_durability, rent_type = EXCLUDED.rent_type, rent_start = EXCLUDED.rent_start, rent_end = EXCLUDED.rent_end",
                     character_id as i32,
                     item.reference.common.ref_id as i32,
                     item.type_data.upgrade_level().map(|a| a as i16).unwrap_or(0),
                     *slot as i16,
                     item.variance.map(|a| a as i64),
-                    item.type_data.amount() as i16 // This should be fine, since we should never have gold inside an item slot
-                ).execute(pool).await?;
+                    item.type_data.amount() as i16, // This should be fine, since we should never have gold inside an item slot
+                    magic_params_of(&item.type_data),
+                    item.durability().map(|durability| durability.current() as i32),
+                    item.durability().map(|durability| durability.max() as i32),
+                    item.rental.map(|rental| rent_type_of(rental.kind)),
+                    item.rental.and_then(|rental| DateTime::from_timestamp(rental.start as i64, 0)),
+                    item.rental.and_then(|rental| DateTime::from_timestamp(rental.end as i64, 0)),
+                ).execute(&mut *connection).await?;
             },
             InventoryChange::ChangeTypeData { slot, new_item, .. } => {
                 sqlx::query!(
-                    "UPDATE character_items SET upgrade_level = $1, amount = $2 WHERE character_id = $3 AND slot = $4",
+                    "UPDATE character_items SET upgrade_level = $1, amount = $2, magic_params = $3, durability = $4, max_durability = $5 WHERE character_id = $6 AND slot = $7",
                     new_item.upgrade_level().map(|a| a as i16).unwrap_or(0),
                     new_item.amount() as i16, // This should be fine, since we should never have gold inside an item slot
+                    magic_params_of(new_item),
+                    new_item.durability().map(|durability| durability.current() as i32),
+                    new_item.durability().map(|durability| durability.max() as i32),
                     character_id as i32,
                     *slot as i16,
                 )
-                .execute(pool)
+                .execute(&mut *connection)
                 .await?;
             },
             InventoryChange::MoveItem {
@@ -72,7 +170,7 @@ impl ApplyToDatabase for InventoryChange {
                     character_id as i32,
                     *source_slot as i16,
                 )
-                .execute(pool)
+                .execute(&mut *connection)
                 .await?;
             },
             InventoryChange::RemoveItem { slot } => {
@@ -81,7 +179,7 @@ impl ApplyToDatabase for InventoryChange {
                     character_id as i32,
                     *slot as i16,
                 )
-                .execute(pool)
+                .execute(&mut *connection)
                 .await?;
             },
             InventoryChange::Swap {
@@ -94,7 +192,16 @@ impl ApplyToDatabase for InventoryChange {
                     *first_slot as i16,
                     *second_slot as i16,
                 )
-                .execute(pool)
+                .execute(&mut *connection)
+                .await?;
+            },
+            InventoryChange::Resize { size } => {
+                sqlx::query!(
+                    "UPDATE characters SET inventory_size = $1 WHERE id = $2",
+                    *size as i16,
+                    character_id as i32,
+                )
+                .execute(&mut *connection)
                 .await?;
             },
         }
@@ -102,33 +209,153 @@ impl ApplyToDatabase for InventoryChange {
     }
 }
 
+#[async_trait]
+impl ApplyToDatabase for InventoryChange {
+    async fn apply(&self, character_id: u32, pool: &PgPool) -> Result<(), sqlx::Error> {
+        let mut connection = pool.acquire().await?;
+        self.apply_in(character_id, &mut connection).await
+    }
+}
+
+/// Creates the protocol representation of the given item, as used when sending inventory contents.
+pub(crate) fn item_content_data(item: &Item) -> InventoryItemContentData {
+    match &item.type_data {
+        ItemTypeData::Equipment {
+            upgrade_level,
+            magic,
+            durability,
+        } => InventoryItemContentData::Equipment {
+            plus_level: *upgrade_level,
+            variance: item.variance.unwrap_or_default(),
+            durability: durability.current(),
+            magic: magic_data(magic),
+            bindings_1: InventoryItemBindingData::new(1, 0),
+            bindings_2: InventoryItemBindingData::new(2, 0),
+            bindings_3: InventoryItemBindingData::new(3, 0),
+            bindings_4: InventoryItemBindingData::new(4, 0),
+        },
+        _ => InventoryItemContentData::Expendable {
+            stack_size: item.stack_size(),
+        },
+    }
+}
+
+fn magic_data(magic: &MagicOptions) -> Vec<InventoryItemMagicData> {
+    magic
+        .iter()
+        .map(|param| InventoryItemMagicData::new(param.id, param.value))
+        .collect()
+}
+
+/// Creates the representation of an item that is worn by a character, as seen by other players.
+pub(crate) fn spawn_item_data(item: &Item) -> CharacterSpawnItemData {
+    let magic = match &item.type_data {
+        ItemTypeData::Equipment { magic, .. } => magic_data(magic),
+        _ => Vec::new(),
+    };
+    CharacterSpawnItemData::new(item.reference.common.ref_id, item.upgrade_level(), magic)
+}
+
+/// Creates the protocol representation of the rental status of the given item.
+pub(crate) fn rent_info(item: &Item) -> RentInfo {
+    match item.rental {
+        None => RentInfo::Empty,
+        Some(Rental {
+            kind: RentalKind::Period,
+            start,
+            end,
+        }) => RentInfo::first(1, start, end),
+        Some(Rental {
+            kind: RentalKind::Rechargeable,
+            start,
+            end,
+        }) => RentInfo::third(1, 1, start, end, 0),
+    }
+}
+
+fn rent_type_of(kind: RentalKind) -> i16 {
+    match kind {
+        RentalKind::Period => 1,
+        RentalKind::Rechargeable => 3,
+    }
+}
+
+fn rental_of(item: &CharacterItem) -> Option<Rental> {
+    let kind = match item.rent_type? {
+        1 => RentalKind::Period,
+        3 => RentalKind::Rechargeable,
+        _ => return None,
+    };
+    let start = item.rent_start.map(|start| start.timestamp() as u64).unwrap_or(0);
+    let end = item.rent_end?.timestamp() as u64;
+    Some(Rental::new(kind, start, end))
+}
+
+/// Sums up the durability magic options among the given options, as a percentage of the maximum
+/// durability.
+fn durability_bonus(options: &MagicOptions) -> u32 {
+    let magic_options = WorldData::magic_options();
+    options
+        .iter()
+        .filter(|param| {
+            magic_options
+                .find_id(param.id)
+                .is_some_and(|option| option.kind == MagicOptionKind::Durability)
+        })
+        .fold(0u32, |bonus, param| bonus.saturating_add(param.value))
+}
+
+pub(crate) fn magic_params_of(type_data: &ItemTypeData) -> Vec<i64> {
+    type_data
+        .magic_options()
+        .map(|options| options.iter().map(|param| param.packed() as i64).collect())
+        .unwrap_or_default()
+}
+
 impl PlayerInventory {
     fn from_db_inventory(items: &[CharacterItem], size: usize) -> Inventory {
-        let item_map = WorldData::items();
         let mut inventory = Inventory::new(size);
 
         for item in items {
-            let item_def = item_map.find_id(item.item_obj_id as u32).unwrap();
-
-            inventory.set_item(
-                item.slot as u8,
-                Item {
-                    reference: item_def,
-                    variance: item.variance.map(|v| v as u64),
-                    type_data: Self::item_type_data_for(item_def, item).unwrap(),
-                },
-            );
+            inventory.set_item(item.slot as u8, Self::item_from_db(item));
         }
 
         inventory
     }
 
+    /// Recreates the item stored in the given row. Besides the inventory itself, this is also used
+    /// for items that are stored elsewhere but share the same columns, such as consignment listings.
+    pub(crate) fn item_from_db(item: &CharacterItem) -> Item {
+        let item_def = WorldData::items().find_id(item.item_obj_id as u32).unwrap();
+        Item {
+            reference: item_def,
+            variance: item.variance.map(|v| v as u64),
+            type_data: Self::item_type_data_for(item_def, item).unwrap(),
+            rental: rental_of(item),
+        }
+    }
+
     fn item_type_data_for(ref_data: &RefItemData, item: &CharacterItem) -> Option<ItemTypeData> {
         let obj_type = ObjectType::from_type_id(&ref_data.common.type_id).unwrap();
         if let ObjectType::Item(item_type) = obj_type {
             let res = match item_type {
-                ObjectItem::Equippable(_) => ItemTypeData::Equipment {
-                    upgrade_level: item.upgrade_level as u8,
+                ObjectItem::Equippable(_) => {
+                    let magic: MagicOptions = item
+                        .magic_params
+                        .iter()
+                        .map(|param| MagicParam::from_packed(*param as u64))
+                        .collect();
+                    let durability = match (item.durability, item.max_durability) {
+                        (Some(current), Some(max)) => Durability::new(current as u32, max as u32),
+                        // Items that have not been touched since they were created are still at full
+                        // durability, which we can derive from the item itself.
+                        _ => Durability::initial(ref_data, item.variance.map(|v| v as u64), durability_bonus(&magic)),
+                    };
+                    ItemTypeData::Equipment {
+                        upgrade_level: item.upgrade_level as u8,
+                        magic,
+                        durability,
+                    }
                 },
                 ObjectItem::Pet(_) => ItemTypeData::COS,
                 _ => ItemTypeData::Consumable {
@@ -0,0 +1,8 @@
+use bevy::prelude::*;
+
+/// Marks a player that has been invited by another player, for example to an exchange or a party,
+/// and has yet to answer. The answer of the client doesn't say which invitation it belongs to, so a
+/// player may only have a single invitation at a time and further ones are refused until then.
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+pub(crate) struct PendingInvitation;
@@ -44,6 +44,10 @@ impl MasteryKnowledge {
         self.masteries.get(&ref_id).copied()
     }
 
+    pub(crate) fn levels(&self) -> impl Iterator<Item = (u32, u8)> + '_ {
+        self.masteries.iter().map(|(mastery, level)| (*mastery, *level))
+    }
+
     pub(crate) fn total(&self) -> u16 {
         self.masteries.values().map(|v| u16::from(*v)).sum()
     }
@@ -3,14 +3,17 @@ pub(crate) mod drop;
 pub(crate) mod exp;
 pub(crate) mod gold;
 pub(crate) mod inventory;
+pub(crate) mod invitation;
 pub(crate) mod mastery;
 pub(crate) mod monster;
 pub(crate) mod net;
 pub(crate) mod npc;
 pub(crate) mod player;
 pub(crate) mod pos;
+pub(crate) mod recall;
 pub(crate) mod skill;
 pub(crate) mod spawner;
+pub(crate) mod stats;
 pub(crate) mod visibility;
 
 use crate::db::user::ServerUser;
@@ -74,14 +77,20 @@ impl Health {
     }
 
     pub fn upgrade(&mut self, new_max: u32) {
-        let diff = new_max - self.current_health;
+        let diff = new_max.saturating_sub(self.current_health);
         self.max_health = new_max;
         self.increase_max(new_max);
         self.add_change(diff as i32)
     }
 
+    /// Changes the maximum health, lowering the current health if it no longer fits.
     pub fn increase_max(&mut self, new_max: u32) {
         self.max_health = new_max;
+        if self.current_health > new_max {
+            let lost = self.current_health - new_max;
+            self.current_health = new_max;
+            self.add_change(-(lost as i32));
+        }
     }
 
     pub fn collect_change(&self) -> Option<i32> {
@@ -119,7 +128,7 @@ impl Mana {
     }
 
     pub fn upgrade(&mut self, new_max: u32) {
-        let diff = new_max - self.current_mana;
+        let diff = new_max.saturating_sub(self.current_mana);
         self.max_mana = new_max;
         self.increase_max(new_max);
         self.add_change(diff as i32)
@@ -130,8 +139,14 @@ impl Mana {
         self.add_change(-(amount as i32));
     }
 
+    /// Changes the maximum mana, lowering the current mana if it no longer fits.
     pub fn increase_max(&mut self, new_max: u32) {
-        self.current_mana = new_max;
+        self.max_mana = new_max;
+        if self.current_mana > new_max {
+            let lost = self.current_mana - new_max;
+            self.current_mana = new_max;
+            self.add_change(-(lost as i32));
+        }
     }
 
     pub fn collect_change(&self) -> Option<i32> {
@@ -156,3 +171,30 @@ impl From<Duration> for Despawn {
         Despawn(Timer::new(duration, TimerMode::Once))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_lower_max_health() {
+        let mut health = Health::new(200);
+        health.increase_max(150);
+        assert_eq!(150, health.current_health);
+        assert_eq!(150, health.max_health);
+
+        health.upgrade(100);
+        assert_eq!(100, health.current_health);
+        assert_eq!(100, health.max_health);
+    }
+
+    #[test]
+    pub fn test_lower_max_mana() {
+        let mut mana = Mana::with_max(200);
+        mana.increase_max(150);
+        assert_eq!(150, mana.current_mana);
+        mana.upgrade(100);
+        assert_eq!(100, mana.current_mana);
+        assert_eq!(100, mana.max_mana);
+    }
+}
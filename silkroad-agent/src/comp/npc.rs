@@ -27,3 +27,25 @@ impl NpcBundle {
         }
     }
 }
+
+/// A teleport building, such as a gate or a ferry, which players can use to travel to linked
+/// teleport locations.
+#[derive(Component)]
+pub(crate) struct TeleportGate;
+
+#[derive(Bundle)]
+pub(crate) struct TeleportGateBundle {
+    game_entity: GameEntity,
+    gate: TeleportGate,
+    position: Position,
+}
+
+impl TeleportGateBundle {
+    pub fn new(unique_id: u32, ref_id: u32, position: LocalPosition) -> Self {
+        Self {
+            game_entity: GameEntity { unique_id, ref_id },
+            gate: TeleportGate,
+            position: Position::new(position.to_global(), Heading(0.0)),
+        }
+    }
+}
@@ -4,10 +4,12 @@ use crate::agent::state::AgentStateQueue;
 use crate::comp::damage::DamageReceiver;
 use crate::comp::exp::{Experienced, Leveled, SP};
 use crate::comp::gold::GoldPouch;
-use crate::comp::inventory::PlayerInventory;
+use crate::comp::inventory::{PlayerAvatarInventory, PlayerInventory};
 use crate::comp::mastery::MasteryKnowledge;
 use crate::comp::pos::Position;
+use crate::comp::recall::{RecallPoint, ReverseLocations};
 use crate::comp::skill::{Hotbar, SkillBook};
+use crate::comp::stats::CharacterStats;
 use crate::comp::visibility::Visibility;
 use crate::comp::{GameEntity, Health, Mana};
 use crate::db::character::CharacterData;
@@ -54,6 +56,7 @@ impl Player {
             gold: data.gold as u64,
             beginner_mark: data.beginner_mark,
             gm: data.gm,
+            recall_point: data.recall_point.map(|id| id as u16),
             state: SpawningState::Loading,
             masteries: Vec::new(),
             skills: Vec::new(),
@@ -75,6 +78,7 @@ pub(crate) struct Buffed {
 pub(crate) struct PlayerBundle {
     player: Player,
     inventory: PlayerInventory,
+    avatars: PlayerAvatarInventory,
     gold: GoldPouch,
     game_entity: GameEntity,
     agent: Agent,
@@ -93,10 +97,13 @@ pub(crate) struct PlayerBundle {
     goal: GoalTracker,
     persistence: Persistable,
     stat_points: StatPoints,
+    character_stats: CharacterStats,
     masteries: MasteryKnowledge,
     skills: SkillBook,
     race: CharacterRace,
     hotbar: Hotbar,
+    recall_point: RecallPoint,
+    reverse_locations: ReverseLocations,
 }
 
 impl PlayerBundle {
@@ -104,6 +111,7 @@ impl PlayerBundle {
         player: Player,
         game_entity: GameEntity,
         inventory: PlayerInventory,
+        avatars: PlayerAvatarInventory,
         gold: GoldPouch,
         agent: Agent,
         pos: Position,
@@ -112,8 +120,9 @@ impl PlayerBundle {
     ) -> Self {
         let stat_points = StatPoints::new(player.character.stats, player.character.stat_points);
         let level = player.character.level;
-        let max_hp = stat_points.stats().max_health(level);
-        let max_mana = stat_points.stats().max_mana(level);
+        let character_stats = CharacterStats::from_equipment(&inventory);
+        let max_hp = character_stats.max_health(stat_points.stats(), level);
+        let max_mana = character_stats.max_mana(stat_points.stats(), level);
         let sp = player.character.sp;
         let sp_exp = player.character.sp_exp;
         let exp = player.character.exp;
@@ -121,10 +130,12 @@ impl PlayerBundle {
         let master_knowledge = MasteryKnowledge::new(&player.character.masteries);
         let skills = SkillBook::new(&player.character.skills);
         let race = player.character.race.into();
+        let recall_point = RecallPoint::new(player.character.recall_point);
         Self {
             player,
             game_entity,
             inventory,
+            avatars,
             agent,
             pos,
             buff: Buffed {},
@@ -142,10 +153,13 @@ impl PlayerBundle {
             goal: GoalTracker::default(),
             persistence: Persistable,
             stat_points,
+            character_stats,
             masteries: master_knowledge,
             skills,
             race,
             hotbar,
+            recall_point,
+            reverse_locations: ReverseLocations::default(),
         }
     }
 }
@@ -4,6 +4,12 @@ use cgmath::{MetricSpace, Vector2};
 use silkroad_game_base::{GlobalLocation, GlobalPosition, Heading};
 use silkroad_protocol::movement::{EntityMovementState, MovementType};
 
+/// Converts a local coordinate for packets that send coordinates as unsigned numbers. The client
+/// reads them as signed numbers, as they may be negative inside dungeons.
+pub(crate) fn protocol_coordinate(value: f32) -> u16 {
+    value as i16 as u16
+}
+
 #[derive(Component, Copy, Clone)]
 pub(crate) struct Position {
     location: GlobalPosition,
@@ -38,9 +44,9 @@ impl Position {
         EntityMovementState::Moving {
             movement_type: MovementType::Running,
             region: local.0.id(),
-            x: local.1.x as u16,
-            y: local.1.y as u16,
-            z: local.1.z as u16,
+            x: protocol_coordinate(local.1.x),
+            y: protocol_coordinate(local.1.y),
+            z: protocol_coordinate(local.1.z),
         }
     }
 
@@ -0,0 +1,56 @@
+use crate::persistence::ApplyToDatabase;
+use axum::async_trait;
+use bevy::prelude::*;
+use silkroad_game_base::{ChangeProvided, GlobalPosition};
+use sqlx::PgPool;
+
+/// The teleport location a character returns to when using a return scroll or after reviving in
+/// town. Without a designated recall point, the nearest respawn point is used instead.
+#[derive(Component, Copy, Clone, Default)]
+pub(crate) struct RecallPoint(Option<u16>);
+
+impl RecallPoint {
+    pub(crate) fn new(teleport_id: Option<u16>) -> Self {
+        RecallPoint(teleport_id)
+    }
+
+    pub(crate) fn teleport_id(&self) -> Option<u16> {
+        self.0
+    }
+
+    pub(crate) fn designate(&mut self, teleport_id: u16) {
+        self.0 = Some(teleport_id);
+    }
+}
+
+pub(crate) struct RecallPointChange(Option<u16>);
+
+impl ChangeProvided for RecallPoint {
+    type Change = RecallPointChange;
+
+    fn as_change(&self) -> Self::Change {
+        RecallPointChange(self.0)
+    }
+}
+
+#[async_trait]
+impl ApplyToDatabase for RecallPointChange {
+    async fn apply(&self, character_id: u32, pool: &PgPool) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "UPDATE characters SET recall_point = $1 WHERE id = $2",
+            self.0.map(|id| id as i16),
+            character_id as i32
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+}
+
+/// The locations a reverse return scroll can bring a character back to. These are only kept for
+/// the current session.
+#[derive(Component, Copy, Clone, Default)]
+pub(crate) struct ReverseLocations {
+    pub(crate) death: Option<GlobalPosition>,
+    pub(crate) recent: Option<GlobalPosition>,
+}
@@ -27,6 +27,10 @@ impl SkillBook {
         self.new_skills.push((skill.group, skill.level));
     }
 
+    pub(crate) fn levels(&self) -> impl Iterator<Item = (u32, u8)> + '_ {
+        self.skills.iter().map(|(group, level)| (*group, *level))
+    }
+
     pub(crate) fn has_required_skills_for(&self, skill: &RefSkillData) -> bool {
         skill
             .required_skills
@@ -0,0 +1,78 @@
+use crate::config::get_config;
+use crate::world::WorldData;
+use bevy::prelude::*;
+use silkroad_game_base::{EquipmentStats, Inventory, StatBonus, Stats};
+use silkroad_protocol::character::CharacterStatsMessage;
+
+/// The stats of a character that are derived from the equipment they're wearing, on top of their
+/// base [Stats]. This includes both the white stats of the equipment and the bonuses of their magic
+/// options.
+#[derive(Component, Copy, Clone, Default, PartialEq)]
+pub(crate) struct CharacterStats {
+    equipment: EquipmentStats,
+    bonus: StatBonus,
+}
+
+impl CharacterStats {
+    pub(crate) fn from_equipment(inventory: &Inventory) -> Self {
+        let magic_options = WorldData::magic_options();
+        let mut equipment = EquipmentStats::default();
+        let mut bonus = StatBonus::default();
+        for (_, item) in inventory.intact_equipment_items() {
+            equipment += EquipmentStats::of(item);
+
+            let Some(options) = item.magic_options() else {
+                continue;
+            };
+
+            for param in options.iter() {
+                if let Some(option) = magic_options.find_id(param.id) {
+                    bonus.add_magic_option(option.kind, param.value);
+                }
+            }
+        }
+
+        CharacterStats { equipment, bonus }
+    }
+
+    pub(crate) fn bonus(&self) -> &StatBonus {
+        &self.bonus
+    }
+
+    pub(crate) fn equipment(&self) -> &EquipmentStats {
+        &self.equipment
+    }
+
+    pub(crate) fn max_health(&self, stats: Stats, level: u8) -> u32 {
+        stats.max_health_with(level, &self.bonus)
+    }
+
+    pub(crate) fn max_mana(&self, stats: Stats, level: u8) -> u32 {
+        stats.max_mana_with(level, &self.bonus)
+    }
+
+    pub(crate) fn as_message(&self, stats: Stats, level: u8) -> CharacterStatsMessage {
+        let total = stats.with_bonus(&self.bonus);
+        let equipment = &self.equipment;
+        let config = &get_config().game.stats;
+        let (attack, defense) = (config.attack_per_point, config.defense_per_point);
+        CharacterStatsMessage::new(
+            (total.phys_attack(attack) + equipment.phys_attack_min) as u32,
+            (total.phys_attack(attack) + equipment.phys_attack_max) as u32,
+            (total.mag_attack(attack) + equipment.mag_attack_min) as u32,
+            (total.mag_attack(attack) + equipment.mag_attack_max) as u32,
+            (total.phys_defense(defense) + equipment.phys_defense) as u16,
+            (total.mag_defense(defense) + equipment.mag_defense) as u16,
+            apply_percentage(equipment.hit_rate, self.bonus.hit_rate_percent) as u16,
+            apply_percentage(equipment.parry_rate, self.bonus.parry_rate_percent) as u16,
+            self.max_health(stats, level),
+            self.max_mana(stats, level),
+            total.strength(),
+            total.intelligence(),
+        )
+    }
+}
+
+fn apply_percentage(base: f32, percent: u16) -> f32 {
+    base * (100.0 + f32::from(percent)) / 100.0
+}
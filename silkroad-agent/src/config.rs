@@ -53,6 +53,9 @@ pub(crate) struct GameConfig {
     pub(crate) masteries: MasteryConfig,
     pub(crate) persist_interval: u64,
     pub(crate) drop: DropConfig,
+    pub(crate) chat: ChatConfig,
+    pub(crate) memo: MemoConfig,
+    pub(crate) stats: StatsConfig,
 }
 
 #[derive(Deserialize, Default, Clone)]
@@ -98,6 +101,34 @@ pub(crate) struct DropConfig {
     pub(crate) sp_experience: f32,
 }
 
+#[derive(Deserialize, Default, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct ChatConfig {
+    /// Words that may not appear in chat messages, regardless of their case.
+    pub(crate) blocked_words: Vec<String>,
+    /// The text that replaces every blocked word.
+    pub(crate) replacement: String,
+}
+
+#[derive(Deserialize, Default, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct MemoConfig {
+    /// The maximum amount of memos a character can have in their inbox.
+    pub(crate) inbox_size: usize,
+    /// The share of the attached gold, in percent, the sender additionally has to pay.
+    pub(crate) gold_fee: u64,
+}
+
+#[derive(Deserialize, Default, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct StatsConfig {
+    /// The attack a character gets per point of strength or intelligence, on top of their equipment.
+    /// This is built into the official server and not part of the game data, so it is set here.
+    pub(crate) attack_per_point: f32,
+    /// The defense a character gets per point of strength or intelligence, on top of their equipment.
+    pub(crate) defense_per_point: f32,
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub(crate) struct GameServerConfig {
@@ -0,0 +1,138 @@
+use crate::comp::inventory::magic_params_of;
+use crate::consignment::system::MAX_PRICE;
+use crate::db::character::CharacterItem;
+use crate::persistence::ApplyInTransaction;
+use axum::async_trait;
+use chrono::{DateTime, Utc};
+use silkroad_game_base::Item;
+use sqlx::{Error, PgConnection, PgPool};
+use std::borrow::Borrow;
+
+pub(crate) struct ListingRow {
+    pub id: i32,
+    pub seller_id: i32,
+    pub seller_name: String,
+    pub item_obj_id: i32,
+    pub upgrade_level: i16,
+    pub variance: Option<i64>,
+    pub amount: i16,
+    pub magic_params: Vec<i64>,
+    pub durability: Option<i32>,
+    pub max_durability: Option<i32>,
+    pub price: i64,
+    pub deposit: i64,
+    pub fee: i64,
+    pub end_date: DateTime<Utc>,
+    pub sold: bool,
+}
+
+impl ListingRow {
+    /// Provides the listed item in the same shape as an item in a character's inventory, which has no
+    /// meaningful slot.
+    pub(crate) fn as_character_item(&self) -> CharacterItem {
+        CharacterItem {
+            id: self.id,
+            character_id: self.seller_id,
+            item_obj_id: self.item_obj_id,
+            upgrade_level: self.upgrade_level,
+            variance: self.variance,
+            slot: 0,
+            amount: self.amount,
+            magic_params: self.magic_params.clone(),
+            durability: self.durability,
+            max_durability: self.max_durability,
+            rent_type: None,
+            rent_start: None,
+            rent_end: None,
+        }
+    }
+}
+
+pub(crate) async fn load_listings<T: Borrow<PgPool>>(server_id: u16, pool: T) -> Result<Vec<ListingRow>, Error> {
+    sqlx::query_as!(
+        ListingRow,
+        "SELECT l.id, l.seller_id, c.charname AS seller_name, l.item_obj_id, l.upgrade_level, l.variance, l.amount, l.magic_params, l.durability, l.max_durability, l.price, l.deposit, l.fee, l.end_date, l.sold FROM consignment_listings l JOIN characters c ON c.id = l.seller_id WHERE l.server_id = $1",
+        server_id as i16
+    )
+    .fetch_all(pool.borrow())
+    .await
+}
+
+/// Creates a new listing for the seller, given as the character id when applying. The price is capped
+/// at [MAX_PRICE], so it, as well as the deposit and fee derived from it, always fit into the database.
+pub(crate) struct InsertListing {
+    pub(crate) server_id: u16,
+    pub(crate) id: u32,
+    pub(crate) item: Item,
+    pub(crate) price: u64,
+    pub(crate) deposit: u64,
+    pub(crate) fee: u64,
+    pub(crate) end_date: DateTime<Utc>,
+}
+
+#[async_trait]
+impl ApplyInTransaction for InsertListing {
+    async fn apply_in(&self, character_id: u32, connection: &mut PgConnection) -> Result<(), Error> {
+        sqlx::query!(
+            "INSERT INTO consignment_listings(server_id, id, seller_id, item_obj_id, upgrade_level, variance, amount, magic_params, durability, max_durability, price, deposit, fee, end_date) VALUES($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)",
+            self.server_id as i16,
+            self.id as i32,
+            character_id as i32,
+            self.item.reference.common.ref_id as i32,
+            self.item.type_data.upgrade_level().map(|a| a as i16).unwrap_or(0),
+            self.item.variance.map(|a| a as i64),
+            self.item.type_data.amount() as i16,
+            magic_params_of(&self.item.type_data),
+            self.item.durability().map(|durability| durability.current() as i32),
+            self.item.durability().map(|durability| durability.max() as i32),
+            self.price as i64,
+            self.deposit as i64,
+            self.fee as i64,
+            self.end_date,
+        )
+        .execute(connection)
+        .await?;
+        Ok(())
+    }
+}
+
+pub(crate) struct MarkListingSold {
+    pub(crate) server_id: u16,
+    pub(crate) id: u32,
+}
+
+#[async_trait]
+impl ApplyInTransaction for MarkListingSold {
+    async fn apply_in(&self, _: u32, connection: &mut PgConnection) -> Result<(), Error> {
+        sqlx::query!(
+            "UPDATE consignment_listings SET sold = TRUE WHERE server_id = $1 AND id = $2",
+            self.server_id as i16,
+            self.id as i32,
+        )
+        .execute(connection)
+        .await?;
+        Ok(())
+    }
+}
+
+/// Removes the given listings of the seller, given as the character id when applying, once they have
+/// been settled.
+pub(crate) struct DeleteListings {
+    pub(crate) server_id: u16,
+    pub(crate) ids: Vec<i32>,
+}
+
+#[async_trait]
+impl ApplyInTransaction for DeleteListings {
+    async fn apply_in(&self, character_id: u32, connection: &mut PgConnection) -> Result<(), Error> {
+        sqlx::query!(
+            "DELETE FROM consignment_listings WHERE server_id = $1 AND seller_id = $2 AND id = ANY($3)",
+            self.server_id as i16,
+            character_id as i32,
+            &self.ids,
+        )
+        .execute(connection)
+        .await?;
+        Ok(())
+    }
+}
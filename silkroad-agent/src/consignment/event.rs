@@ -0,0 +1,13 @@
+use bevy::prelude::*;
+use silkroad_protocol::inventory::{ConsignmentBuy, ConsignmentRegister, ConsignmentSearch};
+
+pub(crate) enum ConsignmentRequest {
+    List,
+    Register(ConsignmentRegister),
+    Settle,
+    Buy(ConsignmentBuy),
+    Search(ConsignmentSearch),
+}
+
+#[derive(Event)]
+pub(crate) struct ConsignmentRequestEvent(pub Entity, pub ConsignmentRequest);
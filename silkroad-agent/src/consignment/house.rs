@@ -0,0 +1,169 @@
+use crate::comp::inventory::{item_content_data, rent_info, PlayerInventory};
+use crate::consignment::db::ListingRow;
+use bevy::prelude::*;
+use chrono::{DateTime, Utc};
+use silkroad_data::DataEntry;
+use silkroad_game_base::Item;
+use silkroad_protocol::inventory::{ConsignmentItem, ConsignmentSearch, ConsignmentSearchEntry, InventoryItemData};
+use std::collections::BTreeMap;
+
+const LISTING_STATUS_LISTED: u8 = 0;
+const LISTING_STATUS_SOLD: u8 = 1;
+const LISTING_STATUS_EXPIRED: u8 = 2;
+
+pub(crate) struct Listing {
+    pub(crate) seller_id: u32,
+    pub(crate) seller_name: String,
+    pub(crate) item: Item,
+    pub(crate) price: u64,
+    pub(crate) deposit: u64,
+    pub(crate) fee: u64,
+    pub(crate) end_date: DateTime<Utc>,
+    pub(crate) sold: bool,
+}
+
+impl Listing {
+    pub(crate) fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        !self.sold && self.end_date <= now
+    }
+
+    pub(crate) fn is_available(&self, now: DateTime<Utc>) -> bool {
+        !self.sold && self.end_date > now
+    }
+
+    fn matches(&self, search: &ConsignmentSearch) -> bool {
+        let type_id = self.item.reference.common.type_id;
+        let level = self.item.reference.required_level.map(|level| level.get()).unwrap_or(0);
+        (search.type_id_2 == 0 || search.type_id_2 == type_id.1)
+            && (search.type_id_3 == 0 || search.type_id_3 == type_id.2)
+            && (search.type_id_4 == 0 || search.type_id_4 == type_id.3)
+            && level >= search.min_level
+            && (search.max_level == 0 || level <= search.max_level)
+            && (search.max_price == 0 || self.price <= search.max_price)
+    }
+
+    fn as_protocol(&self, id: u32, now: DateTime<Utc>) -> ConsignmentItem {
+        let status = if self.sold {
+            LISTING_STATUS_SOLD
+        } else if self.is_expired(now) {
+            LISTING_STATUS_EXPIRED
+        } else {
+            LISTING_STATUS_LISTED
+        };
+        ConsignmentItem::new(
+            id,
+            status,
+            self.item.reference.ref_id(),
+            u32::from(self.item.stack_size()),
+            self.price,
+            self.deposit,
+            self.fee,
+            self.end_date.timestamp() as u32,
+        )
+    }
+}
+
+/// All listings of the consignment house on this server, including sold and expired ones which have
+/// not been settled by their seller yet. The listings are only available once they have been loaded
+/// from the database.
+#[derive(Resource, Default)]
+pub(crate) struct ConsignmentHouse {
+    listings: Option<BTreeMap<u32, Listing>>,
+    next_id: u32,
+}
+
+impl ConsignmentHouse {
+    pub(crate) fn load(&mut self, rows: Vec<ListingRow>) {
+        let listings = rows
+            .into_iter()
+            .map(|row| {
+                let listing = Listing {
+                    seller_id: row.seller_id as u32,
+                    seller_name: row.seller_name.clone(),
+                    item: PlayerInventory::item_from_db(&row.as_character_item()),
+                    price: row.price as u64,
+                    deposit: row.deposit as u64,
+                    fee: row.fee as u64,
+                    end_date: row.end_date,
+                    sold: row.sold,
+                };
+                (row.id as u32, listing)
+            })
+            .collect::<BTreeMap<_, _>>();
+        self.next_id = listings.keys().max().map(|id| id + 1).unwrap_or(1);
+        self.listings = Some(listings);
+    }
+
+    pub(crate) fn listings(&self) -> Option<&BTreeMap<u32, Listing>> {
+        self.listings.as_ref()
+    }
+
+    pub(crate) fn listings_mut(&mut self) -> Option<&mut BTreeMap<u32, Listing>> {
+        self.listings.as_mut()
+    }
+
+    /// Adds the listing, returning the id it has been assigned.
+    pub(crate) fn add(&mut self, listing: Listing) -> Option<u32> {
+        let listings = self.listings.as_mut()?;
+        let id = self.next_id;
+        self.next_id += 1;
+        listings.insert(id, listing);
+        Some(id)
+    }
+
+    pub(crate) fn count_of(&self, seller_id: u32) -> usize {
+        self.listings
+            .iter()
+            .flat_map(|listings| listings.values())
+            .filter(|listing| listing.seller_id == seller_id)
+            .count()
+    }
+
+    pub(crate) fn items_of(&self, seller_id: u32, now: DateTime<Utc>) -> Vec<ConsignmentItem> {
+        self.listings
+            .iter()
+            .flat_map(|listings| listings.iter())
+            .filter(|(_, listing)| listing.seller_id == seller_id)
+            .map(|(id, listing)| listing.as_protocol(*id, now))
+            .collect()
+    }
+
+    /// Finds all available listings matching the search, cheapest first, and returns the requested
+    /// page of them together with the total amount of pages.
+    pub(crate) fn search(
+        &self,
+        search: &ConsignmentSearch,
+        page_size: usize,
+        now: DateTime<Utc>,
+    ) -> (Vec<ConsignmentSearchEntry>, usize) {
+        let mut found = self
+            .listings
+            .iter()
+            .flat_map(|listings| listings.iter())
+            .filter(|(_, listing)| listing.is_available(now) && listing.matches(search))
+            .collect::<Vec<_>>();
+        found.sort_by_key(|(id, listing)| (listing.price, **id));
+
+        let total_pages = found.len().div_ceil(page_size);
+        let entries = found
+            .into_iter()
+            .skip(usize::from(search.page) * page_size)
+            .take(page_size)
+            .map(|(id, listing)| {
+                ConsignmentSearchEntry::new(
+                    *id,
+                    listing.seller_name.clone(),
+                    InventoryItemData::new(
+                        0,
+                        rent_info(&listing.item),
+                        listing.item.reference.ref_id(),
+                        item_content_data(&listing.item),
+                    ),
+                    listing.price,
+                    listing.end_date.timestamp() as u32,
+                )
+            })
+            .collect();
+        (entries, total_pages)
+    }
+}
@@ -0,0 +1,23 @@
+use crate::consignment::event::ConsignmentRequestEvent;
+use crate::consignment::house::ConsignmentHouse;
+use crate::consignment::system::{finish_loading_consignment, handle_consignment_requests, load_consignment};
+use bevy::prelude::*;
+
+mod db;
+pub(crate) mod event;
+mod house;
+mod system;
+
+pub(crate) struct ConsignmentPlugin;
+
+impl Plugin for ConsignmentPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ConsignmentRequestEvent>()
+            .init_resource::<ConsignmentHouse>()
+            .add_systems(Startup, load_consignment)
+            .add_systems(
+                Update,
+                (finish_loading_consignment, handle_consignment_requests).chain(),
+            );
+    }
+}
@@ -0,0 +1,397 @@
+use crate::comp::gold::{GoldChange, GoldPouch};
+use crate::comp::inventory::{item_content_data, rent_info, PlayerInventory};
+use crate::comp::net::Client;
+use crate::comp::npc::NPC;
+use crate::comp::player::Player;
+use crate::comp::pos::Position;
+use crate::comp::GameEntity;
+use crate::consignment::db::{load_listings, DeleteListings, InsertListing, ListingRow, MarkListingSold};
+use crate::consignment::event::{ConsignmentRequest, ConsignmentRequestEvent};
+use crate::consignment::house::{ConsignmentHouse, Listing};
+use crate::ext::DbPool;
+use crate::game::target::{Target, MAX_TARGET_DISTANCE};
+use crate::persistence::PersistTogetherEvent;
+use crate::server_plugin::ServerId;
+use crate::tasks::TaskCreator;
+use crate::world::WorldData;
+use bevy::prelude::*;
+use chrono::Utc;
+use silkroad_data::DataEntry;
+use silkroad_game_base::Inventory;
+use silkroad_protocol::inventory::{
+    ConsignmentBuy, ConsignmentBuyResponse, ConsignmentErrorCode, ConsignmentRegister, ConsignmentRegisterResponse,
+    ConsignmentResponse, ConsignmentResult, ConsignmentSearch, ConsignmentSearchResponse, ConsignmentSettleResponse,
+    InventoryOperationResult, ItemRemovalReason,
+};
+use sqlx::{Error, PgPool};
+use tokio::sync::oneshot::error::TryRecvError;
+use tokio::sync::oneshot::Receiver;
+use tracing::error;
+
+const MAX_LISTINGS: usize = 10;
+const MAX_LISTING_DAYS: u8 = 7;
+const SEARCH_PAGE_SIZE: usize = 10;
+/// The highest price an item can be listed for. This keeps the deposit and fee calculations from
+/// overflowing and lets every price fit into the database.
+pub(crate) const MAX_PRICE: u64 = 1_000_000_000_000;
+/// The share of the price, in percent, that has to be deposited when listing an item. It is returned
+/// once the listing has been settled, regardless of whether the item was sold or not.
+const DEPOSIT_PERCENTAGE: u64 = 1;
+/// The share of the price, in percent, that is kept when an item has been sold.
+const FEE_PERCENTAGE: u64 = 3;
+
+#[derive(Resource)]
+pub(crate) struct ConsignmentLoading(Receiver<Result<Vec<ListingRow>, Error>>);
+
+pub(crate) fn load_consignment(
+    mut cmd: Commands,
+    task_creator: Res<TaskCreator>,
+    pool: Res<DbPool>,
+    server_id: Res<ServerId>,
+) {
+    let receiver = task_creator.create_task(load_listings(server_id.0, PgPool::clone(&pool)));
+    cmd.insert_resource(ConsignmentLoading(receiver));
+}
+
+pub(crate) fn finish_loading_consignment(
+    mut cmd: Commands,
+    loading: Option<ResMut<ConsignmentLoading>>,
+    mut house: ResMut<ConsignmentHouse>,
+) {
+    let Some(mut loading) = loading else {
+        return;
+    };
+
+    match loading.0.try_recv() {
+        Ok(Ok(listings)) => {
+            house.load(listings);
+            cmd.remove_resource::<ConsignmentLoading>();
+        },
+        Ok(Err(e)) => {
+            error!(error = %e, "Could not load consignment listings.");
+            cmd.remove_resource::<ConsignmentLoading>();
+        },
+        Err(TryRecvError::Empty) => {},
+        Err(TryRecvError::Closed) => {
+            cmd.remove_resource::<ConsignmentLoading>();
+        },
+    }
+}
+
+pub(crate) fn handle_consignment_requests(
+    mut events: EventReader<ConsignmentRequestEvent>,
+    mut query: Query<(
+        &Client,
+        &Player,
+        &Position,
+        Option<&Target>,
+        &mut PlayerInventory,
+        &mut GoldPouch,
+    )>,
+    npc_query: Query<(&GameEntity, &Position), With<NPC>>,
+    mut house: ResMut<ConsignmentHouse>,
+    mut persist: EventWriter<PersistTogetherEvent>,
+    server_id: Res<ServerId>,
+) {
+    for ConsignmentRequestEvent(entity, request) in events.read() {
+        let Ok((client, player, position, target, mut inventory, mut gold)) = query.get_mut(*entity) else {
+            continue;
+        };
+
+        if !is_at_consignment_manager(position, target, &npc_query) {
+            reject(client, request, ConsignmentErrorCode::ServiceUnavailable);
+            continue;
+        }
+
+        let character_id = player.character.id;
+
+        match request {
+            ConsignmentRequest::List => match house.listings() {
+                Some(_) => {
+                    let items = house.items_of(character_id, Utc::now());
+                    client.send(ConsignmentResponse::new(ConsignmentResult::success(items)));
+                },
+                None => client.send(ConsignmentResponse::new(ConsignmentResult::error(
+                    ConsignmentErrorCode::ServiceUnavailable,
+                ))),
+            },
+            ConsignmentRequest::Register(register) => {
+                match register_listing(
+                    register,
+                    server_id.0,
+                    client,
+                    player,
+                    &mut inventory,
+                    &mut gold,
+                    &mut house,
+                ) {
+                    Ok(listing) => {
+                        persist.send(
+                            PersistTogetherEvent::new(vec![*entity])
+                                .with_change(character_id, GoldChange(gold.amount()))
+                                .with_change(character_id, listing),
+                        );
+                        let items = house.items_of(character_id, Utc::now());
+                        client.send(ConsignmentRegisterResponse::new(ConsignmentResult::success(items)));
+                    },
+                    Err(code) => client.send(ConsignmentRegisterResponse::new(ConsignmentResult::error(code))),
+                }
+            },
+            ConsignmentRequest::Settle => {
+                let Some(settled) = settle_listings(client, character_id, &mut inventory, &mut gold, &mut house) else {
+                    client.send(ConsignmentSettleResponse::new(ConsignmentResult::error(
+                        ConsignmentErrorCode::ServiceUnavailable,
+                    )));
+                    continue;
+                };
+
+                if !settled.is_empty() {
+                    persist.send(
+                        PersistTogetherEvent::new(vec![*entity])
+                            .with_change(character_id, GoldChange(gold.amount()))
+                            .with_change(
+                                character_id,
+                                DeleteListings {
+                                    server_id: server_id.0,
+                                    ids: settled,
+                                },
+                            ),
+                    );
+                }
+                let items = house.items_of(character_id, Utc::now());
+                client.send(ConsignmentSettleResponse::new(ConsignmentResult::success(items)));
+            },
+            ConsignmentRequest::Buy(buy) => match buy_listing(buy, character_id, &mut inventory, &mut gold, &mut house)
+            {
+                Ok(slot) => {
+                    if let Some(item) = inventory.get_item_at(slot) {
+                        client.send(InventoryOperationResult::success_gain_item(
+                            slot,
+                            rent_info(item),
+                            item.reference.ref_id(),
+                            item_content_data(item),
+                        ));
+                    }
+                    persist.send(
+                        PersistTogetherEvent::new(vec![*entity])
+                            .with_change(character_id, GoldChange(gold.amount()))
+                            .with_change(
+                                character_id,
+                                MarkListingSold {
+                                    server_id: server_id.0,
+                                    id: buy.listing_id,
+                                },
+                            ),
+                    );
+                    client.send(ConsignmentBuyResponse::Success {
+                        listing_id: buy.listing_id,
+                    });
+                },
+                Err(code) => client.send(ConsignmentBuyResponse::Failure { code }),
+            },
+            ConsignmentRequest::Search(search) => client.send(search_listings(search, &house)),
+        }
+    }
+}
+
+/// The consignment house can only be used while standing next to the selected consignment manager of
+/// a town.
+fn is_at_consignment_manager(
+    position: &Position,
+    target: Option<&Target>,
+    npc_query: &Query<(&GameEntity, &Position), With<NPC>>,
+) -> bool {
+    target
+        .and_then(|target| npc_query.get(target.entity()).ok())
+        .is_some_and(|(npc, npc_position)| {
+            WorldData::characters()
+                .find_id(npc.ref_id)
+                .is_some_and(|npc| npc.common.id.ends_with("_CONSIGNMENT"))
+                && position.distance_to(npc_position) < MAX_TARGET_DISTANCE
+        })
+}
+
+fn reject(client: &Client, request: &ConsignmentRequest, code: ConsignmentErrorCode) {
+    match request {
+        ConsignmentRequest::List => client.send(ConsignmentResponse::new(ConsignmentResult::error(code))),
+        ConsignmentRequest::Register(_) => {
+            client.send(ConsignmentRegisterResponse::new(ConsignmentResult::error(code)))
+        },
+        ConsignmentRequest::Settle => client.send(ConsignmentSettleResponse::new(ConsignmentResult::error(code))),
+        ConsignmentRequest::Buy(_) => client.send(ConsignmentBuyResponse::Failure { code }),
+        ConsignmentRequest::Search(_) => client.send(ConsignmentSearchResponse::error(code)),
+    }
+}
+
+/// Moves the item out of the inventory into a new listing and takes the deposit for it.
+fn register_listing(
+    register: &ConsignmentRegister,
+    server_id: u16,
+    client: &Client,
+    player: &Player,
+    inventory: &mut Inventory,
+    gold: &mut GoldPouch,
+    house: &mut ConsignmentHouse,
+) -> Result<InsertListing, ConsignmentErrorCode> {
+    if house.listings().is_none() {
+        return Err(ConsignmentErrorCode::ServiceUnavailable);
+    }
+
+    let character_id = player.character.id;
+    if !(1..=MAX_PRICE).contains(&register.price) || !(1..=MAX_LISTING_DAYS).contains(&register.days) {
+        return Err(ConsignmentErrorCode::InvalidItem);
+    }
+
+    // Rented items cannot be sold, as they would expire while they are listed.
+    let listable = inventory
+        .get_item_at(register.slot)
+        .is_some_and(|item| item.rental.is_none());
+    if Inventory::is_equipment_slot(register.slot) || !listable {
+        return Err(ConsignmentErrorCode::InvalidItem);
+    }
+
+    if house.count_of(character_id) >= MAX_LISTINGS {
+        return Err(ConsignmentErrorCode::TooManyListings);
+    }
+
+    let (Some(deposit), Some(fee)) = (
+        share_of(register.price, DEPOSIT_PERCENTAGE),
+        share_of(register.price, FEE_PERCENTAGE),
+    ) else {
+        return Err(ConsignmentErrorCode::InvalidItem);
+    };
+    if gold.amount() < deposit {
+        return Err(ConsignmentErrorCode::NotEnoughGold);
+    }
+
+    let Some(item) = inventory.remove_item_at(register.slot) else {
+        return Err(ConsignmentErrorCode::InvalidItem);
+    };
+    client.send(InventoryOperationResult::removed_by_server(
+        register.slot,
+        ItemRemovalReason::Removed,
+    ));
+    gold.spend(deposit);
+
+    let end_date = Utc::now() + chrono::Duration::days(i64::from(register.days));
+    let id = house
+        .add(Listing {
+            seller_id: character_id,
+            seller_name: player.character.name.clone(),
+            item,
+            price: register.price,
+            deposit,
+            fee,
+            end_date,
+            sold: false,
+        })
+        .ok_or(ConsignmentErrorCode::ServiceUnavailable)?;
+
+    Ok(InsertListing {
+        server_id,
+        id,
+        item,
+        price: register.price,
+        deposit,
+        fee,
+        end_date,
+    })
+}
+
+/// Pays out all sold listings and returns the items of all expired listings of the character,
+/// returning the ids of the listings that have been settled. Expired listings stay in the house if
+/// the inventory has no room for their item.
+fn settle_listings(
+    client: &Client,
+    character_id: u32,
+    inventory: &mut Inventory,
+    gold: &mut GoldPouch,
+    house: &mut ConsignmentHouse,
+) -> Option<Vec<i32>> {
+    let now = Utc::now();
+    let listings = house.listings_mut()?;
+    let own = listings
+        .iter()
+        .filter(|(_, listing)| listing.seller_id == character_id && (listing.sold || listing.is_expired(now)))
+        .map(|(id, _)| *id)
+        .collect::<Vec<_>>();
+
+    let mut settled = Vec::new();
+    for id in own {
+        let Some(listing) = listings.get(&id) else {
+            continue;
+        };
+
+        if listing.sold {
+            gold.gain(listing.price - listing.fee + listing.deposit);
+        } else {
+            let Some(slot) = inventory.add_item(listing.item) else {
+                continue;
+            };
+            if let Some(item) = inventory.get_item_at(slot) {
+                client.send(InventoryOperationResult::success_gain_item(
+                    slot,
+                    rent_info(item),
+                    item.reference.ref_id(),
+                    item_content_data(item),
+                ));
+            }
+            gold.gain(listing.deposit);
+        }
+
+        listings.remove(&id);
+        settled.push(id as i32);
+    }
+
+    Some(settled)
+}
+
+/// Buys the listed item, returning the inventory slot it has been placed in. The seller will receive
+/// the gold when settling their listings.
+fn buy_listing(
+    buy: &ConsignmentBuy,
+    character_id: u32,
+    inventory: &mut Inventory,
+    gold: &mut GoldPouch,
+    house: &mut ConsignmentHouse,
+) -> Result<u8, ConsignmentErrorCode> {
+    let Some(listings) = house.listings_mut() else {
+        return Err(ConsignmentErrorCode::ServiceUnavailable);
+    };
+
+    let Some(listing) = listings
+        .get_mut(&buy.listing_id)
+        .filter(|listing| listing.is_available(Utc::now()) && listing.seller_id != character_id)
+    else {
+        return Err(ConsignmentErrorCode::ListingUnavailable);
+    };
+
+    if gold.amount() < listing.price {
+        return Err(ConsignmentErrorCode::NotEnoughGold);
+    }
+
+    if inventory.free_slots() == 0 {
+        return Err(ConsignmentErrorCode::InventoryFull);
+    }
+
+    let slot = inventory
+        .add_item(listing.item)
+        .ok_or(ConsignmentErrorCode::InventoryFull)?;
+    gold.spend(listing.price);
+    listing.sold = true;
+    Ok(slot)
+}
+
+/// Calculates the given share, in percent, of the price.
+fn share_of(price: u64, percentage: u64) -> Option<u64> {
+    price.checked_mul(percentage).map(|share| share / 100)
+}
+
+fn search_listings(search: &ConsignmentSearch, house: &ConsignmentHouse) -> ConsignmentSearchResponse {
+    if house.listings().is_none() {
+        return ConsignmentSearchResponse::error(ConsignmentErrorCode::ServiceUnavailable);
+    }
+
+    let (entries, total_pages) = house.search(search, SEARCH_PAGE_SIZE, Utc::now());
+    ConsignmentSearchResponse::success(search.page, total_pages.min(u8::MAX as usize) as u8, entries)
+}
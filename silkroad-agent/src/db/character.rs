@@ -61,6 +61,8 @@ pub struct CharacterData {
     pub beginner_mark: bool,
     pub gm: bool,
     pub last_logout: Option<DateTime<Utc>>,
+    pub recall_point: Option<i16>,
+    pub inventory_size: i16,
 }
 
 impl CharacterData {
@@ -71,7 +73,7 @@ impl CharacterData {
     ) -> Result<Vec<CharacterData>, Error> {
         sqlx::query_as!(
             CharacterData,
-            "SELECT id, user_id, server_id, character_type, scale, level, exp, strength, intelligence, stat_points, current_hp, current_mp, charname, deletion_end, sp, x, y, z, max_level, region, berserk_points, gold, sp_exp, beginner_mark, gm, last_logout, rotation, race as \"race!: DbRace\" FROM characters WHERE user_id = $1 AND server_id = $2 AND (deletion_end > NOW() OR deletion_end is null) ORDER BY id ASC",
+            "SELECT id, user_id, server_id, character_type, scale, level, exp, strength, intelligence, stat_points, current_hp, current_mp, charname, deletion_end, sp, x, y, z, max_level, region, berserk_points, gold, sp_exp, beginner_mark, gm, last_logout, rotation, recall_point, inventory_size, race as \"race!: DbRace\" FROM characters WHERE user_id = $1 AND server_id = $2 AND (deletion_end > NOW() OR deletion_end is null) ORDER BY id ASC",
             user,
             shard as i32
         ).fetch_all(pool.borrow()).await
@@ -110,6 +112,12 @@ pub struct CharacterItem {
     pub variance: Option<i64>,
     pub slot: i16,
     pub amount: i16,
+    pub magic_params: Vec<i64>,
+    pub durability: Option<i32>,
+    pub max_durability: Option<i32>,
+    pub rent_type: Option<i16>,
+    pub rent_start: Option<DateTime<Utc>>,
+    pub rent_end: Option<DateTime<Utc>>,
 }
 
 impl CharacterItem {
@@ -128,6 +136,21 @@ impl CharacterItem {
         let character_item_map = all_items.into_iter().into_group_map_by(|item| item.character_id);
         Ok(character_item_map)
     }
+
+    pub async fn fetch_bulk_avatar_items<T: Borrow<PgPool>>(
+        character_ids: &[i32],
+        pool: T,
+    ) -> Result<HashMap<i32, Vec<CharacterItem>>, Error> {
+        let all_items: Vec<CharacterItem> = sqlx::query_as!(
+            CharacterItem,
+            "SELECT * FROM character_avatar_items WHERE character_id in (SELECT * FROM UNNEST($1::INTEGER[]))",
+            character_ids
+        )
+        .fetch_all(pool.borrow())
+        .await?;
+
+        Ok(all_items.into_iter().into_group_map_by(|item| item.character_id))
+    }
 }
 
 #[derive(sqlx::FromRow, Copy, Clone)]
@@ -2,7 +2,6 @@ use crate::comp::monster::SpawnedBy;
 use crate::comp::{EntityReference, GameEntity};
 use bevy::prelude::*;
 use silkroad_data::skilldata::RefSkillData;
-use silkroad_definitions::TypeId;
 use silkroad_game_base::GlobalLocation;
 
 #[derive(Event)]
@@ -53,6 +52,6 @@ pub(crate) struct SpawnMonster {
 #[derive(Event)]
 pub(crate) struct ConsumeItemEvent {
     pub player: Entity,
-    pub item: TypeId,
+    pub slot: u8,
     pub amount: u16,
 }
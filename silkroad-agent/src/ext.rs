@@ -1,9 +1,10 @@
 use crate::protocol::AgentClientProtocol;
 use bevy::prelude::*;
+use cgmath::InnerSpace;
 use derive_more::{Deref, DerefMut, From};
 use id_pool::IdPool;
 use silkroad_data::npc_pos::NpcPosition;
-use silkroad_game_base::LocalLocation;
+use silkroad_game_base::{GlobalLocation, GlobalPosition, LocalLocation};
 use silkroad_navmesh::GlobalNavmesh;
 use skrillax_server::Server;
 use sqlx::PgPool;
@@ -21,20 +22,57 @@ impl Default for EntityIdPool {
 #[derive(Resource, Deref, DerefMut, From)]
 pub struct DbPool(PgPool);
 
+/// How far away from a blocking edge movement stops, such that the next step doesn't start right on
+/// top of the edge.
+const COLLISION_MARGIN: f32 = 0.5;
+
 #[derive(Resource, Deref, From)]
 pub struct Navmesh(GlobalNavmesh);
 
 impl Navmesh {
     fn height_for_location(&self, local: LocalLocation) -> Option<f32> {
-        self.0
-            .mesh_for(local.0)
-            .and_then(|mesh| mesh.heightmap().height_at_position(local.1.x, local.1.y))
+        self.0.mesh_for(local.0).and_then(|mesh| mesh.ground_height(local.1))
     }
 
     pub fn height_for<T: Into<LocalLocation>>(&self, location: T) -> Option<f32> {
         let local = location.into();
         self.height_for_location(local)
     }
+
+    /// Finds the height of the surface at the given location that an entity at the height
+    /// `reference` would stand on, which may be the floor of a building or a bridge instead of the
+    /// terrain.
+    pub fn height_near<T: Into<LocalLocation>>(&self, location: T, reference: f32) -> Option<f32> {
+        let local = location.into();
+        self.0
+            .mesh_ref_for(local.0)
+            .and_then(|mesh| mesh.height_at(local.1, reference))
+    }
+
+    /// Checks if the given location is on a walkable cell of the navmesh. Locations inside regions
+    /// without a navmesh are never walkable.
+    pub fn is_walkable<T: Into<LocalLocation>>(&self, location: T) -> bool {
+        let local = location.into();
+        self.0
+            .mesh_ref_for(local.0)
+            .is_some_and(|mesh| mesh.is_walkable(local.1.x, local.1.y))
+    }
+
+    /// Moves from `from` towards `to`, stopping right before the first blocking edge that is in the
+    /// way at the height of `from`. Returns the location that can be reached and whether the
+    /// movement was blocked.
+    pub fn clamp_movement(&self, from: GlobalPosition, to: GlobalLocation) -> (GlobalLocation, bool) {
+        let height = from.0.y;
+        let from = from.to_location();
+        match self.0.first_blocker_at(from.0, to.0, height) {
+            Some(fraction) => {
+                let travel = to.0 - from.0;
+                let distance = (travel.magnitude() * fraction - COLLISION_MARGIN).max(0.0);
+                (GlobalLocation(from.0 + travel.normalize_to(distance)), true)
+            },
+            None => (to, false),
+        }
+    }
 }
 
 #[derive(Resource, Deref, DerefMut, From)]
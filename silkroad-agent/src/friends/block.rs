@@ -0,0 +1,91 @@
+use crate::comp::net::Client;
+use crate::comp::player::Player;
+use crate::event::LoadingFinishedEvent;
+use crate::ext::DbPool;
+use crate::friends::db::{load_block_list, BlockedRow};
+use crate::friends::list::BlockList;
+use crate::tasks::TaskCreator;
+use bevy::prelude::*;
+use silkroad_protocol::community::{BlockCharacterResponse, BlockedCharacter, FriendListError};
+use sqlx::{Error, PgPool};
+use tokio::sync::oneshot::error::TryRecvError;
+use tokio::sync::oneshot::Receiver;
+use tracing::error;
+
+#[derive(Component)]
+pub(crate) struct BlockListLoading(Receiver<Result<Vec<BlockedRow>, Error>>);
+
+/// A character the player wants to block, which is waiting for the database to find them.
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+pub(crate) struct BlockCreation(pub(super) Receiver<Result<Option<BlockedRow>, Error>>);
+
+/// Checks whether the player of the given entity has blocked the character with the given id.
+pub(crate) fn is_blocked_by(blocks: &Query<&BlockList>, entity: Entity, char_id: u32) -> bool {
+    blocks.get(entity).is_ok_and(|blocks| blocks.is_blocked(char_id))
+}
+
+pub(crate) fn load_block_lists(
+    mut reader: EventReader<LoadingFinishedEvent>,
+    query: Query<&Player>,
+    task_creator: Res<TaskCreator>,
+    pool: Res<DbPool>,
+    mut cmd: Commands,
+) {
+    for LoadingFinishedEvent(entity) in reader.read() {
+        let Ok(player) = query.get(*entity) else {
+            continue;
+        };
+
+        let receiver = task_creator.create_task(load_block_list(player.character.id, PgPool::clone(&pool)));
+        cmd.entity(*entity).try_insert(BlockListLoading(receiver));
+    }
+}
+
+pub(crate) fn finish_loading_block_lists(
+    mut query: Query<(Entity, &Client, &Player, &mut BlockListLoading)>,
+    mut cmd: Commands,
+) {
+    for (entity, client, player, mut loading) in query.iter_mut() {
+        let list = match loading.0.try_recv() {
+            Ok(Ok(blocked)) => BlockList::from_db(blocked),
+            Ok(Err(e)) => {
+                error!(error = %e, id = player.character.id, "Could not load block list.");
+                BlockList::default()
+            },
+            Err(TryRecvError::Empty) => continue,
+            Err(TryRecvError::Closed) => BlockList::default(),
+        };
+
+        client.send(list.as_protocol());
+        cmd.entity(entity).remove::<BlockListLoading>().insert(list);
+    }
+}
+
+pub(crate) fn finish_blocking_characters(
+    mut query: Query<(Entity, &Client, &mut BlockList, &mut BlockCreation)>,
+    mut cmd: Commands,
+) {
+    for (entity, client, mut list, mut creation) in query.iter_mut() {
+        match creation.0.try_recv() {
+            Ok(Ok(Some(blocked))) => {
+                let char_id = blocked.blocked_id as u32;
+                list.add(char_id, blocked.name.clone());
+                client.send(BlockCharacterResponse::Success(BlockedCharacter {
+                    char_id,
+                    name: blocked.name,
+                }));
+            },
+            Ok(Ok(None)) => {
+                client.send(BlockCharacterResponse::Failure(FriendListError::CharacterNotFound));
+            },
+            Ok(Err(e)) => {
+                error!(error = %e, "Could not block character.");
+                client.send(BlockCharacterResponse::Failure(FriendListError::CharacterNotFound));
+            },
+            Err(TryRecvError::Empty) => continue,
+            Err(TryRecvError::Closed) => {},
+        }
+        cmd.entity(entity).remove::<BlockCreation>();
+    }
+}
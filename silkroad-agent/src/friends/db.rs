@@ -0,0 +1,229 @@
+use sqlx::{Error, PgPool};
+use tracing::error;
+
+#[derive(sqlx::Type, Copy, Clone, Debug)]
+#[sqlx(type_name = "FriendStatus")]
+#[sqlx(rename_all = "UPPERCASE")]
+pub(crate) enum FriendStatus {
+    Requested,
+    Accepted,
+}
+
+pub(crate) struct FriendGroupRow {
+    pub id: i32,
+    pub name: String,
+}
+
+pub(crate) struct FriendRow {
+    pub friend_id: i32,
+    pub group_id: Option<i32>,
+    pub name: String,
+    pub character_type: i32,
+}
+
+pub(crate) async fn load_friend_list(
+    character_id: u32,
+    pool: PgPool,
+) -> Result<(Vec<FriendGroupRow>, Vec<FriendRow>), Error> {
+    // Friend requests only last as long as both characters stay online, so any request still left
+    // from or to the character can no longer be answered.
+    sqlx::query!(
+        "DELETE FROM friends WHERE (character_id = $1 OR friend_id = $1) AND status <> $2",
+        character_id as i32,
+        FriendStatus::Accepted as FriendStatus,
+    )
+    .execute(&pool)
+    .await?;
+
+    let groups = sqlx::query_as!(
+        FriendGroupRow,
+        "SELECT id, name FROM friends_groups WHERE character_id = $1",
+        character_id as i32
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    let friends = sqlx::query_as!(
+        FriendRow,
+        "SELECT f.friend_id, f.group_id, c.charname AS name, c.character_type FROM friends f JOIN characters c ON c.id = f.friend_id WHERE f.character_id = $1 AND f.status = $2",
+        character_id as i32,
+        FriendStatus::Accepted as FriendStatus,
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok((groups, friends))
+}
+
+/// Records the friend request of the character, until the friend answers it.
+pub(crate) async fn add_friend_request(character_id: u32, friend_id: u32, pool: PgPool) {
+    let result = sqlx::query!(
+        "INSERT INTO friends(character_id, friend_id, status) VALUES($1, $2, $3) ON CONFLICT(character_id, friend_id) DO UPDATE SET status = EXCLUDED.status",
+        character_id as i32,
+        friend_id as i32,
+        FriendStatus::Requested as FriendStatus,
+    )
+    .execute(&pool)
+    .await;
+
+    if let Err(e) = result {
+        error!(error = %e, character_id, friend_id, "Could not add friend request.");
+    }
+}
+
+/// Removes the friend request of the character once it was declined or can no longer be answered.
+pub(crate) async fn delete_friend_request(character_id: u32, friend_id: u32, pool: PgPool) {
+    let result = sqlx::query!(
+        "DELETE FROM friends WHERE character_id = $1 AND friend_id = $2 AND status = $3",
+        character_id as i32,
+        friend_id as i32,
+        FriendStatus::Requested as FriendStatus,
+    )
+    .execute(&pool)
+    .await;
+
+    if let Err(e) = result {
+        error!(error = %e, character_id, friend_id, "Could not delete friend request.");
+    }
+}
+
+/// Makes both characters friends with each other.
+pub(crate) async fn add_friendship(character_id: u32, friend_id: u32, pool: PgPool) {
+    let result = sqlx::query!(
+        "INSERT INTO friends(character_id, friend_id, status) VALUES($1, $2, $3), ($2, $1, $3) ON CONFLICT(character_id, friend_id) DO UPDATE SET status = EXCLUDED.status, group_id = NULL",
+        character_id as i32,
+        friend_id as i32,
+        FriendStatus::Accepted as FriendStatus,
+    )
+    .execute(&pool)
+    .await;
+
+    if let Err(e) = result {
+        error!(error = %e, character_id, friend_id, "Could not add friendship.");
+    }
+}
+
+pub(crate) async fn delete_friendship(character_id: u32, friend_id: u32, pool: PgPool) {
+    let result = sqlx::query!(
+        "DELETE FROM friends WHERE (character_id = $1 AND friend_id = $2) OR (character_id = $2 AND friend_id = $1)",
+        character_id as i32,
+        friend_id as i32,
+    )
+    .execute(&pool)
+    .await;
+
+    if let Err(e) = result {
+        error!(error = %e, character_id, friend_id, "Could not delete friendship.");
+    }
+}
+
+pub(crate) async fn create_friend_group(character_id: u32, name: String, pool: PgPool) -> Result<i32, Error> {
+    let row = sqlx::query!(
+        "INSERT INTO friends_groups(character_id, name) VALUES($1, $2) RETURNING id",
+        character_id as i32,
+        name,
+    )
+    .fetch_one(&pool)
+    .await?;
+    Ok(row.id)
+}
+
+/// Deletes the group of the character, moving all friends in it back to no group at all.
+pub(crate) async fn delete_friend_group(character_id: u32, group_id: i32, pool: PgPool) {
+    let result = async {
+        let mut transaction = pool.begin().await?;
+        sqlx::query!(
+            "UPDATE friends SET group_id = NULL WHERE character_id = $1 AND group_id = $2",
+            character_id as i32,
+            group_id,
+        )
+        .execute(&mut *transaction)
+        .await?;
+        sqlx::query!(
+            "DELETE FROM friends_groups WHERE character_id = $1 AND id = $2",
+            character_id as i32,
+            group_id,
+        )
+        .execute(&mut *transaction)
+        .await?;
+        transaction.commit().await
+    }
+    .await;
+
+    if let Err(e) = result {
+        error!(error = %e, character_id, group_id, "Could not delete friend group.");
+    }
+}
+
+pub(crate) async fn move_friend_to_group(character_id: u32, friend_id: u32, group_id: Option<i32>, pool: PgPool) {
+    let result = sqlx::query!(
+        "UPDATE friends SET group_id = $3 WHERE character_id = $1 AND friend_id = $2",
+        character_id as i32,
+        friend_id as i32,
+        group_id,
+    )
+    .execute(&pool)
+    .await;
+
+    if let Err(e) = result {
+        error!(error = %e, character_id, friend_id, "Could not move friend to group.");
+    }
+}
+
+pub(crate) struct BlockedRow {
+    pub blocked_id: i32,
+    pub name: String,
+}
+
+pub(crate) async fn load_block_list(character_id: u32, pool: PgPool) -> Result<Vec<BlockedRow>, Error> {
+    sqlx::query_as!(
+        BlockedRow,
+        "SELECT b.blocked_id, c.charname AS name FROM character_blocks b JOIN characters c ON c.id = b.blocked_id WHERE b.character_id = $1",
+        character_id as i32
+    )
+    .fetch_all(&pool)
+    .await
+}
+
+/// Blocks the character with the given name, returning `None` if no such character exists.
+pub(crate) async fn block_character(
+    character_id: u32,
+    name: String,
+    server_id: u16,
+    pool: PgPool,
+) -> Result<Option<BlockedRow>, Error> {
+    let Some(blocked) = sqlx::query_as!(
+        BlockedRow,
+        "SELECT id AS blocked_id, charname AS name FROM characters WHERE LOWER(charname) = LOWER($1) AND server_id = $2",
+        name,
+        server_id as i32,
+    )
+    .fetch_optional(&pool)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    sqlx::query!(
+        "INSERT INTO character_blocks(character_id, blocked_id) VALUES($1, $2) ON CONFLICT DO NOTHING",
+        character_id as i32,
+        blocked.blocked_id,
+    )
+    .execute(&pool)
+    .await?;
+    Ok(Some(blocked))
+}
+
+pub(crate) async fn unblock_character(character_id: u32, blocked_id: u32, pool: PgPool) {
+    let result = sqlx::query!(
+        "DELETE FROM character_blocks WHERE character_id = $1 AND blocked_id = $2",
+        character_id as i32,
+        blocked_id as i32,
+    )
+    .execute(&pool)
+    .await;
+
+    if let Err(e) = result {
+        error!(error = %e, character_id, blocked_id, "Could not unblock character.");
+    }
+}
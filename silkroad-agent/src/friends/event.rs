@@ -0,0 +1,5 @@
+use bevy::prelude::*;
+use silkroad_protocol::community::FriendListClientProtocol;
+
+#[derive(Event)]
+pub(crate) struct FriendListRequestEvent(pub Entity, pub FriendListClientProtocol);
@@ -0,0 +1,229 @@
+use crate::friends::db::{BlockedRow, FriendGroupRow, FriendRow};
+use bevy::prelude::*;
+use silkroad_protocol::community::{BlockListInfo, BlockedCharacter, FriendListEntry, FriendListGroup, FriendListInfo};
+use std::collections::{BTreeMap, HashMap};
+
+/// The group friends are in when they have not been assigned to any group.
+pub(crate) const UNASSIGNED_GROUP: u16 = 0;
+
+pub(crate) struct Friend {
+    pub(crate) name: String,
+    pub(crate) ref_id: u32,
+    pub(crate) group: u16,
+}
+
+impl Friend {
+    pub(crate) fn as_entry(&self, char_id: u32, online: bool) -> FriendListEntry {
+        FriendListEntry::new(char_id, self.name.clone(), self.ref_id, self.group, !online)
+    }
+}
+
+/// A group a player has created to sort their friends.
+struct FriendGroup {
+    /// The id of the group in the database, which isn't limited to the ids the client supports.
+    db_id: i32,
+    name: String,
+}
+
+/// The friends of a player, keyed by their character id, as well as the groups the player has
+/// created to sort them. Groups are keyed by their own ids for this player, which are sent to the
+/// client instead of their ids in the database.
+#[derive(Component, Default)]
+pub(crate) struct FriendList {
+    groups: BTreeMap<u16, FriendGroup>,
+    friends: BTreeMap<u32, Friend>,
+}
+
+impl FriendList {
+    pub(crate) fn from_db(mut groups: Vec<FriendGroupRow>, friends: Vec<FriendRow>) -> Self {
+        groups.sort_by_key(|group| group.id);
+        let mut list = FriendList::default();
+        let group_ids = groups
+            .into_iter()
+            .filter_map(|group| Some((group.id, list.add_group(group.id, group.name)?)))
+            .collect::<HashMap<_, _>>();
+        list.friends = friends
+            .into_iter()
+            .map(|friend| {
+                let group = friend
+                    .group_id
+                    .and_then(|id| group_ids.get(&id).copied())
+                    .unwrap_or(UNASSIGNED_GROUP);
+                let entry = Friend {
+                    name: friend.name,
+                    ref_id: friend.character_type as u32,
+                    group,
+                };
+                (friend.friend_id as u32, entry)
+            })
+            .collect();
+        list
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.friends.len()
+    }
+
+    pub(crate) fn ids(&self) -> impl Iterator<Item = u32> + use<'_> {
+        self.friends.keys().copied()
+    }
+
+    pub(crate) fn contains(&self, char_id: u32) -> bool {
+        self.friends.contains_key(&char_id)
+    }
+
+    pub(crate) fn add(&mut self, char_id: u32, friend: Friend) {
+        self.friends.insert(char_id, friend);
+    }
+
+    pub(crate) fn remove(&mut self, char_id: u32) -> Option<Friend> {
+        self.friends.remove(&char_id)
+    }
+
+    pub(crate) fn group_count(&self) -> usize {
+        self.groups.len()
+    }
+
+    pub(crate) fn has_group(&self, group_id: u16) -> bool {
+        group_id == UNASSIGNED_GROUP || self.groups.contains_key(&group_id)
+    }
+
+    pub(crate) fn has_group_named(&self, name: &str) -> bool {
+        self.groups.values().any(|group| group.name == name)
+    }
+
+    /// The id of the group in the database, or [None] for the unassigned group.
+    pub(crate) fn group_db_id(&self, group_id: u16) -> Option<i32> {
+        self.groups.get(&group_id).map(|group| group.db_id)
+    }
+
+    /// Adds the group with the given id in the database and returns the id it got for this player,
+    /// which is the lowest id that isn't in use yet. If all ids are in use, [None] is returned.
+    pub(crate) fn add_group(&mut self, db_id: i32, name: String) -> Option<u16> {
+        let group_id = (UNASSIGNED_GROUP + 1..=u16::MAX).find(|id| !self.groups.contains_key(id))?;
+        self.groups.insert(group_id, FriendGroup { db_id, name });
+        Some(group_id)
+    }
+
+    /// Removes the group, moving all friends that were in it back to the unassigned group. Returns
+    /// the id of the removed group in the database.
+    pub(crate) fn remove_group(&mut self, group_id: u16) -> Option<i32> {
+        let group = self.groups.remove(&group_id)?;
+        self.friends
+            .values_mut()
+            .filter(|friend| friend.group == group_id)
+            .for_each(|friend| friend.group = UNASSIGNED_GROUP);
+        Some(group.db_id)
+    }
+
+    pub(crate) fn move_to_group(&mut self, char_id: u32, group_id: u16) -> bool {
+        if !self.has_group(group_id) {
+            return false;
+        }
+
+        match self.friends.get_mut(&char_id) {
+            Some(friend) => {
+                friend.group = group_id;
+                true
+            },
+            None => false,
+        }
+    }
+
+    pub(crate) fn as_protocol(&self, online: &OnlineCharacters) -> FriendListInfo {
+        let groups = std::iter::once(FriendListGroup::not_assigned())
+            .chain(
+                self.groups
+                    .iter()
+                    .map(|(id, group)| FriendListGroup::new(*id, group.name.clone())),
+            )
+            .collect();
+        let friends = self
+            .friends
+            .iter()
+            .map(|(char_id, friend)| friend.as_entry(*char_id, online.is_online(*char_id)))
+            .collect();
+        FriendListInfo::new(groups, friends)
+    }
+}
+
+/// The characters a player has blocked, keyed by their character id.
+#[derive(Component, Default)]
+pub(crate) struct BlockList(BTreeMap<u32, String>);
+
+impl BlockList {
+    pub(crate) fn from_db(blocked: Vec<BlockedRow>) -> Self {
+        BlockList(
+            blocked
+                .into_iter()
+                .map(|blocked| (blocked.blocked_id as u32, blocked.name))
+                .collect(),
+        )
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub(crate) fn is_blocked(&self, char_id: u32) -> bool {
+        self.0.contains_key(&char_id)
+    }
+
+    pub(crate) fn is_blocked_name(&self, name: &str) -> bool {
+        self.0.values().any(|blocked| blocked.eq_ignore_ascii_case(name))
+    }
+
+    pub(crate) fn add(&mut self, char_id: u32, name: String) {
+        self.0.insert(char_id, name);
+    }
+
+    pub(crate) fn remove(&mut self, char_id: u32) -> bool {
+        self.0.remove(&char_id).is_some()
+    }
+
+    pub(crate) fn as_protocol(&self) -> BlockListInfo {
+        BlockListInfo {
+            blocked: self
+                .0
+                .iter()
+                .map(|(char_id, name)| BlockedCharacter {
+                    char_id: *char_id,
+                    name: name.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// All characters that are currently in the game and have their friend list loaded, keyed by their
+/// character id.
+#[derive(Resource, Default)]
+pub(crate) struct OnlineCharacters(HashMap<u32, Entity>);
+
+impl OnlineCharacters {
+    pub(crate) fn add(&mut self, char_id: u32, entity: Entity) {
+        self.0.insert(char_id, entity);
+    }
+
+    pub(crate) fn get(&self, char_id: u32) -> Option<Entity> {
+        self.0.get(&char_id).copied()
+    }
+
+    pub(crate) fn is_online(&self, char_id: u32) -> bool {
+        self.0.contains_key(&char_id)
+    }
+
+    /// Removes all characters whose entity no longer exists, returning their character ids.
+    pub(crate) fn remove_missing(&mut self, exists: impl Fn(Entity) -> bool) -> Vec<u32> {
+        let missing = self
+            .0
+            .iter()
+            .filter(|(_, entity)| !exists(**entity))
+            .map(|(char_id, _)| *char_id)
+            .collect::<Vec<_>>();
+        for char_id in missing.iter() {
+            self.0.remove(char_id);
+        }
+        missing
+    }
+}
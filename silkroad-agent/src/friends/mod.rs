@@ -0,0 +1,37 @@
+use crate::friends::block::{finish_blocking_characters, finish_loading_block_lists, load_block_lists};
+use crate::friends::event::FriendListRequestEvent;
+use crate::friends::list::OnlineCharacters;
+use crate::friends::system::{
+    finish_creating_friend_groups, finish_loading_friend_lists, handle_friend_list_requests, load_friend_lists,
+    notify_offline_friends,
+};
+use bevy::prelude::*;
+
+pub(crate) mod block;
+mod db;
+pub(crate) mod event;
+pub(crate) mod list;
+mod system;
+
+pub(crate) struct FriendsPlugin;
+
+impl Plugin for FriendsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<FriendListRequestEvent>()
+            .init_resource::<OnlineCharacters>()
+            .add_systems(
+                Update,
+                (
+                    load_friend_lists,
+                    finish_loading_friend_lists,
+                    load_block_lists,
+                    finish_loading_block_lists,
+                    handle_friend_list_requests,
+                    finish_creating_friend_groups,
+                    finish_blocking_characters,
+                    notify_offline_friends,
+                )
+                    .chain(),
+            );
+    }
+}
@@ -0,0 +1,443 @@
+use crate::comp::net::Client;
+use crate::comp::player::Player;
+use crate::comp::GameEntity;
+use crate::event::LoadingFinishedEvent;
+use crate::ext::DbPool;
+use crate::friends::block::BlockCreation;
+use crate::friends::db::{
+    add_friend_request, add_friendship, block_character, create_friend_group, delete_friend_group,
+    delete_friend_request, delete_friendship, load_friend_list, move_friend_to_group, unblock_character,
+    FriendGroupRow, FriendRow,
+};
+use crate::friends::event::FriendListRequestEvent;
+use crate::friends::list::{BlockList, Friend, FriendList, OnlineCharacters, UNASSIGNED_GROUP};
+use crate::server_plugin::ServerId;
+use crate::tasks::TaskCreator;
+use crate::world::EntityLookup;
+use bevy::ecs::entity::Entities;
+use bevy::prelude::*;
+use silkroad_protocol::community::{
+    AddFriendResponse, BlockCharacterResponse, CreateFriendGroupResponse, DeleteFriendGroupResponse,
+    DeleteFriendResponse, FriendListClientProtocol, FriendListError, FriendListGroup, FriendListUpdate, FriendRequest,
+    FriendRequestAnswerResponse, MoveFriendToGroupResponse, UnblockCharacterResponse,
+};
+use sqlx::{Error, PgPool};
+use tokio::sync::oneshot::error::TryRecvError;
+use tokio::sync::oneshot::Receiver;
+use tracing::error;
+
+const MAX_FRIENDS: usize = 50;
+const MAX_FRIEND_GROUPS: usize = 10;
+const MAX_GROUP_NAME_LENGTH: usize = 12;
+const MAX_BLOCKED: usize = 50;
+
+#[derive(Component)]
+pub(crate) struct FriendListLoading(Receiver<Result<(Vec<FriendGroupRow>, Vec<FriendRow>), Error>>);
+
+/// A group the player wants to create, which is waiting for the database to assign it an id.
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+pub(crate) struct FriendGroupCreation {
+    receiver: Receiver<Result<i32, Error>>,
+    name: String,
+}
+
+/// A friend request from another player, which the invited player has yet to accept or decline.
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+pub(crate) struct FriendInvitation {
+    requester: Entity,
+    requester_id: u32,
+}
+
+pub(crate) fn load_friend_lists(
+    mut reader: EventReader<LoadingFinishedEvent>,
+    query: Query<&Player>,
+    task_creator: Res<TaskCreator>,
+    pool: Res<DbPool>,
+    mut cmd: Commands,
+) {
+    for LoadingFinishedEvent(entity) in reader.read() {
+        let Ok(player) = query.get(*entity) else {
+            continue;
+        };
+
+        let receiver = task_creator.create_task(load_friend_list(player.character.id, PgPool::clone(&pool)));
+        cmd.entity(*entity).try_insert(FriendListLoading(receiver));
+    }
+}
+
+/// Sends the friend list to the player once it has been loaded and lets all their friends know
+/// that they are now online.
+pub(crate) fn finish_loading_friend_lists(
+    mut query: Query<(Entity, &Client, &Player, &mut FriendListLoading)>,
+    friends: Query<(&Client, &FriendList)>,
+    mut online: ResMut<OnlineCharacters>,
+    mut cmd: Commands,
+) {
+    for (entity, client, player, mut loading) in query.iter_mut() {
+        let list = match loading.0.try_recv() {
+            Ok(Ok((groups, friends))) => FriendList::from_db(groups, friends),
+            Ok(Err(e)) => {
+                error!(error = %e, id = player.character.id, "Could not load friend list.");
+                FriendList::default()
+            },
+            Err(TryRecvError::Empty) => continue,
+            Err(TryRecvError::Closed) => FriendList::default(),
+        };
+
+        let char_id = player.character.id;
+        client.send(list.as_protocol(&online));
+        online.add(char_id, entity);
+        notify_friends_online(&friends, &online, &list, char_id);
+        cmd.entity(entity).remove::<FriendListLoading>().insert(list);
+    }
+}
+
+/// Lets the friends of all players that have left the game know that they're now offline.
+pub(crate) fn notify_offline_friends(
+    mut online: ResMut<OnlineCharacters>,
+    friends: Query<(&Client, &FriendList)>,
+    entities: &Entities,
+) {
+    for char_id in online.remove_missing(|entity| entities.contains(entity)) {
+        for (client, list) in friends.iter() {
+            if list.contains(char_id) {
+                client.send(FriendListUpdate::Status { char_id, offline: true });
+            }
+        }
+    }
+}
+
+fn notify_friends_online(
+    friends: &Query<(&Client, &FriendList)>,
+    online: &OnlineCharacters,
+    list: &FriendList,
+    char_id: u32,
+) {
+    for friend_id in list.ids() {
+        let Some((client, their_list)) = online.get(friend_id).and_then(|friend| friends.get(friend).ok()) else {
+            continue;
+        };
+
+        if their_list.contains(char_id) {
+            client.send(FriendListUpdate::Status {
+                char_id,
+                offline: false,
+            });
+        }
+    }
+}
+
+pub(crate) fn handle_friend_list_requests(
+    mut events: EventReader<FriendListRequestEvent>,
+    mut query: Query<(
+        &Client,
+        &Player,
+        &GameEntity,
+        &mut FriendList,
+        Option<&FriendInvitation>,
+    )>,
+    mut blocks: Query<&mut BlockList>,
+    pending_blocks: Query<(), With<BlockCreation>>,
+    lookup: Res<EntityLookup>,
+    online: Res<OnlineCharacters>,
+    server_id: Res<ServerId>,
+    task_creator: Res<TaskCreator>,
+    pool: Res<DbPool>,
+    mut cmd: Commands,
+) {
+    for FriendListRequestEvent(entity, request) in events.read() {
+        let entity = *entity;
+        match request {
+            FriendListClientProtocol::AddFriend(add) => {
+                let Ok((client, player, game_entity, list, _)) = query.get(entity) else {
+                    continue;
+                };
+
+                let Some((target, (target_client, target_player, _, target_list, target_invitation))) = lookup
+                    .get_entity_for_name(&add.name)
+                    .filter(|target| *target != entity)
+                    .and_then(|target| query.get(target).ok().map(|found| (target, found)))
+                else {
+                    client.send(AddFriendResponse::Failure(FriendListError::CharacterNotFound));
+                    continue;
+                };
+
+                // Requests of blocked characters are dropped without letting them know.
+                if blocks
+                    .get(target)
+                    .is_ok_and(|blocks| blocks.is_blocked(player.character.id))
+                {
+                    continue;
+                }
+
+                let target_id = target_player.character.id;
+                let error = if list.contains(target_id) {
+                    Some(FriendListError::AlreadyFriends)
+                } else if list.len() >= MAX_FRIENDS {
+                    Some(FriendListError::ListFull)
+                } else if target_list.len() >= MAX_FRIENDS {
+                    Some(FriendListError::TargetListFull)
+                } else if target_invitation.is_some() {
+                    Some(FriendListError::Busy)
+                } else {
+                    None
+                };
+                if let Some(error) = error {
+                    client.send(AddFriendResponse::Failure(error));
+                    continue;
+                }
+
+                cmd.entity(target).try_insert(FriendInvitation {
+                    requester: entity,
+                    requester_id: player.character.id,
+                });
+                target_client.send(FriendRequest {
+                    requester: game_entity.unique_id,
+                    name: player.character.name.clone(),
+                });
+                task_creator.spawn(add_friend_request(player.character.id, target_id, PgPool::clone(&pool)));
+            },
+            FriendListClientProtocol::FriendRequestAnswer(answer) => {
+                let Some((char_id, requester, requester_id)) =
+                    query.get(entity).ok().and_then(|(_, player, _, _, invitation)| {
+                        invitation
+                            .map(|invitation| (player.character.id, invitation.requester, invitation.requester_id))
+                    })
+                else {
+                    continue;
+                };
+                cmd.entity(entity).remove::<FriendInvitation>();
+
+                // The requester may have gone offline in the meantime, leaving nobody to become friends with.
+                let Ok([invited, inviting]) = query.get_many_mut([entity, requester]) else {
+                    if let Ok((client, ..)) = query.get(entity) {
+                        client.send(FriendRequestAnswerResponse::Failure(FriendListError::CharacterNotFound));
+                    }
+                    task_creator.spawn(delete_friend_request(requester_id, char_id, PgPool::clone(&pool)));
+                    continue;
+                };
+                let (client, player, game_entity, mut list, _) = invited;
+                let (requester_client, requester_player, requester_game_entity, mut requester_list, _) = inviting;
+
+                if requester_game_entity.unique_id != answer.requester {
+                    continue;
+                }
+
+                if !answer.accepted {
+                    requester_client.send(AddFriendResponse::Failure(FriendListError::Declined));
+                    task_creator.spawn(delete_friend_request(requester_id, char_id, PgPool::clone(&pool)));
+                    continue;
+                }
+
+                if list.len() >= MAX_FRIENDS || requester_list.len() >= MAX_FRIENDS {
+                    requester_client.send(AddFriendResponse::Failure(FriendListError::ListFull));
+                    task_creator.spawn(delete_friend_request(requester_id, char_id, PgPool::clone(&pool)));
+                    continue;
+                }
+
+                let friend = Friend {
+                    name: player.character.name.clone(),
+                    ref_id: game_entity.ref_id,
+                    group: UNASSIGNED_GROUP,
+                };
+                requester_client.send(AddFriendResponse::Success(friend.as_entry(char_id, true)));
+                requester_list.add(char_id, friend);
+
+                let requester_friend = Friend {
+                    name: requester_player.character.name.clone(),
+                    ref_id: requester_game_entity.ref_id,
+                    group: UNASSIGNED_GROUP,
+                };
+                client.send(FriendListUpdate::Added(requester_friend.as_entry(requester_id, true)));
+                list.add(requester_id, requester_friend);
+
+                task_creator.spawn(add_friendship(requester_id, char_id, PgPool::clone(&pool)));
+            },
+            FriendListClientProtocol::DeleteFriend(delete) => {
+                let Ok((client, player, _, mut list, _)) = query.get_mut(entity) else {
+                    continue;
+                };
+
+                let friend_id = delete.friend_character_id;
+                if list.remove(friend_id).is_none() {
+                    client.send(DeleteFriendResponse::Failure(FriendListError::NotFriends));
+                    continue;
+                }
+
+                let char_id = player.character.id;
+                client.send(DeleteFriendResponse::Success {
+                    friend_character_id: friend_id,
+                });
+                task_creator.spawn(delete_friendship(char_id, friend_id, PgPool::clone(&pool)));
+
+                if let Some((friend_client, _, _, mut friend_list, _)) =
+                    online.get(friend_id).and_then(|friend| query.get_mut(friend).ok())
+                {
+                    if friend_list.remove(char_id).is_some() {
+                        friend_client.send(FriendListUpdate::Removed { char_id });
+                    }
+                }
+            },
+            FriendListClientProtocol::CreateFriendGroup(create) => {
+                let Ok((client, player, _, list, _)) = query.get(entity) else {
+                    continue;
+                };
+
+                let name = create.name.trim();
+                if name.is_empty() || name.chars().count() > MAX_GROUP_NAME_LENGTH || list.has_group_named(name) {
+                    client.send(CreateFriendGroupResponse::Failure(FriendListError::InvalidGroup));
+                    continue;
+                }
+
+                if list.group_count() >= MAX_FRIEND_GROUPS {
+                    client.send(CreateFriendGroupResponse::Failure(FriendListError::TooManyGroups));
+                    continue;
+                }
+
+                let receiver = task_creator.create_task(create_friend_group(
+                    player.character.id,
+                    name.to_string(),
+                    PgPool::clone(&pool),
+                ));
+                cmd.entity(entity).try_insert(FriendGroupCreation {
+                    receiver,
+                    name: name.to_string(),
+                });
+            },
+            FriendListClientProtocol::DeleteFriendGroup(delete) => {
+                let Ok((client, player, _, mut list, _)) = query.get_mut(entity) else {
+                    continue;
+                };
+
+                let Some(group_db_id) = list.remove_group(delete.group_id) else {
+                    client.send(DeleteFriendGroupResponse::Failure(FriendListError::InvalidGroup));
+                    continue;
+                };
+
+                client.send(DeleteFriendGroupResponse::Success {
+                    group_id: delete.group_id,
+                });
+                task_creator.spawn(delete_friend_group(
+                    player.character.id,
+                    group_db_id,
+                    PgPool::clone(&pool),
+                ));
+            },
+            FriendListClientProtocol::MoveFriendToGroup(movement) => {
+                let Ok((client, player, _, mut list, _)) = query.get_mut(entity) else {
+                    continue;
+                };
+
+                if !list.contains(movement.friend_character_id) {
+                    client.send(MoveFriendToGroupResponse::Failure(FriendListError::NotFriends));
+                    continue;
+                }
+
+                if !list.move_to_group(movement.friend_character_id, movement.group_id) {
+                    client.send(MoveFriendToGroupResponse::Failure(FriendListError::InvalidGroup));
+                    continue;
+                }
+
+                client.send(MoveFriendToGroupResponse::Success {
+                    friend_character_id: movement.friend_character_id,
+                    group_id: movement.group_id,
+                });
+                task_creator.spawn(move_friend_to_group(
+                    player.character.id,
+                    movement.friend_character_id,
+                    list.group_db_id(movement.group_id),
+                    PgPool::clone(&pool),
+                ));
+            },
+            FriendListClientProtocol::BlockCharacter(block) => {
+                let Ok((client, player, ..)) = query.get(entity) else {
+                    continue;
+                };
+                let Ok(list) = blocks.get(entity) else {
+                    continue;
+                };
+
+                if pending_blocks.contains(entity) {
+                    client.send(BlockCharacterResponse::Failure(FriendListError::Busy));
+                    continue;
+                }
+
+                let name = block.name.trim();
+                if name.is_empty() || name.eq_ignore_ascii_case(&player.character.name) {
+                    client.send(BlockCharacterResponse::Failure(FriendListError::CharacterNotFound));
+                    continue;
+                }
+
+                if list.is_blocked_name(name) {
+                    client.send(BlockCharacterResponse::Failure(FriendListError::AlreadyBlocked));
+                    continue;
+                }
+
+                if list.len() >= MAX_BLOCKED {
+                    client.send(BlockCharacterResponse::Failure(FriendListError::BlockListFull));
+                    continue;
+                }
+
+                let receiver = task_creator.create_task(block_character(
+                    player.character.id,
+                    name.to_string(),
+                    server_id.0,
+                    PgPool::clone(&pool),
+                ));
+                cmd.entity(entity).try_insert(BlockCreation(receiver));
+            },
+            FriendListClientProtocol::UnblockCharacter(unblock) => {
+                let Ok((client, player, ..)) = query.get(entity) else {
+                    continue;
+                };
+                let Ok(mut list) = blocks.get_mut(entity) else {
+                    continue;
+                };
+
+                if !list.remove(unblock.char_id) {
+                    client.send(UnblockCharacterResponse::Failure(FriendListError::NotBlocked));
+                    continue;
+                }
+
+                client.send(UnblockCharacterResponse::Success {
+                    char_id: unblock.char_id,
+                });
+                task_creator.spawn(unblock_character(
+                    player.character.id,
+                    unblock.char_id,
+                    PgPool::clone(&pool),
+                ));
+            },
+        }
+    }
+}
+
+pub(crate) fn finish_creating_friend_groups(
+    mut query: Query<(Entity, &Client, &mut FriendList, &mut FriendGroupCreation)>,
+    mut cmd: Commands,
+) {
+    for (entity, client, mut list, mut creation) in query.iter_mut() {
+        match creation.receiver.try_recv() {
+            Ok(Ok(id)) => match list.add_group(id, creation.name.clone()) {
+                Some(group_id) => {
+                    client.send(CreateFriendGroupResponse::Success(FriendListGroup::new(
+                        group_id,
+                        creation.name.clone(),
+                    )));
+                },
+                None => {
+                    client.send(CreateFriendGroupResponse::Failure(FriendListError::TooManyGroups));
+                },
+            },
+            Ok(Err(e)) => {
+                error!(error = %e, "Could not create friend group.");
+                client.send(CreateFriendGroupResponse::Failure(FriendListError::InvalidGroup));
+            },
+            Err(TryRecvError::Empty) => continue,
+            Err(TryRecvError::Closed) => {},
+        }
+        cmd.entity(entity).remove::<FriendGroupCreation>();
+    }
+}
@@ -0,0 +1,136 @@
+use crate::comp::exp::Leveled;
+use crate::comp::inventory::{PlayerAvatarInventory, PlayerInventory};
+use crate::comp::net::Client;
+use crate::comp::GameEntity;
+use crate::game::exchange::Exchange;
+use crate::game::inventory::{does_gender_match, gender_of};
+use crate::game::stall::Stall;
+use crate::input::PlayerInput;
+use crate::persistence::PersistTogetherEvent;
+use bevy::prelude::*;
+use silkroad_game_base::{AvatarInventory, AvatarSlot, Inventory};
+use silkroad_protocol::inventory::{
+    InventoryOperationError, InventoryOperationRequest, InventoryOperationResponseData, InventoryOperationResult,
+};
+
+pub(crate) fn handle_avatar_input(
+    mut query: Query<(
+        Entity,
+        &Client,
+        &PlayerInput,
+        &GameEntity,
+        &Leveled,
+        &mut PlayerInventory,
+        &mut PlayerAvatarInventory,
+        Has<Exchange>,
+        Has<Stall>,
+    )>,
+    mut persist: EventWriter<PersistTogetherEvent>,
+) {
+    for (entity, client, input, game_entity, level, mut inventory, mut avatars, exchanging, vending) in query.iter_mut()
+    {
+        let Some(ref action) = input.inventory else {
+            continue;
+        };
+
+        let result = match action.data {
+            InventoryOperationRequest::EquipAvatar { .. } | InventoryOperationRequest::UnequipAvatar { .. }
+                if exchanging || vending =>
+            {
+                Err(InventoryOperationError::Busy)
+            },
+            InventoryOperationRequest::EquipAvatar { source, target } => equip_avatar(
+                source,
+                target,
+                game_entity,
+                level.current_level(),
+                &mut inventory,
+                &mut avatars,
+            )
+            .map(|_| InventoryOperationResponseData::EquipAvatar { source, target }),
+            InventoryOperationRequest::UnequipAvatar { source, target } => {
+                unequip_avatar(source, target, &mut inventory, &mut avatars)
+                    .map(|_| InventoryOperationResponseData::UnequipAvatar { source, target })
+            },
+            _ => continue,
+        };
+
+        match result {
+            Ok(response) => {
+                // The item moves between the inventory and the avatars, which must not get lost or
+                // duplicated if only one of them could be stored.
+                persist.send(PersistTogetherEvent::new(vec![entity]));
+                client.send(InventoryOperationResult::Success(response));
+            },
+            Err(error) => client.send(InventoryOperationResult::Failure(error)),
+        }
+    }
+}
+
+/// Moves the avatar item from the bag slot into the avatar slot. An avatar that is already worn in
+/// that slot takes the place of the new avatar in the bag.
+fn equip_avatar(
+    source: u8,
+    target: u8,
+    game_entity: &GameEntity,
+    level: u8,
+    inventory: &mut Inventory,
+    avatars: &mut AvatarInventory,
+) -> Result<(), InventoryOperationError> {
+    if Inventory::is_equipment_slot(source) {
+        return Err(InventoryOperationError::InvalidTarget);
+    }
+
+    let item = inventory
+        .get_item_at(source)
+        .ok_or(InventoryOperationError::InvalidTarget)?;
+    if AvatarSlot::of_item(item).map(u8::from) != Some(target) {
+        return Err(InventoryOperationError::EquipItemErr);
+    }
+
+    if item
+        .reference
+        .required_level
+        .is_some_and(|required| required.get() > level)
+    {
+        return Err(InventoryOperationError::TooLowLevel);
+    }
+
+    if !does_gender_match(gender_of(game_entity), item.reference.biological_type) {
+        return Err(InventoryOperationError::DifferentSex);
+    }
+
+    let item = inventory
+        .remove_item_at(source)
+        .ok_or(InventoryOperationError::InvalidTarget)?;
+    let previous = avatars
+        .equip(target, item)
+        .map_err(|_| InventoryOperationError::EquipItemErr)?;
+    if let Some(previous) = previous {
+        inventory
+            .put_item_at(source, previous)
+            .expect("The slot of the equipped avatar should be free.");
+    }
+    Ok(())
+}
+
+/// Moves the avatar item from the avatar slot into the empty bag slot.
+fn unequip_avatar(
+    source: u8,
+    target: u8,
+    inventory: &mut Inventory,
+    avatars: &mut AvatarInventory,
+) -> Result<(), InventoryOperationError> {
+    let item = avatars
+        .get_item_at(source)
+        .ok_or(InventoryOperationError::InvalidTarget)?;
+    if inventory.get_item_at(target).is_some() {
+        return Err(InventoryOperationError::InventoryFull);
+    }
+
+    inventory
+        .put_item_at(target, *item)
+        .map_err(|_| InventoryOperationError::InvalidTarget)?;
+    avatars.unequip(source);
+    Ok(())
+}
@@ -2,8 +2,10 @@ use crate::comp::drop::{Drop, DropBundle};
 use crate::comp::pos::Position;
 use crate::comp::{Despawn, EntityReference, GameEntity};
 use crate::ext::{EntityIdPool, Navmesh};
+use crate::party::parties::{InParty, Parties, ITEM_SHARE_RANGE_SQUARED};
 use bevy::prelude::*;
 use derive_more::Constructor;
+use rand::prelude::IndexedRandom;
 use rand::Rng;
 use silkroad_data::DataEntry;
 use silkroad_game_base::{GlobalLocation, GlobalPosition, Heading, Item, Vector2Ext};
@@ -11,10 +13,38 @@ use silkroad_game_base::{GlobalLocation, GlobalPosition, Heading, Item, Vector2E
 #[derive(Constructor, Event)]
 pub(crate) struct SpawnDrop {
     pub item: Item,
-    pub relative_position: GlobalLocation,
+    pub relative_position: GlobalPosition,
     pub owner: Option<EntityReference>,
 }
 
+/// Determines who may pick up a drop of a monster killed by the given killer. If the killer is in a
+/// party that shares items, a random member of the party close to the drop is chosen instead.
+pub(crate) fn drop_owner(
+    killer: EntityReference,
+    location: &Position,
+    parties: &Parties,
+    members: &Query<(&GameEntity, &Position, Option<&InParty>)>,
+) -> EntityReference {
+    let Some(party) = members
+        .get(killer.0)
+        .ok()
+        .and_then(|(_, _, in_party)| in_party)
+        .and_then(|in_party| parties.get(in_party.0))
+        .filter(|party| party.party_type.shares_items())
+    else {
+        return killer;
+    };
+
+    let receivers = party
+        .members
+        .iter()
+        .filter_map(|member| members.get(member.0).ok().map(|found| (member.0, found)))
+        .filter(|(_, (_, position, _))| location.distance_to(position) <= ITEM_SHARE_RANGE_SQUARED)
+        .map(|(entity, (game_entity, _, _))| EntityReference(entity, *game_entity))
+        .collect::<Vec<_>>();
+    receivers.choose(&mut rand::rng()).copied().unwrap_or(killer)
+}
+
 pub(crate) fn tick_drop(mut cmd: Commands, time: Res<Time>, mut drops: Query<(Entity, &mut Despawn)>) {
     for (entity, mut despawn) in drops.iter_mut() {
         despawn.0.tick(time.delta());
@@ -50,9 +80,8 @@ pub(crate) fn create_drops(
     }
 }
 
-fn random_position_around(navmesh: &Navmesh, origin: GlobalLocation, radius: f32) -> GlobalPosition {
-    let drop_position = origin.random_in_radius(radius);
-    let local_drop_pos = GlobalLocation(drop_position).to_local();
-    let drop_position = drop_position.with_height(navmesh.height_for(local_drop_pos).unwrap_or(0.0f32));
-    GlobalPosition(drop_position)
+fn random_position_around(navmesh: &Navmesh, origin: GlobalPosition, radius: f32) -> GlobalPosition {
+    let target = GlobalLocation(origin.to_location().random_in_radius(radius));
+    let (drop_location, _) = navmesh.clamp_movement(origin, target);
+    drop_location.with_y(navmesh.height_near(drop_location, origin.0.y).unwrap_or(origin.0.y))
 }
@@ -0,0 +1,171 @@
+use crate::comp::gold::GoldPouch;
+use crate::comp::inventory::PlayerInventory;
+use crate::comp::net::Client;
+use crate::comp::npc::NPC;
+use crate::comp::pos::Position;
+use crate::comp::GameEntity;
+use crate::event::DamageReceiveEvent;
+use crate::game::target::MAX_TARGET_DISTANCE;
+use crate::input::PlayerInput;
+use crate::world::{EntityLookup, WorldData};
+use bevy::prelude::*;
+use cgmath::MetricSpace;
+use rand::{rng, Rng};
+use silkroad_definitions::inventory::EquipmentSlot;
+use silkroad_game_base::{Item, ItemTypeData, WEAPON_SLOT};
+use silkroad_protocol::inventory::{InventoryOperationError, ItemDurabilityUpdate, RepairItemResponse, RepairKind};
+
+const ARMOR_SLOTS: [EquipmentSlot; 7] = [
+    EquipmentSlot::HeadArmor,
+    EquipmentSlot::ShoulderArmor,
+    EquipmentSlot::WristArmor,
+    EquipmentSlot::ChestArmor,
+    EquipmentSlot::LegArmor,
+    EquipmentSlot::FootArmor,
+    EquipmentSlot::SecondaryWeapon,
+];
+
+/// Wears down the weapon of the attacker and a random piece of armor of the target for every hit.
+pub(crate) fn wear_equipment(
+    mut damage_events: EventReader<DamageReceiveEvent>,
+    mut query: Query<(&Client, &mut PlayerInventory)>,
+) {
+    for event in damage_events.read() {
+        if let Ok((client, mut inventory)) = query.get_mut(event.source.0) {
+            if inventory.weapon().is_some_and(can_wear_down) {
+                wear_down(client, &mut inventory, WEAPON_SLOT);
+            }
+        }
+
+        if let Ok((client, mut inventory)) = query.get_mut(event.target.0) {
+            let armor_slots = ARMOR_SLOTS
+                .into_iter()
+                .map(u8::from)
+                .filter(|slot| inventory.get_item_at(*slot).is_some_and(can_wear_down))
+                .collect::<Vec<_>>();
+            if armor_slots.is_empty() {
+                continue;
+            }
+
+            let slot = armor_slots[rng().random_range(0..armor_slots.len())];
+            wear_down(client, &mut inventory, slot);
+        }
+    }
+}
+
+/// Only equipment that can break and isn't broken yet loses durability.
+fn can_wear_down(item: &Item) -> bool {
+    item.durability().is_some_and(|durability| durability.max() > 0) && !item.is_broken()
+}
+
+fn wear_down(client: &Client, inventory: &mut Mut<PlayerInventory>, slot: u8) {
+    // Losing durability only affects the stats once the item breaks, so we don't flag the inventory
+    // as changed for every hit. The durability still gets persisted with the other inventory changes.
+    let updated = inventory.bypass_change_detection().update_type_data(slot, |data| {
+        if let ItemTypeData::Equipment { durability, .. } = data {
+            durability.reduce(1);
+        }
+    });
+
+    let Some(durability) = updated.and_then(|data| data.durability()) else {
+        return;
+    };
+
+    if durability.is_broken() {
+        inventory.set_changed();
+    }
+    client.send(ItemDurabilityUpdate::new(slot, durability.current()));
+}
+
+pub(crate) fn repair_equipment(
+    mut query: Query<(&Client, &PlayerInput, &Position, &mut PlayerInventory, &mut GoldPouch)>,
+    npc_query: Query<(&GameEntity, &Position), With<NPC>>,
+    lookup: Res<EntityLookup>,
+) {
+    for (client, input, position, mut inventory, mut gold) in query.iter_mut() {
+        let Some(ref request) = input.repair else {
+            continue;
+        };
+
+        let is_near_repairer = lookup
+            .get_entity_for_id(request.npc_unique_id)
+            .and_then(|npc| npc_query.get(npc).ok())
+            .is_some_and(|(npc, npc_position)| {
+                offers_repair(npc.ref_id)
+                    && npc_position.position().distance2(position.position().0) < MAX_TARGET_DISTANCE
+            });
+        if !is_near_repairer {
+            client.send(RepairItemResponse::Failure(InventoryOperationError::InvalidTarget));
+            continue;
+        }
+
+        let slots = match request.kind {
+            RepairKind::Single { slot } => {
+                if !inventory.get_item_at(slot).is_some_and(needs_repair) {
+                    client.send(RepairItemResponse::Failure(InventoryOperationError::InvalidTarget));
+                    continue;
+                }
+                vec![slot]
+            },
+            RepairKind::All => inventory
+                .items()
+                .filter(|(_, item)| needs_repair(item))
+                .map(|(slot, _)| *slot)
+                .collect(),
+        };
+
+        let cost = slots
+            .iter()
+            .filter_map(|slot| inventory.get_item_at(*slot))
+            .map(repair_cost)
+            .sum::<u64>();
+        if cost > gold.amount() {
+            client.send(RepairItemResponse::Failure(InventoryOperationError::NotEnoughGold));
+            continue;
+        }
+
+        gold.spend(cost);
+        for slot in slots {
+            let repaired = inventory.update_type_data(slot, |data| {
+                if let ItemTypeData::Equipment { durability, .. } = data {
+                    durability.repair();
+                }
+            });
+
+            if let Some(durability) = repaired.and_then(|data| data.durability()) {
+                client.send(ItemDurabilityUpdate::new(slot, durability.current()));
+            }
+        }
+        client.send(RepairItemResponse::Success);
+    }
+}
+
+/// Only NPCs which sell equipment that can break, like blacksmiths and armor merchants, are able to
+/// repair it.
+fn offers_repair(npc_ref_id: u32) -> bool {
+    let Some(npc) = WorldData::characters().find_id(npc_ref_id) else {
+        return false;
+    };
+
+    let items = WorldData::items();
+    WorldData::shop_goods()
+        .sold_by(&npc.common.id)
+        .iter()
+        .filter_map(|code| items.find_code(code))
+        .any(|item| item.stats.durability.upper > 0.0)
+}
+
+fn needs_repair(item: &Item) -> bool {
+    item.durability().is_some_and(|durability| durability.missing() > 0)
+}
+
+/// Repairing an item costs a share of its price, proportional to the durability that is missing. A
+/// completely broken item thus costs as much to repair as buying it anew.
+fn repair_cost(item: &Item) -> u64 {
+    match item.durability() {
+        Some(durability) if durability.max() > 0 => {
+            item.reference.price * u64::from(durability.missing()) / u64::from(durability.max())
+        },
+        _ => 0,
+    }
+}
@@ -0,0 +1,495 @@
+use crate::agent::state::Dead;
+use crate::comp::gold::{GoldChange, GoldPouch};
+use crate::comp::inventory::{item_content_data, rent_info, PlayerInventory};
+use crate::comp::invitation::PendingInvitation;
+use crate::comp::net::Client;
+use crate::comp::player::Player;
+use crate::comp::pos::Position;
+use crate::comp::GameEntity;
+use crate::friends::block::is_blocked_by;
+use crate::friends::list::BlockList;
+use crate::game::logout::Logout;
+use crate::game::stall::Stall;
+use crate::game::target::MAX_TARGET_DISTANCE;
+use crate::input::PlayerInput;
+use crate::persistence::PersistTogetherEvent;
+use crate::world::EntityLookup;
+use bevy::prelude::*;
+use silkroad_data::DataEntry;
+use silkroad_game_base::{Inventory, Item};
+use silkroad_protocol::exchange::{
+    ExchangeApproveResponse, ExchangeCancelResponse, ExchangeCancelled, ExchangeClientProtocol, ExchangeCompleted,
+    ExchangeConfirmResponse, ExchangeConfirmed, ExchangeGoldUpdate, ExchangeItemsUpdate, ExchangeRequestResponse,
+    ExchangeStarted,
+};
+use silkroad_protocol::inventory::{
+    InventoryItemData, InventoryOperationError, InventoryOperationRequest, InventoryOperationResponseData,
+    InventoryOperationResult,
+};
+use silkroad_protocol::world::{PlayerInvitation, PlayerInvitationResponse};
+
+/// The maximum amount of items a single side may offer in an exchange.
+const MAX_EXCHANGE_ITEMS: usize = 12;
+
+/// An invitation to an exchange, which the invited player has yet to accept or decline.
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+pub(crate) struct ExchangeInvitation {
+    requester: Entity,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum ExchangeState {
+    Open,
+    Confirmed,
+    Approved,
+    Finished,
+}
+
+/// One side of an ongoing exchange, containing everything this player offers to their partner. Items
+/// are kept as they were when they were offered, such that we can verify they're still the same
+/// when the exchange is executed.
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+pub(crate) struct Exchange {
+    partner: Entity,
+    items: Vec<(u8, Item)>,
+    gold: u64,
+    state: ExchangeState,
+}
+
+impl Exchange {
+    fn new(partner: Entity) -> Self {
+        Exchange {
+            partner,
+            items: Vec::new(),
+            gold: 0,
+            state: ExchangeState::Open,
+        }
+    }
+
+    fn is_offered(&self, slot: u8) -> bool {
+        self.items.iter().any(|(offered_slot, _)| *offered_slot == slot)
+    }
+
+    fn as_update(&self, owner: &GameEntity) -> ExchangeItemsUpdate {
+        let items = self
+            .items
+            .iter()
+            .enumerate()
+            .map(|(index, (_, item))| {
+                InventoryItemData::new(
+                    index as u8,
+                    rent_info(item),
+                    item.reference.ref_id(),
+                    item_content_data(item),
+                )
+            })
+            .collect();
+        ExchangeItemsUpdate::new(owner.unique_id, items)
+    }
+}
+
+pub(crate) fn request_exchange(
+    query: Query<(
+        Entity,
+        &Client,
+        &GameEntity,
+        &Player,
+        &PlayerInput,
+        &Position,
+        Option<&Exchange>,
+        Option<&Dead>,
+        Has<Stall>,
+    )>,
+    target_query: Query<(&Client, &Position, Option<&Exchange>, Option<&Dead>, Has<Stall>), With<Player>>,
+    pending: Query<(), With<PendingInvitation>>,
+    blocks: Query<&BlockList>,
+    lookup: Res<EntityLookup>,
+    mut cmd: Commands,
+) {
+    for (entity, client, game_entity, player, input, position, exchange, dead, has_stall) in query.iter() {
+        let Some(ExchangeClientProtocol::ExchangeRequest(ref request)) = input.exchange else {
+            continue;
+        };
+
+        if exchange.is_some() || dead.is_some() || has_stall {
+            client.send(ExchangeRequestResponse::Failure(InventoryOperationError::Busy));
+            continue;
+        }
+
+        let Some((target, (target_client, target_position, target_exchange, target_dead, target_stall))) = lookup
+            .get_entity_for_id(request.target)
+            .filter(|target| *target != entity)
+            .and_then(|target| target_query.get(target).ok().map(|found| (target, found)))
+        else {
+            client.send(ExchangeRequestResponse::Failure(InventoryOperationError::InvalidTarget));
+            continue;
+        };
+
+        if position.distance_to(target_position) >= MAX_TARGET_DISTANCE {
+            client.send(ExchangeRequestResponse::Failure(InventoryOperationError::InvalidTarget));
+            continue;
+        }
+
+        if target_exchange.is_some() || target_dead.is_some() || target_stall || pending.contains(target) {
+            client.send(ExchangeRequestResponse::Failure(InventoryOperationError::Busy));
+            continue;
+        }
+
+        if is_blocked_by(&blocks, target, player.character.id) {
+            continue;
+        }
+
+        cmd.entity(target)
+            .try_insert((ExchangeInvitation { requester: entity }, PendingInvitation));
+        target_client.send(PlayerInvitation::exchange(game_entity.unique_id));
+    }
+}
+
+pub(crate) fn answer_exchange_invitation(
+    query: Query<(
+        Entity,
+        &Client,
+        &GameEntity,
+        &PlayerInput,
+        &Position,
+        &ExchangeInvitation,
+        Has<Stall>,
+    )>,
+    requester_query: Query<(
+        &Client,
+        &GameEntity,
+        &Position,
+        Option<&Exchange>,
+        Option<&Dead>,
+        Has<Stall>,
+    )>,
+    mut cmd: Commands,
+) {
+    for (entity, client, game_entity, input, position, invitation, has_stall) in query.iter() {
+        let Some(ref response) = input.invitation else {
+            continue;
+        };
+
+        cmd.entity(entity).remove::<(ExchangeInvitation, PendingInvitation)>();
+        let Ok((
+            requester_client,
+            requester_game_entity,
+            requester_position,
+            requester_exchange,
+            requester_dead,
+            requester_stall,
+        )) = requester_query.get(invitation.requester)
+        else {
+            continue;
+        };
+
+        match response {
+            PlayerInvitationResponse::Accept => {
+                if requester_exchange.is_some()
+                    || requester_dead.is_some()
+                    || requester_stall
+                    || has_stall
+                    || position.distance_to(requester_position) >= MAX_TARGET_DISTANCE
+                {
+                    requester_client.send(ExchangeRequestResponse::Failure(
+                        InventoryOperationError::ExchangeCancelled,
+                    ));
+                    continue;
+                }
+
+                cmd.entity(entity).try_insert(Exchange::new(invitation.requester));
+                cmd.entity(invitation.requester).try_insert(Exchange::new(entity));
+                requester_client.send(ExchangeRequestResponse::Success);
+                requester_client.send(ExchangeStarted {
+                    partner: game_entity.unique_id,
+                });
+                client.send(ExchangeStarted {
+                    partner: requester_game_entity.unique_id,
+                });
+            },
+            PlayerInvitationResponse::Decline(_) => {
+                requester_client.send(ExchangeRequestResponse::Failure(
+                    InventoryOperationError::ExchangeCancelled,
+                ));
+            },
+        }
+    }
+}
+
+pub(crate) fn handle_exchange(
+    mut query: Query<(
+        Entity,
+        &Client,
+        &GameEntity,
+        &Player,
+        &PlayerInput,
+        &mut PlayerInventory,
+        &mut GoldPouch,
+        &mut Exchange,
+    )>,
+    mut persist: EventWriter<PersistTogetherEvent>,
+    mut cmd: Commands,
+) {
+    let acting = query
+        .iter()
+        .filter(|(_, _, _, _, input, _, _, _)| input.exchange.is_some() || input.inventory.is_some())
+        .map(|(entity, _, _, _, _, _, _, exchange)| (entity, exchange.partner))
+        .collect::<Vec<_>>();
+
+    for (entity, partner) in acting {
+        let Ok([own, other]) = query.get_many_mut([entity, partner]) else {
+            // The exchange will be cancelled once we notice the partner is gone.
+            continue;
+        };
+        let (_, client, game_entity, player, input, mut inventory, mut gold, mut exchange) = own;
+        let (_, partner_client, _, partner_player, _, mut partner_inventory, mut partner_gold, mut partner_exchange) =
+            other;
+
+        if exchange.state == ExchangeState::Finished {
+            continue;
+        }
+
+        if let Some(ref operation) = input.inventory {
+            let is_open = exchange.state == ExchangeState::Open && partner_exchange.state == ExchangeState::Open;
+            match operation.data {
+                InventoryOperationRequest::AddExchangeItem { slot } => {
+                    let can_offer = is_open
+                        && !Inventory::is_equipment_slot(slot)
+                        && !exchange.is_offered(slot)
+                        && exchange.items.len() < MAX_EXCHANGE_ITEMS;
+                    let Some(item) = inventory.get_item_at(slot).filter(|_| can_offer) else {
+                        client.send(InventoryOperationResult::Failure(
+                            InventoryOperationError::InvalidTarget,
+                        ));
+                        continue;
+                    };
+
+                    exchange.items.push((slot, *item));
+                    client.send(InventoryOperationResult::Success(
+                        InventoryOperationResponseData::AddExchangeItem { slot },
+                    ));
+                    let update = exchange.as_update(game_entity);
+                    client.send(update.clone());
+                    partner_client.send(update);
+                },
+                InventoryOperationRequest::RemoveExchangeItem { slot } => {
+                    if !is_open || usize::from(slot) >= exchange.items.len() {
+                        client.send(InventoryOperationResult::Failure(
+                            InventoryOperationError::InvalidTarget,
+                        ));
+                        continue;
+                    }
+
+                    exchange.items.remove(usize::from(slot));
+                    client.send(InventoryOperationResult::Success(
+                        InventoryOperationResponseData::RemoveExchangeItem { slot },
+                    ));
+                    let update = exchange.as_update(game_entity);
+                    client.send(update.clone());
+                    partner_client.send(update);
+                },
+                InventoryOperationRequest::ExchangeGold { amount } => {
+                    if !is_open || amount > gold.amount() {
+                        client.send(InventoryOperationResult::Failure(
+                            InventoryOperationError::NotEnoughGold,
+                        ));
+                        continue;
+                    }
+
+                    exchange.gold = amount;
+                    client.send(InventoryOperationResult::Success(
+                        InventoryOperationResponseData::ExchangeGold { amount },
+                    ));
+                    partner_client.send(ExchangeGoldUpdate { amount });
+                },
+                _ => {},
+            }
+        }
+
+        let Some(ref action) = input.exchange else {
+            continue;
+        };
+
+        match action {
+            ExchangeClientProtocol::ExchangeConfirm(_) => {
+                if exchange.state != ExchangeState::Open {
+                    client.send(ExchangeConfirmResponse::Failure(InventoryOperationError::InvalidTarget));
+                    continue;
+                }
+
+                // The offers can't change anymore once confirmed, so make sure both sides can take
+                // everything they'd receive before going any further.
+                let verified = verify_side(&exchange, &inventory, &gold, &partner_exchange)
+                    .and_then(|_| verify_side(&partner_exchange, &partner_inventory, &partner_gold, &exchange));
+                if let Err(error) = verified {
+                    client.send(ExchangeConfirmResponse::Failure(error));
+                    continue;
+                }
+
+                exchange.state = ExchangeState::Confirmed;
+                client.send(ExchangeConfirmResponse::Success);
+                partner_client.send(ExchangeConfirmed);
+            },
+            ExchangeClientProtocol::ExchangeApprove(_) => {
+                if exchange.state != ExchangeState::Confirmed || partner_exchange.state == ExchangeState::Open {
+                    client.send(ExchangeApproveResponse::Failure(InventoryOperationError::InvalidTarget));
+                    continue;
+                }
+
+                exchange.state = ExchangeState::Approved;
+                client.send(ExchangeApproveResponse::Success);
+                if partner_exchange.state != ExchangeState::Approved {
+                    continue;
+                }
+
+                exchange.state = ExchangeState::Finished;
+                partner_exchange.state = ExchangeState::Finished;
+                cmd.entity(entity).remove::<Exchange>();
+                cmd.entity(partner).remove::<Exchange>();
+
+                let verified = verify_side(&exchange, &inventory, &gold, &partner_exchange)
+                    .and_then(|_| verify_side(&partner_exchange, &partner_inventory, &partner_gold, &exchange));
+                let traded = verified
+                    .ok()
+                    .and_then(|_| trade_items(&exchange, &mut inventory, &partner_exchange, &mut partner_inventory));
+                let Some((received, partner_received)) = traded else {
+                    client.send(ExchangeCancelled);
+                    partner_client.send(ExchangeCancelled);
+                    continue;
+                };
+                announce_received_items(client, &inventory, received);
+                announce_received_items(partner_client, &partner_inventory, partner_received);
+
+                gold.spend(exchange.gold);
+                partner_gold.gain(exchange.gold);
+                partner_gold.spend(partner_exchange.gold);
+                gold.gain(partner_exchange.gold);
+
+                persist.send(
+                    PersistTogetherEvent::new(vec![entity, partner])
+                        .with_change(player.character.id, GoldChange(gold.amount()))
+                        .with_change(partner_player.character.id, GoldChange(partner_gold.amount())),
+                );
+                client.send(ExchangeCompleted);
+                partner_client.send(ExchangeCompleted);
+            },
+            ExchangeClientProtocol::ExchangeCancel(_) => {
+                exchange.state = ExchangeState::Finished;
+                partner_exchange.state = ExchangeState::Finished;
+                cmd.entity(entity).remove::<Exchange>();
+                cmd.entity(partner).remove::<Exchange>();
+                client.send(ExchangeCancelResponse::Success);
+                partner_client.send(ExchangeCancelled);
+            },
+            ExchangeClientProtocol::ExchangeRequest(_) => {},
+        }
+    }
+}
+
+/// Cancels exchanges where either side is no longer able to continue, because they died, started
+/// logging out, moved too far away or disappeared entirely.
+pub(crate) fn cancel_broken_exchanges(
+    query: Query<(Entity, &Client, &Position, &Exchange, Option<&Dead>, Option<&Logout>)>,
+    partner_query: Query<(&Position, &Exchange)>,
+    mut cmd: Commands,
+) {
+    for (entity, client, position, exchange, dead, logout) in query.iter() {
+        if exchange.state == ExchangeState::Finished {
+            continue;
+        }
+
+        let has_partner = partner_query
+            .get(exchange.partner)
+            .is_ok_and(|(partner_position, partner_exchange)| {
+                partner_exchange.partner == entity
+                    && partner_exchange.state != ExchangeState::Finished
+                    && position.distance_to(partner_position) < MAX_TARGET_DISTANCE
+            });
+        if has_partner && dead.is_none() && logout.is_none() {
+            continue;
+        }
+
+        cmd.entity(entity).remove::<Exchange>();
+        client.send(ExchangeCancelled);
+    }
+}
+
+/// Checks that one side still has everything they offered and has enough space left for everything
+/// they'd receive from the other side.
+fn verify_side(
+    exchange: &Exchange,
+    inventory: &Inventory,
+    gold: &GoldPouch,
+    receiving: &Exchange,
+) -> Result<(), InventoryOperationError> {
+    if exchange.gold > gold.amount() {
+        return Err(InventoryOperationError::NotEnoughGold);
+    }
+
+    let has_all_items = exchange.items.iter().all(|(slot, offered)| {
+        inventory.get_item_at(*slot).is_some_and(|item| {
+            item.reference.ref_id() == offered.reference.ref_id()
+                && item.variance == offered.variance
+                && item.type_data == offered.type_data
+        })
+    });
+    if !has_all_items {
+        return Err(InventoryOperationError::InvalidTarget);
+    }
+
+    if inventory.free_slots() + exchange.items.len() < receiving.items.len() {
+        return Err(InventoryOperationError::InventoryFull);
+    }
+
+    Ok(())
+}
+
+/// Hands the offered items of both sides over to the other side and returns the slots each side
+/// received items in. This works on copies of the inventories first, such that neither inventory
+/// changes if either side can't fit everything it receives and no item ever gets lost.
+fn trade_items(
+    exchange: &Exchange,
+    inventory: &mut Inventory,
+    partner_exchange: &Exchange,
+    partner_inventory: &mut Inventory,
+) -> Option<(Vec<u8>, Vec<u8>)> {
+    let mut own = inventory.clone();
+    let mut partner = partner_inventory.clone();
+    let offered = take_offered_items(exchange, &mut own);
+    let partner_offered = take_offered_items(partner_exchange, &mut partner);
+    let received = partner_offered
+        .into_iter()
+        .map(|item| own.add_item(item))
+        .collect::<Option<Vec<_>>>()?;
+    let partner_received = offered
+        .into_iter()
+        .map(|item| partner.add_item(item))
+        .collect::<Option<Vec<_>>>()?;
+
+    *inventory = own;
+    *partner_inventory = partner;
+    Some((received, partner_received))
+}
+
+fn take_offered_items(exchange: &Exchange, inventory: &mut Inventory) -> Vec<Item> {
+    exchange
+        .items
+        .iter()
+        .filter_map(|(slot, _)| inventory.remove_item_at(*slot))
+        .collect()
+}
+
+fn announce_received_items(client: &Client, inventory: &Inventory, slots: Vec<u8>) {
+    for slot in slots {
+        if let Some(received) = inventory.get_item_at(slot) {
+            client.send(InventoryOperationResult::success_gain_item(
+                slot,
+                rent_info(received),
+                received.reference.ref_id(),
+                item_content_data(received),
+            ));
+        }
+    }
+}
@@ -2,15 +2,19 @@ use crate::comp::damage::DamageReceiver;
 use crate::comp::exp::{Experienced, Leveled, SP};
 use crate::comp::player::{Player, StatPoints};
 use crate::comp::pos::Position;
+use crate::comp::stats::CharacterStats;
 use crate::comp::{EntityReference, GameEntity, Health, Mana};
 use crate::config::get_config;
 use crate::event::EntityDeath;
+use crate::party::parties::{InParty, Parties};
 use crate::world::{EntityLookup, WorldData};
 use bevy::prelude::*;
 use silkroad_data::characterdata::RefCharacterData;
 use tracing::warn;
 
 const EXP_RECEIVE_RANGE_SQUARED: f32 = 1000.0 * 1000.0;
+/// The additional experience a party sharing experience receives for each member beyond the first.
+const PARTY_EXP_BONUS_PER_MEMBER: f32 = 0.1;
 
 #[derive(Event)]
 pub struct ReceiveExperienceEvent {
@@ -34,7 +38,8 @@ pub(crate) fn distribute_experience(
     mut experience_writer: EventWriter<ReceiveExperienceEvent>,
     dead_query: Query<(&DamageReceiver, &Position)>,
     lookup: Res<EntityLookup>,
-    receiver_query: Query<(&GameEntity, &Position, &Player)>,
+    parties: Res<Parties>,
+    receiver_query: Query<(&GameEntity, &Position, &Player, Option<&InParty>)>,
 ) {
     let characters = WorldData::characters();
     let config = get_config();
@@ -44,12 +49,23 @@ pub(crate) fn distribute_experience(
         };
 
         let monster_data = characters.find_id(event.died.1.ref_id).unwrap();
+        let mut sharing_parties = Vec::new();
 
         for attacker_id in damage_distribution.all_attackers() {
-            if let Some(((game_entity, position, player), target_entity)) = lookup
+            if let Some(((game_entity, position, player, in_party), target_entity)) = lookup
                 .get_entity_for_id(attacker_id)
                 .and_then(|entity| receiver_query.get(entity).ok().zip(Some(entity)))
             {
+                if let Some(party) = in_party
+                    .and_then(|in_party| parties.get(in_party.0))
+                    .filter(|party| party.party_type.shares_experience())
+                {
+                    if !sharing_parties.contains(&party.id) {
+                        sharing_parties.push(party.id);
+                    }
+                    continue;
+                }
+
                 if death_location.distance_to(position) <= EXP_RECEIVE_RANGE_SQUARED {
                     let event = ReceiveExperienceEvent {
                         source: Some(event.died),
@@ -61,9 +77,39 @@ pub(crate) fn distribute_experience(
                 }
             }
         }
+
+        for party in sharing_parties.into_iter().filter_map(|id| parties.get(id)) {
+            let receivers = party
+                .members
+                .iter()
+                .filter_map(|member| receiver_query.get(member.0).ok().map(|found| (member.0, found)))
+                .filter(|(_, (_, position, _, _))| death_location.distance_to(position) <= EXP_RECEIVE_RANGE_SQUARED)
+                .collect::<Vec<_>>();
+            let share = party_experience_share(receivers.len());
+            for (target_entity, (game_entity, _, player, _)) in receivers {
+                let event = ReceiveExperienceEvent {
+                    source: Some(event.died),
+                    target: EntityReference(target_entity, *game_entity),
+                    exp: (calculate_exp(monster_data, player) as f32 * config.game.drop.experience * share) as u64,
+                    sp: (calculate_sexp(monster_data, player) as f32 * config.game.drop.sp_experience * share) as u64,
+                };
+                experience_writer.send(event);
+            }
+        }
     }
 }
 
+/// The portion of the experience each member of a party that shares experience receives, given the
+/// amount of members close enough to the kill.
+fn party_experience_share(receivers: usize) -> f32 {
+    if receivers == 0 {
+        return 0.0;
+    }
+
+    let bonus = 1.0 + PARTY_EXP_BONUS_PER_MEMBER * (receivers - 1) as f32;
+    bonus / receivers as f32
+}
+
 #[derive(Event)]
 pub(crate) struct LevelUpEvent {
     pub target: EntityReference,
@@ -109,24 +155,28 @@ pub(crate) fn receive_experience(
 
 pub(crate) fn reset_health_mana_on_level(
     mut level_up_events: EventReader<LevelUpEvent>,
-    mut query: Query<(&StatPoints, &mut Health, &mut Mana)>,
+    mut query: Query<(&StatPoints, &CharacterStats, &mut Health, &mut Mana)>,
 ) {
     for event in level_up_events.read() {
-        let Ok((stats, mut health, mut mana)) = query.get_mut(event.target.0) else {
+        let Ok((stats, character_stats, mut health, mut mana)) = query.get_mut(event.target.0) else {
             continue;
         };
-        health.upgrade(stats.stats().max_health(event.level));
-        mana.upgrade(stats.stats().max_mana(event.level));
+        health.upgrade(character_stats.max_health(stats.stats(), event.level));
+        mana.upgrade(character_stats.max_mana(stats.stats(), event.level));
     }
 }
 
 pub(crate) fn update_max_hp_mp_on_stat_change(
-    mut query: Query<(&StatPoints, &Leveled, &mut Health, &mut Mana), Changed<StatPoints>>,
+    mut query: Query<
+        (&StatPoints, Ref<CharacterStats>, &Leveled, &mut Health, &mut Mana),
+        Or<(Changed<StatPoints>, Changed<CharacterStats>)>,
+    >,
 ) {
-    for (stats, leveled, mut health, mut mana) in query.iter_mut() {
-        if stats.has_spent_points() {
-            health.increase_max(stats.stats().max_health(leveled.current_level()));
-            mana.increase_max(stats.stats().max_mana(leveled.current_level()));
+    for (stats, character_stats, leveled, mut health, mut mana) in query.iter_mut() {
+        if stats.has_spent_points() || character_stats.is_changed() {
+            let level = leveled.current_level();
+            health.increase_max(character_stats.max_health(stats.stats(), level));
+            mana.increase_max(character_stats.max_mana(stats.stats(), level));
         }
     }
 }
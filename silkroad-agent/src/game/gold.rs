@@ -3,7 +3,8 @@ use crate::comp::pos::Position;
 use crate::comp::GameEntity;
 use crate::config::get_config;
 use crate::event::EntityDeath;
-use crate::game::drop::SpawnDrop;
+use crate::game::drop::{drop_owner, SpawnDrop};
+use crate::party::parties::{InParty, Parties};
 use crate::world::WorldData;
 use bevy::prelude::*;
 use rand::{rng, Rng};
@@ -28,6 +29,8 @@ pub(crate) fn get_gold_ref_id(amount: u32) -> &'static RefItemData {
 pub(crate) fn drop_gold(
     mut death_events: EventReader<EntityDeath>,
     query: Query<(&GameEntity, &Position), With<Monster>>,
+    members: Query<(&GameEntity, &Position, Option<&InParty>)>,
+    parties: Res<Parties>,
     mut drop_events: EventWriter<SpawnDrop>,
 ) {
     let characters = WorldData::characters();
@@ -48,9 +51,10 @@ pub(crate) fn drop_gold(
                     reference: get_gold_ref_id(amount),
                     variance: None,
                     type_data: ItemTypeData::Gold { amount },
+                    rental: None,
                 },
-                relative_position: pos.location(),
-                owner: event.killer,
+                relative_position: pos.position(),
+                owner: event.killer.map(|killer| drop_owner(killer, pos, &parties, &members)),
             });
         }
     }
@@ -1,36 +1,69 @@
 use crate::comp::exp::Leveled;
 use crate::comp::gold::GoldPouch;
 use crate::comp::inventory::PlayerInventory;
+use crate::comp::mastery::MasteryKnowledge;
 use crate::comp::net::Client;
-use crate::comp::player::CharacterRace;
+use crate::comp::player::{CharacterRace, StatPoints};
 use crate::comp::pos::Position;
+use crate::comp::stats::CharacterStats;
+use crate::comp::GameEntity;
+use crate::event::ConsumeItemEvent;
 use crate::game::drop::SpawnDrop;
+use crate::game::exchange::Exchange;
 use crate::game::gold::get_gold_ref_id;
+use crate::game::stall::Stall;
 use crate::input::PlayerInput;
+use crate::world::WorldData;
 use bevy::prelude::*;
+use silkroad_data::itemdata::RefBiologicalType;
 use silkroad_definitions::type_id::{
-    ObjectClothingPart, ObjectClothingType, ObjectConsumable, ObjectConsumableAmmo, ObjectEquippable, ObjectItem,
-    ObjectJewelryType, ObjectRace, ObjectType, ObjectWeaponType,
+    ObjectClothingPart, ObjectClothingType, ObjectConsumable, ObjectConsumableAmmo, ObjectConsumableItemMall,
+    ObjectConsumableScroll, ObjectEquippable, ObjectItem, ObjectJewelryType, ObjectRace, ObjectType, ObjectWeaponType,
 };
-use silkroad_game_base::{Inventory, Item, ItemTypeData, MoveError, Race};
+use silkroad_game_base::{Inventory, Item, ItemTypeData, MoveError, Race, Stats, MAX_INVENTORY_SIZE, WEAPON_SLOT};
 use silkroad_protocol::inventory::{
     InventoryOperationError, InventoryOperationRequest, InventoryOperationResponseData, InventoryOperationResult,
+    InventorySizeUpdate, ItemAmountUpdate, UseItemResponse,
 };
 use std::cmp::max;
 
+const SHIELD_SLOT: u8 = 7;
+const INVENTORY_EXTENSION_SLOTS: usize = 32;
+
 pub(crate) fn handle_inventory_input(
     mut query: Query<(
         &Client,
         &PlayerInput,
+        &GameEntity,
         &Leveled,
         &CharacterRace,
+        &StatPoints,
+        &CharacterStats,
+        &MasteryKnowledge,
         &mut PlayerInventory,
         &mut GoldPouch,
         &Position,
+        Has<Exchange>,
+        Has<Stall>,
     )>,
     mut item_spawn: EventWriter<SpawnDrop>,
 ) {
-    for (client, input, level, race, mut inventory, mut gold, position) in query.iter_mut() {
+    for (
+        client,
+        input,
+        game_entity,
+        level,
+        race,
+        stat_points,
+        character_stats,
+        masteries,
+        mut inventory,
+        mut gold,
+        position,
+        exchanging,
+        vending,
+    ) in query.iter_mut()
+    {
         if let Some(ref action) = input.inventory {
             match action.data {
                 InventoryOperationRequest::DropGold { amount } => {
@@ -53,8 +86,9 @@ pub(crate) fn handle_inventory_input(
                             reference: item_ref,
                             variance: None,
                             type_data: ItemTypeData::Gold { amount: amount as u32 },
+                            rental: None,
                         },
-                        position.location(),
+                        position.position(),
                         None,
                     ));
 
@@ -65,25 +99,28 @@ pub(crate) fn handle_inventory_input(
                 InventoryOperationRequest::PickupItem { unique_id } => {},
                 InventoryOperationRequest::Move { source, target, amount } => {
                     if let Some(source_item) = inventory.get_item_at(source) {
-                        if Inventory::is_equipment_slot(target) {
-                            let type_id = source_item.reference.common.type_id;
-                            let object_type = ObjectType::from_type_id(&type_id)
-                                .expect("Item to equip should have valid object type.");
-                            let fits = does_object_type_match_slot(target, object_type)
-                                && source_item
-                                    .reference
-                                    .required_level
-                                    .map(|val| val.get() <= level.current_level())
-                                    .unwrap_or(true)
-                                && does_object_type_match_race(race.inner(), object_type);
-                            // TODO: check if equipment requirement sex matches
-                            //  check if required masteries matches
-                            if !fits {
-                                // TODO: Use more appropriate error code
-                                client.send(InventoryOperationResult::Failure(InventoryOperationError::Indisposable));
-                                continue;
+                        let equipper = Equipper {
+                            level: level.current_level(),
+                            race: race.inner(),
+                            gender: gender_of(game_entity),
+                            stats: stat_points.stats().with_bonus(character_stats.bonus()),
+                            masteries,
+                            inventory: &inventory,
+                        };
+                        let mut requirements = equipper.check_move(source_item, target);
+                        if requirements.is_ok() && Inventory::is_equipment_slot(source) {
+                            // When moving onto an occupied slot, the items get swapped and thus the
+                            // target item ends up in the equipment slot we're moving out of.
+                            if let Some(target_item) = inventory.get_item_at(target) {
+                                requirements = equipper.check_move(target_item, source);
                             }
                         }
+
+                        if let Err(error) = requirements {
+                            client.send(InventoryOperationResult::Failure(error));
+                            continue;
+                        }
+
                         match inventory.move_item(source, target, max(1, amount)) {
                             Err(MoveError::Impossible) => {},
                             Err(MoveError::ItemDoesNotExist) => {},
@@ -100,12 +137,221 @@ pub(crate) fn handle_inventory_input(
                         ));
                     }
                 },
+                InventoryOperationRequest::MergeStacks | InventoryOperationRequest::Sort if exchanging || vending => {
+                    // Exchanges and stalls refer to items by their slot, so they must not be moved around.
+                    client.send(InventoryOperationResult::Failure(InventoryOperationError::Busy));
+                },
+                InventoryOperationRequest::MergeStacks => {
+                    for (source, target, amount) in inventory.merge_stacks() {
+                        client.send(InventoryOperationResult::Success(
+                            InventoryOperationResponseData::move_item(source, target, amount),
+                        ));
+                    }
+                },
+                InventoryOperationRequest::Sort => {
+                    for (source, target, amount) in inventory.sort() {
+                        client.send(InventoryOperationResult::Success(
+                            InventoryOperationResponseData::move_item(source, target, amount),
+                        ));
+                    }
+                },
                 InventoryOperationRequest::DropItem { .. } => {},
+                // Handled by `handle_avatar_input`
+                InventoryOperationRequest::EquipAvatar { .. } | InventoryOperationRequest::UnequipAvatar { .. } => {},
+                // Handled as part of an ongoing exchange
+                InventoryOperationRequest::AddExchangeItem { .. }
+                | InventoryOperationRequest::RemoveExchangeItem { .. }
+                | InventoryOperationRequest::ExchangeGold { .. } => {},
             }
         }
     }
 }
 
+pub(crate) fn consume_items(
+    mut consume_events: EventReader<ConsumeItemEvent>,
+    mut query: Query<(&Client, &mut PlayerInventory)>,
+) {
+    for event in consume_events.read() {
+        let Ok((client, mut inventory)) = query.get_mut(event.player) else {
+            continue;
+        };
+
+        if let Ok(remaining) = inventory.consume_item(event.slot, event.amount) {
+            client.send(ItemAmountUpdate::new(event.slot, remaining));
+        }
+    }
+}
+
+pub(crate) fn is_inventory_extension(item: &Item) -> bool {
+    matches!(
+        ObjectType::from_type_id(&item.reference.common.type_id),
+        Some(ObjectType::Item(ObjectItem::Consumable(ObjectConsumable::ItemMall(
+            ObjectConsumableItemMall::InventoryExtension
+        ))))
+    )
+}
+
+pub(crate) fn is_global_chat_scroll(item: &Item) -> bool {
+    matches!(
+        ObjectType::from_type_id(&item.reference.common.type_id),
+        Some(ObjectType::Item(ObjectItem::Consumable(ObjectConsumable::Scroll(
+            ObjectConsumableScroll::GlobalChat
+        ))))
+    )
+}
+
+/// Consumes an inventory extension item to permanently grow the inventory of the character.
+pub(crate) fn use_inventory_extension(mut query: Query<(&Client, &PlayerInput, &mut PlayerInventory)>) {
+    for (client, input, mut inventory) in query.iter_mut() {
+        let Some(ref request) = input.use_item else {
+            continue;
+        };
+
+        let is_extension = inventory.get_item_at(request.slot).is_some_and(|item| {
+            item.reference.common.type_id.packed() == request.item_type >> 2 && is_inventory_extension(item)
+        });
+        if !is_extension {
+            continue;
+        }
+
+        if inventory.size() >= MAX_INVENTORY_SIZE {
+            client.send(UseItemResponse::Failure(InventoryOperationError::Unusable));
+            continue;
+        }
+
+        let Ok(remaining) = inventory.consume_item(request.slot, 1) else {
+            client.send(UseItemResponse::Failure(InventoryOperationError::InvalidTarget));
+            continue;
+        };
+        inventory.expand(INVENTORY_EXTENSION_SLOTS);
+
+        client.send(UseItemResponse::success(request.slot, remaining, request.item_type));
+        client.send(InventorySizeUpdate {
+            size: inventory.size() as u8,
+        });
+    }
+}
+
+/// Finds the slot of the item a skill should consume. Ammunition is only ever used from the shield
+/// slot, while other consumables may be taken from anywhere in the regular inventory.
+pub(crate) fn find_consumable_slot(inventory: &Inventory, kind: ObjectConsumable, amount: u16) -> Option<u8> {
+    let type_id = ObjectType::Item(ObjectItem::Consumable(kind)).type_id();
+    let is_sufficient = |item: &Item| item.reference.common.type_id == type_id && item.stack_size() >= amount;
+    if matches!(kind, ObjectConsumable::Ammo(_)) {
+        return inventory
+            .get_item_at(SHIELD_SLOT)
+            .filter(|item| is_sufficient(item))
+            .map(|_| SHIELD_SLOT);
+    }
+
+    inventory
+        .items()
+        .filter(|(slot, item)| !Inventory::is_equipment_slot(**slot) && is_sufficient(item))
+        .map(|(slot, _)| *slot)
+        .min()
+}
+
+/// The character trying to equip an item, with everything that is relevant to check if they fulfill the
+/// requirements of the item.
+struct Equipper<'a> {
+    level: u8,
+    race: Race,
+    gender: RefBiologicalType,
+    stats: Stats,
+    masteries: &'a MasteryKnowledge,
+    inventory: &'a Inventory,
+}
+
+impl Equipper<'_> {
+    /// Checks if the item may be moved into the given slot. Moving into a non-equipment slot is always
+    /// possible, while for equipment slots all the requirements of the item need to be fulfilled.
+    fn check_move(&self, item: &Item, slot: u8) -> Result<(), InventoryOperationError> {
+        if !Inventory::is_equipment_slot(slot) {
+            return Ok(());
+        }
+
+        let reference = item.reference;
+        let object_type =
+            ObjectType::from_type_id(&reference.common.type_id).ok_or(InventoryOperationError::EquipItemErr)?;
+        if !does_object_type_match_slot(slot, object_type) || !does_object_type_match_race(self.race, object_type) {
+            return Err(InventoryOperationError::EquipItemErr);
+        }
+
+        if reference.required_level.is_some_and(|level| level.get() > self.level) {
+            return Err(InventoryOperationError::TooLowLevel);
+        }
+
+        if !does_gender_match(self.gender, reference.biological_type) {
+            return Err(InventoryOperationError::DifferentSex);
+        }
+
+        if self.stats.strength() < reference.required_strength {
+            return Err(InventoryOperationError::MoreStrengthRequired);
+        }
+
+        if self.stats.intelligence() < reference.required_intelligence {
+            return Err(InventoryOperationError::MoreIntellectRequired);
+        }
+
+        let masteries = WorldData::masteries();
+        let lacks_mastery = reference
+            .required_levels
+            .iter()
+            .filter(|(kind, _)| masteries.find_id(*kind).is_some())
+            .any(|(mastery, level)| self.masteries.level_of(*mastery).unwrap_or(0) < level.get());
+        // We do not know a more specific error code for missing masteries.
+        if lacks_mastery {
+            return Err(InventoryOperationError::EquipItemErr);
+        }
+
+        self.check_two_handed(item, slot, object_type)
+    }
+
+    /// A two-handed weapon cannot be wielded together with a shield.
+    fn check_two_handed(&self, item: &Item, slot: u8, object_type: ObjectType) -> Result<(), InventoryOperationError> {
+        let conflicts = match slot {
+            WEAPON_SLOT if item.reference.two_handed => self.inventory.get_item_at(SHIELD_SLOT).is_some_and(is_shield),
+            SHIELD_SLOT if is_shield_type(object_type) => self
+                .inventory
+                .weapon()
+                .is_some_and(|weapon| weapon.reference.two_handed),
+            _ => false,
+        };
+
+        if conflicts {
+            Err(InventoryOperationError::EquipItemErr)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+pub(crate) fn gender_of(game_entity: &GameEntity) -> RefBiologicalType {
+    WorldData::characters()
+        .find_id(game_entity.ref_id)
+        .map(|character| character.gender)
+        .unwrap_or(RefBiologicalType::Both)
+}
+
+pub(crate) fn does_gender_match(gender: RefBiologicalType, required: RefBiologicalType) -> bool {
+    match required {
+        RefBiologicalType::Both => true,
+        RefBiologicalType::Female | RefBiologicalType::Male => gender == required,
+        RefBiologicalType::Pet1 | RefBiologicalType::Pet2 | RefBiologicalType::Pet3 => false,
+    }
+}
+
+fn is_shield(item: &Item) -> bool {
+    ObjectType::from_type_id(&item.reference.common.type_id).is_some_and(is_shield_type)
+}
+
+fn is_shield_type(object_type: ObjectType) -> bool {
+    matches!(
+        object_type,
+        ObjectType::Item(ObjectItem::Equippable(ObjectEquippable::Shield(_)))
+    )
+}
+
 fn does_object_type_match_race(user_race: Race, obj_type: ObjectType) -> bool {
     if let ObjectType::Item(item) = obj_type {
         match item {
@@ -1,15 +1,14 @@
 use crate::comp::exp::Leveled;
 use crate::comp::net::Client;
 use crate::comp::player::{Player, StatPoints};
+use crate::comp::stats::CharacterStats;
 use crate::comp::GameEntity;
 use crate::config::GameConfig;
 use crate::event::LoadingFinishedEvent;
 use crate::game::daylight::DaylightCycle;
 use bevy::prelude::*;
 use silkroad_game_base::SpawningState;
-use silkroad_protocol::character::CharacterStatsMessage;
 use silkroad_protocol::chat::{ChatSource, ChatUpdate, TextCharacterInitialization};
-use silkroad_protocol::community::{FriendListGroup, FriendListInfo};
 use silkroad_protocol::world::{CelestialUpdate, CharacterFinished};
 use tracing::debug;
 
@@ -17,30 +16,26 @@ pub(crate) fn load_finished(
     mut reader: EventReader<LoadingFinishedEvent>,
     settings: Res<GameConfig>,
     daycycle: Res<DaylightCycle>,
-    mut query: Query<(&Client, &GameEntity, &mut Player, &Leveled, &StatPoints)>,
+    mut query: Query<(
+        &Client,
+        &GameEntity,
+        &mut Player,
+        &Leveled,
+        &StatPoints,
+        &CharacterStats,
+    )>,
 ) {
     for event in reader.read() {
-        let (client, game_entity, mut player, level, stat_points) = match query.get_mut(event.0) {
+        let (client, game_entity, mut player, level, stat_points, character_stats) = match query.get_mut(event.0) {
             Ok(data) => data,
             _ => continue,
         };
 
         debug!(id = ?client.0.id(), "Finished loading.");
         player.character.state = SpawningState::Finished;
-        send_character_stats(client, stat_points, level.current_level());
+        client.send(character_stats.as_message(stat_points.stats(), level.current_level()));
         send_text_initialization(client);
-        let (hour, minute) = daycycle.time();
-        client.send(CelestialUpdate {
-            unique_id: game_entity.unique_id,
-            moon_position: daycycle.moon(),
-            hour,
-            minute,
-        });
-        client.send(CharacterFinished::default());
-        client.send(FriendListInfo {
-            groups: vec![FriendListGroup::not_assigned()],
-            friends: vec![],
-        });
+        send_character_finished(client, game_entity, &daycycle);
 
         if let Some(notice) = &settings.join_notice {
             client.send(ChatUpdate::new(ChatSource::Notice, notice.clone()));
@@ -48,21 +43,16 @@ pub(crate) fn load_finished(
     }
 }
 
-fn send_character_stats(client: &Client, stat_points: &StatPoints, level: u8) {
-    client.send(CharacterStatsMessage::new(
-        100,
-        100,
-        100,
-        100,
-        100,
-        100,
-        100,
-        100,
-        stat_points.stats().max_health(level),
-        stat_points.stats().max_mana(level),
-        stat_points.stats().strength(),
-        stat_points.stats().intelligence(),
-    ));
+/// Sends the current time of day and tells the client that the character has finished spawning.
+pub(crate) fn send_character_finished(client: &Client, game_entity: &GameEntity, daycycle: &DaylightCycle) {
+    let (hour, minute) = daycycle.time();
+    client.send(CelestialUpdate {
+        unique_id: game_entity.unique_id,
+        moon_position: daycycle.moon(),
+        hour,
+        minute,
+    });
+    client.send(CharacterFinished::default());
 }
 
 fn send_text_initialization(client: &Client) {
@@ -1,47 +1,63 @@
 use crate::chat::ChatPlugin;
 use crate::comp::exp::{Experienced, Leveled, SP};
 use crate::comp::gold::GoldPouch;
-use crate::comp::inventory::PlayerInventory;
+use crate::comp::inventory::{PlayerAvatarInventory, PlayerInventory};
 use crate::comp::mastery::MasteryKnowledge;
 use crate::comp::player::StatPoints;
 use crate::comp::pos::Position;
+use crate::comp::recall::RecallPoint;
 use crate::comp::skill::{Hotbar, SkillBook};
 use crate::comp::{Health, Mana};
 use crate::event::{
-    DamageReceiveEvent, EntityDeath, LoadingFinishedEvent, PlayerLevelUp, SpawnMonster, UniqueKilledEvent,
+    ConsumeItemEvent, DamageReceiveEvent, EntityDeath, LoadingFinishedEvent, PlayerLevelUp, SpawnMonster,
+    UniqueKilledEvent,
 };
 use crate::ext::ActionIdCounter;
 use crate::game::action::handle_action;
+use crate::game::avatar::handle_avatar_input;
 use crate::game::damage::{attack_player, handle_damage, handle_monster_death};
 use crate::game::daylight::{advance_daylight, DaylightCycle};
 use crate::game::drop::{create_drops, tick_drop, SpawnDrop};
+use crate::game::durability::{repair_equipment, wear_equipment};
+use crate::game::exchange::{answer_exchange_invitation, cancel_broken_exchanges, handle_exchange, request_exchange};
 use crate::game::exp::{
     distribute_experience, receive_experience, reset_health_mana_on_level, update_max_hp_mp_on_stat_change,
     ReceiveExperienceEvent,
 };
 use crate::game::gold::drop_gold;
 use crate::game::hotbar::update_hotbar;
-use crate::game::inventory::handle_inventory_input;
+use crate::game::inventory::{consume_items, handle_inventory_input, use_inventory_extension};
 use crate::game::join::load_finished;
 use crate::game::logout::{handle_logout, tick_logout};
 use crate::game::mastery::{handle_mastery_levelup, learn_skill};
 use crate::game::movement::movement_monster;
 use crate::game::player_activity::{update_player_activity, PlayerActivity};
+use crate::game::rental::{expire_rentals, RENTAL_CHECK_INTERVAL};
+use crate::game::scroll::{designate_recall_point, record_death_location, tick_scroll_cast, use_scroll};
 use crate::game::spawn::do_spawn_mobs;
-use crate::game::stats::increase_stats;
+use crate::game::stall::{
+    buy_from_stall, cancel_broken_stalls, close_stall, create_stall, enter_stall, leave_stall, update_stall,
+};
+use crate::game::stats::{increase_stats, update_character_stats};
 use crate::game::target::{deselect_despawned, player_update_target};
+use crate::game::teleport::{finish_teleport, handle_teleport_requests, respawn_teleported};
 use crate::game::unique::{setup_unique_timers, unique_killed, unique_spawned, update_timers};
 use crate::game::visibility::{clear_visibility, player_visibility_update, visibility_update};
 use crate::persistence::AppPersistanceExt;
 use crate::sync::SynchronizationStage;
 use bevy::prelude::*;
+use bevy::time::common_conditions::on_timer;
 use exp::LevelUpEvent;
+use std::time::Duration;
 
 mod action;
 pub(crate) mod attack;
+mod avatar;
 mod damage;
 mod daylight;
 pub(crate) mod drop;
+mod durability;
+mod exchange;
 pub(crate) mod exp;
 mod gold;
 mod hotbar;
@@ -51,9 +67,13 @@ pub(crate) mod logout;
 mod mastery;
 mod movement;
 pub(crate) mod player_activity;
+mod rental;
+pub(crate) mod scroll;
 mod spawn;
+pub(crate) mod stall;
 mod stats;
 pub(crate) mod target;
+pub(crate) mod teleport;
 mod unique;
 mod visibility;
 
@@ -74,12 +94,15 @@ impl Plugin for GamePlugin {
             .add_event::<ReceiveExperienceEvent>()
             .add_event::<SpawnMonster>()
             .add_event::<LevelUpEvent>()
+            .add_event::<ConsumeItemEvent>()
             .add_systems(Startup, setup_unique_timers)
             .add_systems(PreUpdate, (update_player_activity, update_timers))
             .add_systems(
                 Update,
                 (
                     handle_inventory_input,
+                    consume_items,
+                    repair_equipment,
                     increase_stats,
                     visibility_update,
                     movement_monster,
@@ -87,6 +110,9 @@ impl Plugin for GamePlugin {
                     handle_logout,
                     handle_action,
                     tick_logout,
+                    use_scroll,
+                    tick_scroll_cast.after(use_scroll),
+                    designate_recall_point,
                     player_update_target,
                     deselect_despawned,
                     attack_player,
@@ -99,14 +125,51 @@ impl Plugin for GamePlugin {
                 Update,
                 (
                     handle_damage,
+                    wear_equipment.after(handle_damage),
                     handle_monster_death.after(handle_damage),
                     distribute_experience.after(handle_damage),
                     drop_gold.after(handle_damage),
+                    record_death_location.after(handle_damage),
                     receive_experience.after(distribute_experience),
                     reset_health_mana_on_level.after(receive_experience),
-                    update_max_hp_mp_on_stat_change.after(increase_stats),
+                    update_character_stats
+                        .after(handle_inventory_input)
+                        .after(consume_items)
+                        .after(repair_equipment)
+                        .after(use_inventory_extension)
+                        .after(wear_equipment),
+                    update_max_hp_mp_on_stat_change
+                        .after(increase_stats)
+                        .after(update_character_stats),
                 ),
             )
+            .add_systems(
+                Update,
+                (
+                    request_exchange,
+                    answer_exchange_invitation,
+                    cancel_broken_exchanges,
+                    handle_exchange.after(cancel_broken_exchanges),
+                    cancel_broken_stalls,
+                    create_stall,
+                    update_stall,
+                    close_stall,
+                    enter_stall.after(cancel_broken_stalls),
+                    leave_stall.after(cancel_broken_stalls),
+                    buy_from_stall.after(cancel_broken_stalls),
+                    use_inventory_extension,
+                    handle_avatar_input,
+                    handle_teleport_requests,
+                    respawn_teleported,
+                    finish_teleport.after(respawn_teleported),
+                ),
+            )
+            .add_systems(
+                Update,
+                expire_rentals
+                    .after(visibility_update)
+                    .run_if(on_timer(Duration::from_secs(RENTAL_CHECK_INTERVAL))),
+            )
             .add_systems(
                 PostUpdate,
                 (
@@ -128,7 +191,11 @@ impl Plugin for GamePlugin {
             .track_change_component::<SP>()
             .track_change_component::<GoldPouch>()
             .track_change_component::<MasteryKnowledge>()
+            .track_change_component::<RecallPoint>()
             .track_component::<PlayerInventory>()
+            .persist_together::<PlayerInventory>()
+            .track_component::<PlayerAvatarInventory>()
+            .persist_together::<PlayerAvatarInventory>()
             .track_component::<SkillBook>()
             .track_component::<Hotbar>()
             .add_systems(Last, clear_visibility);
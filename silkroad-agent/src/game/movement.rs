@@ -21,7 +21,9 @@ pub(crate) fn movement_monster(
 
         if stroll.check_timer.tick(delta).just_finished() {
             let new_location = GlobalLocation(stroll.origin.0.random_in_radius(stroll.radius));
-            let new_y = navmesh.height_for(new_location).unwrap_or(pos.position().0.y);
+            let new_y = navmesh
+                .height_near(new_location, pos.position().0.y)
+                .unwrap_or(pos.position().0.y);
             goal.switch_goal(AgentGoal::moving_to(new_location.with_y(new_y)));
             let next_move_duration = Duration::from_secs(rng().random_range(stroll.movement_timer_range.clone()));
             stroll.check_timer = Timer::new(next_move_duration, TimerMode::Once);
@@ -0,0 +1,82 @@
+use crate::comp::inventory::{PlayerAvatarInventory, PlayerInventory};
+use crate::comp::net::Client;
+use crate::comp::visibility::Visibility;
+use crate::comp::{EntityReference, GameEntity};
+use crate::game::visibility::respawn_for_observers;
+use bevy::prelude::*;
+use chrono::Utc;
+use silkroad_protocol::chat::{ChatSource, ChatUpdate};
+use silkroad_protocol::inventory::{InventoryOperationResponseData, InventoryOperationResult, ItemRemovalReason};
+
+pub(crate) const RENTAL_CHECK_INTERVAL: u64 = 10;
+
+/// Removes all items whose rental period has ended from the inventories and the avatar slots of
+/// players. Equipped items are removed as well, which in turn updates the stats of the character.
+/// Players nearby see the character again without the removed avatars.
+pub(crate) fn expire_rentals(
+    mut query: Query<(
+        Entity,
+        &Client,
+        &GameEntity,
+        &mut PlayerInventory,
+        &mut PlayerAvatarInventory,
+    )>,
+    mut observers: Query<&mut Visibility>,
+) {
+    let now = Utc::now().timestamp() as u64;
+    for (entity, client, game_entity, mut inventory, mut avatars) in query.iter_mut() {
+        let expired = inventory
+            .items()
+            .filter(|(_, item)| item.is_expired(now))
+            .map(|(slot, _)| *slot)
+            .collect::<Vec<_>>();
+        let expired_avatars = avatars
+            .items()
+            .filter(|(_, item)| item.is_expired(now))
+            .map(|(slot, _)| *slot)
+            .collect::<Vec<_>>();
+        let removed = !expired.is_empty();
+        for slot in expired {
+            inventory.remove_item_at(slot);
+            client.send(InventoryOperationResult::removed_by_server(
+                slot,
+                ItemRemovalReason::Expired,
+            ));
+        }
+
+        let mut avatar_removed = false;
+        for slot in expired_avatars {
+            // The client can only remove items from the bag, so the avatar is taken off first. If the
+            // bag is full, it stays worn until there is room for it.
+            if inventory.free_slots() == 0 {
+                continue;
+            }
+
+            let Some(target) = avatars.unequip(slot).and_then(|item| inventory.add_item(item)) else {
+                continue;
+            };
+            client.send(InventoryOperationResult::Success(
+                InventoryOperationResponseData::UnequipAvatar { source: slot, target },
+            ));
+            inventory.remove_item_at(target);
+            client.send(InventoryOperationResult::removed_by_server(
+                target,
+                ItemRemovalReason::Expired,
+            ));
+            avatar_removed = true;
+        }
+
+        if avatar_removed {
+            respawn_for_observers(EntityReference(entity, *game_entity), &mut observers);
+        }
+
+        if !removed && !avatar_removed {
+            continue;
+        }
+
+        client.send(ChatUpdate::new(
+            ChatSource::Notice,
+            "The rental period of an item has ended and it has been removed.".to_string(),
+        ));
+    }
+}
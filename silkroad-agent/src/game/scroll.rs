@@ -0,0 +1,206 @@
+use crate::agent::state::Dead;
+use crate::comp::inventory::PlayerInventory;
+use crate::comp::net::Client;
+use crate::comp::pos::Position;
+use crate::comp::recall::{RecallPoint, ReverseLocations};
+use crate::comp::GameEntity;
+use crate::event::EntityDeath;
+use crate::game::target::MAX_TARGET_DISTANCE;
+use crate::game::teleport::Teleporting;
+use crate::input::PlayerInput;
+use crate::world::{EntityLookup, WorldData};
+use bevy::prelude::*;
+use cgmath::{MetricSpace, Vector3};
+use silkroad_data::teleport::TeleportLocation;
+use silkroad_definitions::type_id::{ObjectConsumable, ObjectConsumableScroll, ObjectItem, ObjectType};
+use silkroad_game_base::{GlobalPosition, Item, LocalPosition};
+use silkroad_protocol::inventory::{
+    DesignateRecallPointResponse, InventoryOperationError, ReverseScrollTarget, UseItemResponse,
+};
+use silkroad_protocol::world::TeleportStart;
+use std::time::Duration;
+
+const SCROLL_CAST_DURATION: Duration = Duration::from_secs(10);
+
+/// A return or reverse scroll that is currently being cast. Once the cast finishes, the player is
+/// moved to the destination. Moving, performing an action or dying cancels the cast.
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+pub(crate) struct CastingScroll {
+    timer: Timer,
+    destination: GlobalPosition,
+}
+
+impl CastingScroll {
+    pub(crate) fn new(destination: GlobalPosition) -> Self {
+        CastingScroll {
+            timer: Timer::new(SCROLL_CAST_DURATION, TimerMode::Once),
+            destination,
+        }
+    }
+}
+
+pub(crate) fn use_scroll(
+    mut query: Query<(
+        Entity,
+        &Client,
+        &PlayerInput,
+        &Position,
+        &RecallPoint,
+        &ReverseLocations,
+        &mut PlayerInventory,
+        Option<&Dead>,
+        Option<&CastingScroll>,
+        Has<Teleporting>,
+    )>,
+    mut cmd: Commands,
+) {
+    for (entity, client, input, position, recall_point, reverse_locations, mut inventory, dead, casting, teleporting) in
+        query.iter_mut()
+    {
+        let Some(ref request) = input.use_item else {
+            continue;
+        };
+
+        let Some(scroll) = inventory
+            .get_item_at(request.slot)
+            .filter(|item| item.reference.common.type_id.packed() == request.item_type >> 2)
+            .and_then(teleport_scroll_of)
+        else {
+            continue;
+        };
+
+        if dead.is_some() || casting.is_some() || teleporting {
+            client.send(UseItemResponse::Failure(InventoryOperationError::Busy));
+            continue;
+        }
+
+        let destination = match scroll {
+            ObjectConsumableScroll::Return => return_destination(recall_point, position.position()),
+            _ => match request.reverse_target {
+                Some(ReverseScrollTarget::RecentLocation) => reverse_locations.recent,
+                Some(ReverseScrollTarget::DeathLocation) => reverse_locations.death,
+                // Map locations refer to the optional teleport destinations, which aren't loaded, so
+                // there is no location to go to.
+                Some(ReverseScrollTarget::MapLocation(_)) | None => None,
+            },
+        };
+
+        let Some(destination) = destination else {
+            client.send(UseItemResponse::Failure(InventoryOperationError::Unusable));
+            continue;
+        };
+
+        let Ok(remaining) = inventory.consume_item(request.slot, 1) else {
+            client.send(UseItemResponse::Failure(InventoryOperationError::InvalidTarget));
+            continue;
+        };
+
+        client.send(UseItemResponse::success(request.slot, remaining, request.item_type));
+        cmd.entity(entity).try_insert(CastingScroll::new(destination));
+    }
+}
+
+/// The kind of the scroll, if the item is a scroll that moves the player somewhere else when cast.
+fn teleport_scroll_of(item: &Item) -> Option<ObjectConsumableScroll> {
+    match ObjectType::from_type_id(&item.reference.common.type_id) {
+        Some(ObjectType::Item(ObjectItem::Consumable(ObjectConsumable::Scroll(
+            scroll @ (ObjectConsumableScroll::Return | ObjectConsumableScroll::Reverse),
+        )))) => Some(scroll),
+        _ => None,
+    }
+}
+
+/// Advances the casts of scrolls. Once a cast finishes, the player is teleported to the destination
+/// the same way a teleporter would, see [Teleporting].
+pub(crate) fn tick_scroll_cast(
+    mut query: Query<(Entity, &Client, &PlayerInput, &mut CastingScroll, Option<&Dead>)>,
+    time: Res<Time>,
+    mut cmd: Commands,
+) {
+    let delta = time.delta();
+    for (entity, client, input, mut casting, dead) in query.iter_mut() {
+        if dead.is_some() || input.movement.is_some() || input.action.is_some() {
+            cmd.entity(entity).remove::<CastingScroll>();
+            continue;
+        }
+
+        casting.timer.tick(delta);
+        if casting.timer.just_finished() {
+            client.send(TeleportStart);
+            cmd.entity(entity)
+                .remove::<CastingScroll>()
+                .try_insert(Teleporting::to(casting.destination));
+        }
+    }
+}
+
+pub(crate) fn record_death_location(
+    mut death_events: EventReader<EntityDeath>,
+    mut query: Query<(&Position, &mut ReverseLocations)>,
+) {
+    for event in death_events.read() {
+        if let Ok((position, mut reverse_locations)) = query.get_mut(event.died.0) {
+            reverse_locations.death = Some(position.position());
+        }
+    }
+}
+
+pub(crate) fn designate_recall_point(
+    mut query: Query<(&Client, &PlayerInput, &Position, &mut RecallPoint)>,
+    npc_query: Query<(&GameEntity, &Position)>,
+    lookup: Res<EntityLookup>,
+) {
+    for (client, input, position, mut recall_point) in query.iter_mut() {
+        let Some(ref request) = input.recall_point else {
+            continue;
+        };
+
+        let teleport = lookup
+            .get_entity_for_id(request.npc_unique_id)
+            .and_then(|npc| npc_query.get(npc).ok())
+            .filter(|(_, npc_position)| npc_position.position().distance2(position.position().0) < MAX_TARGET_DISTANCE)
+            .and_then(|(npc, _)| {
+                WorldData::teleports().values().find(|teleport| {
+                    teleport.active && teleport.npc_id.is_some_and(|id| u32::from(id.get()) == npc.ref_id)
+                })
+            });
+
+        match teleport {
+            Some(teleport) => {
+                recall_point.designate(teleport.ref_id);
+                client.send(DesignateRecallPointResponse::Success);
+            },
+            None => client.send(DesignateRecallPointResponse::Failure(
+                InventoryOperationError::InvalidTarget,
+            )),
+        }
+    }
+}
+
+/// Finds the destination of a return scroll, which is the designated recall point or, if none has
+/// been designated yet, the closest respawn point.
+fn return_destination(recall_point: &RecallPoint, current: GlobalPosition) -> Option<GlobalPosition> {
+    let teleports = WorldData::teleports();
+    if let Some(teleport) = recall_point.teleport_id().and_then(|id| teleports.get(&id)) {
+        return Some(spawn_position(teleport));
+    }
+
+    teleports
+        .values()
+        .filter(|teleport| teleport.active && teleport.respawn_point)
+        .map(spawn_position)
+        .min_by(|a, b| a.distance2(current.0).total_cmp(&b.distance2(current.0)))
+}
+
+pub(crate) fn spawn_position(teleport: &TeleportLocation) -> GlobalPosition {
+    LocalPosition(
+        teleport.spawn_region,
+        Vector3::new(
+            f32::from(teleport.spawn_x),
+            f32::from(teleport.spawn_y),
+            f32::from(teleport.spawn_z),
+        ),
+    )
+    .to_global()
+}
@@ -0,0 +1,486 @@
+use crate::agent::state::Dead;
+use crate::comp::gold::{GoldChange, GoldPouch};
+use crate::comp::inventory::{item_content_data, rent_info, PlayerInventory};
+use crate::comp::net::Client;
+use crate::comp::player::Player;
+use crate::comp::pos::Position;
+use crate::comp::GameEntity;
+use crate::game::exchange::Exchange;
+use crate::game::logout::Logout;
+use crate::game::target::MAX_TARGET_DISTANCE;
+use crate::input::PlayerInput;
+use crate::persistence::PersistTogetherEvent;
+use crate::world::EntityLookup;
+use bevy::prelude::*;
+use silkroad_data::DataEntry;
+use silkroad_game_base::{Inventory, Item};
+use silkroad_protocol::inventory::{InventoryItemData, InventoryOperationError, InventoryOperationResult};
+use silkroad_protocol::stall::{
+    StallBuyResponse, StallClientProtocol, StallCloseResponse, StallContentUpdate, StallCreateResponse,
+    StallEnterResponse, StallItemData, StallLeaveResponse, StallUpdate, StallUpdateResponse, StallVisitorUpdate,
+};
+use std::collections::BTreeMap;
+
+/// The amount of item slots a stall provides.
+const MAX_STALL_ITEMS: u8 = 10;
+
+struct StallItem {
+    inventory_slot: u8,
+    item: Item,
+    price: u64,
+}
+
+/// A stall a player has set up to sell items to other players. Items can only be changed while the
+/// stall is not open, and only open stalls can be bought from.
+#[derive(Component)]
+pub(crate) struct Stall {
+    pub(crate) title: String,
+    open: bool,
+    items: BTreeMap<u8, StallItem>,
+    pub(crate) visitors: Vec<Entity>,
+}
+
+impl Stall {
+    fn new(title: String) -> Self {
+        Stall {
+            title,
+            open: false,
+            items: BTreeMap::new(),
+            visitors: Vec::new(),
+        }
+    }
+
+    fn is_offered(&self, inventory_slot: u8) -> bool {
+        self.items.values().any(|item| item.inventory_slot == inventory_slot)
+    }
+
+    fn item_data(&self) -> Vec<StallItemData> {
+        self.items
+            .iter()
+            .map(|(stall_slot, stall_item)| {
+                let item = &stall_item.item;
+                StallItemData::new(
+                    InventoryItemData::new(
+                        *stall_slot,
+                        rent_info(item),
+                        item.reference.ref_id(),
+                        item_content_data(item),
+                    ),
+                    stall_item.inventory_slot,
+                    stall_item.price,
+                )
+            })
+            .collect()
+    }
+
+    fn remove_visitor(&mut self, visitor: Entity) {
+        self.visitors.retain(|other| *other != visitor);
+    }
+}
+
+/// Marks a player as currently browsing the stall of another player.
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+pub(crate) struct VisitingStall(pub(crate) Entity);
+
+pub(crate) fn create_stall(
+    query: Query<(
+        Entity,
+        &Client,
+        &PlayerInput,
+        Has<Stall>,
+        Has<VisitingStall>,
+        Has<Exchange>,
+        Has<Dead>,
+    )>,
+    mut cmd: Commands,
+) {
+    for (entity, client, input, has_stall, visiting, exchanging, dead) in query.iter() {
+        let Some(StallClientProtocol::StallCreate(ref request)) = input.stall else {
+            continue;
+        };
+
+        if has_stall || visiting || exchanging || dead {
+            client.send(StallCreateResponse::Failure(InventoryOperationError::Busy));
+            continue;
+        }
+
+        if request.title.trim().is_empty() {
+            client.send(StallCreateResponse::Failure(InventoryOperationError::InvalidTarget));
+            continue;
+        }
+
+        cmd.entity(entity).try_insert(Stall::new(request.title.clone()));
+        client.send(StallCreateResponse::Success);
+    }
+}
+
+pub(crate) fn update_stall(
+    mut query: Query<(&Client, &PlayerInput, &PlayerInventory, &mut Stall)>,
+    visitors: Query<&Client>,
+    mut cmd: Commands,
+) {
+    for (client, input, inventory, mut stall) in query.iter_mut() {
+        let Some(StallClientProtocol::StallUpdate(update)) = input.stall else {
+            continue;
+        };
+
+        let result = match update {
+            StallUpdate::AddItem {
+                stall_slot,
+                inventory_slot,
+                price,
+            } => {
+                let item = inventory
+                    .get_item_at(inventory_slot)
+                    .filter(|_| !Inventory::is_equipment_slot(inventory_slot));
+                match item {
+                    Some(item)
+                        if !stall.open
+                            && price > 0
+                            && stall_slot < MAX_STALL_ITEMS
+                            && !stall.items.contains_key(&stall_slot)
+                            && !stall.is_offered(inventory_slot) =>
+                    {
+                        let item = *item;
+                        stall.items.insert(
+                            stall_slot,
+                            StallItem {
+                                inventory_slot,
+                                item,
+                                price,
+                            },
+                        );
+                        Ok(())
+                    },
+                    _ => Err(InventoryOperationError::InvalidTarget),
+                }
+            },
+            StallUpdate::RemoveItem { stall_slot } => {
+                if !stall.open && stall.items.remove(&stall_slot).is_some() {
+                    Ok(())
+                } else {
+                    Err(InventoryOperationError::InvalidTarget)
+                }
+            },
+            StallUpdate::State { open } => {
+                if open && stall.items.is_empty() {
+                    Err(InventoryOperationError::InvalidTarget)
+                } else {
+                    // Visitors can't browse a stall that is being changed, so they have to leave
+                    // just like when the stall gets closed.
+                    if stall.open && !open {
+                        send_visitors_away(&stall, &visitors, &mut cmd);
+                        stall.visitors.clear();
+                    }
+                    stall.open = open;
+                    Ok(())
+                }
+            },
+        };
+
+        match result {
+            Ok(()) => {
+                let items = stall.item_data();
+                client.send(StallUpdateResponse::success(stall.open, items.clone()));
+                let content = StallContentUpdate::new(stall.open, items);
+                for visitor in visitors.iter_many(&stall.visitors) {
+                    visitor.send(content.clone());
+                }
+            },
+            Err(error) => client.send(StallUpdateResponse::Failure(error)),
+        }
+    }
+}
+
+pub(crate) fn close_stall(
+    query: Query<(Entity, &Client, &PlayerInput, Option<&Stall>)>,
+    clients: Query<&Client>,
+    mut cmd: Commands,
+) {
+    for (entity, client, input, stall) in query.iter() {
+        let Some(StallClientProtocol::StallClose(_)) = input.stall else {
+            continue;
+        };
+
+        let Some(stall) = stall else {
+            client.send(StallCloseResponse::Failure(InventoryOperationError::InvalidTarget));
+            continue;
+        };
+
+        close(entity, stall, &clients, &mut cmd);
+        client.send(StallCloseResponse::Success);
+    }
+}
+
+pub(crate) fn enter_stall(
+    query: Query<(
+        Entity,
+        &Client,
+        &GameEntity,
+        &PlayerInput,
+        &Position,
+        Has<Stall>,
+        Has<VisitingStall>,
+        Has<Dead>,
+    )>,
+    mut stalls: Query<(&GameEntity, &Position, &mut Stall)>,
+    clients: Query<&Client>,
+    lookup: Res<EntityLookup>,
+    mut cmd: Commands,
+) {
+    for (entity, client, game_entity, input, position, has_stall, visiting, dead) in query.iter() {
+        let Some(StallClientProtocol::StallEnter(ref request)) = input.stall else {
+            continue;
+        };
+
+        if has_stall || visiting || dead {
+            client.send(StallEnterResponse::Failure(InventoryOperationError::Busy));
+            continue;
+        }
+
+        let Some((owner, (owner_entity, owner_position, mut stall))) = lookup
+            .get_entity_for_id(request.target)
+            .filter(|owner| *owner != entity)
+            .and_then(|owner| stalls.get_mut(owner).ok().map(|found| (owner, found)))
+        else {
+            client.send(StallEnterResponse::Failure(InventoryOperationError::InvalidTarget));
+            continue;
+        };
+
+        if !stall.open || position.distance_to(owner_position) >= MAX_TARGET_DISTANCE {
+            client.send(StallEnterResponse::Failure(InventoryOperationError::InvalidTarget));
+            continue;
+        }
+
+        let entered = StallVisitorUpdate::Entered {
+            unique_id: game_entity.unique_id,
+        };
+        for other in clients.iter_many(stall.visitors.iter().chain([&owner])) {
+            other.send(entered.clone());
+        }
+
+        stall.visitors.push(entity);
+        cmd.entity(entity).try_insert(VisitingStall(owner));
+        client.send(StallEnterResponse::success(
+            owner_entity.unique_id,
+            stall.open,
+            stall.item_data(),
+        ));
+    }
+}
+
+pub(crate) fn leave_stall(
+    query: Query<(Entity, &Client, &GameEntity, &PlayerInput, Option<&VisitingStall>)>,
+    mut stalls: Query<&mut Stall>,
+    clients: Query<&Client>,
+    mut cmd: Commands,
+) {
+    for (entity, client, game_entity, input, visiting) in query.iter() {
+        let Some(StallClientProtocol::StallLeave(_)) = input.stall else {
+            continue;
+        };
+
+        let Some(visiting) = visiting else {
+            client.send(StallLeaveResponse::Failure(InventoryOperationError::InvalidTarget));
+            continue;
+        };
+
+        cmd.entity(entity).remove::<VisitingStall>();
+        if let Ok(mut stall) = stalls.get_mut(visiting.0) {
+            stall.remove_visitor(entity);
+            notify_left(&stall, visiting.0, game_entity, &clients);
+        }
+        client.send(StallLeaveResponse::Success);
+    }
+}
+
+pub(crate) fn buy_from_stall(
+    mut query: Query<(
+        Entity,
+        &Client,
+        &Player,
+        &PlayerInput,
+        &mut PlayerInventory,
+        &mut GoldPouch,
+        Option<&VisitingStall>,
+        Option<&mut Stall>,
+    )>,
+    clients: Query<&Client>,
+    mut persist: EventWriter<PersistTogetherEvent>,
+    mut cmd: Commands,
+) {
+    let buying = query
+        .iter()
+        .filter_map(|(entity, client, _, input, _, _, visiting, _)| match input.stall {
+            Some(StallClientProtocol::StallBuy(request)) => match visiting {
+                Some(visiting) => Some((entity, visiting.0, request.stall_slot)),
+                None => {
+                    client.send(StallBuyResponse::Failure(InventoryOperationError::InvalidTarget));
+                    None
+                },
+            },
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    for (buyer, seller, stall_slot) in buying {
+        let Ok([buying, selling]) = query.get_many_mut([buyer, seller]) else {
+            continue;
+        };
+        let (_, client, player, _, mut inventory, mut gold, _, _) = buying;
+        let (_, seller_client, seller_player, _, mut seller_inventory, mut seller_gold, _, stall) = selling;
+        let Some(mut stall) = stall else {
+            client.send(StallBuyResponse::Failure(InventoryOperationError::InvalidTarget));
+            continue;
+        };
+
+        let Some(offered) = stall.items.get(&stall_slot).filter(|_| stall.open) else {
+            client.send(StallBuyResponse::Failure(InventoryOperationError::InvalidTarget));
+            continue;
+        };
+
+        let still_owned = seller_inventory
+            .get_item_at(offered.inventory_slot)
+            .is_some_and(|item| {
+                item.reference.ref_id() == offered.item.reference.ref_id()
+                    && item.variance == offered.item.variance
+                    && item.type_data == offered.item.type_data
+            });
+        if !still_owned {
+            stall.items.remove(&stall_slot);
+            client.send(StallBuyResponse::Failure(InventoryOperationError::InvalidTarget));
+            continue;
+        }
+
+        let price = offered.price;
+        if gold.amount() < price {
+            client.send(StallBuyResponse::Failure(InventoryOperationError::NotEnoughGold));
+            continue;
+        }
+
+        if inventory.free_slots() == 0 {
+            client.send(StallBuyResponse::Failure(InventoryOperationError::InventoryFull));
+            continue;
+        }
+
+        let inventory_slot = offered.inventory_slot;
+        let Some(item) = seller_inventory.remove_item_at(inventory_slot) else {
+            continue;
+        };
+
+        let Some(slot) = inventory.add_item(item) else {
+            // The free slot was checked above, but the item must never vanish if it still doesn't
+            // fit, so it goes back to the seller and nobody pays anything.
+            let _ = seller_inventory.put_item_at(inventory_slot, item);
+            client.send(StallBuyResponse::Failure(InventoryOperationError::InventoryFull));
+            continue;
+        };
+        stall.items.remove(&stall_slot);
+        if let Some(received) = inventory.get_item_at(slot) {
+            client.send(InventoryOperationResult::success_gain_item(
+                slot,
+                rent_info(received),
+                item.reference.ref_id(),
+                item_content_data(received),
+            ));
+        }
+        gold.spend(price);
+        seller_gold.gain(price);
+        persist.send(
+            PersistTogetherEvent::new(vec![buyer, seller])
+                .with_change(player.character.id, GoldChange(gold.amount()))
+                .with_change(seller_player.character.id, GoldChange(seller_gold.amount())),
+        );
+
+        client.send(StallBuyResponse::Success { stall_slot });
+        let bought = StallVisitorUpdate::Bought {
+            stall_slot,
+            buyer: player.character.name.clone(),
+        };
+        seller_client.send(bought.clone());
+        for visitor in clients.iter_many(&stall.visitors) {
+            visitor.send(bought.clone());
+        }
+
+        if stall.items.is_empty() {
+            close(seller, &stall, &clients, &mut cmd);
+        }
+    }
+}
+
+/// Closes stalls of players that moved, died or started logging out, and removes visitors that can
+/// no longer browse the stall they're visiting.
+pub(crate) fn cancel_broken_stalls(
+    visitors: Query<(
+        Entity,
+        &Client,
+        &GameEntity,
+        &PlayerInput,
+        &Position,
+        &VisitingStall,
+        Has<Dead>,
+        Has<Logout>,
+    )>,
+    mut stalls: Query<(Entity, &PlayerInput, &Position, &mut Stall, Has<Dead>, Has<Logout>)>,
+    clients: Query<&Client>,
+    mut cmd: Commands,
+) {
+    let mut closed = Vec::new();
+    for (entity, input, _, stall, dead, logout) in stalls.iter() {
+        if input.movement.is_some() || dead || logout {
+            close(entity, stall, &clients, &mut cmd);
+            closed.push(entity);
+        }
+    }
+
+    for (entity, client, game_entity, input, position, visiting, dead, logout) in visitors.iter() {
+        if closed.contains(&visiting.0) {
+            continue;
+        }
+
+        let Ok((_, _, stall_position, mut stall, _, _)) = stalls.get_mut(visiting.0) else {
+            // The owner of the stall is gone entirely.
+            cmd.entity(entity).remove::<VisitingStall>();
+            client.send(StallLeaveResponse::Success);
+            continue;
+        };
+
+        let can_browse = stall.visitors.contains(&entity)
+            && position.distance_to(stall_position) < MAX_TARGET_DISTANCE
+            && input.movement.is_none()
+            && !dead
+            && !logout;
+        if can_browse {
+            continue;
+        }
+
+        cmd.entity(entity).remove::<VisitingStall>();
+        stall.remove_visitor(entity);
+        notify_left(&stall, visiting.0, game_entity, &clients);
+    }
+}
+
+/// Closes the stall of the given owner and sends all its visitors out of it.
+fn close(owner: Entity, stall: &Stall, clients: &Query<&Client>, cmd: &mut Commands) {
+    cmd.entity(owner).remove::<Stall>();
+    send_visitors_away(stall, clients, cmd);
+}
+
+fn send_visitors_away(stall: &Stall, clients: &Query<&Client>, cmd: &mut Commands) {
+    for visitor in stall.visitors.iter().copied() {
+        cmd.entity(visitor).remove::<VisitingStall>();
+        if let Ok(client) = clients.get(visitor) {
+            client.send(StallLeaveResponse::Success);
+        }
+    }
+}
+
+fn notify_left(stall: &Stall, owner: Entity, visitor: &GameEntity, clients: &Query<&Client>) {
+    let left = StallVisitorUpdate::Left {
+        unique_id: visitor.unique_id,
+    };
+    for other in clients.iter_many(stall.visitors.iter().chain([&owner])) {
+        other.send(left.clone());
+    }
+}
@@ -1,5 +1,7 @@
+use crate::comp::inventory::PlayerInventory;
 use crate::comp::net::Client;
 use crate::comp::player::StatPoints;
+use crate::comp::stats::CharacterStats;
 use crate::input::PlayerInput;
 use bevy::prelude::*;
 use silkroad_game_base::StatType;
@@ -30,3 +32,11 @@ pub(crate) fn increase_stats(mut query: Query<(&mut PlayerInput, &mut StatPoints
         }
     }
 }
+
+pub(crate) fn update_character_stats(
+    mut query: Query<(&PlayerInventory, &mut CharacterStats), Changed<PlayerInventory>>,
+) {
+    for (inventory, mut stats) in query.iter_mut() {
+        stats.set_if_neq(CharacterStats::from_equipment(inventory));
+    }
+}
@@ -11,7 +11,7 @@ use cgmath::MetricSpace;
 use derive_more::Deref;
 use silkroad_protocol::world::{TargetEntityError, TargetEntityResponse, TargetEntityResult, UnTargetEntityResponse};
 
-const MAX_TARGET_DISTANCE: f32 = 500. * 500.;
+pub(crate) const MAX_TARGET_DISTANCE: f32 = 500. * 500.;
 
 #[derive(Component, Deref)]
 #[component(storage = "SparseSet")]
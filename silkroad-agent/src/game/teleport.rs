@@ -0,0 +1,281 @@
+use crate::agent::goal::GoalTracker;
+use crate::agent::state::Dead;
+use crate::comp::exp::{Experienced, Leveled, SP};
+use crate::comp::gold::GoldPouch;
+use crate::comp::inventory::{PlayerAvatarInventory, PlayerInventory};
+use crate::comp::mastery::MasteryKnowledge;
+use crate::comp::net::Client;
+use crate::comp::npc::{TeleportGate, NPC};
+use crate::comp::player::{Player, StatPoints};
+use crate::comp::pos::Position;
+use crate::comp::recall::ReverseLocations;
+use crate::comp::skill::{Hotbar, SkillBook};
+use crate::comp::stats::CharacterStats;
+use crate::comp::visibility::Visibility;
+use crate::comp::{GameEntity, Health, Mana};
+use crate::config::GameConfig;
+use crate::game::daylight::DaylightCycle;
+use crate::game::exchange::Exchange;
+use crate::game::join::send_character_finished;
+use crate::game::scroll::{spawn_position, CastingScroll};
+use crate::game::stall::{Stall, VisitingStall};
+use crate::game::target::MAX_TARGET_DISTANCE;
+use crate::input::PlayerInput;
+use crate::login::charselect::send_spawn;
+use crate::world::{EntityLookup, WorldData};
+use bevy::prelude::*;
+use cgmath::MetricSpace;
+use silkroad_data::teleport::{TeleportLink, TeleportLocation};
+use silkroad_game_base::{Character, GlobalPosition, SpawningState};
+use silkroad_protocol::inventory::{InventoryOperationError, TeleportKind, TeleportUseResponse};
+use silkroad_protocol::world::TeleportStart;
+use tracing::debug;
+
+/// A player that is being moved to another location by a teleporter. The client first switches to
+/// the loading screen, after which the player gets spawned at the destination. Once the client
+/// has finished loading the new location, the teleport is complete.
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+pub(crate) struct Teleporting {
+    destination: GlobalPosition,
+    arrived: bool,
+}
+
+impl Teleporting {
+    pub(crate) fn to(destination: GlobalPosition) -> Self {
+        Teleporting {
+            destination,
+            arrived: false,
+        }
+    }
+}
+
+pub(crate) fn handle_teleport_requests(
+    mut query: Query<(
+        Entity,
+        &Client,
+        &PlayerInput,
+        &Position,
+        &Leveled,
+        &mut GoldPouch,
+        &mut GoalTracker,
+        Option<&Dead>,
+        Option<&CastingScroll>,
+        Option<&Teleporting>,
+        (Has<Exchange>, Has<Stall>, Has<VisitingStall>),
+    )>,
+    teleporter_query: Query<(&GameEntity, &Position), Or<(With<NPC>, With<TeleportGate>)>>,
+    lookup: Res<EntityLookup>,
+    mut cmd: Commands,
+) {
+    for (
+        entity,
+        client,
+        input,
+        position,
+        level,
+        mut gold,
+        mut mind,
+        dead,
+        casting,
+        teleporting,
+        (exchanging, vending, visiting),
+    ) in query.iter_mut()
+    {
+        let Some(ref request) = input.teleport else {
+            continue;
+        };
+
+        if dead.is_some() || casting.is_some() || teleporting.is_some() || exchanging || vending || visiting {
+            client.send(TeleportUseResponse::Failure(InventoryOperationError::Busy));
+            continue;
+        }
+
+        let TeleportKind::Teleport { destination } = request.kind;
+        let route = lookup
+            .get_entity_for_id(request.npc_unique_id)
+            .and_then(|teleporter| teleporter_query.get(teleporter).ok())
+            .filter(|(_, teleporter_position)| {
+                teleporter_position.position().distance2(position.position().0) < MAX_TARGET_DISTANCE
+            })
+            .and_then(|(teleporter, _)| find_route(teleporter.ref_id, destination));
+
+        let Some((link, target)) = route else {
+            client.send(TeleportUseResponse::Failure(InventoryOperationError::InvalidTarget));
+            continue;
+        };
+
+        if level.current_level() < link.minimum_level {
+            client.send(TeleportUseResponse::Failure(InventoryOperationError::TooLowLevel));
+            continue;
+        }
+
+        if link.cost > gold.amount() {
+            client.send(TeleportUseResponse::Failure(InventoryOperationError::NotEnoughGold));
+            continue;
+        }
+
+        gold.spend(link.cost);
+        mind.reset();
+        client.send(TeleportUseResponse::Success);
+        client.send(TeleportStart);
+        cmd.entity(entity).try_insert(Teleporting::to(spawn_position(target)));
+    }
+}
+
+/// Finds the link from the teleport location belonging to the given teleporter to the requested
+/// destination, if both of them are active.
+fn find_route(teleporter_ref_id: u32, destination: u32) -> Option<(&'static TeleportLink, &'static TeleportLocation)> {
+    let teleports = WorldData::teleports();
+    let source = teleports.values().find(|teleport| {
+        teleport.active
+            && teleport
+                .npc_id
+                .is_some_and(|id| u32::from(id.get()) == teleporter_ref_id)
+    })?;
+    let target = u16::try_from(destination)
+        .ok()
+        .and_then(|id| teleports.get(&id))
+        .filter(|teleport| teleport.active)?;
+    let link = WorldData::teleport_links()
+        .iter()
+        .find(|link| link.active && link.source_id == source.ref_id && link.target_id == target.ref_id)?;
+    Some((link, target))
+}
+
+pub(crate) fn respawn_teleported(
+    mut query: Query<(
+        &Client,
+        &GameEntity,
+        &PlayerInput,
+        &mut Teleporting,
+        &mut Player,
+        &mut Position,
+        &mut ReverseLocations,
+        &mut Visibility,
+        (&PlayerInventory, &PlayerAvatarInventory, &Hotbar),
+        (
+            &Leveled,
+            &Experienced,
+            &SP,
+            &GoldPouch,
+            &Health,
+            &Mana,
+            &StatPoints,
+            &MasteryKnowledge,
+            &SkillBook,
+        ),
+    )>,
+    settings: Res<GameConfig>,
+) {
+    for (
+        client,
+        game_entity,
+        input,
+        mut teleporting,
+        mut player,
+        mut position,
+        mut reverse_locations,
+        mut visibility,
+        (inventory, avatars, hotbar),
+        (level, exp, sp, gold, health, mana, stat_points, masteries, skills),
+    ) in query.iter_mut()
+    {
+        if input.teleport_ready.is_none() || teleporting.arrived {
+            continue;
+        }
+
+        debug!(id = ?client.0.id(), "Spawning teleported player at destination.");
+        reverse_locations.recent = Some(position.position());
+        position.move_to(teleporting.destination);
+
+        // The client forgets about all entities during the loading screen, so everything around the
+        // new location needs to be spawned again.
+        visibility.entities_in_radius.clear();
+        visibility.added_entities.clear();
+        visibility.removed_entities.clear();
+
+        refresh_character(
+            &mut player.character,
+            level,
+            exp,
+            sp,
+            gold,
+            health,
+            mana,
+            stat_points,
+            masteries,
+            skills,
+        );
+        send_spawn(
+            client,
+            game_entity,
+            &player,
+            inventory,
+            avatars,
+            &position,
+            settings.max_level,
+            hotbar,
+        );
+        teleporting.arrived = true;
+    }
+}
+
+/// The spawn sequence is built from the character data loaded at login, which needs to reflect the
+/// current state of the character when spawning it again.
+fn refresh_character(
+    character: &mut Character,
+    level: &Leveled,
+    exp: &Experienced,
+    sp: &SP,
+    gold: &GoldPouch,
+    health: &Health,
+    mana: &Mana,
+    stat_points: &StatPoints,
+    masteries: &MasteryKnowledge,
+    skills: &SkillBook,
+) {
+    character.level = level.current_level();
+    character.max_level = level.max_level_reached();
+    character.exp = exp.experience();
+    character.sp_exp = exp.sp_experience() as u32;
+    character.sp = sp.current();
+    character.gold = gold.amount();
+    character.stats = stat_points.stats();
+    character.stat_points = stat_points.remaining_points();
+    character.current_hp = health.current_health;
+    character.current_mp = mana.current_mana;
+    character.masteries = masteries.levels().collect();
+    character.skills = skills.levels().collect();
+    character.state = SpawningState::Loading;
+}
+
+pub(crate) fn finish_teleport(
+    mut query: Query<(
+        Entity,
+        &Client,
+        &GameEntity,
+        &PlayerInput,
+        &Teleporting,
+        &mut Player,
+        &Leveled,
+        &StatPoints,
+        &CharacterStats,
+    )>,
+    daycycle: Res<DaylightCycle>,
+    mut cmd: Commands,
+) {
+    for (entity, client, game_entity, input, teleporting, mut player, level, stat_points, character_stats) in
+        query.iter_mut()
+    {
+        if input.teleport_loaded.is_none() || !teleporting.arrived {
+            continue;
+        }
+
+        debug!(id = ?client.0.id(), "Finished loading after teleport.");
+        player.character.state = SpawningState::Finished;
+        client.send(character_stats.as_message(stat_points.stats(), level.current_level()));
+        send_character_finished(client, game_entity, &daycycle);
+        cmd.entity(entity).remove::<Teleporting>();
+    }
+}
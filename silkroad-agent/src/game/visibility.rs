@@ -1,28 +1,29 @@
 use crate::agent::component::Agent;
 use crate::comp::drop::Drop;
-use crate::comp::inventory::PlayerInventory;
+use crate::comp::inventory::{spawn_item_data, PlayerAvatarInventory, PlayerInventory};
 use crate::comp::monster::Monster;
 use crate::comp::net::Client;
-use crate::comp::npc::NPC;
+use crate::comp::npc::{TeleportGate, NPC};
 use crate::comp::player::Player;
 use crate::comp::pos::Position;
 use crate::comp::visibility::{Invisible, Visibility};
 use crate::comp::{EntityReference, GameEntity};
 use crate::game::player_activity::PlayerActivity;
+use crate::game::scroll::CastingScroll;
+use crate::game::stall::Stall;
+use crate::guild::guilds::{spawn_information, Guilds, InGuild};
 use bevy::prelude::*;
 use cgmath::num_traits::Pow;
-use silkroad_data::DataEntry;
 use silkroad_definitions::Region;
-use silkroad_game_base::ItemTypeData;
+use silkroad_game_base::{ItemTypeData, AVATAR_INVENTORY_SIZE};
 use silkroad_navmesh::region::GridRegion;
-use silkroad_protocol::community::GuildInformation;
-use silkroad_protocol::inventory::CharacterSpawnItemData;
 use silkroad_protocol::spawn::{
     DroppedItemSource, EntityTypeSpawnData, GroupEntitySpawnData, GroupEntitySpawnEnd, GroupEntitySpawnStart,
-    GroupSpawnDataContent, GroupSpawnType, ItemSpawnData,
+    GroupSpawnDataContent, GroupSpawnType, ItemSpawnData, StallSpawnData, StallSpawnInfo,
 };
 use silkroad_protocol::world::{
-    ActionState, ActiveScroll, AliveState, BodyState, EntityState, InteractOptions, JobType, PlayerKillState, PvpCape,
+    ActionState, ActiveScroll, AliveState, BodyState, EntityState, InteractMode, InteractOptions, JobType,
+    PlayerKillState, PvpCape,
 };
 use std::collections::{BTreeMap, HashSet};
 use tracing::{instrument, trace};
@@ -86,37 +87,53 @@ pub(crate) fn visibility_update(
 }
 
 pub(crate) fn player_visibility_update(
-    mut query: Query<(&Client, &GameEntity, &mut Visibility)>,
+    mut query: Query<(&Client, &GameEntity, &mut Visibility, Option<&InGuild>)>,
     lookup: Query<
         (
             &Position,
             Option<&PlayerInventory>,
+            Option<&PlayerAvatarInventory>,
             Option<&Agent>,
             Option<&Player>,
             Option<&Monster>,
             Option<&Drop>,
             Option<&NPC>,
+            Option<&CastingScroll>,
+            Option<&Stall>,
+            Option<&InGuild>,
+            Option<&TeleportGate>,
         ),
         Without<Invisible>,
     >,
+    guilds: Res<Guilds>,
 ) {
-    for (client, player, mut visibility) in query.iter_mut() {
+    for (client, player, mut visibility, viewer_guild) in query.iter_mut() {
         let mut spawns = Vec::new();
         for reference in visibility.added_entities.iter() {
             let added = reference.0;
             let entity = reference.1;
-            if let Ok((pos, inventory_opt, agent_opt, player_opt, monster_opt, item_opt, npc_opt)) = lookup.get(added) {
+            if let Ok((
+                pos,
+                inventory_opt,
+                avatars_opt,
+                agent_opt,
+                player_opt,
+                monster_opt,
+                item_opt,
+                npc_opt,
+                scroll_opt,
+                stall_opt,
+                guild_opt,
+                gate_opt,
+            )) = lookup.get(added)
+            {
                 if let Some(player) = player_opt {
                     let agent = agent_opt.unwrap();
                     let items = inventory_opt
-                        .map(|inv| {
-                            inv.equipment_items()
-                                .map(|(_, item)| CharacterSpawnItemData {
-                                    item_id: item.reference.ref_id(),
-                                    upgrade_level: item.upgrade_level(),
-                                })
-                                .collect()
-                        })
+                        .map(|inv| inv.equipment_items().map(|(_, item)| spawn_item_data(item)).collect())
+                        .unwrap_or_default();
+                    let avatar_items = avatars_opt
+                        .map(|avatars| avatars.items().map(|(_, item)| spawn_item_data(item)).collect())
                         .unwrap_or_default();
                     spawns.push(GroupSpawnDataContent::Spawn {
                         object_id: entity.ref_id,
@@ -129,8 +146,8 @@ pub(crate) fn player_visibility_update(
                             title: 0,
                             inventory_size: inventory_opt.map(|inv| inv.size() as u8).unwrap_or(0),
                             equipment: items,
-                            avatar_inventory_size: 5,
-                            avatar_items: vec![],
+                            avatar_inventory_size: AVATAR_INVENTORY_SIZE as u8,
+                            avatar_items,
                             mask: None,
                             position: pos.as_protocol(),
                             movement: pos.as_standing(),
@@ -140,19 +157,30 @@ pub(crate) fn player_visibility_update(
                             pk_state: PlayerKillState::None,
                             mounted: false,
                             in_combat: false,
-                            active_scroll: ActiveScroll::None,
-                            unknown2: 0,
-                            guild: GuildInformation {
-                                name: "".to_string(),
-                                id: 0,
-                                member: "".to_string(),
-                                last_icon_rev: 0,
-                                union_id: 0,
-                                last_union_icon_rev: 0,
-                                is_friendly: 0,
-                                siege_unknown: 0,
+                            active_scroll: if scroll_opt.is_some() {
+                                ActiveScroll::ReturnScroll
+                            } else {
+                                ActiveScroll::None
                             },
+                            interact_mode: if stall_opt.is_some() {
+                                InteractMode::Stall
+                            } else {
+                                InteractMode::None
+                            },
+                            guild: spawn_information(
+                                &guilds,
+                                guild_opt.map(|in_guild| in_guild.0),
+                                viewer_guild.map(|in_guild| in_guild.0),
+                            ),
                             unknown3: [0; 11],
+                            stall: stall_opt
+                                .map(|stall| {
+                                    StallSpawnData::Open(StallSpawnInfo {
+                                        title: stall.title.clone(),
+                                        decoration: 0,
+                                    })
+                                })
+                                .unwrap_or(StallSpawnData::None),
                             equipment_cooldown: false,
                             unknown4: 0,
                             unknown5: 0,
@@ -191,18 +219,24 @@ pub(crate) fn player_visibility_update(
                             interaction_options: InteractOptions::None,
                         },
                     ));
+                } else if gate_opt.is_some() {
+                    spawns.push(GroupSpawnDataContent::spawn(
+                        entity.ref_id,
+                        EntityTypeSpawnData::teleport(entity.unique_id, pos.as_protocol()),
+                    ));
                 }
             }
         }
 
-        send_group_spawn_packet(client, GroupSpawnType::Spawn, spawns);
-
+        // Entities that are respawned to refresh their appearance are both removed and added, so they
+        // need to be gone before they can be spawned again.
         let mut despawns = Vec::new();
         for reference in visibility.removed_entities.iter() {
             despawns.push(GroupSpawnDataContent::despawn(reference.1.unique_id));
         }
 
         send_group_spawn_packet(client, GroupSpawnType::Despawn, despawns);
+        send_group_spawn_packet(client, GroupSpawnType::Spawn, spawns);
 
         visibility.added_entities.clear();
         visibility.removed_entities.clear();
@@ -225,7 +259,7 @@ fn entity_state_from_agent(agent: &Agent) -> EntityState {
 
 fn spawndata_from_item(entity: GameEntity, pos: &Position, drop: &Drop, for_player: &GameEntity) -> ItemSpawnData {
     match drop.item.type_data {
-        ItemTypeData::Equipment { upgrade_level } => ItemSpawnData::Equipment {
+        ItemTypeData::Equipment { upgrade_level, .. } => ItemSpawnData::Equipment {
             upgrade: upgrade_level,
             unique_id: entity.unique_id,
             position: pos.as_protocol(),
@@ -261,6 +295,17 @@ fn spawndata_from_item(entity: GameEntity, pos: &Position, drop: &Drop, for_play
     }
 }
 
+/// Makes every player that currently sees the entity spawn it anew, so they pick up changes to its
+/// appearance that have no dedicated update.
+pub(crate) fn respawn_for_observers(reference: EntityReference, observers: &mut Query<&mut Visibility>) {
+    for mut visibility in observers.iter_mut() {
+        if visibility.entities_in_radius.contains(&reference) {
+            visibility.removed_entities.push(reference);
+            visibility.added_entities.push(reference);
+        }
+    }
+}
+
 pub(crate) fn clear_visibility(mut query: Query<&mut Visibility, Without<Player>>) {
     for mut visibility in query.iter_mut() {
         visibility.added_entities.clear();
@@ -0,0 +1,252 @@
+use crate::comp::gold::GoldChange;
+use crate::persistence::ApplyInTransaction;
+use axum::async_trait;
+use sqlx::{Error, PgConnection, PgPool};
+use tracing::error;
+
+pub(crate) struct GuildRow {
+    pub id: i32,
+    pub name: String,
+    pub level: i16,
+    pub gp: i64,
+    pub notice_title: String,
+    pub notice: String,
+    pub union_id: Option<i32>,
+}
+
+pub(crate) struct GuildMemberRow {
+    pub character_id: i32,
+    pub name: String,
+    pub level: i16,
+    pub character_type: i32,
+    pub master: bool,
+    pub permissions: i32,
+}
+
+pub(crate) struct UnionGuildRow {
+    pub id: i32,
+    pub name: String,
+}
+
+pub(crate) struct LoadedGuild {
+    pub guild: GuildRow,
+    pub members: Vec<GuildMemberRow>,
+    pub union: Vec<UnionGuildRow>,
+}
+
+/// Loads the guild the character is a member of, including all of its members and the guilds of
+/// its union.
+pub(crate) async fn load_guild_of(character_id: u32, pool: PgPool) -> Result<Option<LoadedGuild>, Error> {
+    let guild = sqlx::query_as!(
+        GuildRow,
+        "SELECT g.id, g.name, g.level, g.gp, g.notice_title, g.notice, g.union_id FROM guilds g JOIN guild_members m ON m.guild_id = g.id WHERE m.character_id = $1",
+        character_id as i32
+    )
+    .fetch_optional(&pool)
+    .await?;
+
+    let Some(guild) = guild else {
+        return Ok(None);
+    };
+
+    let members = sqlx::query_as!(
+        GuildMemberRow,
+        "SELECT m.character_id, c.charname AS name, c.level, c.character_type, m.master, m.permissions FROM guild_members m JOIN characters c ON c.id = m.character_id WHERE m.guild_id = $1",
+        guild.id
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    let union = match guild.union_id {
+        Some(union_id) => {
+            sqlx::query_as!(
+                UnionGuildRow,
+                "SELECT id, name FROM guilds WHERE union_id = $1",
+                union_id
+            )
+            .fetch_all(&pool)
+            .await?
+        },
+        None => Vec::new(),
+    };
+
+    Ok(Some(LoadedGuild { guild, members, union }))
+}
+
+/// Creates the guild with the character as its master, returning the id of the new guild or `None`
+/// if the name is already taken on this server. The gold of the master, after paying the creation fee,
+/// is stored in the same transaction.
+pub(crate) async fn create_guild(
+    server_id: u16,
+    name: String,
+    master_id: u32,
+    master_gold: u64,
+    permissions: u32,
+    pool: PgPool,
+) -> Result<Option<i32>, Error> {
+    let mut transaction = pool.begin().await?;
+    let guild = sqlx::query!(
+        "INSERT INTO guilds(server_id, name) VALUES($1, $2) ON CONFLICT(server_id, name) DO NOTHING RETURNING id",
+        server_id as i16,
+        name,
+    )
+    .fetch_optional(&mut *transaction)
+    .await?;
+
+    let Some(guild) = guild else {
+        return Ok(None);
+    };
+
+    sqlx::query!(
+        "INSERT INTO guild_members(character_id, guild_id, master, permissions) VALUES($1, $2, TRUE, $3)",
+        master_id as i32,
+        guild.id,
+        permissions as i32,
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    GoldChange(master_gold).apply_in(master_id, &mut transaction).await?;
+    transaction.commit().await?;
+    Ok(Some(guild.id))
+}
+
+pub(crate) async fn add_guild_member(guild_id: u32, character_id: u32, pool: PgPool) {
+    let result = sqlx::query!(
+        "INSERT INTO guild_members(character_id, guild_id) VALUES($1, $2)",
+        character_id as i32,
+        guild_id as i32,
+    )
+    .execute(&pool)
+    .await;
+
+    if let Err(e) = result {
+        error!(error = %e, guild_id, character_id, "Could not add guild member.");
+    }
+}
+
+pub(crate) async fn remove_guild_member(guild_id: u32, character_id: u32, pool: PgPool) {
+    let result = sqlx::query!(
+        "DELETE FROM guild_members WHERE character_id = $1 AND guild_id = $2",
+        character_id as i32,
+        guild_id as i32,
+    )
+    .execute(&pool)
+    .await;
+
+    if let Err(e) = result {
+        error!(error = %e, guild_id, character_id, "Could not remove guild member.");
+    }
+}
+
+pub(crate) async fn update_member_permissions(guild_id: u32, character_id: u32, permissions: u32, pool: PgPool) {
+    let result = sqlx::query!(
+        "UPDATE guild_members SET permissions = $3 WHERE character_id = $1 AND guild_id = $2",
+        character_id as i32,
+        guild_id as i32,
+        permissions as i32,
+    )
+    .execute(&pool)
+    .await;
+
+    if let Err(e) = result {
+        error!(error = %e, guild_id, character_id, "Could not update guild member permissions.");
+    }
+}
+
+pub(crate) async fn update_guild_notice(guild_id: u32, title: String, notice: String, pool: PgPool) {
+    let result = sqlx::query!(
+        "UPDATE guilds SET notice_title = $2, notice = $3 WHERE id = $1",
+        guild_id as i32,
+        title,
+        notice,
+    )
+    .execute(&pool)
+    .await;
+
+    if let Err(e) = result {
+        error!(error = %e, guild_id, "Could not update guild notice.");
+    }
+}
+
+/// Stores the level and GP of the guild, such that it can be applied together with the gold or SP
+/// that was spent on it.
+pub(crate) struct GuildProgress {
+    pub(crate) guild_id: u32,
+    pub(crate) level: u8,
+    pub(crate) gp: u32,
+}
+
+#[async_trait]
+impl ApplyInTransaction for GuildProgress {
+    async fn apply_in(&self, _: u32, connection: &mut PgConnection) -> Result<(), Error> {
+        sqlx::query!(
+            "UPDATE guilds SET level = $2, gp = $3 WHERE id = $1",
+            self.guild_id as i32,
+            i16::from(self.level),
+            i64::from(self.gp),
+        )
+        .execute(connection)
+        .await?;
+        Ok(())
+    }
+}
+
+/// Makes the given member the master of the guild, while the previous master becomes a regular member
+/// without any permissions.
+pub(crate) async fn transfer_guild_master(
+    guild_id: u32,
+    old_master: u32,
+    new_master: u32,
+    permissions: u32,
+    pool: PgPool,
+) {
+    let result = sqlx::query!(
+        "UPDATE guild_members SET master = (character_id = $3), permissions = CASE WHEN character_id = $3 THEN $4 ELSE 0 END WHERE guild_id = $1 AND character_id IN ($2, $3)",
+        guild_id as i32,
+        old_master as i32,
+        new_master as i32,
+        permissions as i32,
+    )
+    .execute(&pool)
+    .await;
+
+    if let Err(e) = result {
+        error!(error = %e, guild_id, old_master, new_master, "Could not transfer guild master.");
+    }
+}
+
+/// Deletes the guild, which removes all of its members as well.
+pub(crate) async fn disband_guild(guild_id: u32, pool: PgPool) {
+    let result = sqlx::query!("DELETE FROM guilds WHERE id = $1", guild_id as i32)
+        .execute(&pool)
+        .await;
+
+    if let Err(e) = result {
+        error!(error = %e, guild_id, "Could not disband guild.");
+    }
+}
+
+pub(crate) async fn set_guild_union(guild_id: u32, union_id: Option<u32>, pool: PgPool) {
+    let result = sqlx::query!(
+        "UPDATE guilds SET union_id = $2 WHERE id = $1",
+        guild_id as i32,
+        union_id.map(|id| id as i32),
+    )
+    .execute(&pool)
+    .await;
+
+    if let Err(e) = result {
+        error!(error = %e, guild_id, "Could not update guild union.");
+    }
+}
+
+pub(crate) async fn disband_union(union_id: u32, pool: PgPool) {
+    let result = sqlx::query!("UPDATE guilds SET union_id = NULL WHERE union_id = $1", union_id as i32)
+        .execute(&pool)
+        .await;
+
+    if let Err(e) = result {
+        error!(error = %e, union_id, "Could not disband union.");
+    }
+}
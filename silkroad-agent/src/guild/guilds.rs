@@ -0,0 +1,293 @@
+use crate::guild::db::{GuildProgress, LoadedGuild};
+use bevy::prelude::*;
+use silkroad_protocol::community::{GuildInfo, GuildInformation, GuildMemberData, UnionGuildData, UnionInfo};
+use std::collections::{BTreeMap, HashMap};
+
+/// The maximum amount of members, as well as the gold and GP needed to reach the next level, for
+/// each level of a guild.
+const GUILD_LEVELS: [GuildLevel; 5] = [
+    GuildLevel::new(15, 3_000_000, 5_400),
+    GuildLevel::new(20, 9_000_000, 50_400),
+    GuildLevel::new(25, 15_000_000, 135_000),
+    GuildLevel::new(30, 21_000_000, 378_000),
+    GuildLevel::new(50, 0, 0),
+];
+
+pub(crate) const MAX_GUILD_LEVEL: u8 = GUILD_LEVELS.len() as u8;
+
+pub(crate) const MAX_UNION_GUILDS: usize = 8;
+
+struct GuildLevel {
+    max_members: usize,
+    upgrade_gold: u64,
+    upgrade_gp: u32,
+}
+
+impl GuildLevel {
+    const fn new(max_members: usize, upgrade_gold: u64, upgrade_gp: u32) -> Self {
+        GuildLevel {
+            max_members,
+            upgrade_gold,
+            upgrade_gp,
+        }
+    }
+
+    fn of(level: u8) -> &'static GuildLevel {
+        let index = usize::from(level.clamp(1, MAX_GUILD_LEVEL)) - 1;
+        &GUILD_LEVELS[index]
+    }
+}
+
+/// What a member of the guild besides the master is allowed to do.
+#[derive(Copy, Clone, Eq, PartialEq, Default)]
+pub(crate) struct GuildPermissions(u32);
+
+impl GuildPermissions {
+    pub(crate) const INVITE: GuildPermissions = GuildPermissions(0x1);
+    pub(crate) const KICK: GuildPermissions = GuildPermissions(0x2);
+    pub(crate) const NOTICE: GuildPermissions = GuildPermissions(0x4);
+    pub(crate) const ALL: GuildPermissions = GuildPermissions(0x7);
+
+    pub(crate) fn from_bits(bits: u32) -> Self {
+        GuildPermissions(bits & Self::ALL.0)
+    }
+
+    pub(crate) fn bits(&self) -> u32 {
+        self.0
+    }
+
+    pub(crate) fn allows(&self, permission: GuildPermissions) -> bool {
+        self.0 & permission.0 == permission.0
+    }
+}
+
+pub(crate) struct GuildMember {
+    pub(crate) name: String,
+    pub(crate) ref_id: u32,
+    pub(crate) level: u8,
+    pub(crate) master: bool,
+    pub(crate) permissions: GuildPermissions,
+    /// The player entity of the member, if they are currently online.
+    pub(crate) entity: Option<Entity>,
+}
+
+impl GuildMember {
+    pub(crate) fn can(&self, permission: GuildPermissions) -> bool {
+        self.master || self.permissions.allows(permission)
+    }
+
+    pub(crate) fn as_protocol(&self, character_id: u32) -> GuildMemberData {
+        GuildMemberData {
+            character_id,
+            name: self.name.clone(),
+            ref_id: self.ref_id,
+            level: self.level,
+            master: self.master,
+            permissions: self.permissions.bits(),
+            online: self.entity.is_some(),
+        }
+    }
+}
+
+/// The union a guild is allied in. Every loaded guild of the union holds its own copy, which all
+/// get updated together.
+#[derive(Clone)]
+pub(crate) struct GuildUnion {
+    /// The id of the union, which is the id of the guild that formed and leads it.
+    pub(crate) id: u32,
+    pub(crate) guilds: BTreeMap<u32, String>,
+}
+
+impl GuildUnion {
+    pub(crate) fn is_full(&self) -> bool {
+        self.guilds.len() >= MAX_UNION_GUILDS
+    }
+
+    pub(crate) fn as_protocol(&self) -> UnionInfo {
+        UnionInfo {
+            id: self.id,
+            guilds: self
+                .guilds
+                .iter()
+                .map(|(id, name)| UnionGuildData {
+                    id: *id,
+                    name: name.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+pub(crate) struct Guild {
+    pub(crate) id: u32,
+    pub(crate) name: String,
+    pub(crate) level: u8,
+    pub(crate) gp: u32,
+    pub(crate) notice_title: String,
+    pub(crate) notice: String,
+    pub(crate) members: BTreeMap<u32, GuildMember>,
+    pub(crate) union: Option<GuildUnion>,
+}
+
+impl Guild {
+    pub(crate) fn from_db(loaded: LoadedGuild) -> Self {
+        let LoadedGuild { guild, members, union } = loaded;
+        let members = members
+            .into_iter()
+            .map(|member| {
+                let entry = GuildMember {
+                    name: member.name,
+                    ref_id: member.character_type as u32,
+                    level: member.level as u8,
+                    master: member.master,
+                    permissions: GuildPermissions::from_bits(member.permissions as u32),
+                    entity: None,
+                };
+                (member.character_id as u32, entry)
+            })
+            .collect();
+        let union = guild.union_id.map(|union_id| GuildUnion {
+            id: union_id as u32,
+            guilds: union
+                .into_iter()
+                .map(|union_guild| (union_guild.id as u32, union_guild.name))
+                .collect(),
+        });
+        Guild {
+            id: guild.id as u32,
+            name: guild.name,
+            level: guild.level as u8,
+            gp: guild.gp.clamp(0, i64::from(u32::MAX)) as u32,
+            notice_title: guild.notice_title,
+            notice: guild.notice,
+            members,
+            union,
+        }
+    }
+
+    pub(crate) fn is_full(&self) -> bool {
+        self.members.len() >= GuildLevel::of(self.level).max_members
+    }
+
+    /// The gold and GP needed to raise the guild to the next level, if it hasn't reached the
+    /// maximum level yet.
+    pub(crate) fn upgrade_cost(&self) -> Option<(u64, u32)> {
+        if self.level >= MAX_GUILD_LEVEL {
+            return None;
+        }
+
+        let level = GuildLevel::of(self.level);
+        Some((level.upgrade_gold, level.upgrade_gp))
+    }
+
+    pub(crate) fn progress(&self) -> GuildProgress {
+        GuildProgress {
+            guild_id: self.id,
+            level: self.level,
+            gp: self.gp,
+        }
+    }
+
+    pub(crate) fn union_id(&self) -> Option<u32> {
+        self.union.as_ref().map(|union| union.id)
+    }
+
+    pub(crate) fn leads_union(&self) -> bool {
+        self.union_id() == Some(self.id)
+    }
+
+    pub(crate) fn online_members(&self) -> impl Iterator<Item = Entity> + use<'_> {
+        self.members.values().filter_map(|member| member.entity)
+    }
+
+    pub(crate) fn as_protocol(&self) -> GuildInfo {
+        GuildInfo {
+            id: self.id,
+            name: self.name.clone(),
+            level: self.level,
+            gp: self.gp,
+            notice_title: self.notice_title.clone(),
+            notice: self.notice.clone(),
+            members: self
+                .members
+                .iter()
+                .map(|(character_id, member)| member.as_protocol(*character_id))
+                .collect(),
+        }
+    }
+}
+
+/// The guild information of a player in the given guild, as it is shown to a player in the viewer's
+/// guild. Players of the same guild or union are shown as friendly.
+pub(crate) fn spawn_information(guilds: &Guilds, guild: Option<u32>, viewer: Option<u32>) -> GuildInformation {
+    let Some(guild) = guild.and_then(|guild| guilds.get(guild)) else {
+        return GuildInformation::new(String::new(), 0, String::new(), 0, 0, 0, 0);
+    };
+
+    let friendly = viewer.is_some_and(|viewer| guilds.are_allied(guild.id, viewer));
+    GuildInformation::new(
+        guild.name.clone(),
+        guild.id,
+        String::new(),
+        0,
+        guild.union_id().unwrap_or(0),
+        0,
+        u8::from(friendly),
+    )
+}
+
+/// Marks a player as being a member of the guild with the given id.
+#[derive(Component, Copy, Clone)]
+pub(crate) struct InGuild(pub(crate) u32);
+
+/// All guilds that have at least one member online, keyed by their id.
+#[derive(Resource, Default)]
+pub(crate) struct Guilds(HashMap<u32, Guild>);
+
+impl Guilds {
+    pub(crate) fn get(&self, id: u32) -> Option<&Guild> {
+        self.0.get(&id)
+    }
+
+    pub(crate) fn get_mut(&mut self, id: u32) -> Option<&mut Guild> {
+        self.0.get_mut(&id)
+    }
+
+    pub(crate) fn remove(&mut self, id: u32) -> Option<Guild> {
+        self.0.remove(&id)
+    }
+
+    /// Adds the guild, unless it has already been loaded for another member.
+    pub(crate) fn insert_if_absent(&mut self, guild: Guild) -> &mut Guild {
+        self.0.entry(guild.id).or_insert(guild)
+    }
+
+    pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = &mut Guild> {
+        self.0.values_mut()
+    }
+
+    /// All loaded guilds that are part of the given union.
+    pub(crate) fn union_guilds(&self, union_id: u32) -> impl Iterator<Item = &Guild> {
+        self.0.values().filter(move |guild| guild.union_id() == Some(union_id))
+    }
+
+    pub(crate) fn union_guilds_mut(&mut self, union_id: u32) -> impl Iterator<Item = &mut Guild> {
+        self.0
+            .values_mut()
+            .filter(move |guild| guild.union_id() == Some(union_id))
+    }
+
+    /// Checks if both guilds are the same guild or are part of the same union.
+    pub(crate) fn are_allied(&self, guild: u32, other: u32) -> bool {
+        guild == other
+            || self
+                .get(guild)
+                .and_then(Guild::union_id)
+                .is_some_and(|union_id| self.get(other).and_then(Guild::union_id) == Some(union_id))
+    }
+
+    /// Removes all guilds of which no member is online anymore.
+    pub(crate) fn remove_offline(&mut self) {
+        self.0.retain(|_, guild| guild.online_members().next().is_some());
+    }
+}
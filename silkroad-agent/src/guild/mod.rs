@@ -0,0 +1,33 @@
+use crate::guild::guilds::Guilds;
+use crate::guild::system::{
+    answer_guild_invitations, finish_creating_guilds, finish_loading_guilds, handle_guild_requests, load_guilds,
+    update_offline_guild_members,
+};
+use crate::guild::union::{answer_union_invitations, handle_union_requests};
+use bevy::prelude::*;
+
+mod db;
+pub(crate) mod guilds;
+mod system;
+mod union;
+
+pub(crate) struct GuildPlugin;
+
+impl Plugin for GuildPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Guilds>().add_systems(
+            Update,
+            (
+                load_guilds,
+                finish_loading_guilds,
+                handle_guild_requests,
+                answer_guild_invitations,
+                handle_union_requests,
+                answer_union_invitations,
+                finish_creating_guilds,
+                update_offline_guild_members,
+            )
+                .chain(),
+        );
+    }
+}
@@ -0,0 +1,786 @@
+use crate::comp::exp::{Leveled, SP};
+use crate::comp::gold::{GoldChange, GoldPouch};
+use crate::comp::invitation::PendingInvitation;
+use crate::comp::net::Client;
+use crate::comp::npc::NPC;
+use crate::comp::player::Player;
+use crate::comp::pos::Position;
+use crate::comp::visibility::Visibility;
+use crate::comp::GameEntity;
+use crate::event::LoadingFinishedEvent;
+use crate::ext::DbPool;
+use crate::game::target::MAX_TARGET_DISTANCE;
+use crate::guild::db::{
+    add_guild_member, create_guild, disband_guild, load_guild_of, remove_guild_member, transfer_guild_master,
+    update_guild_notice, update_member_permissions, LoadedGuild,
+};
+use crate::guild::guilds::{spawn_information, Guild, GuildMember, GuildPermissions, Guilds, InGuild};
+use crate::input::PlayerInput;
+use crate::persistence::PersistTogetherEvent;
+use crate::server_plugin::ServerId;
+use crate::tasks::TaskCreator;
+use crate::world::{EntityLookup, WorldData};
+use bevy::ecs::entity::Entities;
+use bevy::prelude::*;
+use silkroad_protocol::community::{
+    EntityGuildUpdate, GuildClientProtocol, GuildCreateResponse, GuildDisbandResponse, GuildDonateGpResponse,
+    GuildError, GuildInviteResponse, GuildKickResponse, GuildLeaveResponse, GuildLevelUpResponse, GuildPromoteResponse,
+    GuildTransferMasterResponse, GuildUpdate, GuildUpdateNoticeResponse,
+};
+use silkroad_protocol::world::{PlayerInvitation, PlayerInvitationResponse};
+use skrillax_stream::packet::AsPacket;
+use sqlx::{Error, PgPool};
+use std::collections::BTreeMap;
+use tokio::sync::oneshot::error::TryRecvError;
+use tokio::sync::oneshot::Receiver;
+use tracing::error;
+
+const GUILD_CREATION_FEE: u64 = 10_000;
+const GUILD_CREATION_MIN_LEVEL: u8 = 20;
+const MIN_GUILD_NAME_LENGTH: usize = 2;
+const MAX_GUILD_NAME_LENGTH: usize = 12;
+const MAX_NOTICE_TITLE_LENGTH: usize = 32;
+const MAX_NOTICE_LENGTH: usize = 256;
+
+#[derive(Component)]
+pub(crate) struct GuildLoading(Receiver<Result<Option<LoadedGuild>, Error>>);
+
+/// A guild the player wants to create, which is waiting for the database to assign it an id. The
+/// creation fee has already been paid and is refunded if the guild could not be created.
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+pub(crate) struct GuildCreation {
+    receiver: Receiver<Result<Option<i32>, Error>>,
+    name: String,
+}
+
+/// An invitation to a guild, which the invited player has yet to accept or decline.
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+pub(crate) struct GuildInvitation {
+    requester: Entity,
+    guild_id: u32,
+}
+
+pub(crate) fn load_guilds(
+    mut reader: EventReader<LoadingFinishedEvent>,
+    query: Query<&Player>,
+    task_creator: Res<TaskCreator>,
+    pool: Res<DbPool>,
+    mut cmd: Commands,
+) {
+    for LoadingFinishedEvent(entity) in reader.read() {
+        let Ok(player) = query.get(*entity) else {
+            continue;
+        };
+
+        let receiver = task_creator.create_task(load_guild_of(player.character.id, PgPool::clone(&pool)));
+        cmd.entity(*entity).try_insert(GuildLoading(receiver));
+    }
+}
+
+/// Sends the guild to the player once it has been loaded and lets the other members know that they
+/// are now online.
+pub(crate) fn finish_loading_guilds(
+    mut query: Query<(Entity, &Client, &GameEntity, &Player, &Visibility, &mut GuildLoading)>,
+    clients: Query<&Client>,
+    viewers: Query<(&Client, Option<&InGuild>)>,
+    mut guilds: ResMut<Guilds>,
+    mut cmd: Commands,
+) {
+    for (entity, client, game_entity, player, visibility, mut loading) in query.iter_mut() {
+        let loaded = match loading.0.try_recv() {
+            Ok(Ok(loaded)) => loaded,
+            Ok(Err(e)) => {
+                error!(error = %e, id = player.character.id, "Could not load guild.");
+                None
+            },
+            Err(TryRecvError::Empty) => continue,
+            Err(TryRecvError::Closed) => None,
+        };
+        cmd.entity(entity).remove::<GuildLoading>();
+
+        let Some(loaded) = loaded else {
+            continue;
+        };
+
+        let char_id = player.character.id;
+        let guild = guilds.insert_if_absent(Guild::from_db(loaded));
+        let Some(member) = guild.members.get_mut(&char_id) else {
+            continue;
+        };
+        member.entity = Some(entity);
+
+        cmd.entity(entity).insert(InGuild(guild.id));
+        client.send(guild.as_protocol());
+        if let Some(union) = &guild.union {
+            client.send(union.as_protocol());
+        }
+        notify_members(
+            guild,
+            &clients,
+            Some(entity),
+            GuildUpdate::MemberStatus {
+                character_id: char_id,
+                online: true,
+            },
+        );
+        let guild_id = guild.id;
+        announce_guild(game_entity, visibility, Some(guild_id), &guilds, &viewers);
+    }
+}
+
+pub(crate) fn handle_guild_requests(
+    mut query: Query<(
+        Entity,
+        &Client,
+        &GameEntity,
+        &Player,
+        &Leveled,
+        &Position,
+        &Visibility,
+        &PlayerInput,
+        &mut GoldPouch,
+        &mut SP,
+        Option<&InGuild>,
+        Has<GuildCreation>,
+    )>,
+    targets: Query<(&Client, &GameEntity, &Visibility, Option<&InGuild>)>,
+    pending: Query<(), With<PendingInvitation>>,
+    clients: Query<&Client>,
+    viewers: Query<(&Client, Option<&InGuild>)>,
+    npc_query: Query<(&GameEntity, &Position), With<NPC>>,
+    lookup: Res<EntityLookup>,
+    mut guilds: ResMut<Guilds>,
+    server_id: Res<ServerId>,
+    task_creator: Res<TaskCreator>,
+    pool: Res<DbPool>,
+    mut persist: EventWriter<PersistTogetherEvent>,
+    mut cmd: Commands,
+) {
+    for (
+        entity,
+        client,
+        game_entity,
+        player,
+        level,
+        position,
+        visibility,
+        input,
+        mut gold,
+        mut sp,
+        in_guild,
+        creating,
+    ) in query.iter_mut()
+    {
+        let Some(ref request) = input.guild else {
+            continue;
+        };
+
+        let char_id = player.character.id;
+        match request {
+            GuildClientProtocol::GuildCreate(create) => {
+                if in_guild.is_some() || creating {
+                    client.send(GuildCreateResponse::Failure(GuildError::AlreadyInGuild));
+                    continue;
+                }
+
+                if !is_near_guild_manager(create.npc_unique_id, position, &lookup, &npc_query) {
+                    client.send(GuildCreateResponse::Failure(GuildError::InvalidNpc));
+                    continue;
+                }
+
+                if level.current_level() < GUILD_CREATION_MIN_LEVEL {
+                    client.send(GuildCreateResponse::Failure(GuildError::LevelTooLow));
+                    continue;
+                }
+
+                let name = create.name.trim();
+                if !is_valid_guild_name(name) {
+                    client.send(GuildCreateResponse::Failure(GuildError::InvalidName));
+                    continue;
+                }
+
+                if gold.amount() < GUILD_CREATION_FEE {
+                    client.send(GuildCreateResponse::Failure(GuildError::NotEnoughGold));
+                    continue;
+                }
+
+                gold.spend(GUILD_CREATION_FEE);
+                let receiver = task_creator.create_task(create_guild(
+                    server_id.0,
+                    name.to_string(),
+                    char_id,
+                    gold.amount(),
+                    GuildPermissions::ALL.bits(),
+                    PgPool::clone(&pool),
+                ));
+                cmd.entity(entity).try_insert(GuildCreation {
+                    receiver,
+                    name: name.to_string(),
+                });
+            },
+            GuildClientProtocol::GuildDisband(_) => {
+                let Some(guild) = in_guild.and_then(|in_guild| guilds.get(in_guild.0)) else {
+                    client.send(GuildDisbandResponse::Failure(GuildError::NotInGuild));
+                    continue;
+                };
+
+                if !guild.members.get(&char_id).is_some_and(|member| member.master) {
+                    client.send(GuildDisbandResponse::Failure(GuildError::NoPermission));
+                    continue;
+                }
+
+                // The union would otherwise keep a guild that no longer exists.
+                if guild.union.is_some() {
+                    client.send(GuildDisbandResponse::Failure(GuildError::AlreadyInUnion));
+                    continue;
+                }
+
+                let guild_id = guild.id;
+                let Some(guild) = guilds.remove(guild_id) else {
+                    continue;
+                };
+
+                client.send(GuildDisbandResponse::Success);
+                notify_members(&guild, &clients, None, GuildUpdate::Disbanded);
+                for (member, (_, member_game_entity, member_visibility, _)) in guild
+                    .online_members()
+                    .filter_map(|member| targets.get(member).ok().map(|found| (member, found)))
+                {
+                    cmd.entity(member).remove::<InGuild>();
+                    announce_guild(member_game_entity, member_visibility, None, &guilds, &viewers);
+                }
+                task_creator.spawn(disband_guild(guild.id, PgPool::clone(&pool)));
+            },
+            GuildClientProtocol::GuildTransferMaster(transfer) => {
+                let Some(guild) = in_guild.and_then(|in_guild| guilds.get_mut(in_guild.0)) else {
+                    client.send(GuildTransferMasterResponse::Failure(GuildError::NotInGuild));
+                    continue;
+                };
+
+                if !guild.members.get(&char_id).is_some_and(|member| member.master) {
+                    client.send(GuildTransferMasterResponse::Failure(GuildError::NoPermission));
+                    continue;
+                }
+
+                let Some(new_master) = guild
+                    .members
+                    .get_mut(&transfer.character_id)
+                    .filter(|member| !member.master)
+                else {
+                    client.send(GuildTransferMasterResponse::Failure(GuildError::InvalidTarget));
+                    continue;
+                };
+
+                new_master.master = true;
+                new_master.permissions = GuildPermissions::ALL;
+                if let Some(old_master) = guild.members.get_mut(&char_id) {
+                    old_master.master = false;
+                    old_master.permissions = GuildPermissions::default();
+                }
+
+                client.send(GuildTransferMasterResponse::Success);
+                notify_members(
+                    guild,
+                    &clients,
+                    None,
+                    GuildUpdate::MasterTransferred {
+                        old_master: char_id,
+                        new_master: transfer.character_id,
+                    },
+                );
+                task_creator.spawn(transfer_guild_master(
+                    guild.id,
+                    char_id,
+                    transfer.character_id,
+                    GuildPermissions::ALL.bits(),
+                    PgPool::clone(&pool),
+                ));
+            },
+            GuildClientProtocol::GuildLeave(_) => {
+                let Some(guild) = in_guild.and_then(|in_guild| guilds.get_mut(in_guild.0)) else {
+                    client.send(GuildLeaveResponse::Failure(GuildError::NotInGuild));
+                    continue;
+                };
+
+                if guild.members.get(&char_id).is_some_and(|member| member.master) {
+                    client.send(GuildLeaveResponse::Failure(GuildError::MasterCannotLeave));
+                    continue;
+                }
+
+                guild.members.remove(&char_id);
+                cmd.entity(entity).remove::<InGuild>();
+                client.send(GuildLeaveResponse::Success);
+                notify_members(
+                    guild,
+                    &clients,
+                    None,
+                    GuildUpdate::MemberLeft {
+                        character_id: char_id,
+                        kicked: false,
+                    },
+                );
+                task_creator.spawn(remove_guild_member(guild.id, char_id, PgPool::clone(&pool)));
+                announce_guild(game_entity, visibility, None, &guilds, &viewers);
+            },
+            GuildClientProtocol::GuildInvite(invite) => {
+                let Some(guild) = in_guild.and_then(|in_guild| guilds.get(in_guild.0)) else {
+                    client.send(GuildInviteResponse::Failure(GuildError::NotInGuild));
+                    continue;
+                };
+
+                if !guild
+                    .members
+                    .get(&char_id)
+                    .is_some_and(|member| member.can(GuildPermissions::INVITE))
+                {
+                    client.send(GuildInviteResponse::Failure(GuildError::NoPermission));
+                    continue;
+                }
+
+                if guild.is_full() {
+                    client.send(GuildInviteResponse::Failure(GuildError::GuildFull));
+                    continue;
+                }
+
+                let Some((target, (target_client, _, _, target_guild))) = lookup
+                    .get_entity_for_id(invite.target)
+                    .filter(|target| *target != entity)
+                    .and_then(|target| targets.get(target).ok().map(|found| (target, found)))
+                else {
+                    client.send(GuildInviteResponse::Failure(GuildError::InvalidTarget));
+                    continue;
+                };
+
+                if target_guild.is_some() {
+                    client.send(GuildInviteResponse::Failure(GuildError::TargetInGuild));
+                    continue;
+                }
+
+                if pending.contains(target) {
+                    client.send(GuildInviteResponse::Failure(GuildError::Busy));
+                    continue;
+                }
+
+                cmd.entity(target).try_insert((
+                    GuildInvitation {
+                        requester: entity,
+                        guild_id: guild.id,
+                    },
+                    PendingInvitation,
+                ));
+                target_client.send(PlayerInvitation::guild(game_entity.unique_id));
+            },
+            GuildClientProtocol::GuildKick(kick) => {
+                let Some(guild) = in_guild.and_then(|in_guild| guilds.get_mut(in_guild.0)) else {
+                    client.send(GuildKickResponse::Failure(GuildError::NotInGuild));
+                    continue;
+                };
+
+                if !guild
+                    .members
+                    .get(&char_id)
+                    .is_some_and(|member| member.can(GuildPermissions::KICK))
+                {
+                    client.send(GuildKickResponse::Failure(GuildError::NoPermission));
+                    continue;
+                }
+
+                if kick.character_id == char_id
+                    || !guild
+                        .members
+                        .get(&kick.character_id)
+                        .is_some_and(|member| !member.master)
+                {
+                    client.send(GuildKickResponse::Failure(GuildError::InvalidTarget));
+                    continue;
+                }
+
+                let Some(kicked) = guild.members.remove(&kick.character_id) else {
+                    continue;
+                };
+
+                let update = GuildUpdate::MemberLeft {
+                    character_id: kick.character_id,
+                    kicked: true,
+                };
+                client.send(GuildKickResponse::Success);
+                notify_members(guild, &clients, None, update.clone());
+                task_creator.spawn(remove_guild_member(guild.id, kick.character_id, PgPool::clone(&pool)));
+
+                if let Some((kicked_entity, (kicked_client, kicked_game_entity, kicked_visibility, _))) = kicked
+                    .entity
+                    .and_then(|kicked| targets.get(kicked).ok().map(|found| (kicked, found)))
+                {
+                    cmd.entity(kicked_entity).remove::<InGuild>();
+                    kicked_client.send(update);
+                    announce_guild(kicked_game_entity, kicked_visibility, None, &guilds, &viewers);
+                }
+            },
+            GuildClientProtocol::GuildLevelUp(level_up) => {
+                let Some(guild) = in_guild.and_then(|in_guild| guilds.get_mut(in_guild.0)) else {
+                    client.send(GuildLevelUpResponse::Failure(GuildError::NotInGuild));
+                    continue;
+                };
+
+                if !guild.members.get(&char_id).is_some_and(|member| member.master) {
+                    client.send(GuildLevelUpResponse::Failure(GuildError::NoPermission));
+                    continue;
+                }
+
+                if !is_near_guild_manager(level_up.npc_unique_id, position, &lookup, &npc_query) {
+                    client.send(GuildLevelUpResponse::Failure(GuildError::InvalidNpc));
+                    continue;
+                }
+
+                let Some((gold_cost, gp_cost)) = guild.upgrade_cost() else {
+                    client.send(GuildLevelUpResponse::Failure(GuildError::MaxLevel));
+                    continue;
+                };
+
+                if gold.amount() < gold_cost {
+                    client.send(GuildLevelUpResponse::Failure(GuildError::NotEnoughGold));
+                    continue;
+                }
+
+                if guild.gp < gp_cost {
+                    client.send(GuildLevelUpResponse::Failure(GuildError::NotEnoughGp));
+                    continue;
+                }
+
+                gold.spend(gold_cost);
+                guild.gp -= gp_cost;
+                guild.level += 1;
+                persist.send(
+                    PersistTogetherEvent::new(vec![entity])
+                        .with_change(char_id, GoldChange(gold.amount()))
+                        .with_change(char_id, guild.progress()),
+                );
+                client.send(GuildLevelUpResponse::Success { level: guild.level });
+                notify_members(
+                    guild,
+                    &clients,
+                    None,
+                    GuildUpdate::Progress {
+                        level: guild.level,
+                        gp: guild.gp,
+                    },
+                );
+            },
+            GuildClientProtocol::GuildUpdateNotice(notice) => {
+                let Some(guild) = in_guild.and_then(|in_guild| guilds.get_mut(in_guild.0)) else {
+                    client.send(GuildUpdateNoticeResponse::Failure(GuildError::NotInGuild));
+                    continue;
+                };
+
+                if !guild
+                    .members
+                    .get(&char_id)
+                    .is_some_and(|member| member.can(GuildPermissions::NOTICE))
+                {
+                    client.send(GuildUpdateNoticeResponse::Failure(GuildError::NoPermission));
+                    continue;
+                }
+
+                guild.notice_title = notice.title.trim().chars().take(MAX_NOTICE_TITLE_LENGTH).collect();
+                guild.notice = notice.notice.chars().take(MAX_NOTICE_LENGTH).collect();
+                client.send(GuildUpdateNoticeResponse::Success);
+                notify_members(
+                    guild,
+                    &clients,
+                    None,
+                    GuildUpdate::Notice {
+                        title: guild.notice_title.clone(),
+                        notice: guild.notice.clone(),
+                    },
+                );
+                task_creator.spawn(update_guild_notice(
+                    guild.id,
+                    guild.notice_title.clone(),
+                    guild.notice.clone(),
+                    PgPool::clone(&pool),
+                ));
+            },
+            GuildClientProtocol::GuildPromote(promote) => {
+                let Some(guild) = in_guild.and_then(|in_guild| guilds.get_mut(in_guild.0)) else {
+                    client.send(GuildPromoteResponse::Failure(GuildError::NotInGuild));
+                    continue;
+                };
+
+                if !guild.members.get(&char_id).is_some_and(|member| member.master) {
+                    client.send(GuildPromoteResponse::Failure(GuildError::NoPermission));
+                    continue;
+                }
+
+                let Some(member) = guild
+                    .members
+                    .get_mut(&promote.character_id)
+                    .filter(|member| !member.master)
+                else {
+                    client.send(GuildPromoteResponse::Failure(GuildError::InvalidTarget));
+                    continue;
+                };
+
+                member.permissions = GuildPermissions::from_bits(promote.permissions);
+                let permissions = member.permissions.bits();
+                client.send(GuildPromoteResponse::Success);
+                notify_members(
+                    guild,
+                    &clients,
+                    None,
+                    GuildUpdate::MemberPermissions {
+                        character_id: promote.character_id,
+                        permissions,
+                    },
+                );
+                task_creator.spawn(update_member_permissions(
+                    guild.id,
+                    promote.character_id,
+                    permissions,
+                    PgPool::clone(&pool),
+                ));
+            },
+            GuildClientProtocol::GuildDonateGp(donation) => {
+                let Some(guild) = in_guild.and_then(|in_guild| guilds.get_mut(in_guild.0)) else {
+                    client.send(GuildDonateGpResponse::Failure(GuildError::NotInGuild));
+                    continue;
+                };
+
+                if donation.amount == 0 || sp.current() < donation.amount {
+                    client.send(GuildDonateGpResponse::Failure(GuildError::NotEnoughSp));
+                    continue;
+                }
+
+                sp.spend(donation.amount);
+                guild.gp = guild.gp.saturating_add(donation.amount);
+                persist.send(PersistTogetherEvent::new(vec![entity]).with_change(char_id, guild.progress()));
+                client.send(GuildDonateGpResponse::Success { gp: guild.gp });
+                notify_members(
+                    guild,
+                    &clients,
+                    Some(entity),
+                    GuildUpdate::Progress {
+                        level: guild.level,
+                        gp: guild.gp,
+                    },
+                );
+            },
+        }
+    }
+}
+
+pub(crate) fn answer_guild_invitations(
+    query: Query<(Entity, &PlayerInput, &GuildInvitation)>,
+    members: Query<(&Client, &GameEntity, &Player, &Leveled, &Visibility, Option<&InGuild>)>,
+    clients: Query<&Client>,
+    viewers: Query<(&Client, Option<&InGuild>)>,
+    mut guilds: ResMut<Guilds>,
+    task_creator: Res<TaskCreator>,
+    pool: Res<DbPool>,
+    mut cmd: Commands,
+) {
+    for (entity, input, invitation) in query.iter() {
+        let Some(ref response) = input.invitation else {
+            continue;
+        };
+
+        cmd.entity(entity).remove::<(GuildInvitation, PendingInvitation)>();
+        let Ok((requester_client, ..)) = members.get(invitation.requester) else {
+            continue;
+        };
+        let Ok((client, game_entity, player, level, visibility, own_guild)) = members.get(entity) else {
+            continue;
+        };
+
+        if matches!(response, PlayerInvitationResponse::Decline(_)) {
+            requester_client.send(GuildInviteResponse::Failure(GuildError::Declined));
+            continue;
+        }
+
+        if own_guild.is_some() {
+            requester_client.send(GuildInviteResponse::Failure(GuildError::TargetInGuild));
+            continue;
+        }
+
+        let Some(guild) = guilds.get_mut(invitation.guild_id) else {
+            requester_client.send(GuildInviteResponse::Failure(GuildError::NotInGuild));
+            continue;
+        };
+
+        if guild.is_full() {
+            requester_client.send(GuildInviteResponse::Failure(GuildError::GuildFull));
+            continue;
+        }
+
+        let char_id = player.character.id;
+        let member = GuildMember {
+            name: player.character.name.clone(),
+            ref_id: game_entity.ref_id,
+            level: level.current_level(),
+            master: false,
+            permissions: GuildPermissions::default(),
+            entity: Some(entity),
+        };
+        notify_members(
+            guild,
+            &clients,
+            None,
+            GuildUpdate::MemberJoined(member.as_protocol(char_id)),
+        );
+        guild.members.insert(char_id, member);
+        cmd.entity(entity).try_insert(InGuild(guild.id));
+        requester_client.send(GuildInviteResponse::Success);
+        client.send(guild.as_protocol());
+        if let Some(union) = &guild.union {
+            client.send(union.as_protocol());
+        }
+        task_creator.spawn(add_guild_member(guild.id, char_id, PgPool::clone(&pool)));
+        announce_guild(game_entity, visibility, Some(invitation.guild_id), &guilds, &viewers);
+    }
+}
+
+pub(crate) fn finish_creating_guilds(
+    mut query: Query<(
+        Entity,
+        &Client,
+        &GameEntity,
+        &Player,
+        &Leveled,
+        &Visibility,
+        &mut GoldPouch,
+        &mut GuildCreation,
+    )>,
+    viewers: Query<(&Client, Option<&InGuild>)>,
+    mut guilds: ResMut<Guilds>,
+    mut cmd: Commands,
+) {
+    for (entity, client, game_entity, player, level, visibility, mut gold, mut creation) in query.iter_mut() {
+        let result = match creation.receiver.try_recv() {
+            Ok(Ok(Some(id))) => Ok(id as u32),
+            Ok(Ok(None)) => Err(GuildError::NameTaken),
+            Ok(Err(e)) => {
+                error!(error = %e, id = player.character.id, "Could not create guild.");
+                Err(GuildError::InvalidName)
+            },
+            Err(TryRecvError::Empty) => continue,
+            Err(TryRecvError::Closed) => Err(GuildError::InvalidName),
+        };
+        cmd.entity(entity).remove::<GuildCreation>();
+
+        let id = match result {
+            Ok(id) => id,
+            Err(error) => {
+                gold.gain(GUILD_CREATION_FEE);
+                client.send(GuildCreateResponse::Failure(error));
+                continue;
+            },
+        };
+
+        let master = GuildMember {
+            name: player.character.name.clone(),
+            ref_id: game_entity.ref_id,
+            level: level.current_level(),
+            master: true,
+            permissions: GuildPermissions::ALL,
+            entity: Some(entity),
+        };
+        let guild = guilds.insert_if_absent(Guild {
+            id,
+            name: creation.name.clone(),
+            level: 1,
+            gp: 0,
+            notice_title: String::new(),
+            notice: String::new(),
+            members: BTreeMap::from([(player.character.id, master)]),
+            union: None,
+        });
+        cmd.entity(entity).try_insert(InGuild(id));
+        client.send(GuildCreateResponse::Success);
+        client.send(guild.as_protocol());
+        announce_guild(game_entity, visibility, Some(id), &guilds, &viewers);
+    }
+}
+
+/// Marks all members that have left the game as offline, letting the other members know, and
+/// unloads guilds of which no member is online anymore.
+pub(crate) fn update_offline_guild_members(mut guilds: ResMut<Guilds>, clients: Query<&Client>, entities: &Entities) {
+    for guild in guilds.iter_mut() {
+        let offline = guild
+            .members
+            .iter_mut()
+            .filter(|(_, member)| member.entity.is_some_and(|entity| !entities.contains(entity)))
+            .map(|(character_id, member)| {
+                member.entity = None;
+                *character_id
+            })
+            .collect::<Vec<_>>();
+
+        for character_id in offline {
+            notify_members(
+                guild,
+                &clients,
+                None,
+                GuildUpdate::MemberStatus {
+                    character_id,
+                    online: false,
+                },
+            );
+        }
+    }
+    guilds.remove_offline();
+}
+
+pub(super) fn notify_members<T: AsPacket + Clone + Send + 'static>(
+    guild: &Guild,
+    clients: &Query<&Client>,
+    except: Option<Entity>,
+    update: T,
+) {
+    for member in guild.online_members().filter(|member| Some(*member) != except) {
+        if let Ok(client) = clients.get(member) {
+            client.send(update.clone());
+        }
+    }
+}
+
+/// Lets everyone close to the player know about the guild they're now in.
+pub(super) fn announce_guild(
+    game_entity: &GameEntity,
+    visibility: &Visibility,
+    guild: Option<u32>,
+    guilds: &Guilds,
+    viewers: &Query<(&Client, Option<&InGuild>)>,
+) {
+    for other in visibility.entities_in_radius.iter() {
+        if let Ok((client, viewer_guild)) = viewers.get(other.0) {
+            client.send(EntityGuildUpdate {
+                unique_id: game_entity.unique_id,
+                guild: spawn_information(guilds, guild, viewer_guild.map(|in_guild| in_guild.0)),
+            });
+        }
+    }
+}
+
+fn is_valid_guild_name(name: &str) -> bool {
+    (MIN_GUILD_NAME_LENGTH..=MAX_GUILD_NAME_LENGTH).contains(&name.chars().count())
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Guilds are created and leveled up at the guild manager of a town.
+fn is_near_guild_manager(
+    npc_unique_id: u32,
+    position: &Position,
+    lookup: &EntityLookup,
+    npc_query: &Query<(&GameEntity, &Position), With<NPC>>,
+) -> bool {
+    lookup
+        .get_entity_for_id(npc_unique_id)
+        .and_then(|npc| npc_query.get(npc).ok())
+        .is_some_and(|(npc, npc_position)| {
+            WorldData::characters()
+                .find_id(npc.ref_id)
+                .is_some_and(|npc| npc.common.id.ends_with("_GUILD"))
+                && position.distance_to(npc_position) < MAX_TARGET_DISTANCE
+        })
+}
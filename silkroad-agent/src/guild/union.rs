@@ -0,0 +1,282 @@
+use crate::comp::invitation::PendingInvitation;
+use crate::comp::net::Client;
+use crate::comp::player::Player;
+use crate::comp::visibility::Visibility;
+use crate::comp::GameEntity;
+use crate::ext::DbPool;
+use crate::guild::db::{disband_union, set_guild_union};
+use crate::guild::guilds::{Guild, GuildUnion, Guilds, InGuild};
+use crate::guild::system::{announce_guild, notify_members};
+use crate::input::PlayerInput;
+use crate::tasks::TaskCreator;
+use crate::world::EntityLookup;
+use bevy::prelude::*;
+use silkroad_protocol::community::{
+    GuildError, UnionClientProtocol, UnionGuildData, UnionInviteResponse, UnionLeaveResponse, UnionUpdate,
+};
+use silkroad_protocol::world::{PlayerInvitation, PlayerInvitationResponse};
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+
+/// An invitation for the guild of the invited master to join the union of the requesting guild,
+/// which the invited master has yet to accept or decline.
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+pub(crate) struct UnionInvitation {
+    requester: Entity,
+    guild_id: u32,
+}
+
+pub(crate) fn handle_union_requests(
+    query: Query<(Entity, &Client, &GameEntity, &Player, &PlayerInput, Option<&InGuild>)>,
+    targets: Query<(&Client, &Player, Option<&InGuild>)>,
+    pending: Query<(), With<PendingInvitation>>,
+    members: Query<(&GameEntity, &Visibility)>,
+    clients: Query<&Client>,
+    viewers: Query<(&Client, Option<&InGuild>)>,
+    lookup: Res<EntityLookup>,
+    mut guilds: ResMut<Guilds>,
+    task_creator: Res<TaskCreator>,
+    pool: Res<DbPool>,
+    mut cmd: Commands,
+) {
+    for (entity, client, game_entity, player, input, in_guild) in query.iter() {
+        let Some(ref request) = input.union else {
+            continue;
+        };
+
+        let char_id = player.character.id;
+        match request {
+            UnionClientProtocol::UnionInvite(invite) => {
+                let Some(guild) = in_guild.and_then(|in_guild| guilds.get(in_guild.0)) else {
+                    client.send(UnionInviteResponse::Failure(GuildError::NotInGuild));
+                    continue;
+                };
+
+                if !is_master(guild, char_id) {
+                    client.send(UnionInviteResponse::Failure(GuildError::NoPermission));
+                    continue;
+                }
+
+                if let Some(union) = &guild.union {
+                    if !guild.leads_union() {
+                        client.send(UnionInviteResponse::Failure(GuildError::NoPermission));
+                        continue;
+                    }
+
+                    if union.is_full() {
+                        client.send(UnionInviteResponse::Failure(GuildError::UnionFull));
+                        continue;
+                    }
+                }
+
+                let Some((target, (target_client, target_player, target_guild))) = lookup
+                    .get_entity_for_id(invite.target)
+                    .filter(|target| *target != entity)
+                    .and_then(|target| targets.get(target).ok().map(|found| (target, found)))
+                else {
+                    client.send(UnionInviteResponse::Failure(GuildError::InvalidTarget));
+                    continue;
+                };
+
+                let Some(target_guild) = target_guild
+                    .and_then(|target_guild| guilds.get(target_guild.0))
+                    .filter(|target_guild| target_guild.id != guild.id)
+                    .filter(|target_guild| is_master(target_guild, target_player.character.id))
+                else {
+                    client.send(UnionInviteResponse::Failure(GuildError::InvalidTarget));
+                    continue;
+                };
+
+                if target_guild.union.is_some() {
+                    client.send(UnionInviteResponse::Failure(GuildError::TargetInUnion));
+                    continue;
+                }
+
+                if pending.contains(target) {
+                    client.send(UnionInviteResponse::Failure(GuildError::Busy));
+                    continue;
+                }
+
+                cmd.entity(target).try_insert((
+                    UnionInvitation {
+                        requester: entity,
+                        guild_id: guild.id,
+                    },
+                    PendingInvitation,
+                ));
+                target_client.send(PlayerInvitation::union(game_entity.unique_id));
+            },
+            UnionClientProtocol::UnionLeave(_) => {
+                let Some(guild) = in_guild.and_then(|in_guild| guilds.get(in_guild.0)) else {
+                    client.send(UnionLeaveResponse::Failure(GuildError::NotInGuild));
+                    continue;
+                };
+
+                if !is_master(guild, char_id) {
+                    client.send(UnionLeaveResponse::Failure(GuildError::NoPermission));
+                    continue;
+                }
+
+                let Some(union) = &guild.union else {
+                    client.send(UnionLeaveResponse::Failure(GuildError::NotInUnion));
+                    continue;
+                };
+
+                let guild_id = guild.id;
+                let union_id = union.id;
+                let affected = guilds.union_guilds(union_id).map(|guild| guild.id).collect::<Vec<_>>();
+                client.send(UnionLeaveResponse::Success);
+
+                // A union cannot exist without the guild leading it, or with only a single guild.
+                if guild.leads_union() || union.guilds.len() <= 2 {
+                    notify_union(&guilds, union_id, &clients, UnionUpdate::Disbanded);
+                    for guild in guilds.union_guilds_mut(union_id) {
+                        guild.union = None;
+                    }
+                    task_creator.spawn(disband_union(union_id, PgPool::clone(&pool)));
+                } else {
+                    notify_union(&guilds, union_id, &clients, UnionUpdate::GuildLeft { guild_id });
+                    for guild in guilds.union_guilds_mut(union_id) {
+                        if guild.id == guild_id {
+                            guild.union = None;
+                        } else if let Some(union) = &mut guild.union {
+                            union.guilds.remove(&guild_id);
+                        }
+                    }
+                    task_creator.spawn(set_guild_union(guild_id, None, PgPool::clone(&pool)));
+                }
+
+                announce_guild_members(&affected, &guilds, &members, &viewers);
+            },
+        }
+    }
+}
+
+pub(crate) fn answer_union_invitations(
+    query: Query<(Entity, &PlayerInput, &UnionInvitation)>,
+    players: Query<(&Client, &Player, Option<&InGuild>)>,
+    members: Query<(&GameEntity, &Visibility)>,
+    clients: Query<&Client>,
+    viewers: Query<(&Client, Option<&InGuild>)>,
+    mut guilds: ResMut<Guilds>,
+    task_creator: Res<TaskCreator>,
+    pool: Res<DbPool>,
+    mut cmd: Commands,
+) {
+    for (entity, input, invitation) in query.iter() {
+        let Some(ref response) = input.invitation else {
+            continue;
+        };
+
+        cmd.entity(entity).remove::<(UnionInvitation, PendingInvitation)>();
+        let Ok((requester_client, ..)) = players.get(invitation.requester) else {
+            continue;
+        };
+        let Ok((_, player, own_guild)) = players.get(entity) else {
+            continue;
+        };
+
+        if matches!(response, PlayerInvitationResponse::Decline(_)) {
+            requester_client.send(UnionInviteResponse::Failure(GuildError::Declined));
+            continue;
+        }
+
+        let Some(joining) = own_guild
+            .and_then(|own_guild| guilds.get(own_guild.0))
+            .filter(|joining| is_master(joining, player.character.id))
+        else {
+            requester_client.send(UnionInviteResponse::Failure(GuildError::InvalidTarget));
+            continue;
+        };
+
+        if joining.union.is_some() {
+            requester_client.send(UnionInviteResponse::Failure(GuildError::TargetInUnion));
+            continue;
+        }
+
+        let Some(leading) = guilds.get(invitation.guild_id) else {
+            requester_client.send(UnionInviteResponse::Failure(GuildError::NotInGuild));
+            continue;
+        };
+
+        if leading.union.is_some() && !leading.leads_union() {
+            requester_client.send(UnionInviteResponse::Failure(GuildError::NoPermission));
+            continue;
+        }
+
+        if leading.union.as_ref().is_some_and(GuildUnion::is_full) {
+            requester_client.send(UnionInviteResponse::Failure(GuildError::UnionFull));
+            continue;
+        }
+
+        let union_id = leading.id;
+        let joined = UnionGuildData {
+            id: joining.id,
+            name: joining.name.clone(),
+        };
+        let formed = leading.union.is_none();
+        if formed {
+            if let Some(leading) = guilds.get_mut(union_id) {
+                leading.union = Some(GuildUnion {
+                    id: union_id,
+                    guilds: BTreeMap::from([(union_id, leading.name.clone())]),
+                });
+            }
+            task_creator.spawn(set_guild_union(union_id, Some(union_id), PgPool::clone(&pool)));
+        } else {
+            notify_union(&guilds, union_id, &clients, UnionUpdate::GuildJoined(joined.clone()));
+        }
+
+        for guild in guilds.union_guilds_mut(union_id) {
+            if let Some(union) = &mut guild.union {
+                union.guilds.insert(joined.id, joined.name.clone());
+            }
+        }
+        let union = guilds.get(union_id).and_then(|leading| leading.union.clone());
+        if let Some(joining) = guilds.get_mut(joined.id) {
+            joining.union = union;
+        }
+        task_creator.spawn(set_guild_union(joined.id, Some(union_id), PgPool::clone(&pool)));
+        requester_client.send(UnionInviteResponse::Success);
+
+        let informed = if formed {
+            vec![union_id, joined.id]
+        } else {
+            vec![joined.id]
+        };
+        for guild in informed.into_iter().filter_map(|id| guilds.get(id)) {
+            if let Some(union) = &guild.union {
+                notify_members(guild, &clients, None, union.as_protocol());
+            }
+        }
+
+        let affected = guilds.union_guilds(union_id).map(|guild| guild.id).collect::<Vec<_>>();
+        announce_guild_members(&affected, &guilds, &members, &viewers);
+    }
+}
+
+fn is_master(guild: &Guild, character_id: u32) -> bool {
+    guild.members.get(&character_id).is_some_and(|member| member.master)
+}
+
+fn notify_union(guilds: &Guilds, union_id: u32, clients: &Query<&Client>, update: UnionUpdate) {
+    for guild in guilds.union_guilds(union_id) {
+        notify_members(guild, clients, None, update.clone());
+    }
+}
+
+/// Announces the guild of every online member of the given guilds again, as their standing towards
+/// other players changed with the union.
+fn announce_guild_members(
+    guild_ids: &[u32],
+    guilds: &Guilds,
+    members: &Query<(&GameEntity, &Visibility)>,
+    viewers: &Query<(&Client, Option<&InGuild>)>,
+) {
+    for guild in guild_ids.iter().filter_map(|id| guilds.get(*id)) {
+        for (game_entity, visibility) in members.iter_many(guild.online_members()) {
+            announce_guild(game_entity, visibility, Some(guild.id), guilds, viewers);
+        }
+    }
+}
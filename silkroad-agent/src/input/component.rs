@@ -1,14 +1,20 @@
 use bevy::prelude::*;
 use silkroad_game_base::StatType;
 use silkroad_protocol::auth::{AuthRequest, LogoutRequest};
-use silkroad_protocol::character::{CharacterJoinRequest, CharacterListRequestAction};
+use silkroad_protocol::character::{CharacterJoinRequest, CharacterListRequestAction, FinishLoading};
 use silkroad_protocol::chat::ChatClientProtocol;
 use silkroad_protocol::combat::PerformAction;
+use silkroad_protocol::community::{GuildClientProtocol, MemoClientProtocol, UnionClientProtocol};
+use silkroad_protocol::exchange::ExchangeClientProtocol;
 use silkroad_protocol::gm::GmCommand;
-use silkroad_protocol::inventory::InventoryOperation;
+use silkroad_protocol::inventory::{
+    DesignateRecallPoint, InventoryOperation, RepairItemRequest, TeleportUse, UseItemRequest,
+};
 use silkroad_protocol::movement::{MovementTarget, Rotation};
+use silkroad_protocol::party::PartyClientProtocol;
 use silkroad_protocol::skill::{HotbarItem, LearnSkill, LevelUpMastery};
-use silkroad_protocol::world::{TargetEntity, UnTargetEntity};
+use silkroad_protocol::stall::StallClientProtocol;
+use silkroad_protocol::world::{PlayerInvitationResponse, TargetEntity, TeleportReady, UnTargetEntity};
 use std::mem;
 
 #[derive(Component, Default)]
@@ -21,6 +27,19 @@ pub(crate) struct PlayerInput {
     pub movement: Option<MovementTarget>,
     pub rotation: Option<Rotation>,
     pub inventory: Option<InventoryOperation>,
+    pub repair: Option<RepairItemRequest>,
+    pub use_item: Option<UseItemRequest>,
+    pub recall_point: Option<DesignateRecallPoint>,
+    pub teleport: Option<TeleportUse>,
+    pub teleport_ready: Option<TeleportReady>,
+    pub teleport_loaded: Option<FinishLoading>,
+    pub exchange: Option<ExchangeClientProtocol>,
+    pub invitation: Option<PlayerInvitationResponse>,
+    pub stall: Option<StallClientProtocol>,
+    pub party: Option<PartyClientProtocol>,
+    pub guild: Option<GuildClientProtocol>,
+    pub union: Option<UnionClientProtocol>,
+    pub memo: Option<MemoClientProtocol>,
     pub gm: Option<GmCommand>,
     pub mastery: Option<LevelUpMastery>,
     pub skill_add: Option<LearnSkill>,
@@ -1,6 +1,9 @@
 use crate::comp::net::{Client, LastAction};
 use crate::config::GameConfig;
+use crate::consignment::event::{ConsignmentRequest, ConsignmentRequestEvent};
 use crate::event::{ClientDisconnectedEvent, LoadingFinishedEvent};
+use crate::friends::event::FriendListRequestEvent;
+use crate::game::teleport::Teleporting;
 use crate::input::{LoginInput, PlayerInput};
 use crate::mall::event::MallOpenRequestEvent;
 use crate::protocol::AgentClientProtocol;
@@ -11,7 +14,7 @@ use silkroad_protocol::character::CharselectClientProtocol;
 use silkroad_protocol::combat::CombatClientProtocol;
 use silkroad_protocol::general::{BaseProtocol, IdentityInformation};
 use silkroad_protocol::gm::GmClientProtocol;
-use silkroad_protocol::inventory::{ConsignmentResponse, InventoryClientProtocol};
+use silkroad_protocol::inventory::InventoryClientProtocol;
 use silkroad_protocol::movement::MovementClientProtocol;
 use silkroad_protocol::skill::SkillClientProtocol;
 use silkroad_protocol::world::{GameGuideResponse, StatClientProtocol, WorldClientProtocol};
@@ -34,6 +37,9 @@ pub(crate) fn receive_game_inputs(
     mut loading_events: EventWriter<LoadingFinishedEvent>,
     mut disconnect_events: EventWriter<ClientDisconnectedEvent>,
     mut mall_events: EventWriter<MallOpenRequestEvent>,
+    mut consignment_events: EventWriter<ConsignmentRequestEvent>,
+    mut friend_events: EventWriter<FriendListRequestEvent>,
+    teleporting: Query<(), With<Teleporting>>,
 ) {
     for (entity, client, mut input, mut last_action) in query.iter_mut() {
         let mut had_action = false;
@@ -53,7 +59,9 @@ pub(crate) fn receive_game_inputs(
                         AgentClientProtocol::MovementClientProtocol(MovementClientProtocol::Rotation(rotate)) => {
                             input.rotation = Some(rotate);
                         },
-                        AgentClientProtocol::FriendListClientProtocol(friend_list) => {},
+                        AgentClientProtocol::FriendListClientProtocol(friend_list) => {
+                            friend_events.send(FriendListRequestEvent(entity, friend_list));
+                        },
                         AgentClientProtocol::SkillClientProtocol(SkillClientProtocol::LearnSkill(skill)) => {
                             input.skill_add = Some(skill);
                         },
@@ -80,9 +88,22 @@ pub(crate) fn receive_game_inputs(
                             WorldClientProtocol::UpdateGameGuide(guide) => {
                                 client.send(GameGuideResponse::Success(guide.0));
                             },
+                            WorldClientProtocol::PlayerInvitationResponse(response) => {
+                                input.invitation = Some(response);
+                            },
+                            WorldClientProtocol::TeleportReady(ready) => {
+                                input.teleport_ready = Some(ready);
+                            },
                         },
-                        AgentClientProtocol::CharselectClientProtocol(CharselectClientProtocol::FinishLoading(_)) => {
-                            loading_events.send(LoadingFinishedEvent(entity));
+                        AgentClientProtocol::CharselectClientProtocol(CharselectClientProtocol::FinishLoading(
+                            finished,
+                        )) => {
+                            // Loading after a teleport shouldn't trigger everything that happens on login.
+                            if teleporting.contains(entity) {
+                                input.teleport_loaded = Some(finished);
+                            } else {
+                                loading_events.send(LoadingFinishedEvent(entity));
+                            }
                         },
                         AgentClientProtocol::InventoryClientProtocol(inventory) => match inventory {
                             InventoryClientProtocol::OpenItemMall(_) => {
@@ -92,8 +113,52 @@ pub(crate) fn receive_game_inputs(
                                 input.inventory = Some(inventory);
                             },
                             InventoryClientProtocol::ConsignmentList(_) => {
-                                client.send(ConsignmentResponse::success_empty());
+                                consignment_events.send(ConsignmentRequestEvent(entity, ConsignmentRequest::List));
+                            },
+                            InventoryClientProtocol::ConsignmentRegister(register) => {
+                                consignment_events
+                                    .send(ConsignmentRequestEvent(entity, ConsignmentRequest::Register(register)));
+                            },
+                            InventoryClientProtocol::ConsignmentSettle(_) => {
+                                consignment_events.send(ConsignmentRequestEvent(entity, ConsignmentRequest::Settle));
+                            },
+                            InventoryClientProtocol::ConsignmentBuy(buy) => {
+                                consignment_events.send(ConsignmentRequestEvent(entity, ConsignmentRequest::Buy(buy)));
+                            },
+                            InventoryClientProtocol::ConsignmentSearch(search) => {
+                                consignment_events
+                                    .send(ConsignmentRequestEvent(entity, ConsignmentRequest::Search(search)));
+                            },
+                            InventoryClientProtocol::RepairItemRequest(repair) => {
+                                input.repair = Some(repair);
                             },
+                            InventoryClientProtocol::UseItemRequest(use_item) => {
+                                input.use_item = Some(use_item);
+                            },
+                            InventoryClientProtocol::DesignateRecallPoint(recall) => {
+                                input.recall_point = Some(recall);
+                            },
+                            InventoryClientProtocol::TeleportUse(teleport) => {
+                                input.teleport = Some(teleport);
+                            },
+                        },
+                        AgentClientProtocol::ExchangeClientProtocol(exchange) => {
+                            input.exchange = Some(exchange);
+                        },
+                        AgentClientProtocol::StallClientProtocol(stall) => {
+                            input.stall = Some(stall);
+                        },
+                        AgentClientProtocol::PartyClientProtocol(party) => {
+                            input.party = Some(party);
+                        },
+                        AgentClientProtocol::GuildClientProtocol(guild) => {
+                            input.guild = Some(guild);
+                        },
+                        AgentClientProtocol::UnionClientProtocol(union) => {
+                            input.union = Some(union);
+                        },
+                        AgentClientProtocol::MemoClientProtocol(memo) => {
+                            input.memo = Some(memo);
                         },
                         AgentClientProtocol::AuthProtocol(AuthProtocol::LogoutRequest(logout)) => {
                             input.logout = Some(logout);
@@ -10,6 +10,7 @@ use tracing::{debug, instrument};
 pub struct DbCharacter {
     pub(crate) character_data: CharacterData,
     pub(crate) items: Vec<CharacterItem>,
+    pub(crate) avatar_items: Vec<CharacterItem>,
     pub(crate) masteries: Vec<CharacterMastery>,
     pub(crate) skills: Vec<CharacterSkill>,
     pub(crate) hotbar: Vec<HotbarEntry>,
@@ -25,6 +26,9 @@ impl DbCharacter {
         let mut character_items = CharacterItem::fetch_bulk_character_items(&character_ids, pool.borrow())
             .await
             .unwrap();
+        let mut character_avatar_items = CharacterItem::fetch_bulk_avatar_items(&character_ids, pool.borrow())
+            .await
+            .unwrap();
         let mut character_masteries = CharacterMastery::fetch_for_characters(&character_ids, pool.borrow())
             .await
             .unwrap()
@@ -46,6 +50,7 @@ impl DbCharacter {
 
         for character in characters {
             let items = character_items.remove(&character.id).unwrap_or_default();
+            let avatar_items = character_avatar_items.remove(&character.id).unwrap_or_default();
             let masteries = character_masteries.remove(&character.id).unwrap_or_default();
             let skills = character_skills.remove(&character.id).unwrap_or_default();
             let hotbar = hotbar_entries.remove(&character.id).unwrap_or_default();
@@ -53,6 +58,7 @@ impl DbCharacter {
             all_characters.push(DbCharacter {
                 character_data: character,
                 items,
+                avatar_items,
                 masteries,
                 skills,
                 hotbar,
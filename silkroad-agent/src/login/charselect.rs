@@ -1,6 +1,6 @@
 use crate::agent::component::Agent;
 use crate::comp::gold::GoldPouch;
-use crate::comp::inventory::PlayerInventory;
+use crate::comp::inventory::{item_content_data, rent_info, PlayerAvatarInventory, PlayerInventory};
 use crate::comp::net::Client;
 use crate::comp::player::{Player, PlayerBundle};
 use crate::comp::pos::Position;
@@ -24,15 +24,13 @@ use bevy::prelude::*;
 use cgmath::Vector3;
 use chrono::{TimeZone, Utc};
 use silkroad_data::DataEntry;
-use silkroad_game_base::{Heading, ItemTypeData, LocalPosition};
+use silkroad_game_base::{Heading, LocalPosition, DEFAULT_INVENTORY_SIZE};
 use silkroad_protocol::auth::{AuthResponse, AuthResult, AuthResultError, UnknownLargePacket};
 use silkroad_protocol::character::{
     CharacterJoinResponse, CharacterListAction, CharacterListContent, CharacterListError, CharacterListRequestAction,
     CharacterListResponse, CharacterListResult, MacroStatus, UnknownPacket, UnknownPacket2, MACRO_POTION,
 };
-use silkroad_protocol::inventory::{
-    BagContent, InventoryItemBindingData, InventoryItemContentData, InventoryItemData, RentInfo,
-};
+use silkroad_protocol::inventory::{BagContent, InventoryItemData};
 use silkroad_protocol::skill::{HotbarItem, MasteryData, SkillData};
 use silkroad_protocol::spawn::{CharacterSpawn, CharacterSpawnEnd, CharacterSpawnStart, JobInformation};
 use silkroad_protocol::world::{ActionState, AliveState, BodyState, EntityState};
@@ -173,7 +171,9 @@ pub(crate) fn handle_join(
                     }
 
                     let mut player = Player::from_db_data(playing.0.clone(), &character.character_data);
-                    let inventory = PlayerInventory::from_db(&character.items, 45);
+                    let inventory =
+                        PlayerInventory::from_db(&character.items, character.character_data.inventory_size as usize);
+                    let avatars = PlayerAvatarInventory::from_db(&character.avatar_items);
                     let gold = GoldPouch::new(character.character_data.gold as u64);
                     let hotbar = Hotbar::from_list(
                         &character
@@ -215,6 +215,7 @@ pub(crate) fn handle_join(
                         &game_entity,
                         &player,
                         &inventory,
+                        &avatars,
                         &position,
                         settings.max_level,
                         &hotbar,
@@ -233,6 +234,7 @@ pub(crate) fn handle_join(
                             player,
                             game_entity,
                             inventory,
+                            avatars,
                             gold,
                             agent,
                             position,
@@ -314,11 +316,12 @@ fn send_job_spread(client: &Client, hunters: u8, thieves: u8) {
     ));
 }
 
-fn send_spawn(
+pub(crate) fn send_spawn(
     client: &Client,
     entity: &GameEntity,
     player: &Player,
     inventory: &PlayerInventory,
+    avatars: &PlayerAvatarInventory,
     position: &Position,
     max_level: u8,
     hotbar: &Hotbar,
@@ -343,22 +346,19 @@ fn send_spawn(
         .items()
         .map(|(slot, item)| InventoryItemData {
             slot: *slot,
-            rent_data: RentInfo::Empty,
+            rent_data: rent_info(item),
             item_id: item.reference.ref_id(),
-            content_data: match item.type_data {
-                ItemTypeData::Equipment { upgrade_level } => InventoryItemContentData::Equipment {
-                    plus_level: upgrade_level,
-                    variance: item.variance.unwrap_or_default(),
-                    durability: 1,
-                    magic: vec![],
-                    bindings_1: InventoryItemBindingData::new(1, 0),
-                    bindings_2: InventoryItemBindingData::new(2, 0),
-                    bindings_3: InventoryItemBindingData::new(3, 0),
-                    bindings_4: InventoryItemBindingData::new(4, 0),
-                },
-                ItemTypeData::Consumable { amount } => InventoryItemContentData::Expendable { stack_size: amount },
-                _ => panic!("Missing inventory type representation."),
-            },
+            content_data: item_content_data(item),
+        })
+        .collect();
+
+    let avatar_items = avatars
+        .items()
+        .map(|(slot, item)| InventoryItemData {
+            slot: *slot,
+            rent_data: rent_info(item),
+            item_id: item.reference.ref_id(),
+            content_data: item_content_data(item),
         })
         .collect();
 
@@ -389,7 +389,7 @@ fn send_spawn(
         0,
         max_level,
         BagContent::new(inventory.size() as u8, inventory_items),
-        BagContent::new(5, Vec::new()),
+        BagContent::new(avatars.size() as u8, avatar_items),
         player
             .character
             .masteries
@@ -499,6 +499,8 @@ pub(crate) fn create_character_from(
         beginner_mark: true,
         gm: false,
         last_logout: None,
+        recall_point: None,
+        inventory_size: DEFAULT_INVENTORY_SIZE as i16,
         race: if ref_id > 2000 {
             DbRace::European
         } else {
@@ -513,6 +515,12 @@ pub(crate) fn create_character_from(
             item_obj_id: chest as i32,
             upgrade_level: 0,
             variance: None,
+            magic_params: Vec::new(),
+            durability: None,
+            max_durability: None,
+            rent_type: None,
+            rent_start: None,
+            rent_end: None,
             slot: 1,
             amount: 1,
         },
@@ -522,6 +530,12 @@ pub(crate) fn create_character_from(
             item_obj_id: pants as i32,
             upgrade_level: 0,
             variance: None,
+            magic_params: Vec::new(),
+            durability: None,
+            max_durability: None,
+            rent_type: None,
+            rent_start: None,
+            rent_end: None,
             slot: 4,
             amount: 1,
         },
@@ -531,6 +545,12 @@ pub(crate) fn create_character_from(
             item_obj_id: boots as i32,
             upgrade_level: 0,
             variance: None,
+            magic_params: Vec::new(),
+            durability: None,
+            max_durability: None,
+            rent_type: None,
+            rent_start: None,
+            rent_end: None,
             slot: 5,
             amount: 1,
         },
@@ -540,6 +560,12 @@ pub(crate) fn create_character_from(
             item_obj_id: weapon as i32,
             upgrade_level: 0,
             variance: None,
+            magic_params: Vec::new(),
+            durability: None,
+            max_durability: None,
+            rent_type: None,
+            rent_start: None,
+            rent_end: None,
             slot: 6,
             amount: 1,
         },
@@ -548,6 +574,7 @@ pub(crate) fn create_character_from(
     DbCharacter {
         character_data: character,
         items,
+        avatar_items: vec![],
         masteries: vec![],
         skills: vec![],
         hotbar: vec![], // TODO fill with default actions
@@ -8,8 +8,8 @@ use crate::login::{
 use bevy::prelude::*;
 use chrono::Utc;
 use silkroad_protocol::character::{
-    CharacterListAction, CharacterListContent, CharacterListEntry, CharacterListEquippedItem, CharacterListError,
-    CharacterListResponse, CharacterListResult, TimeInformation,
+    CharacterListAction, CharacterListAvatarItem, CharacterListContent, CharacterListEntry, CharacterListEquippedItem,
+    CharacterListError, CharacterListResponse, CharacterListResult, TimeInformation,
 };
 use silkroad_protocol::SilkroadTime;
 use tokio::sync::oneshot::error::TryRecvError;
@@ -76,7 +76,11 @@ fn from_character(character: &DbCharacter) -> CharacterListEntry {
             .filter(|item| item.slot < 13)
             .map(from_item)
             .collect(),
-        avatar_items: Vec::new(),
+        avatar_items: character
+            .avatar_items
+            .iter()
+            .map(|item| CharacterListAvatarItem::new(item.item_obj_id as u32))
+            .collect(),
     }
 }
 
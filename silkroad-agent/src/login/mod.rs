@@ -8,7 +8,7 @@ use crate::LoginQueue;
 use bevy::prelude::*;
 
 pub mod character_loader;
-mod charselect;
+pub(crate) mod charselect;
 mod components;
 pub mod job_distribution;
 mod jobs;
@@ -5,14 +5,19 @@ mod chat;
 mod cmd;
 mod comp;
 mod config;
+mod consignment;
 mod db;
 mod event;
 mod ext;
+mod friends;
 mod game;
+mod guild;
 mod input;
 mod login;
 mod mall;
+mod memo;
 mod net;
+mod party;
 mod persistence;
 mod population;
 mod protocol;
@@ -24,13 +29,18 @@ mod world;
 use crate::agent::AgentPlugin;
 use crate::cmd::CommandPlugin;
 use crate::config::get_config;
+use crate::consignment::ConsignmentPlugin;
 use crate::db::server::ServerRegistration;
 use crate::ext::DbPool;
+use crate::friends::FriendsPlugin;
 use crate::game::GamePlugin;
+use crate::guild::GuildPlugin;
 use crate::input::ReceivePlugin;
 use crate::login::LoginPlugin;
 use crate::mall::MallPlugin;
+use crate::memo::MemoPlugin;
 use crate::net::NetworkPlugin;
+use crate::party::PartyPlugin;
 use crate::persistence::PersistencePlugin;
 use crate::population::{CapacityController, LoginQueue};
 use crate::server_plugin::ServerPlugin;
@@ -117,6 +127,11 @@ fn main() {
         .add_plugins(LoginPlugin::new(queue))
         .add_plugins(GamePlugin)
         .add_plugins(MallPlugin)
+        .add_plugins(ConsignmentPlugin)
+        .add_plugins(FriendsPlugin)
+        .add_plugins(MemoPlugin)
+        .add_plugins(PartyPlugin)
+        .add_plugins(GuildPlugin)
         .add_plugins(CommandPlugin)
         .run();
 }
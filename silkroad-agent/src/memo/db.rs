@@ -0,0 +1,145 @@
+use crate::comp::gold::GoldChange;
+use crate::persistence::ApplyInTransaction;
+use chrono::{DateTime, Utc};
+use silkroad_protocol::community::MemoError;
+use sqlx::{Error, PgPool};
+use tracing::error;
+
+pub(crate) struct MemoRow {
+    pub id: i32,
+    pub sender_name: String,
+    pub gold: i64,
+    pub read: bool,
+    pub sent_at: DateTime<Utc>,
+}
+
+pub(crate) struct ReadMemoRow {
+    pub id: i32,
+    pub sender_name: String,
+    pub message: String,
+    pub gold: i64,
+    pub sent_at: DateTime<Utc>,
+}
+
+pub(crate) struct SentMemo {
+    pub id: i32,
+    pub recipient_id: i32,
+    pub sent_at: DateTime<Utc>,
+}
+
+pub(crate) async fn load_memos(character_id: u32, pool: PgPool) -> Result<Vec<MemoRow>, Error> {
+    sqlx::query_as!(
+        MemoRow,
+        "SELECT id, sender_name, gold, read, sent_at FROM memos WHERE recipient_id = $1 ORDER BY sent_at",
+        character_id as i32
+    )
+    .fetch_all(&pool)
+    .await
+}
+
+pub(crate) struct NewMemo {
+    pub sender_id: u32,
+    pub sender_name: String,
+    pub recipient: String,
+    pub message: String,
+    pub gold: u64,
+    /// The gold the sender has left after paying for the memo.
+    pub sender_gold: u64,
+}
+
+/// Sends the memo to the character with the given name, unless their inbox is already full. The
+/// gold of the sender is stored together with the memo.
+pub(crate) async fn send_memo(
+    memo: NewMemo,
+    server_id: u16,
+    inbox_size: usize,
+    pool: PgPool,
+) -> Result<Result<SentMemo, MemoError>, Error> {
+    let mut transaction = pool.begin().await?;
+    let Some(found) = sqlx::query!(
+        "SELECT c.id, (SELECT COUNT(*) FROM memos m WHERE m.recipient_id = c.id) AS \"memos!\" FROM characters c WHERE c.charname = $1 AND c.server_id = $2",
+        memo.recipient,
+        server_id as i32,
+    )
+    .fetch_optional(&mut *transaction)
+    .await?
+    else {
+        return Ok(Err(MemoError::CharacterNotFound));
+    };
+
+    if found.memos as usize >= inbox_size {
+        return Ok(Err(MemoError::InboxFull));
+    }
+
+    let sent = sqlx::query!(
+        "INSERT INTO memos(recipient_id, sender_id, sender_name, message, gold) VALUES($1, $2, $3, $4, $5) RETURNING id, sent_at",
+        found.id,
+        memo.sender_id as i32,
+        memo.sender_name,
+        memo.message,
+        memo.gold as i64,
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+
+    GoldChange(memo.sender_gold)
+        .apply_in(memo.sender_id, &mut transaction)
+        .await?;
+
+    transaction.commit().await?;
+    Ok(Ok(SentMemo {
+        id: sent.id,
+        recipient_id: found.id,
+        sent_at: sent.sent_at,
+    }))
+}
+
+/// Marks the memo as read and removes the attached gold from it, such that it can only ever be
+/// claimed once. The attached gold is added to the given gold of the recipient in the same
+/// transaction. The returned memo contains the gold that was attached before.
+pub(crate) async fn claim_memo(
+    id: u32,
+    recipient_id: u32,
+    gold: u64,
+    pool: PgPool,
+) -> Result<Option<ReadMemoRow>, Error> {
+    let mut transaction = pool.begin().await?;
+    let Some(memo) = sqlx::query_as!(
+        ReadMemoRow,
+        "SELECT id, sender_name, message, gold, sent_at FROM memos WHERE id = $1 AND recipient_id = $2 FOR UPDATE",
+        id as i32,
+        recipient_id as i32,
+    )
+    .fetch_optional(&mut *transaction)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    sqlx::query!("UPDATE memos SET read = TRUE, gold = 0 WHERE id = $1", id as i32)
+        .execute(&mut *transaction)
+        .await?;
+
+    if memo.gold > 0 {
+        GoldChange(gold.saturating_add(memo.gold as u64))
+            .apply_in(recipient_id, &mut transaction)
+            .await?;
+    }
+
+    transaction.commit().await?;
+    Ok(Some(memo))
+}
+
+pub(crate) async fn delete_memo(id: u32, recipient_id: u32, pool: PgPool) {
+    let result = sqlx::query!(
+        "DELETE FROM memos WHERE id = $1 AND recipient_id = $2",
+        id as i32,
+        recipient_id as i32,
+    )
+    .execute(&pool)
+    .await;
+
+    if let Err(e) = result {
+        error!(error = %e, id, recipient_id, "Could not delete memo.");
+    }
+}
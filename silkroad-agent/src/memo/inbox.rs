@@ -0,0 +1,77 @@
+use crate::memo::db::MemoRow;
+use bevy::prelude::*;
+use chrono::{DateTime, Utc};
+use silkroad_protocol::community::{MemoListInfo, MemoSummary};
+use silkroad_protocol::SilkroadTime;
+use std::collections::BTreeMap;
+
+pub(crate) struct Memo {
+    pub(crate) sender: String,
+    pub(crate) gold: u64,
+    pub(crate) read: bool,
+    pub(crate) sent_at: DateTime<Utc>,
+}
+
+/// The memos other characters have sent to the player, keyed by their id. Only a summary of each
+/// memo is kept, the message itself is loaded when the memo is read.
+#[derive(Component, Default)]
+pub(crate) struct Inbox(BTreeMap<u32, Memo>);
+
+impl Inbox {
+    pub(crate) fn from_db(memos: Vec<MemoRow>) -> Self {
+        Inbox(
+            memos
+                .into_iter()
+                .map(|memo| {
+                    let entry = Memo {
+                        sender: memo.sender_name,
+                        gold: memo.gold as u64,
+                        read: memo.read,
+                        sent_at: memo.sent_at,
+                    };
+                    (memo.id as u32, entry)
+                })
+                .collect(),
+        )
+    }
+
+    pub(crate) fn get(&self, id: u32) -> Option<&Memo> {
+        self.0.get(&id)
+    }
+
+    pub(crate) fn add(&mut self, id: u32, memo: Memo) {
+        self.0.insert(id, memo);
+    }
+
+    pub(crate) fn remove(&mut self, id: u32) -> Option<Memo> {
+        self.0.remove(&id)
+    }
+
+    /// Marks the memo as read, with any attached gold having been handed over.
+    pub(crate) fn mark_read(&mut self, id: u32) {
+        if let Some(memo) = self.0.get_mut(&id) {
+            memo.read = true;
+            memo.gold = 0;
+        }
+    }
+
+    pub(crate) fn unread(&self) -> u16 {
+        self.0.values().filter(|memo| !memo.read).count() as u16
+    }
+
+    pub(crate) fn as_protocol(&self) -> MemoListInfo {
+        MemoListInfo {
+            memos: self
+                .0
+                .iter()
+                .map(|(id, memo)| MemoSummary {
+                    id: *id,
+                    sender: memo.sender.clone(),
+                    gold: memo.gold,
+                    read: memo.read,
+                    sent_at: SilkroadTime::from(memo.sent_at),
+                })
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,23 @@
+use crate::memo::system::{finish_loading_inboxes, finish_memo_operations, handle_memo_requests, load_inboxes};
+use bevy::prelude::*;
+
+mod db;
+mod inbox;
+mod system;
+
+pub(crate) struct MemoPlugin;
+
+impl Plugin for MemoPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                load_inboxes,
+                finish_loading_inboxes,
+                handle_memo_requests,
+                finish_memo_operations,
+            )
+                .chain(),
+        );
+    }
+}
@@ -0,0 +1,278 @@
+use crate::comp::gold::GoldPouch;
+use crate::comp::net::Client;
+use crate::comp::player::Player;
+use crate::config::GameConfig;
+use crate::event::LoadingFinishedEvent;
+use crate::ext::DbPool;
+use crate::friends::list::OnlineCharacters;
+use crate::input::PlayerInput;
+use crate::memo::db::{claim_memo, delete_memo, load_memos, send_memo, MemoRow, NewMemo, ReadMemoRow, SentMemo};
+use crate::memo::inbox::{Inbox, Memo};
+use crate::server_plugin::ServerId;
+use crate::tasks::TaskCreator;
+use bevy::prelude::*;
+use silkroad_protocol::community::{
+    DeleteMemoResponse, MemoClientProtocol, MemoError, MemoNotification, ReadMemoResponse, SendMemoResponse,
+};
+use silkroad_protocol::SilkroadTime;
+use sqlx::{Error, PgPool};
+use tokio::sync::oneshot::error::TryRecvError;
+use tokio::sync::oneshot::Receiver;
+use tracing::error;
+
+const MAX_MEMO_LENGTH: usize = 200;
+
+#[derive(Component)]
+pub(crate) struct InboxLoading(Receiver<Result<Vec<MemoRow>, Error>>);
+
+/// A memo operation of the player that is waiting for the database. Only a single operation may be
+/// pending at a time.
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+pub(crate) enum PendingMemo {
+    /// The memo is being sent, with the cost having already been taken from the sender, such that it
+    /// can be refunded if sending fails.
+    Sending {
+        receiver: Receiver<Result<Result<SentMemo, MemoError>, Error>>,
+        cost: u64,
+        fee: u64,
+        gold: u64,
+    },
+    Reading(Receiver<Result<Option<ReadMemoRow>, Error>>),
+}
+
+pub(crate) fn load_inboxes(
+    mut reader: EventReader<LoadingFinishedEvent>,
+    query: Query<&Player>,
+    task_creator: Res<TaskCreator>,
+    pool: Res<DbPool>,
+    mut cmd: Commands,
+) {
+    for LoadingFinishedEvent(entity) in reader.read() {
+        let Ok(player) = query.get(*entity) else {
+            continue;
+        };
+
+        let receiver = task_creator.create_task(load_memos(player.character.id, PgPool::clone(&pool)));
+        cmd.entity(*entity).try_insert(InboxLoading(receiver));
+    }
+}
+
+/// Sends the memos to the player once they have been loaded, letting them know if any of them are
+/// still unread.
+pub(crate) fn finish_loading_inboxes(
+    mut query: Query<(Entity, &Client, &Player, &mut InboxLoading)>,
+    mut cmd: Commands,
+) {
+    for (entity, client, player, mut loading) in query.iter_mut() {
+        let inbox = match loading.0.try_recv() {
+            Ok(Ok(memos)) => Inbox::from_db(memos),
+            Ok(Err(e)) => {
+                error!(error = %e, id = player.character.id, "Could not load memos.");
+                Inbox::default()
+            },
+            Err(TryRecvError::Empty) => continue,
+            Err(TryRecvError::Closed) => Inbox::default(),
+        };
+
+        client.send(inbox.as_protocol());
+        let unread = inbox.unread();
+        if unread > 0 {
+            client.send(MemoNotification { unread });
+        }
+        cmd.entity(entity).remove::<InboxLoading>().insert(inbox);
+    }
+}
+
+pub(crate) fn handle_memo_requests(
+    mut query: Query<(
+        Entity,
+        &Client,
+        &Player,
+        &PlayerInput,
+        &mut GoldPouch,
+        &mut Inbox,
+        Option<&PendingMemo>,
+    )>,
+    config: Res<GameConfig>,
+    server_id: Res<ServerId>,
+    task_creator: Res<TaskCreator>,
+    pool: Res<DbPool>,
+    mut cmd: Commands,
+) {
+    for (entity, client, player, input, mut gold, mut inbox, pending) in query.iter_mut() {
+        let Some(ref request) = input.memo else {
+            continue;
+        };
+
+        match request {
+            MemoClientProtocol::ListMemos(_) => {
+                client.send(inbox.as_protocol());
+            },
+            MemoClientProtocol::SendMemo(send) => {
+                if pending.is_some() {
+                    client.send(SendMemoResponse::Failure(MemoError::Busy));
+                    continue;
+                }
+
+                let recipient = send.recipient.trim();
+                if recipient.is_empty() || recipient.eq_ignore_ascii_case(&player.character.name) {
+                    client.send(SendMemoResponse::Failure(MemoError::CharacterNotFound));
+                    continue;
+                }
+
+                if send.message.is_empty() || send.message.chars().count() > MAX_MEMO_LENGTH {
+                    client.send(SendMemoResponse::Failure(MemoError::InvalidMessage));
+                    continue;
+                }
+
+                let fee = send.gold.saturating_mul(config.memo.gold_fee) / 100;
+                let Some(cost) = send.gold.checked_add(fee).filter(|cost| *cost <= gold.amount()) else {
+                    client.send(SendMemoResponse::Failure(MemoError::NotEnoughGold));
+                    continue;
+                };
+
+                // The cost is taken right away, such that the gold cannot be spent again while the
+                // memo is being sent.
+                gold.spend(cost);
+                let memo = NewMemo {
+                    sender_id: player.character.id,
+                    sender_name: player.character.name.clone(),
+                    recipient: recipient.to_string(),
+                    message: send.message.clone(),
+                    gold: send.gold,
+                    sender_gold: gold.amount(),
+                };
+                let receiver = task_creator.create_task(send_memo(
+                    memo,
+                    server_id.0,
+                    config.memo.inbox_size,
+                    PgPool::clone(&pool),
+                ));
+                cmd.entity(entity).try_insert(PendingMemo::Sending {
+                    receiver,
+                    cost,
+                    fee,
+                    gold: send.gold,
+                });
+            },
+            MemoClientProtocol::ReadMemo(read) => {
+                if pending.is_some() {
+                    client.send(ReadMemoResponse::Failure(MemoError::Busy));
+                    continue;
+                }
+
+                if inbox.get(read.id).is_none() {
+                    client.send(ReadMemoResponse::Failure(MemoError::MemoNotFound));
+                    continue;
+                }
+
+                let receiver = task_creator.create_task(claim_memo(
+                    read.id,
+                    player.character.id,
+                    gold.amount(),
+                    PgPool::clone(&pool),
+                ));
+                cmd.entity(entity).try_insert(PendingMemo::Reading(receiver));
+            },
+            MemoClientProtocol::DeleteMemo(delete) => {
+                let Some(memo) = inbox.get(delete.id) else {
+                    client.send(DeleteMemoResponse::Failure(MemoError::MemoNotFound));
+                    continue;
+                };
+
+                if memo.gold > 0 {
+                    client.send(DeleteMemoResponse::Failure(MemoError::GoldNotClaimed));
+                    continue;
+                }
+
+                inbox.remove(delete.id);
+                client.send(DeleteMemoResponse::Success { id: delete.id });
+                task_creator.spawn(delete_memo(delete.id, player.character.id, PgPool::clone(&pool)));
+            },
+        }
+    }
+}
+
+pub(crate) fn finish_memo_operations(
+    mut query: Query<(Entity, &Client, &Player, &mut GoldPouch, &mut PendingMemo)>,
+    mut inboxes: Query<(&Client, &mut Inbox)>,
+    online: Res<OnlineCharacters>,
+    mut cmd: Commands,
+) {
+    for (entity, client, player, mut gold, mut pending) in query.iter_mut() {
+        match pending.as_mut() {
+            PendingMemo::Sending {
+                receiver,
+                cost,
+                fee,
+                gold: attached,
+            } => {
+                let result = match receiver.try_recv() {
+                    Ok(Ok(result)) => result,
+                    Ok(Err(e)) => {
+                        error!(error = %e, id = player.character.id, "Could not send memo.");
+                        Err(MemoError::CharacterNotFound)
+                    },
+                    Err(TryRecvError::Empty) => continue,
+                    Err(TryRecvError::Closed) => Err(MemoError::CharacterNotFound),
+                };
+
+                match result {
+                    Ok(sent) => {
+                        client.send(SendMemoResponse::Success { fee: *fee });
+                        let recipient = online
+                            .get(sent.recipient_id as u32)
+                            .and_then(|recipient| inboxes.get_mut(recipient).ok());
+                        if let Some((recipient_client, mut inbox)) = recipient {
+                            inbox.add(
+                                sent.id as u32,
+                                Memo {
+                                    sender: player.character.name.clone(),
+                                    gold: *attached,
+                                    read: false,
+                                    sent_at: sent.sent_at,
+                                },
+                            );
+                            recipient_client.send(inbox.as_protocol());
+                            recipient_client.send(MemoNotification { unread: inbox.unread() });
+                        }
+                    },
+                    Err(error) => {
+                        gold.gain(*cost);
+                        client.send(SendMemoResponse::Failure(error));
+                    },
+                }
+            },
+            PendingMemo::Reading(receiver) => match receiver.try_recv() {
+                Ok(Ok(Some(memo))) => {
+                    let id = memo.id as u32;
+                    let claimed = memo.gold as u64;
+                    gold.gain(claimed);
+                    if let Ok((_, mut inbox)) = inboxes.get_mut(entity) {
+                        inbox.mark_read(id);
+                    }
+                    client.send(ReadMemoResponse::Success {
+                        id,
+                        sender: memo.sender_name,
+                        message: memo.message,
+                        gold: claimed,
+                        sent_at: SilkroadTime::from(memo.sent_at),
+                    });
+                },
+                Ok(Ok(None)) => {
+                    client.send(ReadMemoResponse::Failure(MemoError::MemoNotFound));
+                },
+                Ok(Err(e)) => {
+                    error!(error = %e, id = player.character.id, "Could not read memo.");
+                    client.send(ReadMemoResponse::Failure(MemoError::MemoNotFound));
+                },
+                Err(TryRecvError::Empty) => continue,
+                Err(TryRecvError::Closed) => {
+                    client.send(ReadMemoResponse::Failure(MemoError::MemoNotFound));
+                },
+            },
+        }
+        cmd.entity(entity).remove::<PendingMemo>();
+    }
+}
@@ -0,0 +1,22 @@
+use crate::party::parties::Parties;
+use crate::party::system::{answer_party_invitations, handle_party_requests, remove_missing_party_members};
+use bevy::prelude::*;
+
+pub(crate) mod parties;
+mod system;
+
+pub(crate) struct PartyPlugin;
+
+impl Plugin for PartyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Parties>().add_systems(
+            Update,
+            (
+                handle_party_requests,
+                answer_party_invitations,
+                remove_missing_party_members,
+            )
+                .chain(),
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use crate::comp::EntityReference;
+use bevy::prelude::*;
+use silkroad_protocol::party::PartyType;
+use std::collections::BTreeMap;
+
+pub(crate) const MAX_PARTY_MEMBERS: usize = 8;
+
+/// The maximum squared distance to the drop a party member may be at to be able to receive a
+/// share of the items.
+pub(crate) const ITEM_SHARE_RANGE_SQUARED: f32 = 1000.0 * 1000.0;
+
+/// Marks a player as being a member of the party with the given id.
+#[derive(Component, Copy, Clone)]
+pub(crate) struct InParty(pub(crate) u32);
+
+/// The listing of a party in the party matching, which allows other players to ask to join it.
+pub(crate) struct PartyMatching {
+    pub(crate) min_level: u8,
+    pub(crate) max_level: u8,
+    pub(crate) title: String,
+}
+
+impl PartyMatching {
+    pub(crate) fn accepts_level(&self, level: u8) -> bool {
+        (self.min_level..=self.max_level).contains(&level)
+    }
+}
+
+pub(crate) struct Party {
+    pub(crate) id: u32,
+    pub(crate) leader: EntityReference,
+    pub(crate) members: Vec<EntityReference>,
+    pub(crate) party_type: PartyType,
+    pub(crate) matching: Option<PartyMatching>,
+}
+
+impl Party {
+    pub(crate) fn is_full(&self) -> bool {
+        self.members.len() >= MAX_PARTY_MEMBERS
+    }
+
+    pub(crate) fn is_leader(&self, entity: Entity) -> bool {
+        self.leader.0 == entity
+    }
+
+    pub(crate) fn member(&self, unique_id: u32) -> Option<EntityReference> {
+        self.members
+            .iter()
+            .find(|member| member.1.unique_id == unique_id)
+            .copied()
+    }
+
+    /// Removes the member from the party. If the member was the leader, the member that joined the
+    /// earliest becomes the new leader, which is returned.
+    pub(crate) fn remove_member(&mut self, entity: Entity) -> Option<EntityReference> {
+        self.members.retain(|member| member.0 != entity);
+        if self.leader.0 != entity {
+            return None;
+        }
+
+        let new_leader = self.members.first().copied()?;
+        self.leader = new_leader;
+        Some(new_leader)
+    }
+}
+
+/// All parties that currently exist, keyed by their id.
+#[derive(Resource, Default)]
+pub(crate) struct Parties {
+    next_id: u32,
+    parties: BTreeMap<u32, Party>,
+}
+
+impl Parties {
+    pub(crate) fn create(&mut self, leader: EntityReference, member: EntityReference, party_type: PartyType) -> &Party {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.parties.entry(id).or_insert(Party {
+            id,
+            leader,
+            members: vec![leader, member],
+            party_type,
+            matching: None,
+        })
+    }
+
+    pub(crate) fn get(&self, id: u32) -> Option<&Party> {
+        self.parties.get(&id)
+    }
+
+    pub(crate) fn get_mut(&mut self, id: u32) -> Option<&mut Party> {
+        self.parties.get_mut(&id)
+    }
+
+    pub(crate) fn remove(&mut self, id: u32) -> Option<Party> {
+        self.parties.remove(&id)
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Party> {
+        self.parties.values()
+    }
+
+    /// All parties that are currently listed in the party matching, ordered by their id.
+    pub(crate) fn listed(&self) -> impl Iterator<Item = (&Party, &PartyMatching)> {
+        self.parties
+            .values()
+            .filter_map(|party| party.matching.as_ref().map(|matching| (party, matching)))
+    }
+}
@@ -0,0 +1,551 @@
+use crate::comp::exp::Leveled;
+use crate::comp::invitation::PendingInvitation;
+use crate::comp::net::Client;
+use crate::comp::player::Player;
+use crate::comp::{EntityReference, GameEntity};
+use crate::friends::block::is_blocked_by;
+use crate::friends::list::BlockList;
+use crate::input::PlayerInput;
+use crate::party::parties::{InParty, Parties, Party, PartyMatching};
+use crate::world::EntityLookup;
+use bevy::ecs::entity::Entities;
+use bevy::prelude::*;
+use silkroad_protocol::party::{
+    PartyClientProtocol, PartyCreateResponse, PartyError, PartyInfo, PartyInviteResponse, PartyKickResponse,
+    PartyLeaderTransferResponse, PartyLeaveResponse, PartyMatchingDeleteResponse, PartyMatchingEntry,
+    PartyMatchingFormResponse, PartyMatchingJoinResponse, PartyMatchingListResponse, PartyMember, PartyType,
+    PartyUpdate,
+};
+use silkroad_protocol::world::{InvitationKind, PlayerInvitation, PlayerInvitationResponse};
+
+const MATCHING_PAGE_SIZE: usize = 10;
+const MAX_MATCHING_TITLE_LENGTH: usize = 64;
+
+#[derive(Copy, Clone)]
+enum PartyInvitationKind {
+    Creation(PartyType),
+    Invitation(u32),
+    JoinRequest(u32),
+}
+
+/// An invitation to form or join a party, which the invited player has yet to accept or decline.
+/// For requests to join through the party matching, the invited player is the leader of the party.
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+pub(crate) struct PartyInvitation {
+    requester: Entity,
+    kind: PartyInvitationKind,
+}
+
+pub(crate) fn handle_party_requests(
+    query: Query<(Entity, &Client, &GameEntity, &Leveled, &PlayerInput, Option<&InParty>)>,
+    members: Query<(&Client, &GameEntity, &Player, &Leveled, Option<&InParty>)>,
+    pending: Query<(), With<PendingInvitation>>,
+    blocks: Query<&BlockList>,
+    lookup: Res<EntityLookup>,
+    mut parties: ResMut<Parties>,
+    mut cmd: Commands,
+) {
+    for (entity, client, game_entity, level, input, in_party) in query.iter() {
+        let Some(ref request) = input.party else {
+            continue;
+        };
+        let Ok((_, _, player, ..)) = members.get(entity) else {
+            continue;
+        };
+        let char_id = player.character.id;
+
+        match request {
+            PartyClientProtocol::PartyCreateRequest(create) => {
+                if in_party.is_some() {
+                    client.send(PartyCreateResponse::Failure(PartyError::AlreadyInParty));
+                    continue;
+                }
+
+                let Some((target, (target_client, _, _, _, target_party))) = lookup
+                    .get_entity_for_id(create.target)
+                    .filter(|target| *target != entity)
+                    .and_then(|target| members.get(target).ok().map(|found| (target, found)))
+                else {
+                    client.send(PartyCreateResponse::Failure(PartyError::InvalidTarget));
+                    continue;
+                };
+
+                if target_party.is_some() {
+                    client.send(PartyCreateResponse::Failure(PartyError::TargetInParty));
+                    continue;
+                }
+
+                if pending.contains(target) {
+                    client.send(PartyCreateResponse::Failure(PartyError::Busy));
+                    continue;
+                }
+
+                if is_blocked_by(&blocks, target, char_id) {
+                    continue;
+                }
+
+                cmd.entity(target).try_insert((
+                    PartyInvitation {
+                        requester: entity,
+                        kind: PartyInvitationKind::Creation(create.party_type),
+                    },
+                    PendingInvitation,
+                ));
+                target_client.send(PlayerInvitation::party(
+                    InvitationKind::PartyCreation,
+                    game_entity.unique_id,
+                ));
+            },
+            PartyClientProtocol::PartyInvite(invite) => {
+                let Some(party) = in_party.and_then(|in_party| parties.get(in_party.0)) else {
+                    client.send(PartyInviteResponse::Failure(PartyError::NotInParty));
+                    continue;
+                };
+
+                if !party.is_leader(entity) {
+                    client.send(PartyInviteResponse::Failure(PartyError::NotLeader));
+                    continue;
+                }
+
+                if party.is_full() {
+                    client.send(PartyInviteResponse::Failure(PartyError::PartyFull));
+                    continue;
+                }
+
+                let Some((target, (target_client, _, _, _, target_party))) = lookup
+                    .get_entity_for_id(invite.target)
+                    .filter(|target| *target != entity)
+                    .and_then(|target| members.get(target).ok().map(|found| (target, found)))
+                else {
+                    client.send(PartyInviteResponse::Failure(PartyError::InvalidTarget));
+                    continue;
+                };
+
+                if target_party.is_some() {
+                    client.send(PartyInviteResponse::Failure(PartyError::TargetInParty));
+                    continue;
+                }
+
+                if pending.contains(target) {
+                    client.send(PartyInviteResponse::Failure(PartyError::Busy));
+                    continue;
+                }
+
+                if is_blocked_by(&blocks, target, char_id) {
+                    continue;
+                }
+
+                cmd.entity(target).try_insert((
+                    PartyInvitation {
+                        requester: entity,
+                        kind: PartyInvitationKind::Invitation(party.id),
+                    },
+                    PendingInvitation,
+                ));
+                target_client.send(PlayerInvitation::party(
+                    InvitationKind::PartyInvitation,
+                    game_entity.unique_id,
+                ));
+            },
+            PartyClientProtocol::PartyLeave(_) => {
+                let Some(InParty(party_id)) = in_party.copied() else {
+                    client.send(PartyLeaveResponse::Failure(PartyError::NotInParty));
+                    continue;
+                };
+
+                client.send(PartyLeaveResponse::Success);
+                leave_party(&mut parties, party_id, entity, false, &members, &mut cmd);
+            },
+            PartyClientProtocol::PartyKick(kick) => {
+                let Some(party) = in_party.and_then(|in_party| parties.get(in_party.0)) else {
+                    client.send(PartyKickResponse::Failure(PartyError::NotInParty));
+                    continue;
+                };
+
+                if !party.is_leader(entity) {
+                    client.send(PartyKickResponse::Failure(PartyError::NotLeader));
+                    continue;
+                }
+
+                let Some(member) = party.member(kick.member).filter(|member| member.0 != entity) else {
+                    client.send(PartyKickResponse::Failure(PartyError::InvalidTarget));
+                    continue;
+                };
+
+                client.send(PartyKickResponse::Success);
+                let party_id = party.id;
+                leave_party(&mut parties, party_id, member.0, true, &members, &mut cmd);
+            },
+            PartyClientProtocol::PartyLeaderTransfer(transfer) => {
+                let Some(party) = in_party.and_then(|in_party| parties.get_mut(in_party.0)) else {
+                    client.send(PartyLeaderTransferResponse::Failure(PartyError::NotInParty));
+                    continue;
+                };
+
+                if !party.is_leader(entity) {
+                    client.send(PartyLeaderTransferResponse::Failure(PartyError::NotLeader));
+                    continue;
+                }
+
+                let Some(member) = party.member(transfer.member).filter(|member| member.0 != entity) else {
+                    client.send(PartyLeaderTransferResponse::Failure(PartyError::InvalidTarget));
+                    continue;
+                };
+
+                party.leader = member;
+                client.send(PartyLeaderTransferResponse::Success);
+                notify_members(
+                    party,
+                    &members,
+                    PartyUpdate::LeaderChanged {
+                        leader: member.1.unique_id,
+                    },
+                );
+            },
+            PartyClientProtocol::PartyMatchingForm(form) => {
+                let Some(party) = in_party.and_then(|in_party| parties.get_mut(in_party.0)) else {
+                    client.send(PartyMatchingFormResponse::Failure(PartyError::NotInParty));
+                    continue;
+                };
+
+                if !party.is_leader(entity) {
+                    client.send(PartyMatchingFormResponse::Failure(PartyError::NotLeader));
+                    continue;
+                }
+
+                if party.matching.is_some() {
+                    client.send(PartyMatchingFormResponse::Failure(PartyError::AlreadyListed));
+                    continue;
+                }
+
+                if form.min_level == 0 || form.min_level > form.max_level {
+                    client.send(PartyMatchingFormResponse::Failure(PartyError::InvalidLevelRange));
+                    continue;
+                }
+
+                let matching = PartyMatching {
+                    min_level: form.min_level,
+                    max_level: form.max_level,
+                    title: form.title.trim().chars().take(MAX_MATCHING_TITLE_LENGTH).collect(),
+                };
+                if let Some(entry) = matching_entry(party, &matching, &members) {
+                    client.send(PartyMatchingFormResponse::Success(entry));
+                }
+                party.matching = Some(matching);
+            },
+            PartyClientProtocol::PartyMatchingDelete(delete) => {
+                let Some(party) = parties.get_mut(delete.id).filter(|party| party.matching.is_some()) else {
+                    client.send(PartyMatchingDeleteResponse::Failure(PartyError::EntryNotFound));
+                    continue;
+                };
+
+                if !party.is_leader(entity) {
+                    client.send(PartyMatchingDeleteResponse::Failure(PartyError::NotLeader));
+                    continue;
+                }
+
+                party.matching = None;
+                client.send(PartyMatchingDeleteResponse::Success { id: delete.id });
+            },
+            PartyClientProtocol::PartyMatchingList(list) => {
+                let entries = parties
+                    .listed()
+                    .filter_map(|(party, matching)| matching_entry(party, matching, &members))
+                    .collect::<Vec<_>>();
+                let page_count = entries.len().div_ceil(MATCHING_PAGE_SIZE);
+                let page = usize::from(list.page).min(page_count.saturating_sub(1));
+                let entries = entries
+                    .into_iter()
+                    .skip(page * MATCHING_PAGE_SIZE)
+                    .take(MATCHING_PAGE_SIZE)
+                    .collect();
+                client.send(PartyMatchingListResponse::Success {
+                    page_count: page_count as u8,
+                    page: page as u8,
+                    entries,
+                });
+            },
+            PartyClientProtocol::PartyMatchingJoin(join) => {
+                if in_party.is_some() {
+                    client.send(PartyMatchingJoinResponse::Failure(PartyError::AlreadyInParty));
+                    continue;
+                }
+
+                let Some((party, matching)) = parties
+                    .get(join.id)
+                    .and_then(|party| party.matching.as_ref().map(|matching| (party, matching)))
+                else {
+                    client.send(PartyMatchingJoinResponse::Failure(PartyError::EntryNotFound));
+                    continue;
+                };
+
+                if !matching.accepts_level(level.current_level()) {
+                    client.send(PartyMatchingJoinResponse::Failure(PartyError::LevelOutOfRange));
+                    continue;
+                }
+
+                if party.is_full() {
+                    client.send(PartyMatchingJoinResponse::Failure(PartyError::PartyFull));
+                    continue;
+                }
+
+                let Ok((leader_client, ..)) = members.get(party.leader.0) else {
+                    client.send(PartyMatchingJoinResponse::Failure(PartyError::EntryNotFound));
+                    continue;
+                };
+
+                if is_blocked_by(&blocks, party.leader.0, char_id) {
+                    continue;
+                }
+
+                if pending.contains(party.leader.0) {
+                    client.send(PartyMatchingJoinResponse::Failure(PartyError::Busy));
+                    continue;
+                }
+
+                cmd.entity(party.leader.0).try_insert((
+                    PartyInvitation {
+                        requester: entity,
+                        kind: PartyInvitationKind::JoinRequest(party.id),
+                    },
+                    PendingInvitation,
+                ));
+                leader_client.send(PlayerInvitation::party(
+                    InvitationKind::PartyJoinRequest,
+                    game_entity.unique_id,
+                ));
+            },
+        }
+    }
+}
+
+pub(crate) fn answer_party_invitations(
+    query: Query<(Entity, &PlayerInput, &PartyInvitation)>,
+    members: Query<(&Client, &GameEntity, &Player, &Leveled, Option<&InParty>)>,
+    mut parties: ResMut<Parties>,
+    mut cmd: Commands,
+) {
+    for (entity, input, invitation) in query.iter() {
+        let Some(ref response) = input.invitation else {
+            continue;
+        };
+
+        cmd.entity(entity).remove::<(PartyInvitation, PendingInvitation)>();
+        let Ok((requester_client, requester_game_entity, _, _, requester_party)) = members.get(invitation.requester)
+        else {
+            continue;
+        };
+        let Ok((client, game_entity, _, _, own_party)) = members.get(entity) else {
+            continue;
+        };
+
+        let accepted = matches!(response, PlayerInvitationResponse::Accept);
+        let requester = EntityReference(invitation.requester, *requester_game_entity);
+        let invited = EntityReference(entity, *game_entity);
+        match invitation.kind {
+            PartyInvitationKind::Creation(party_type) => {
+                if !accepted {
+                    requester_client.send(PartyCreateResponse::Failure(PartyError::Declined));
+                    continue;
+                }
+
+                if requester_party.is_some() || own_party.is_some() {
+                    requester_client.send(PartyCreateResponse::Failure(PartyError::TargetInParty));
+                    continue;
+                }
+
+                let party = parties.create(requester, invited, party_type);
+                cmd.entity(requester.0).try_insert(InParty(party.id));
+                cmd.entity(invited.0).try_insert(InParty(party.id));
+                requester_client.send(PartyCreateResponse::Success);
+                let info = party_info(party, &members);
+                requester_client.send(info.clone());
+                client.send(info);
+            },
+            PartyInvitationKind::Invitation(party_id) => {
+                let result = if !accepted {
+                    Err(PartyError::Declined)
+                } else if own_party.is_some() {
+                    Err(PartyError::TargetInParty)
+                } else {
+                    join_party(&mut parties, party_id, invited, &members, &mut cmd)
+                };
+
+                match result {
+                    Ok(_) => requester_client.send(PartyInviteResponse::Success),
+                    Err(error) => requester_client.send(PartyInviteResponse::Failure(error)),
+                }
+            },
+            PartyInvitationKind::JoinRequest(party_id) => {
+                let result = if !accepted {
+                    Err(PartyError::Declined)
+                } else if requester_party.is_some() {
+                    Err(PartyError::AlreadyInParty)
+                } else {
+                    join_party(&mut parties, party_id, requester, &members, &mut cmd)
+                };
+
+                match result {
+                    Ok(_) => requester_client.send(PartyMatchingJoinResponse::Success),
+                    Err(error) => requester_client.send(PartyMatchingJoinResponse::Failure(error)),
+                }
+            },
+        }
+    }
+}
+
+/// Removes all members that have left the game from their party.
+pub(crate) fn remove_missing_party_members(
+    mut parties: ResMut<Parties>,
+    members: Query<(&Client, &GameEntity, &Player, &Leveled, Option<&InParty>)>,
+    entities: &Entities,
+    mut cmd: Commands,
+) {
+    let missing = parties
+        .iter()
+        .flat_map(|party| {
+            party
+                .members
+                .iter()
+                .filter(|member| !entities.contains(member.0))
+                .map(|member| (party.id, member.0))
+        })
+        .collect::<Vec<_>>();
+
+    for (party_id, member) in missing {
+        leave_party(&mut parties, party_id, member, false, &members, &mut cmd);
+    }
+}
+
+fn join_party(
+    parties: &mut Parties,
+    party_id: u32,
+    joining: EntityReference,
+    members: &Query<(&Client, &GameEntity, &Player, &Leveled, Option<&InParty>)>,
+    cmd: &mut Commands,
+) -> Result<(), PartyError> {
+    let party = parties.get_mut(party_id).ok_or(PartyError::NotInParty)?;
+    if party.is_full() {
+        return Err(PartyError::PartyFull);
+    }
+
+    let (client, game_entity, player, level, _) = members.get(joining.0).map_err(|_| PartyError::InvalidTarget)?;
+    let member = PartyMember::new(
+        game_entity.unique_id,
+        player.character.name.clone(),
+        game_entity.ref_id,
+        level.current_level(),
+    );
+    notify_members(party, members, PartyUpdate::MemberJoined(member));
+    party.members.push(joining);
+    cmd.entity(joining.0).try_insert(InParty(party_id));
+    client.send(party_info(party, members));
+    Ok(())
+}
+
+/// Removes the member from the party and lets the remaining members know. A party that only has a
+/// single member left is disbanded.
+fn leave_party(
+    parties: &mut Parties,
+    party_id: u32,
+    member: Entity,
+    kicked: bool,
+    members: &Query<(&Client, &GameEntity, &Player, &Leveled, Option<&InParty>)>,
+    cmd: &mut Commands,
+) {
+    let Some(party) = parties.get_mut(party_id) else {
+        return;
+    };
+    let Some(leaving) = party.members.iter().find(|other| other.0 == member).copied() else {
+        return;
+    };
+
+    if let Ok((client, ..)) = members.get(member) {
+        if kicked {
+            client.send(PartyUpdate::MemberLeft {
+                member: leaving.1.unique_id,
+                kicked,
+            });
+        }
+        cmd.entity(member).remove::<InParty>();
+    }
+
+    let new_leader = party.remove_member(member);
+    if party.members.len() < 2 {
+        let Some(party) = parties.remove(party_id) else {
+            return;
+        };
+        for remaining in party.members {
+            if let Ok((client, ..)) = members.get(remaining.0) {
+                client.send(PartyUpdate::Disbanded);
+                cmd.entity(remaining.0).remove::<InParty>();
+            }
+        }
+        return;
+    }
+
+    notify_members(
+        party,
+        members,
+        PartyUpdate::MemberLeft {
+            member: leaving.1.unique_id,
+            kicked,
+        },
+    );
+    if let Some(leader) = new_leader {
+        notify_members(
+            party,
+            members,
+            PartyUpdate::LeaderChanged {
+                leader: leader.1.unique_id,
+            },
+        );
+    }
+}
+
+fn notify_members(
+    party: &Party,
+    members: &Query<(&Client, &GameEntity, &Player, &Leveled, Option<&InParty>)>,
+    update: PartyUpdate,
+) {
+    for (client, ..) in members.iter_many(party.members.iter().map(|member| member.0)) {
+        client.send(update.clone());
+    }
+}
+
+fn party_info(
+    party: &Party,
+    members: &Query<(&Client, &GameEntity, &Player, &Leveled, Option<&InParty>)>,
+) -> PartyInfo {
+    let party_members = members
+        .iter_many(party.members.iter().map(|member| member.0))
+        .map(|(_, game_entity, player, level, _)| {
+            PartyMember::new(
+                game_entity.unique_id,
+                player.character.name.clone(),
+                game_entity.ref_id,
+                level.current_level(),
+            )
+        })
+        .collect();
+    PartyInfo::new(party.id, party.leader.1.unique_id, party.party_type, party_members)
+}
+
+fn matching_entry(
+    party: &Party,
+    matching: &PartyMatching,
+    members: &Query<(&Client, &GameEntity, &Player, &Leveled, Option<&InParty>)>,
+) -> Option<PartyMatchingEntry> {
+    let (_, _, leader, _, _) = members.get(party.leader.0).ok()?;
+    Some(PartyMatchingEntry {
+        id: party.id,
+        leader: party.leader.1.unique_id,
+        leader_name: leader.character.name.clone(),
+        member_count: party.members.len() as u8,
+        party_type: party.party_type,
+        min_level: matching.min_level,
+        max_level: matching.max_level,
+        title: matching.title.clone(),
+    })
+}
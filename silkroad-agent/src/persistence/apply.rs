@@ -5,13 +5,20 @@ use crate::comp::pos::Position;
 use crate::comp::{Health, Mana};
 use axum::async_trait;
 use silkroad_game_base::{ChangeProvided, GlobalPosition, Heading, Stats};
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
 
 #[async_trait]
 pub trait ApplyToDatabase: Send + Sync {
     async fn apply(&self, character_id: u32, pool: &PgPool) -> Result<(), sqlx::Error>;
 }
 
+/// Like [ApplyToDatabase], but using an existing connection instead, such that changes of multiple
+/// characters can be applied within a single transaction.
+#[async_trait]
+pub trait ApplyInTransaction: Send + Sync {
+    async fn apply_in(&self, character_id: u32, connection: &mut PgConnection) -> Result<(), sqlx::Error>;
+}
+
 pub struct PositionChange(GlobalPosition, Heading);
 
 impl ChangeProvided for Position {
@@ -185,15 +192,23 @@ impl ApplyToDatabase for SpChange {
 }
 
 #[async_trait]
-impl ApplyToDatabase for GoldChange {
-    async fn apply(&self, character_id: u32, pool: &PgPool) -> Result<(), sqlx::Error> {
+impl ApplyInTransaction for GoldChange {
+    async fn apply_in(&self, character_id: u32, connection: &mut PgConnection) -> Result<(), sqlx::Error> {
         sqlx::query!(
             "UPDATE characters SET gold = $1 WHERE id = $2",
             self.0 as i32,
             character_id as i32
         )
-        .execute(pool)
+        .execute(connection)
         .await?;
         Ok(())
     }
 }
+
+#[async_trait]
+impl ApplyToDatabase for GoldChange {
+    async fn apply(&self, character_id: u32, pool: &PgPool) -> Result<(), sqlx::Error> {
+        let mut connection = pool.acquire().await?;
+        self.apply_in(character_id, &mut connection).await
+    }
+}
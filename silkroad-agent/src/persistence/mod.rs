@@ -3,7 +3,7 @@ use crate::config::GameConfig;
 use crate::event::ClientDisconnectedEvent;
 use crate::ext::DbPool;
 use crate::tasks::TaskCreator;
-pub use apply::ApplyToDatabase;
+pub use apply::{ApplyInTransaction, ApplyToDatabase};
 use bevy::ecs::component::ComponentId;
 use bevy::prelude::*;
 use bevy::ptr::Ptr;
@@ -13,6 +13,7 @@ use silkroad_game_base::{ChangeProvided, ChangeTracked, ToOptimizedChange};
 use sqlx::PgPool;
 use std::mem;
 use std::ops::Deref;
+use std::sync::Arc;
 use std::time::Duration;
 use tracing::error;
 
@@ -40,6 +41,39 @@ struct PersistenceInfo {
 #[derive(Resource, Default)]
 struct PersistedComponents(Vec<PersistenceInfo>);
 
+/// The components registered through [AppPersistanceExt::persist_together] and the changes they
+/// collected for each [PersistTogetherEvent] of the current tick, in the order of the events.
+#[derive(Resource, Default)]
+struct PersistedTogether {
+    components: Vec<ComponentId>,
+    pending: Vec<Vec<(u32, Arc<dyn ApplyInTransaction>)>>,
+}
+
+/// Requests the pending changes of all the given entities to be applied immediately, within a single
+/// transaction. This is necessary when changes across characters need to be consistent, for example
+/// when items change hands.
+#[derive(Event)]
+pub(crate) struct PersistTogetherEvent {
+    entities: Vec<Entity>,
+    additional: Vec<(u32, Arc<dyn ApplyInTransaction>)>,
+}
+
+impl PersistTogetherEvent {
+    pub(crate) fn new(entities: Vec<Entity>) -> Self {
+        PersistTogetherEvent {
+            entities,
+            additional: Vec::new(),
+        }
+    }
+
+    /// Adds a change that isn't tracked by any component, which will be applied in the same transaction
+    /// after all the changes of the entities.
+    pub(crate) fn with_change<C: ApplyInTransaction + 'static>(mut self, character_id: u32, change: C) -> Self {
+        self.additional.push((character_id, Arc::new(change)));
+        self
+    }
+}
+
 pub struct PersistencePlugin;
 
 impl Plugin for PersistencePlugin {
@@ -50,7 +84,9 @@ impl Plugin for PersistencePlugin {
             .expect("Game config should exist.")
             .persist_interval;
         app.init_resource::<PersistedComponents>()
-            .add_systems(PostUpdate, apply_changes_combined)
+            .init_resource::<PersistedTogether>()
+            .add_event::<PersistTogetherEvent>()
+            .add_systems(PostUpdate, (apply_changes_combined, apply_changes_together))
             .add_systems(
                 PostUpdate,
                 apply_changes_periodically.run_if(on_timer(Duration::from_secs(persist_interval))),
@@ -66,6 +102,13 @@ pub(crate) trait AppPersistanceExt {
     fn track_change_component<T: ChangeProvided + Component>(&mut self) -> &mut Self
     where
         T::Change: ApplyToDatabase;
+
+    /// Allows the changes of the component to be applied together with the changes of all other
+    /// components registered this way, see [PersistTogetherEvent]. Each component may only be
+    /// registered once.
+    fn persist_together<T: ChangeTracked + Component>(&mut self) -> &mut Self
+    where
+        T::ChangeItem: ApplyInTransaction + 'static;
 }
 
 impl AppPersistanceExt for App {
@@ -114,6 +157,31 @@ impl AppPersistanceExt for App {
         });
         self
     }
+
+    fn persist_together<T: ChangeTracked + Component>(&mut self) -> &mut Self
+    where
+        T::ChangeItem: ApplyInTransaction + 'static,
+    {
+        let comp = self.world_mut().register_component::<T>();
+        let mut persisted_together = self
+            .world_mut()
+            .get_resource_mut::<PersistedTogether>()
+            .expect("Persistence plugin should be initialized.");
+        assert!(
+            !persisted_together.components.contains(&comp),
+            "{} is already persisted together.",
+            std::any::type_name::<T>()
+        );
+        persisted_together.components.push(comp);
+
+        self.add_systems(
+            PostUpdate,
+            collect_changes_together::<T>
+                .after(collect_changes::<T>)
+                .before(apply_changes_together),
+        );
+        self
+    }
 }
 
 fn add_change_tracker<T: ChangeTracked + Component>(mut cmd: Commands, query: Query<Entity, Added<T>>) {
@@ -178,6 +246,61 @@ fn apply_changes_exit<T: ChangeTracked + Component>(
     }
 }
 
+fn collect_changes_together<T: ChangeTracked + Component>(
+    mut query: Query<(&Player, &mut PersistenceCollection<T>)>,
+    mut event_reader: EventReader<PersistTogetherEvent>,
+    mut persisted_together: ResMut<PersistedTogether>,
+) where
+    T::ChangeItem: ApplyInTransaction + 'static,
+{
+    for (index, event) in event_reader.read().enumerate() {
+        if persisted_together.pending.len() <= index {
+            persisted_together.pending.resize_with(index + 1, Vec::new);
+        }
+
+        for entity in event.entities.iter() {
+            let Ok((player, mut changes)) = query.get_mut(*entity) else {
+                continue;
+            };
+            let character_id = player.character.id;
+            let changes = mem::take(&mut changes.changes).optimize();
+            persisted_together.pending[index].extend(
+                changes
+                    .into_iter()
+                    .map(|change| (character_id, Arc::new(change) as Arc<dyn ApplyInTransaction>)),
+            );
+        }
+    }
+}
+
+fn apply_changes_together(
+    mut event_reader: EventReader<PersistTogetherEvent>,
+    mut persisted_together: ResMut<PersistedTogether>,
+    task_creator: Res<TaskCreator>,
+    pool: Res<DbPool>,
+) {
+    let mut pending = mem::take(&mut persisted_together.pending).into_iter();
+    for event in event_reader.read() {
+        let mut changes = pending.next().unwrap_or_default();
+        changes.extend(event.additional.iter().cloned());
+        let pool = pool.deref().deref().clone();
+        task_creator.spawn(async move {
+            let result: Result<(), sqlx::Error> = async {
+                let mut transaction = pool.begin().await?;
+                for (character_id, change) in changes.iter() {
+                    change.apply_in(*character_id, &mut transaction).await?;
+                }
+                transaction.commit().await
+            }
+            .await;
+
+            if let Err(e) = result {
+                error!(error = %e, "Could not apply updates together");
+            }
+        });
+    }
+}
+
 fn apply_changes_combined(
     components: Res<PersistedComponents>,
     mut disconnections: EventReader<ClientDisconnectedEvent>,
@@ -2,12 +2,18 @@ use silkroad_protocol::auth::AuthProtocol;
 use silkroad_protocol::character::{CharselectClientProtocol, CharselectServerProtocol};
 use silkroad_protocol::chat::{ChatClientProtocol, ChatServerProtocol};
 use silkroad_protocol::combat::{CombatClientProtocol, CombatServerProtocol};
-use silkroad_protocol::community::{FriendListClientProtocol, FriendListServerProtocol};
+use silkroad_protocol::community::{
+    FriendListClientProtocol, FriendListServerProtocol, GuildClientProtocol, GuildServerProtocol, MemoClientProtocol,
+    MemoServerProtocol, UnionClientProtocol, UnionServerProtocol,
+};
+use silkroad_protocol::exchange::{ExchangeClientProtocol, ExchangeServerProtocol};
 use silkroad_protocol::general::BaseProtocol;
 use silkroad_protocol::gm::{GmClientProtocol, GmServerProtocol};
 use silkroad_protocol::inventory::{InventoryClientProtocol, InventoryServerProtocol};
 use silkroad_protocol::movement::{MovementClientProtocol, MovementServerProtocol};
+use silkroad_protocol::party::{PartyClientProtocol, PartyServerProtocol};
 use silkroad_protocol::skill::{SkillClientProtocol, SkillServerProtocol};
+use silkroad_protocol::stall::{StallClientProtocol, StallServerProtocol};
 use silkroad_protocol::world::{StatClientProtocol, StatServerProtocol, WorldClientProtocol, WorldServerProtocol};
 use skrillax_protocol::{define_inbound_protocol, define_outbound_protocol};
 
@@ -19,11 +25,17 @@ define_inbound_protocol! { AgentClientProtocol =>
     SkillClientProtocol,
     ChatClientProtocol,
     FriendListClientProtocol,
+    GuildClientProtocol,
+    UnionClientProtocol,
+    MemoClientProtocol,
     CharselectClientProtocol,
     StatClientProtocol,
     CombatClientProtocol,
     WorldClientProtocol,
     InventoryClientProtocol,
+    ExchangeClientProtocol,
+    StallClientProtocol,
+    PartyClientProtocol,
     GmClientProtocol
 }
 
@@ -34,11 +46,17 @@ define_outbound_protocol! { AgentServerProtocol =>
     ChatServerProtocol,
     MovementServerProtocol,
     FriendListServerProtocol,
+    GuildServerProtocol,
+    UnionServerProtocol,
+    MemoServerProtocol,
     CharselectServerProtocol,
     SkillServerProtocol,
     StatServerProtocol,
     CombatServerProtocol,
     WorldServerProtocol,
     InventoryServerProtocol,
+    ExchangeServerProtocol,
+    StallServerProtocol,
+    PartyServerProtocol,
     GmServerProtocol
 }
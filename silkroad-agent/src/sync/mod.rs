@@ -6,9 +6,9 @@ use crate::comp::{Health, Mana};
 use crate::sync::reset::AppResetExt;
 use crate::sync::system::{
     collect_alives, collect_body_states, collect_deaths, collect_gold_changes, collect_mastery_changes,
-    collect_movement_speed_change, collect_movement_update, collect_pickup_animation, collect_stat_changes,
-    synchronize_updates, system_collect_bars_update, system_collect_exp_update, system_collect_level_up,
-    system_collect_sp_update,
+    collect_movement_speed_change, collect_movement_update, collect_pickup_animation, collect_scroll_casts,
+    collect_stall_changes, collect_stat_changes, synchronize_updates, system_collect_bars_update,
+    system_collect_exp_update, system_collect_level_up, system_collect_sp_update,
 };
 use bevy::prelude::*;
 use derive_more::From;
@@ -17,6 +17,7 @@ use silkroad_protocol::character::CharacterStatsMessage;
 use silkroad_protocol::combat::ReceiveExperience;
 use silkroad_protocol::movement::{EntityMovementInterrupt, PlayerMovementResponse};
 use silkroad_protocol::skill::LevelUpMasteryResponse;
+use silkroad_protocol::stall::{StallEntityClosed, StallEntityCreated};
 use silkroad_protocol::world::{
     CharacterPointsUpdate, EntityBarsUpdate, EntityUpdateState, LevelUpEffect, PlayerPickupAnimation,
 };
@@ -63,6 +64,8 @@ pub(crate) enum SelfUpdate {
     EntityUpdateState(EntityUpdateState),
     PlayerPickupAnimation(PlayerPickupAnimation),
     LevelUpMasteryResponse(LevelUpMasteryResponse),
+    StallEntityCreated(StallEntityCreated),
+    StallEntityClosed(StallEntityClosed),
 }
 
 impl AsPacket for SelfUpdate {
@@ -78,6 +81,8 @@ impl AsPacket for SelfUpdate {
             SelfUpdate::EntityUpdateState(p) => p.as_packet(),
             SelfUpdate::PlayerPickupAnimation(p) => p.as_packet(),
             SelfUpdate::LevelUpMasteryResponse(p) => p.as_packet(),
+            SelfUpdate::StallEntityCreated(p) => p.as_packet(),
+            SelfUpdate::StallEntityClosed(p) => p.as_packet(),
         }
     }
 }
@@ -90,6 +95,8 @@ pub(crate) enum OtherUpdate {
     PlayerMovementResponse(PlayerMovementResponse),
     EntityUpdateState(EntityUpdateState),
     PlayerPickupAnimation(PlayerPickupAnimation),
+    StallEntityCreated(StallEntityCreated),
+    StallEntityClosed(StallEntityClosed),
 }
 
 impl AsPacket for OtherUpdate {
@@ -101,6 +108,8 @@ impl AsPacket for OtherUpdate {
             OtherUpdate::PlayerMovementResponse(p) => p.as_packet(),
             OtherUpdate::EntityUpdateState(p) => p.as_packet(),
             OtherUpdate::PlayerPickupAnimation(p) => p.as_packet(),
+            OtherUpdate::StallEntityCreated(p) => p.as_packet(),
+            OtherUpdate::StallEntityClosed(p) => p.as_packet(),
         }
     }
 }
@@ -168,6 +177,8 @@ impl Plugin for SynchronizationPlugin {
                     collect_stat_changes,
                     collect_gold_changes,
                     collect_mastery_changes,
+                    collect_scroll_casts,
+                    collect_stall_changes,
                 )
                     .in_set(SynchronizationStage::Collection),
             )
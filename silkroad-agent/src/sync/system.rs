@@ -1,28 +1,33 @@
 use crate::agent::component::MovementState;
+use crate::agent::pathfinding::FollowingPath;
 use crate::agent::state::{AgentState, Dead, Idle, MovementTarget, Moving, PickingUp, StateTransitionEvent};
 use crate::comp::damage::Invincible;
 use crate::comp::exp::{Experienced, Leveled, SP};
 use crate::comp::gold::GoldPouch;
 use crate::comp::mastery::MasteryKnowledge;
 use crate::comp::net::Client;
-use crate::comp::player::{Player, StatPoints};
-use crate::comp::pos::Position;
+use crate::comp::player::StatPoints;
+use crate::comp::pos::{protocol_coordinate, Position};
+use crate::comp::stats::CharacterStats;
 use crate::comp::visibility::{Invisible, Visibility};
 use crate::comp::{GameEntity, Health, Mana};
 use crate::event::LoadingFinishedEvent;
+use crate::ext::Navmesh;
 use crate::game::exp::LevelUpEvent;
+use crate::game::scroll::CastingScroll;
+use crate::game::stall::Stall;
 use crate::sync::{SynchronizationCollector, Update};
 use bevy::prelude::*;
 use silkroad_game_base::{Heading, LocalPosition, MovementSpeed};
-use silkroad_protocol::character::CharacterStatsMessage;
 use silkroad_protocol::combat::ReceiveExperience;
 use silkroad_protocol::movement::{
     EntityMovementInterrupt, MovementDestination, MovementSource, MovementType, PlayerMovementResponse,
 };
 use silkroad_protocol::skill::LevelUpMasteryResponse;
+use silkroad_protocol::stall::{StallEntityClosed, StallEntityCreated};
 use silkroad_protocol::world::{
-    AliveState, BodyState, CharacterPointsUpdate, EntityBarUpdateSource, EntityBarUpdates, EntityBarsUpdate,
-    EntityUpdateState, LevelUpEffect, PlayerPickupAnimation, UpdatedState,
+    ActiveScroll, AliveState, BodyState, CharacterPointsUpdate, EntityBarUpdateSource, EntityBarUpdates,
+    EntityBarsUpdate, EntityUpdateState, LevelUpEffect, PlayerPickupAnimation, UpdatedState,
 };
 use std::ops::Deref;
 use tracing::debug;
@@ -155,10 +160,10 @@ pub(crate) fn system_collect_exp_update(
 pub(crate) fn system_collect_level_up(
     collector: Res<SynchronizationCollector>,
     mut level_up_events: EventReader<LevelUpEvent>,
-    mut query: Query<(Entity, &GameEntity, Option<&Player>)>,
+    query: Query<(Entity, &GameEntity, Option<(&StatPoints, &CharacterStats)>)>,
 ) {
     for event in level_up_events.read() {
-        let Ok((entity, game_entity, maybe_player)) = query.get_mut(event.target.0) else {
+        let Ok((entity, game_entity, player_stats)) = query.get(event.target.0) else {
             continue;
         };
 
@@ -168,22 +173,8 @@ pub(crate) fn system_collect_level_up(
 
         collector.send_update(Update::update_all(entity, animation));
 
-        if let Some(player) = maybe_player {
-            let update = CharacterStatsMessage {
-                phys_attack_min: 100,
-                phys_attack_max: 100,
-                mag_attack_min: 100,
-                mag_attack_max: 100,
-                phys_defense: 100,
-                mag_defense: 100,
-                hit_rate: 100,
-                parry_rate: 100,
-                max_hp: player.character.stats.max_health(event.level),
-                max_mp: player.character.stats.max_mana(event.level),
-                strength: player.character.stats.strength(),
-                intelligence: player.character.stats.intelligence(),
-            };
-
+        if let Some((stats, character_stats)) = player_stats {
+            let update = character_stats.as_message(stats.stats(), event.level);
             collector.send_update(Update::self_update(entity, update));
         }
     }
@@ -210,13 +201,29 @@ pub(crate) fn collect_movement_transitions(
     }
 }
 
+/// Sends the start of a movement, or of the next part of it when walking along a path. Clients only
+/// walk in straight lines, so every waypoint of a path is sent as its own movement.
 pub(crate) fn collect_movement_starts(
     collector: Res<SynchronizationCollector>,
-    mut query: Query<(Entity, &GameEntity, &Position, &Moving), Or<(Added<Moving>, Changed<Moving>)>>,
+    mut query: Query<
+        (Entity, &GameEntity, &Position, &Moving, Option<&FollowingPath>),
+        Or<(Added<Moving>, Changed<Moving>, Changed<FollowingPath>)>,
+    >,
+    navmesh: Res<Navmesh>,
 ) {
-    for (entity, game_entity, pos, moving) in query.iter_mut() {
+    for (entity, game_entity, pos, moving, path) in query.iter_mut() {
         let update = match moving.parameter {
-            MovementTarget::Location(dest) => MovementUpdate::StartMove(pos.position().to_local(), dest.to_local()),
+            MovementTarget::Location(dest) => {
+                let current_height = pos.position().y;
+                let target = path
+                    .filter(|path| path.is_for(dest) && path.has_further_waypoints())
+                    .and_then(|path| path.next_waypoint())
+                    .map(|waypoint| {
+                        let height = navmesh.height_near(waypoint, current_height).unwrap_or(current_height);
+                        waypoint.with_y(height)
+                    });
+                MovementUpdate::StartMove(pos.position().to_local(), target.unwrap_or(dest).to_local())
+            },
             MovementTarget::Direction(direction) => {
                 MovementUpdate::StartMoveTowards(pos.position().to_local(), direction)
             },
@@ -256,12 +263,17 @@ fn create_movement_packet(entity: &GameEntity, update: MovementUpdate) -> Player
     match update {
         MovementUpdate::StartMove(current, target) => PlayerMovementResponse::new(
             entity.unique_id,
-            MovementDestination::location(target.0.id(), target.1.x as u16, target.1.y as u16, target.1.z as u16),
+            MovementDestination::location(
+                target.0.id(),
+                protocol_coordinate(target.1.x),
+                protocol_coordinate(target.1.y),
+                protocol_coordinate(target.1.z),
+            ),
             Some(MovementSource::new(
                 current.0.id(),
-                (current.1.x * 10.) as u16,
+                protocol_coordinate(current.1.x * 10.),
                 current.1.y * 10.,
-                (current.1.z * 10.) as u16,
+                protocol_coordinate(current.1.z * 10.),
             )),
         ),
         MovementUpdate::StartMoveTowards(current, direction) => PlayerMovementResponse::new(
@@ -269,18 +281,18 @@ fn create_movement_packet(entity: &GameEntity, update: MovementUpdate) -> Player
             MovementDestination::direction(true, direction.into()),
             Some(MovementSource::new(
                 current.0.id(),
-                (current.1.x * 10.) as u16,
+                protocol_coordinate(current.1.x * 10.),
                 current.1.y * 10.,
-                (current.1.z * 10.) as u16,
+                protocol_coordinate(current.1.z * 10.),
             )),
         ),
         MovementUpdate::StopMove(current, _heading) => PlayerMovementResponse::new(
             entity.unique_id,
             MovementDestination::location(
                 current.0.id(),
-                current.1.x as u16,
-                current.1.y as u16,
-                current.1.z as u16,
+                protocol_coordinate(current.1.x),
+                protocol_coordinate(current.1.y),
+                protocol_coordinate(current.1.z),
             ),
             None,
         ),
@@ -401,29 +413,17 @@ pub(crate) fn collect_body_states(
 
 pub(crate) fn collect_stat_changes(
     collector: Res<SynchronizationCollector>,
-    query: Query<(Entity, &Leveled, &StatPoints), Changed<StatPoints>>,
+    query: Query<
+        (Entity, &Leveled, &StatPoints, Ref<CharacterStats>),
+        Or<(Changed<StatPoints>, Changed<CharacterStats>)>,
+    >,
 ) {
-    for (entity, level, stats) in query.iter() {
-        if stats.has_spent_points() {
+    for (entity, level, stats, character_stats) in query.iter() {
+        let equipment_changed = character_stats.is_changed() && !character_stats.is_added();
+        if stats.has_spent_points() || equipment_changed {
             collector.send_update(Update {
                 source: entity,
-                change_self: Some(
-                    CharacterStatsMessage {
-                        phys_attack_min: 100,
-                        phys_attack_max: 100,
-                        mag_attack_min: 100,
-                        mag_attack_max: 100,
-                        phys_defense: 100,
-                        mag_defense: 100,
-                        hit_rate: 100,
-                        parry_rate: 100,
-                        max_hp: stats.stats().max_health(level.current_level()),
-                        max_mp: stats.stats().max_mana(level.current_level()),
-                        strength: stats.stats().strength(),
-                        intelligence: stats.stats().intelligence(),
-                    }
-                    .into(),
-                ),
+                change_self: Some(character_stats.as_message(stats.stats(), level.current_level()).into()),
                 change_others: None,
             })
         }
@@ -469,3 +469,45 @@ pub(crate) fn collect_mastery_changes(
         }
     }
 }
+
+pub(crate) fn collect_scroll_casts(
+    collector: Res<SynchronizationCollector>,
+    started_query: Query<(Entity, &GameEntity), Added<CastingScroll>>,
+    mut stopped: RemovedComponents<CastingScroll>,
+    game_entity_query: Query<&GameEntity>,
+) {
+    for (entity, game_entity) in started_query.iter() {
+        let update = EntityUpdateState::scroll(game_entity.unique_id, ActiveScroll::ReturnScroll);
+        collector.send_update(Update::update_all(entity, update));
+    }
+
+    for entity in stopped.read() {
+        let Ok(game_entity) = game_entity_query.get(entity) else {
+            continue;
+        };
+        let update = EntityUpdateState::scroll(game_entity.unique_id, ActiveScroll::None);
+        collector.send_update(Update::update_all(entity, update));
+    }
+}
+
+pub(crate) fn collect_stall_changes(
+    collector: Res<SynchronizationCollector>,
+    created_query: Query<(Entity, &GameEntity, &Stall), Added<Stall>>,
+    mut closed: RemovedComponents<Stall>,
+    game_entity_query: Query<&GameEntity>,
+) {
+    for (entity, game_entity, stall) in created_query.iter() {
+        let update = StallEntityCreated::new(game_entity.unique_id, stall.title.clone(), 0);
+        collector.send_update(Update::update_all(entity, update));
+    }
+
+    for entity in closed.read() {
+        let Ok(game_entity) = game_entity_query.get(entity) else {
+            continue;
+        };
+        let update = StallEntityClosed {
+            unique_id: game_entity.unique_id,
+        };
+        collector.send_update(Update::update_all(entity, update));
+    }
+}
@@ -5,7 +5,9 @@ use silkroad_data::datamap::DataMap;
 use silkroad_data::gold::{load_gold_map, GoldMap};
 use silkroad_data::itemdata::{load_item_map, RefItemData};
 use silkroad_data::level::{load_level_map, LevelMap};
+use silkroad_data::magicoption::{load_magic_option_map, RefMagicOption};
 use silkroad_data::masterydata::{load_mastery_map, RefMasteryData};
+use silkroad_data::shop::{load_shop_goods, ShopGoods};
 use silkroad_data::skilldata::{load_skill_map, RefSkillData};
 use silkroad_data::teleport::{
     load_teleport_buildings, load_teleport_links, load_teleport_map, TeleportBuilding, TeleportLink, TeleportLocation,
@@ -19,9 +21,11 @@ static SKILLS: OnceCell<DataMap<RefSkillData>> = OnceCell::new();
 static LEVELS: OnceCell<LevelMap> = OnceCell::new();
 static GOLD: OnceCell<GoldMap> = OnceCell::new();
 static MASTERIES: OnceCell<DataMap<RefMasteryData>> = OnceCell::new();
+static MAGIC_OPTIONS: OnceCell<DataMap<RefMagicOption>> = OnceCell::new();
 static TELEPORTS: OnceCell<HashMap<u16, TeleportLocation>> = OnceCell::new();
 static TELEPORT_LINKS: OnceCell<Vec<TeleportLink>> = OnceCell::new();
 static TELEPORT_BUILDINGS: OnceCell<DataMap<TeleportBuilding>> = OnceCell::new();
+static SHOP_GOODS: OnceCell<ShopGoods> = OnceCell::new();
 
 pub struct WorldData;
 
@@ -33,9 +37,11 @@ impl WorldData {
         let items = load_item_map(media_pk2)?;
         let skills = load_skill_map(media_pk2)?;
         let masteries = load_mastery_map(media_pk2)?;
+        let magic_options = load_magic_option_map(media_pk2)?;
         let teleports = load_teleport_map(media_pk2)?;
         let teleport_links = load_teleport_links(media_pk2)?;
         let teleport_buildings = load_teleport_buildings(media_pk2)?;
+        let shop_goods = load_shop_goods(media_pk2)?;
 
         let _ = LEVELS.set(levels);
         let _ = GOLD.set(gold);
@@ -43,9 +49,11 @@ impl WorldData {
         let _ = ITEMS.set(items);
         let _ = SKILLS.set(skills);
         let _ = MASTERIES.set(masteries);
+        let _ = MAGIC_OPTIONS.set(magic_options);
         let _ = TELEPORTS.set(teleports);
         let _ = TELEPORT_LINKS.set(teleport_links);
         let _ = TELEPORT_BUILDINGS.set(teleport_buildings);
+        let _ = SHOP_GOODS.set(shop_goods);
         Ok(())
     }
 
@@ -72,4 +80,26 @@ impl WorldData {
     pub fn masteries() -> &'static DataMap<RefMasteryData> {
         MASTERIES.get().expect("Masteries should have been set")
     }
+
+    pub fn magic_options() -> &'static DataMap<RefMagicOption> {
+        MAGIC_OPTIONS.get().expect("Magic options should have been set")
+    }
+
+    pub fn teleports() -> &'static HashMap<u16, TeleportLocation> {
+        TELEPORTS.get().expect("Teleports should have been set")
+    }
+
+    pub fn teleport_links() -> &'static [TeleportLink] {
+        TELEPORT_LINKS.get().expect("Teleport links should have been set")
+    }
+
+    pub fn teleport_buildings() -> &'static DataMap<TeleportBuilding> {
+        TELEPORT_BUILDINGS
+            .get()
+            .expect("Teleport buildings should have been set")
+    }
+
+    pub fn shop_goods() -> &'static ShopGoods {
+        SHOP_GOODS.get().expect("Shop goods should have been set")
+    }
 }
@@ -35,7 +35,7 @@ impl Plugin for WorldPlugin {
         app.insert_resource(EntityIdPool::default())
             .insert_resource(EntityLookup::default())
             .insert_resource::<NpcPositionList>(npcs.into())
-            .add_systems(Startup, spawning::spawn_npcs)
+            .add_systems(Startup, (spawning::spawn_npcs, spawning::spawn_teleport_buildings))
             .add_systems(First, maintain_entities)
             .add_systems(Last, collect_entities)
             .add_systems(Update, spawning::spawn_monsters)
@@ -3,7 +3,7 @@ use crate::agent::goal::GoalTracker;
 use crate::agent::state::{AgentStateQueue, Dead};
 use crate::comp::damage::DamageReceiver;
 use crate::comp::monster::{Monster, MonsterAiBundle, MonsterBundle, RandomStroll, SpawnedBy};
-use crate::comp::npc::NpcBundle;
+use crate::comp::npc::{NpcBundle, TeleportGateBundle};
 use crate::comp::pos::Position;
 use crate::comp::spawner::Spawner;
 use crate::comp::visibility::Visibility;
@@ -64,6 +64,21 @@ pub(crate) fn spawn_npcs(
     }
 }
 
+pub(crate) fn spawn_teleport_buildings(mut commands: Commands, mut id_pool: ResMut<EntityIdPool>) {
+    for building in WorldData::teleport_buildings().iter() {
+        commands.spawn(TeleportGateBundle::new(
+            id_pool
+                .request_id()
+                .expect("Should have ID available for teleport building"),
+            building.ref_id(),
+            LocalPosition(
+                building.region,
+                Vector3::new(building.x as f32, building.y as f32, building.z as f32),
+            ),
+        ));
+    }
+}
+
 pub(crate) fn spawn_monsters(
     mut query: Query<(Entity, &mut Spawner, &Position)>,
     mut commands: Commands,
@@ -119,7 +134,7 @@ fn spawn_n_monsters(
 ) -> usize {
     (0..to_spawn)
         .map(|_| generate_position(position, spawner.radius))
-        .filter_map(|loc| to_position(loc, navmesh))
+        .filter_map(|loc| to_position(loc, position.position().y, navmesh))
         .for_each(|pos| {
             spawn_monster(
                 spawner_entity,
@@ -176,13 +191,9 @@ fn generate_position(source_position: &Position, radius: f32) -> GlobalLocation
     GlobalLocation(vec)
 }
 
-fn to_position(location: GlobalLocation, navmesh: &GlobalNavmesh) -> Option<Position> {
+fn to_position(location: GlobalLocation, reference_height: f32, navmesh: &GlobalNavmesh) -> Option<Position> {
     let local = location.to_local();
-    let navmesh = navmesh.mesh_for(local.0)?;
-    let height = navmesh
-        .heightmap()
-        .height_at_position(local.1.x, local.1.y)
-        .expect("Location should be inside region.");
+    let height = navmesh.mesh_ref_for(local.0)?.height_at(local.1, reference_height)?;
     let pos = location.with_y(height);
     let heading = Heading(rand::rng().random_range(0..360) as f32);
     Some(Position::new(pos, heading))